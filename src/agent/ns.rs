@@ -1,5 +1,6 @@
 use crate::config::SERVER_CONFIG;
 use crate::entities::namespaces;
+use crate::query::Query;
 use anyhow::Result;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use tracing::{debug, error};
@@ -33,10 +34,41 @@ pub async fn auth_ns(
         debug!("User is authorized to access root namespace");
     }
 
-    if !owner_ids.contains(&namespace_entity.owner) {
+    let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
+    if !owner_ids.contains(&namespace_entity.owner)
+        && Query::find_namespace_grant(db_pool, namespace, &owner_id_refs)
+            .await?
+            .is_none()
+    {
         error!("User not authorized to access namespace");
         return Err(anyhow::anyhow!("User not authorized to access namespace"));
     }
     debug!("User is authorized to access namespace");
     Ok(namespace_entity.owner)
 }
+
+/// Returned by [`auth_ns_for_create`] for a namespace that is being deleted.
+#[derive(Debug, thiserror::Error)]
+#[error("Namespace '{0}' is being deleted")]
+pub struct NamespaceTerminating(pub String);
+
+/// Like [`auth_ns`], but also refuses namespaces that are being deleted, so
+/// nothing new is created while their resources are torn down.
+pub async fn auth_ns_for_create(
+    db_pool: &DatabaseConnection,
+    owner_ids: &Vec<String>,
+    namespace: &str,
+) -> Result<String> {
+    let owner = auth_ns(db_pool, owner_ids, namespace).await?;
+
+    let terminating = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(namespace))
+        .one(db_pool)
+        .await?
+        .is_some_and(|ns| ns.is_terminating());
+    if terminating {
+        error!("Namespace {} is being deleted", namespace);
+        return Err(NamespaceTerminating(namespace.to_string()).into());
+    }
+    Ok(owner)
+}
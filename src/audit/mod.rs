@@ -0,0 +1,218 @@
+pub mod models;
+
+use crate::auth::scope::ScopedRequest;
+use crate::entities::audit_events;
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderMap, Method};
+use models::V1AuditEvent;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+use serde_json::Value;
+use std::net::{IpAddr, SocketAddr};
+use tracing::error;
+
+/// Header carrying the id that ties a request to its audit event.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Most fields kept from one request body.
+const MAX_CHANGED_FIELDS: usize = 50;
+
+/// How deep into a request body fields are named, e.g. `metadata.labels.team`.
+const MAX_FIELD_DEPTH: usize = 3;
+
+/// Whether a request is audited: every call that changes something, and every
+/// read of secrets, since those return secret values.
+pub fn is_audited(method: &Method, scoped: &ScopedRequest) -> bool {
+    match *method {
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE => true,
+        Method::GET => scoped.kind == "secrets",
+        _ => false,
+    }
+}
+
+/// Name the fields a JSON request body sets, without their values.
+pub fn changed_fields(body: &Value) -> Vec<String> {
+    fn collect(value: &Value, prefix: &str, depth: usize, fields: &mut Vec<String>) {
+        match value {
+            Value::Object(map) if depth < MAX_FIELD_DEPTH && !map.is_empty() => {
+                for (key, value) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    collect(value, &path, depth + 1, fields);
+                }
+            }
+            Value::Null => {}
+            _ if !prefix.is_empty() => fields.push(prefix.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut fields = Vec::new();
+    collect(body, "", 0, &mut fields);
+    fields.sort();
+    fields.truncate(MAX_CHANGED_FIELDS);
+    fields
+}
+
+/// Whether `ip` falls within the `(address, prefix length)` range.
+fn in_range(ip: IpAddr, (network, prefix): (IpAddr, u8)) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// The caller's address. `X-Forwarded-For` is only believed when the peer is
+/// one of the `trusted` proxies, in which case the caller is the nearest hop
+/// that is not itself a trusted proxy.
+pub fn source_ip(
+    headers: &HeaderMap,
+    connect_info: Option<&ConnectInfo<SocketAddr>>,
+    trusted: &[(IpAddr, u8)],
+) -> Option<String> {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip())?;
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|range| in_range(ip, *range));
+    if !is_trusted(peer) {
+        return Some(peer.to_string());
+    }
+
+    let hops: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    let caller = hops
+        .iter()
+        .rev()
+        .find(|hop| !is_trusted(**hop))
+        .or(hops.first())
+        .copied()
+        .unwrap_or(peer);
+    Some(caller.to_string())
+}
+
+/// Append an event to the audit log. Failures are logged, since the request
+/// they describe has already been handled.
+pub async fn record_event(db: &DatabaseConnection, event: V1AuditEvent) {
+    let created_at = chrono::DateTime::from_timestamp(event.created_at, 0)
+        .unwrap_or_else(chrono::Utc::now)
+        .into();
+    let model = audit_events::ActiveModel {
+        id: Set(event.id.clone()),
+        actor: Set(event.actor),
+        api_key_id: Set(event.api_key_id),
+        action: Set(event.action),
+        method: Set(event.method),
+        path: Set(event.path),
+        kind: Set(event.kind),
+        namespace: Set(event.namespace),
+        name: Set(event.name),
+        request_id: Set(event.request_id),
+        status: Set(event.status),
+        changes: Set(event.changes.map(|changes| serde_json::json!(changes))),
+        source_ip: Set(event.source_ip),
+        user_agent: Set(event.user_agent),
+        created_at: Set(created_at),
+    };
+    if let Err(e) = model.insert(db).await {
+        error!("[Audit] Failed to record audit event {}: {}", event.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_ip_range;
+
+    fn headers(forwarded_for: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", forwarded_for.parse().unwrap());
+        headers
+    }
+
+    fn peer(ip: &str) -> ConnectInfo<SocketAddr> {
+        ConnectInfo(SocketAddr::new(ip.parse().unwrap(), 40000))
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let trusted = [parse_ip_range("10.0.0.0/8").unwrap()];
+        assert_eq!(
+            source_ip(&headers("1.2.3.4"), Some(&peer("203.0.113.9")), &trusted),
+            Some("203.0.113.9".to_string())
+        );
+        assert_eq!(
+            source_ip(&headers("1.2.3.4"), Some(&peer("10.0.0.2")), &[]),
+            Some("10.0.0.2".to_string())
+        );
+    }
+
+    #[test]
+    fn forwarded_for_is_used_behind_trusted_proxies() {
+        let trusted = [
+            parse_ip_range("10.0.0.0/8").unwrap(),
+            parse_ip_range("192.0.2.7").unwrap(),
+        ];
+        // A spoofed leading hop is skipped in favour of the address the
+        // nearest trusted proxy saw.
+        assert_eq!(
+            source_ip(
+                &headers("6.6.6.6, 198.51.100.4, 192.0.2.7"),
+                Some(&peer("10.0.0.2")),
+                &trusted
+            ),
+            Some("198.51.100.4".to_string())
+        );
+        assert_eq!(
+            source_ip(&headers("10.1.1.1"), Some(&peer("10.0.0.2")), &trusted),
+            Some("10.1.1.1".to_string())
+        );
+        assert_eq!(
+            source_ip(&HeaderMap::new(), Some(&peer("10.0.0.2")), &trusted),
+            Some("10.0.0.2".to_string())
+        );
+    }
+
+    #[test]
+    fn no_peer_means_no_source_ip() {
+        assert_eq!(source_ip(&headers("1.2.3.4"), None, &[]), None);
+    }
+
+    #[test]
+    fn ip_ranges() {
+        assert!(parse_ip_range("10.0.0.0/33").is_none());
+        assert!(parse_ip_range("not-an-ip").is_none());
+        let range = parse_ip_range("2001:db8::/32").unwrap();
+        assert!(in_range("2001:db8::1".parse().unwrap(), range));
+        assert!(!in_range("2001:db9::1".parse().unwrap(), range));
+        assert!(!in_range("10.0.0.1".parse().unwrap(), range));
+        assert!(in_range(
+            "8.8.8.8".parse().unwrap(),
+            parse_ip_range("0.0.0.0/0").unwrap()
+        ));
+    }
+
+    #[test]
+    fn changed_fields_names_nested_fields() {
+        let body = serde_json::json!({
+            "metadata": {"name": "db", "labels": {"team": "ml"}},
+            "value": "hunter2",
+            "source": null
+        });
+        assert_eq!(
+            changed_fields(&body),
+            vec!["metadata.labels.team", "metadata.name", "value"]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// One audited API call.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AuditEvent {
+    pub id: String,
+    /// Email of the caller
+    pub actor: String,
+    pub api_key_id: Option<String>,
+    /// `create`, `update`, `delete`, `read`, ... as classified for RBAC
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub request_id: String,
+    /// HTTP status of the response
+    pub status: i32,
+    /// Fields the request body set, e.g. `image` or `metadata.labels.team`.
+    /// Values are not kept
+    pub changes: Option<Vec<String>>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AuditEvents {
+    pub events: Vec<V1AuditEvent>,
+}
+
+/// Filters for `GET /v1/audit`. Times are unix seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AuditQuery {
+    pub actor: Option<String>,
+    pub api_key_id: Option<String>,
+    pub action: Option<String>,
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub request_id: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Newest events returned, default 100
+    pub limit: Option<u64>,
+    /// `json` (default) or `jsonl`
+    pub format: Option<String>,
+}
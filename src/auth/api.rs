@@ -1,17 +1,37 @@
 use crate::auth::db;
 use crate::auth::models;
 use crate::auth::models::SanitizedApiKey;
+use crate::config::SERVER_CONFIG;
 use argon2::{
     password_hash::{PasswordHash, PasswordVerifier, SaltString},
     Argon2, PasswordHasher,
 };
 use base64::{engine::general_purpose, Engine as _};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use rand::rngs::OsRng;
 use rand::RngCore;
 use sea_orm::entity::*;
-use sea_orm::DatabaseConnection;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, QueryFilter};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
 use uuid::Uuid;
 
+/// API keys used since the last flush, with when each was last used.
+static PENDING_LAST_USED: Lazy<DashMap<String, chrono::DateTime<chrono::Utc>>> =
+    Lazy::new(DashMap::new);
+
+/// The key id of a `nebu-<id>.<secret>` token.
+pub fn api_key_id(token: &str) -> Option<&str> {
+    token
+        .strip_prefix("nebu-")
+        .and_then(|key| key.split_once('.'))
+        .map(|(id, _)| id)
+}
+
 pub async fn get_api_key(
     db_conn: &DatabaseConnection,
     id: &str,
@@ -44,6 +64,14 @@ pub async fn list_api_keys(
 
 pub async fn generate_api_key(
     db_conn: &DatabaseConnection,
+) -> Result<String, Box<dyn std::error::Error>> {
+    generate_scoped_api_key(db_conn, models::ApiKeyOptions::default()).await
+}
+
+/// Generate an API key with a label, expiry and scope.
+pub async fn generate_scoped_api_key(
+    db_conn: &DatabaseConnection,
+    options: models::ApiKeyOptions,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let mut raw_key = [0u8; 32];
     OsRng.fill_bytes(&mut raw_key);
@@ -57,7 +85,7 @@ pub async fn generate_api_key(
     };
 
     let id = Uuid::new_v4().to_string();
-    let api_key = models::ApiKey::new(id.clone(), hash);
+    let api_key = models::ApiKey::new(id.clone(), hash).with_options(options);
     let new_api_key: db::ActiveModel = db::Model::from(api_key).into();
     new_api_key.insert(db_conn).await?;
 
@@ -68,32 +96,44 @@ pub async fn validate_api_key(
     db_conn: &DatabaseConnection,
     provided_key: &str,
 ) -> Result<bool, Box<dyn std::error::Error + Sync + Send>> {
+    Ok(authenticate_api_key(db_conn, provided_key).await?.is_some())
+}
+
+/// Verify a `nebu-` key, returning it if it is valid, unrevoked and unexpired.
+pub async fn authenticate_api_key(
+    db_conn: &DatabaseConnection,
+    provided_key: &str,
+) -> Result<Option<models::ApiKey>, Box<dyn std::error::Error + Sync + Send>> {
     if let Some(full_key) = provided_key.strip_prefix("nebu-") {
         let parts: Vec<&str> = full_key.split('.').collect();
         if parts.len() == 2 {
             let (id, key) = (parts[0], parts[1]);
-            if let Some(mut api_key) = db::Entity::find_by_id(id).one(db_conn).await? {
+            if let Some(api_key) = db::Entity::find_by_id(id).one(db_conn).await? {
                 if api_key.revoked_at.is_some() {
-                    return Ok(false);
+                    return Ok(None);
+                }
+                if api_key
+                    .expires_at
+                    .map_or(false, |expires_at| expires_at <= chrono::Utc::now())
+                {
+                    return Ok(None);
                 }
                 let parsed_hash = match PasswordHash::new(&api_key.hash) {
                     Ok(hash) => hash,
-                    Err(_) => return Ok(false),
+                    Err(_) => return Ok(None),
                 };
                 let argon2 = Argon2::default();
                 return match argon2.verify_password(key.as_bytes(), &parsed_hash) {
                     Ok(_) => {
-                        let mut active_api_key: db::ActiveModel = api_key.into();
-                        active_api_key.last_used_at = Set(Some(chrono::Utc::now()));
-                        active_api_key.update(db_conn).await?;
-                        Ok(true)
+                        record_api_key_use(&api_key.id);
+                        Ok(Some(models::ApiKey::from(api_key)))
                     }
-                    Err(_) => Ok(false),
+                    Err(_) => Ok(None),
                 };
             }
         }
     }
-    Ok(false)
+    Ok(None)
 }
 
 pub async fn revoke_api_key(
@@ -110,3 +150,89 @@ pub async fn revoke_api_key(
         Err("API key not found".into())
     }
 }
+
+pub async fn set_api_key_rate_limit(
+    db_conn: &DatabaseConnection,
+    id: &str,
+    rate_limit: Option<crate::models::V1RateLimit>,
+) -> Result<SanitizedApiKey, Box<dyn std::error::Error>> {
+    if let Some(api_key) = db::Entity::find_by_id(id).one(db_conn).await? {
+        let mut current_api_key: db::ActiveModel = api_key.into();
+        current_api_key.rate_limit = Set(rate_limit.map(serde_json::to_value).transpose()?);
+        let result = current_api_key.update(db_conn).await?;
+        Ok(models::ApiKey::from(result).into())
+    } else {
+        Err("API key not found".into())
+    }
+}
+
+/// Look up the rate limit configured for an API key, if any.
+pub async fn get_api_key_rate_limit(
+    db_conn: &DatabaseConnection,
+    id: &str,
+) -> Result<Option<crate::models::V1RateLimit>, sea_orm::DbErr> {
+    let api_key = db::Entity::find_by_id(id).one(db_conn).await?;
+    Ok(api_key
+        .and_then(|k| k.rate_limit)
+        .and_then(|json| serde_json::from_value(json).ok()))
+}
+
+/// Note that an API key was just used. Writes to `last_used_at` are batched
+/// and applied by `flush_api_key_usage`.
+pub fn record_api_key_use(id: &str) {
+    PENDING_LAST_USED.insert(id.to_string(), chrono::Utc::now());
+}
+
+/// Write all pending `last_used_at` updates, returning how many keys were updated.
+///
+/// Updates that fail are queued again for the next flush, and the first
+/// error is returned once every update has been tried.
+pub async fn flush_api_key_usage(db_conn: &DatabaseConnection) -> Result<usize, sea_orm::DbErr> {
+    let ids: Vec<String> = PENDING_LAST_USED
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+    let pending: HashMap<String, chrono::DateTime<chrono::Utc>> = ids
+        .iter()
+        .filter_map(|id| PENDING_LAST_USED.remove(id))
+        .collect();
+
+    let mut flushed = 0;
+    let mut first_error = None;
+    for (id, last_used_at) in pending {
+        let result = db::Entity::update_many()
+            .col_expr(db::Column::LastUsedAt, Expr::value(last_used_at))
+            .filter(db::Column::Id.eq(id.as_str()))
+            .exec(db_conn)
+            .await;
+        match result {
+            Ok(_) => flushed += 1,
+            Err(e) => {
+                // A use recorded since the drain is newer, so keep it
+                PENDING_LAST_USED.entry(id).or_insert(last_used_at);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(flushed),
+    }
+}
+
+/// Spawn a background task that periodically flushes API key `last_used_at` updates.
+pub fn spawn_last_used_flusher(db_conn: DatabaseConnection) -> JoinHandle<()> {
+    let interval_secs = SERVER_CONFIG.auth.last_used_flush_secs.max(1);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            match flush_api_key_usage(&db_conn).await {
+                Ok(0) => {}
+                Ok(n) => debug!("[Auth] Flushed last_used_at for {} API keys", n),
+                Err(e) => error!("[Auth] Failed to flush API key usage: {}", e),
+            }
+        }
+    })
+}
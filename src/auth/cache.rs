@@ -0,0 +1,245 @@
+use crate::auth::models::ApiKeyScope;
+use crate::config::SERVER_CONFIG;
+use crate::models::V1UserProfile;
+use crate::state::MessageQueue;
+use dashmap::DashMap;
+use futures::StreamExt;
+use once_cell::sync::{Lazy, OnceCell};
+use rdkafka::config::ClientConfig as KafkaClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use rdkafka::producer::FutureRecord;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Channel (Redis) or topic (Kafka) carrying revoked API key ids.
+pub const REVOCATION_CHANNEL: &str = "nebu-auth-revocations";
+
+/// Delay before reconnecting a dropped revocation subscription.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Queue revocations are published on, set when the listener is spawned.
+static REVOCATION_QUEUE: OnceCell<MessageQueue> = OnceCell::new();
+
+static IDENTITY_CACHE: Lazy<IdentityCache> = Lazy::new(|| {
+    IdentityCache::new(
+        Duration::from_secs(SERVER_CONFIG.auth.cache_ttl_secs),
+        SERVER_CONFIG.auth.cache_max_entries,
+    )
+});
+
+/// An identity that recently authenticated successfully.
+#[derive(Debug, Clone)]
+pub struct CachedIdentity {
+    pub user_profile: V1UserProfile,
+    pub scope: Option<ApiKeyScope>,
+    /// API key id, or workload subject, the identity is revoked under.
+    pub api_key_id: Option<String>,
+    expires_at: Instant,
+}
+
+/// Bounded, TTL'd cache of authenticated identities keyed by a hash of the
+/// bearer token, so raw tokens are never held in memory longer than a request.
+pub struct IdentityCache {
+    entries: DashMap<String, CachedIdentity>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl IdentityCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl,
+            max_entries,
+        }
+    }
+
+    pub fn get(&self, token: &str) -> Option<CachedIdentity> {
+        let key = token_hash(token);
+        let entry = self.entries.get(&key)?;
+        if entry.expires_at > Instant::now() {
+            return Some(entry.clone());
+        }
+        drop(entry);
+        self.entries.remove(&key);
+        None
+    }
+
+    /// Cache an identity for the configured TTL, or until `not_after` if sooner.
+    pub fn insert(
+        &self,
+        token: &str,
+        user_profile: V1UserProfile,
+        scope: Option<ApiKeyScope>,
+        api_key_id: Option<String>,
+        not_after: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        if self.ttl.is_zero() || self.max_entries == 0 {
+            return;
+        }
+
+        let mut ttl = self.ttl;
+        if let Some(not_after) = not_after {
+            let remaining = (not_after - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            ttl = ttl.min(remaining);
+        }
+
+        if self.entries.len() >= self.max_entries {
+            self.evict();
+        }
+
+        self.entries.insert(
+            token_hash(token),
+            CachedIdentity {
+                user_profile,
+                scope,
+                api_key_id,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+    }
+
+    /// Drop every cached identity for an API key.
+    pub fn invalidate_api_key(&self, id: &str) {
+        self.entries
+            .retain(|_, entry| entry.api_key_id.as_deref() != Some(id));
+    }
+
+    /// Make room by dropping expired entries, then the ones closest to expiry.
+    fn evict(&self) {
+        let now = Instant::now();
+        self.entries.retain(|_, entry| entry.expires_at > now);
+
+        let excess = (self.entries.len() + 1).saturating_sub(self.max_entries);
+        if excess == 0 {
+            return;
+        }
+        let mut by_expiry: Vec<(String, Instant)> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.expires_at))
+            .collect();
+        by_expiry.sort_by_key(|(_, expires_at)| *expires_at);
+        for (key, _) in by_expiry.into_iter().take(excess) {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+/// Get the global identity cache.
+pub fn identity_cache() -> &'static IdentityCache {
+    &IDENTITY_CACHE
+}
+
+fn token_hash(token: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    digest
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Invalidate an API key locally and tell every other server replica to do the same.
+pub async fn publish_revocation(message_queue: &MessageQueue, api_key_id: &str) {
+    identity_cache().invalidate_api_key(api_key_id);
+
+    let result: Result<(), String> = match message_queue {
+        MessageQueue::Redis { client } => publish_redis(client, api_key_id)
+            .await
+            .map_err(|e| e.to_string()),
+        MessageQueue::Kafka { producer, .. } => producer
+            .send(
+                FutureRecord::to(REVOCATION_CHANNEL)
+                    .key(api_key_id)
+                    .payload(api_key_id),
+                Duration::from_secs(5),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| e.to_string()),
+    };
+
+    if let Err(e) = result {
+        error!(
+            "[Auth] Failed to publish revocation of API key {}: {}",
+            api_key_id, e
+        );
+    }
+}
+
+/// Revoke an API key on every server replica from code without the app state,
+/// such as mutations. Before the revocation listener is spawned, only this
+/// replica's cache is cleared.
+pub async fn broadcast_revocation(api_key_id: &str) {
+    match REVOCATION_QUEUE.get() {
+        Some(message_queue) => publish_revocation(message_queue, api_key_id).await,
+        None => identity_cache().invalidate_api_key(api_key_id),
+    }
+}
+
+async fn publish_redis(client: &redis::Client, api_key_id: &str) -> redis::RedisResult<()> {
+    let mut conn = client.get_multiplexed_async_connection().await?;
+    redis::cmd("PUBLISH")
+        .arg(REVOCATION_CHANNEL)
+        .arg(api_key_id)
+        .query_async::<i64>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+/// Spawn a background task that drops cached identities for API keys revoked
+/// on any server replica.
+pub fn spawn_revocation_listener(message_queue: MessageQueue) -> JoinHandle<()> {
+    let _ = REVOCATION_QUEUE.set(message_queue.clone());
+    tokio::spawn(async move {
+        info!("[Auth] Listening for API key revocations");
+        loop {
+            let result = match &message_queue {
+                MessageQueue::Redis { client } => listen_redis(client).await,
+                MessageQueue::Kafka { .. } => listen_kafka().await,
+            };
+            if let Err(e) = result {
+                warn!("[Auth] Revocation subscription dropped: {}", e);
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    })
+}
+
+async fn listen_redis(
+    client: &redis::Client,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(REVOCATION_CHANNEL).await?;
+    let mut messages = pubsub.on_message();
+
+    while let Some(message) = messages.next().await {
+        let api_key_id: String = message.get_payload()?;
+        debug!("[Auth] API key {} revoked", api_key_id);
+        identity_cache().invalidate_api_key(&api_key_id);
+    }
+    Ok(())
+}
+
+async fn listen_kafka() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // A unique group per replica so every server sees every revocation
+    let consumer: StreamConsumer = KafkaClientConfig::new()
+        .set("bootstrap.servers", &SERVER_CONFIG.kafka.bootstrap_servers)
+        .set("group.id", format!("nebu-auth-{}", uuid::Uuid::new_v4()))
+        .set("auto.offset.reset", "latest")
+        .create()?;
+    consumer.subscribe(&[REVOCATION_CHANNEL])?;
+
+    loop {
+        let message = consumer.recv().await?;
+        if let Some(Ok(api_key_id)) = message.payload_view::<str>() {
+            debug!("[Auth] API key {} revoked", api_key_id);
+            identity_cache().invalidate_api_key(api_key_id);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::prelude::{DateTimeWithTimeZone, Json};
 use sea_orm::DerivePrimaryKey;
 use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter, PrimaryKeyTrait};
 
@@ -11,6 +11,11 @@ pub struct Model {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub rate_limit: Option<Json>,
+    pub label: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub scope: Option<Json>,
+    pub profile: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,4 +1,8 @@
 pub mod api;
+pub mod cache;
 pub mod db;
 pub mod models;
+pub mod oidc;
+pub mod scope;
 pub mod server;
+pub mod workload;
@@ -1,5 +1,108 @@
 use crate::auth::db;
+use crate::auth::scope::kind_matches;
+use crate::models::{V1RateLimit, V1UserProfile};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tracing::warn;
+
+/// A single grant on a scoped API key, e.g. `send` on `processors/my-proc`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyPermission {
+    /// Allowed verbs such as `get`, `list`, `create`, `update`, `delete`,
+    /// `send`, `scale` or `logs`; `*` allows all.
+    pub verbs: Vec<String>,
+    /// Allowed resource kinds such as `containers` or `processors`; `*` allows all.
+    pub kinds: Vec<String>,
+    /// Restrict the grant to these resource names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub names: Option<Vec<String>>,
+}
+
+impl ApiKeyPermission {
+    pub fn allows(&self, verb: &str, kind: &str, name: Option<&str>) -> bool {
+        let verb_ok = self.verbs.iter().any(|v| v == "*" || v == verb);
+        let kind_ok = self.kinds.iter().any(|k| kind_matches(k, kind));
+        let name_ok = match (&self.names, name) {
+            (None, _) => true,
+            (Some(names), Some(name)) => names.iter().any(|n| n == name),
+            (Some(_), None) => false,
+        };
+        verb_ok && kind_ok && name_ok
+    }
+}
+
+/// Parses `verb[,verb]:kind[/name]`, e.g. `get,list:containers` or
+/// `send:processors/my-proc`.
+impl FromStr for ApiKeyPermission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (verbs, target) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid permission '{}', expected verb:kind[/name]", s))?;
+        let (kind, name) = match target.split_once('/') {
+            Some((kind, name)) => (kind, Some(vec![name.to_string()])),
+            None => (target, None),
+        };
+        if verbs.is_empty() || kind.is_empty() {
+            return Err(format!(
+                "Invalid permission '{}', expected verb:kind[/name]",
+                s
+            ));
+        }
+        Ok(Self {
+            verbs: verbs.split(',').map(|v| v.trim().to_string()).collect(),
+            kinds: vec![kind.to_string()],
+            names: name,
+        })
+    }
+}
+
+/// Restrictions on what an API key may do. Keys without a scope are unrestricted.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyScope {
+    /// Namespaces the key may act in; all namespaces when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespaces: Option<Vec<String>>,
+    /// Verbs and kinds the key may use; everything when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<ApiKeyPermission>>,
+}
+
+impl ApiKeyScope {
+    /// A scope that allows nothing, for keys whose stored scope cannot be read.
+    pub fn deny_all() -> Self {
+        Self {
+            namespaces: Some(Vec::new()),
+            permissions: Some(Vec::new()),
+        }
+    }
+
+    pub fn allows_namespace(&self, namespace: Option<&str>) -> bool {
+        match (&self.namespaces, namespace) {
+            (None, _) => true,
+            (Some(allowed), Some(ns)) => allowed.iter().any(|a| a == "*" || a == ns),
+            (Some(_), None) => false,
+        }
+    }
+
+    pub fn allows(&self, verb: &str, kind: &str, name: Option<&str>) -> bool {
+        match &self.permissions {
+            None => true,
+            Some(permissions) => permissions.iter().any(|p| p.allows(verb, kind, name)),
+        }
+    }
+}
+
+/// Options for generating a new API key.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyOptions {
+    pub label: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub scope: Option<ApiKeyScope>,
+    /// Identity the key authenticates as, e.g. a user who logged in with OIDC.
+    pub profile: Option<V1UserProfile>,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ApiKey {
@@ -9,6 +112,11 @@ pub struct ApiKey {
     pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
     pub is_active: bool,
+    pub rate_limit: Option<V1RateLimit>,
+    pub label: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub scope: Option<ApiKeyScope>,
+    pub profile: Option<V1UserProfile>,
 }
 
 impl ApiKey {
@@ -20,19 +128,54 @@ impl ApiKey {
             last_used_at: None,
             revoked_at: None,
             is_active: true,
+            rate_limit: None,
+            label: None,
+            expires_at: None,
+            scope: None,
+            profile: None,
         }
     }
+
+    pub fn with_options(mut self, options: ApiKeyOptions) -> Self {
+        self.label = options.label;
+        self.expires_at = options.expires_at;
+        self.scope = options.scope;
+        self.profile = options.profile;
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= chrono::Utc::now())
+    }
 }
 
 impl From<db::Model> for ApiKey {
     fn from(model: db::Model) -> Self {
+        // A stored scope that no longer parses must not leave the key unrestricted
+        let scope = model.scope.map(|json| {
+            serde_json::from_value(json).unwrap_or_else(|e| {
+                warn!("API key {} has an unreadable scope: {}", model.id, e);
+                ApiKeyScope::deny_all()
+            })
+        });
         Self {
             id: model.id,
             hash: model.hash,
             created_at: model.created_at,
             last_used_at: model.last_used_at,
             revoked_at: model.revoked_at,
-            is_active: model.revoked_at.is_none(),
+            is_active: model.revoked_at.is_none()
+                && model.expires_at.map_or(true, |e| e > chrono::Utc::now()),
+            rate_limit: model
+                .rate_limit
+                .and_then(|json| serde_json::from_value(json).ok()),
+            label: model.label,
+            expires_at: model.expires_at,
+            scope,
+            profile: model
+                .profile
+                .and_then(|json| serde_json::from_value(json).ok()),
         }
     }
 }
@@ -45,6 +188,17 @@ impl From<ApiKey> for db::Model {
             created_at: api_key.created_at,
             last_used_at: api_key.last_used_at,
             revoked_at: api_key.revoked_at,
+            rate_limit: api_key
+                .rate_limit
+                .and_then(|limit| serde_json::to_value(limit).ok()),
+            label: api_key.label,
+            expires_at: api_key.expires_at,
+            scope: api_key
+                .scope
+                .and_then(|scope| serde_json::to_value(scope).ok()),
+            profile: api_key
+                .profile
+                .and_then(|profile| serde_json::to_value(profile).ok()),
         }
     }
 }
@@ -56,6 +210,10 @@ pub struct SanitizedApiKey {
     pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
     pub is_active: bool,
+    pub rate_limit: Option<V1RateLimit>,
+    pub label: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub scope: Option<ApiKeyScope>,
 }
 
 impl From<ApiKey> for SanitizedApiKey {
@@ -66,6 +224,114 @@ impl From<ApiKey> for SanitizedApiKey {
             last_used_at: api_key.last_used_at,
             revoked_at: api_key.revoked_at,
             is_active: api_key.is_active,
+            rate_limit: api_key.rate_limit,
+            label: api_key.label,
+            expires_at: api_key.expires_at,
+            scope: api_key.scope,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn permission(s: &str) -> ApiKeyPermission {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_permission() {
+        assert_eq!(
+            permission("get, list:containers"),
+            ApiKeyPermission {
+                verbs: vec!["get".to_string(), "list".to_string()],
+                kinds: vec!["containers".to_string()],
+                names: None,
+            }
+        );
+        assert_eq!(
+            permission("send:processors/my-proc").names,
+            Some(vec!["my-proc".to_string()])
+        );
+        assert!("containers".parse::<ApiKeyPermission>().is_err());
+        assert!(":containers".parse::<ApiKeyPermission>().is_err());
+        assert!("get:".parse::<ApiKeyPermission>().is_err());
+    }
+
+    #[test]
+    fn test_permission_matches_verb_kind_and_name() {
+        let send = permission("send:processors/my-proc");
+        assert!(send.allows("send", "processors", Some("my-proc")));
+        assert!(!send.allows("send", "processors", Some("other")));
+        assert!(!send.allows("send", "processors", None));
+        assert!(!send.allows("delete", "processors", Some("my-proc")));
+        assert!(!send.allows("send", "containers", Some("my-proc")));
+
+        let all = permission("*:*");
+        assert!(all.allows("delete", "secrets", None));
+    }
+
+    #[test]
+    fn test_permission_kind_singular_and_plural() {
+        assert!(permission("get:policy").allows("get", "policies", None));
+        assert!(permission("get:policies").allows("get", "policies", None));
+        assert!(permission("get:container").allows("get", "containers", None));
+        assert!(permission("delete:api-key").allows("delete", "api-keys", None));
+        assert!(!permission("get:policies").allows("get", "processors", None));
+        // Only exact matches for kinds outside the table
+        assert!(!permission("get:widget").allows("get", "widgets", None));
+    }
+
+    #[test]
+    fn test_scope_namespaces() {
+        let unrestricted = ApiKeyScope::default();
+        assert!(unrestricted.allows_namespace(None));
+        assert!(unrestricted.allows("delete", "containers", None));
+
+        let scoped = ApiKeyScope {
+            namespaces: Some(vec!["team".to_string()]),
+            permissions: Some(vec![permission("get,list:containers")]),
+        };
+        assert!(scoped.allows_namespace(Some("team")));
+        assert!(!scoped.allows_namespace(Some("other")));
+        assert!(!scoped.allows_namespace(None));
+        assert!(scoped.allows("list", "containers", None));
+        assert!(!scoped.allows("delete", "containers", None));
+    }
+
+    #[test]
+    fn test_deny_all_scope() {
+        let scope = ApiKeyScope::deny_all();
+        assert!(!scope.allows_namespace(Some("team")));
+        assert!(!scope.allows_namespace(None));
+        assert!(!scope.allows("get", "containers", None));
+    }
+
+    #[test]
+    fn test_unreadable_stored_scope_denies_all() {
+        let model = db::Model {
+            id: "key".to_string(),
+            hash: "hash".to_string(),
+            created_at: chrono::Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+            rate_limit: None,
+            label: None,
+            expires_at: None,
+            scope: Some(json!({"namespaces": "not-a-list"})),
+            profile: None,
+        };
+        assert_eq!(
+            ApiKey::from(model.clone()).scope,
+            Some(ApiKeyScope::deny_all())
+        );
+
+        let unscoped = db::Model {
+            scope: None,
+            ..model
+        };
+        assert_eq!(ApiKey::from(unscoped).scope, None);
+    }
+}
@@ -0,0 +1,642 @@
+use crate::auth::api::generate_scoped_api_key;
+use crate::auth::models::ApiKeyOptions;
+use crate::config::OidcConfig;
+use crate::models::V1UserProfile;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+
+/// Device-code grant type from RFC 8628.
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// How long a browser login may take between redirect and callback.
+const LOGIN_STATE_TTL: Duration = Duration::from_secs(10 * 60);
+
+static PROVIDER_METADATA: OnceCell<ProviderMetadata> = OnceCell::const_new();
+
+/// Browser logins waiting for their callback, keyed by `state`.
+static PENDING_LOGINS: Lazy<DashMap<String, PendingLogin>> = Lazy::new(DashMap::new);
+
+#[derive(Debug)]
+pub enum OidcError {
+    NotConfigured,
+    /// The user has not finished the device flow yet (`authorization_pending`
+    /// or `slow_down`).
+    Pending(String),
+    InvalidState,
+    InvalidToken(String),
+    Provider(String),
+}
+
+impl fmt::Display for OidcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OidcError::NotConfigured => write!(f, "OIDC login is not configured"),
+            OidcError::Pending(code) => write!(f, "{}", code),
+            OidcError::InvalidState => write!(f, "Unknown or expired login state"),
+            OidcError::InvalidToken(e) => write!(f, "Invalid ID token: {}", e),
+            OidcError::Provider(e) => write!(f, "OIDC provider error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OidcError {}
+
+impl From<reqwest::Error> for OidcError {
+    fn from(e: reqwest::Error) -> Self {
+        OidcError::Provider(e.to_string())
+    }
+}
+
+/// The subset of the provider's discovery document we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub device_authorization_endpoint: Option<String>,
+    pub userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Device authorization returned to the CLI, as defined by RFC 8628.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    pub interval: Option<u64>,
+}
+
+/// Result of a completed login: an API key that authenticates as the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcLoginResponse {
+    pub api_key: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub user_profile: V1UserProfile,
+}
+
+struct PendingLogin {
+    verifier: String,
+    nonce: String,
+    created: Instant,
+}
+
+/// Get the configured provider, failing if OIDC is not enabled.
+pub fn oidc_config() -> Result<&'static OidcConfig, OidcError> {
+    crate::config::SERVER_CONFIG
+        .auth
+        .oidc
+        .as_ref()
+        .ok_or(OidcError::NotConfigured)
+}
+
+/// Fetch and cache the provider's discovery document.
+pub async fn provider_metadata(
+    config: &OidcConfig,
+) -> Result<&'static ProviderMetadata, OidcError> {
+    PROVIDER_METADATA
+        .get_or_try_init(|| async {
+            let url = format!("{}/.well-known/openid-configuration", config.issuer);
+            debug!("[OIDC] Fetching provider metadata from {}", url);
+            let metadata = reqwest::get(&url)
+                .await?
+                .error_for_status()?
+                .json::<ProviderMetadata>()
+                .await?;
+            Ok(metadata)
+        })
+        .await
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Build the provider's authorization URL for a browser login, using PKCE.
+pub async fn authorization_url(config: &OidcConfig) -> Result<String, OidcError> {
+    let metadata = provider_metadata(config).await?;
+    let redirect_url = config
+        .redirect_url
+        .as_deref()
+        .ok_or_else(|| OidcError::Provider("NEBU_OIDC_REDIRECT_URL is not set".to_string()))?;
+
+    let now = Instant::now();
+    PENDING_LOGINS.retain(|_, login| now.duration_since(login.created) < LOGIN_STATE_TTL);
+
+    let state = random_token();
+    let nonce = random_token();
+    let verifier = random_token();
+    let challenge = URL_SAFE_NO_PAD.encode(ring::digest::digest(
+        &ring::digest::SHA256,
+        verifier.as_bytes(),
+    ));
+
+    let mut url = reqwest::Url::parse(&metadata.authorization_endpoint)
+        .map_err(|e| OidcError::Provider(e.to_string()))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", redirect_url)
+        .append_pair("scope", &config.scopes)
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    PENDING_LOGINS.insert(
+        state,
+        PendingLogin {
+            verifier,
+            nonce,
+            created: now,
+        },
+    );
+    Ok(url.to_string())
+}
+
+/// Complete a browser login by exchanging the authorization code.
+pub async fn exchange_code(
+    config: &OidcConfig,
+    code: &str,
+    state: &str,
+) -> Result<V1UserProfile, OidcError> {
+    let (_, login) = PENDING_LOGINS
+        .remove(state)
+        .filter(|(_, login)| login.created.elapsed() < LOGIN_STATE_TTL)
+        .ok_or(OidcError::InvalidState)?;
+
+    let redirect_url = config.redirect_url.clone().unwrap_or_default();
+    let mut form = vec![
+        ("grant_type", "authorization_code".to_string()),
+        ("code", code.to_string()),
+        ("redirect_uri", redirect_url),
+        ("code_verifier", login.verifier),
+    ];
+    let tokens = request_tokens(config, &mut form).await?;
+    profile_from_tokens(config, &tokens, Some(&login.nonce)).await
+}
+
+/// Start a device-code login for the CLI.
+pub async fn start_device_authorization(
+    config: &OidcConfig,
+) -> Result<DeviceAuthorization, OidcError> {
+    let metadata = provider_metadata(config).await?;
+    let endpoint = metadata
+        .device_authorization_endpoint
+        .as_deref()
+        .ok_or_else(|| {
+            OidcError::Provider("Provider does not support the device flow".to_string())
+        })?;
+
+    let response = reqwest::Client::new()
+        .post(endpoint)
+        .form(&[
+            ("client_id", config.client_id.as_str()),
+            ("scope", config.scopes.as_str()),
+        ])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let error = response.json::<TokenErrorResponse>().await?;
+        return Err(OidcError::Provider(
+            error.error_description.unwrap_or(error.error),
+        ));
+    }
+    Ok(response.json::<DeviceAuthorization>().await?)
+}
+
+/// Check once whether the user has approved a device-code login.
+pub async fn poll_device_token(
+    config: &OidcConfig,
+    device_code: &str,
+) -> Result<V1UserProfile, OidcError> {
+    let mut form = vec![
+        ("grant_type", DEVICE_CODE_GRANT.to_string()),
+        ("device_code", device_code.to_string()),
+    ];
+    let tokens = request_tokens(config, &mut form).await?;
+    profile_from_tokens(config, &tokens, None).await
+}
+
+async fn request_tokens(
+    config: &OidcConfig,
+    form: &mut Vec<(&str, String)>,
+) -> Result<TokenResponse, OidcError> {
+    let metadata = provider_metadata(config).await?;
+    form.push(("client_id", config.client_id.clone()));
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret.clone()));
+    }
+
+    let response = reqwest::Client::new()
+        .post(&metadata.token_endpoint)
+        .form(form)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let error = response.json::<TokenErrorResponse>().await?;
+        return Err(match error.error.as_str() {
+            "authorization_pending" | "slow_down" => OidcError::Pending(error.error),
+            _ => OidcError::Provider(error.error_description.unwrap_or(error.error)),
+        });
+    }
+    Ok(response.json::<TokenResponse>().await?)
+}
+
+async fn profile_from_tokens(
+    config: &OidcConfig,
+    tokens: &TokenResponse,
+    nonce: Option<&str>,
+) -> Result<V1UserProfile, OidcError> {
+    let metadata = provider_metadata(config).await?;
+    let id_token = tokens
+        .id_token
+        .as_deref()
+        .ok_or_else(|| OidcError::InvalidToken("no id_token in response".to_string()))?;
+    let mut claims = validate_id_token(config, metadata, id_token, nonce)?;
+
+    // Some providers only put profile claims on the userinfo endpoint
+    if claims.get("email").is_none() {
+        if let Some(userinfo_endpoint) = &metadata.userinfo_endpoint {
+            let userinfo = reqwest::Client::new()
+                .get(userinfo_endpoint)
+                .bearer_auth(&tokens.access_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Value>()
+                .await?;
+            if let (Some(claims), Value::Object(userinfo)) = (claims.as_object_mut(), userinfo) {
+                for (key, value) in userinfo {
+                    claims.entry(key).or_insert(value);
+                }
+            }
+        }
+    }
+
+    profile_from_claims(config, &claims)
+}
+
+/// Validate the claims of an ID token received directly from the token
+/// endpoint. The TLS connection to the provider authenticates the token, so
+/// the signature is not checked (OpenID Connect Core 3.1.3.7).
+fn validate_id_token(
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+    nonce: Option<&str>,
+) -> Result<Value, OidcError> {
+    let payload = id_token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| OidcError::InvalidToken("malformed token".to_string()))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+    let claims: Value =
+        serde_json::from_slice(&payload).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+
+    if claims.get("iss").and_then(Value::as_str) != Some(metadata.issuer.as_str()) {
+        return Err(OidcError::InvalidToken("issuer mismatch".to_string()));
+    }
+
+    let audience_ok = match claims.get("aud") {
+        Some(Value::String(aud)) => aud == &config.client_id,
+        Some(Value::Array(auds)) => auds.iter().any(|a| a.as_str() == Some(&config.client_id)),
+        _ => false,
+    };
+    if !audience_ok {
+        return Err(OidcError::InvalidToken("audience mismatch".to_string()));
+    }
+
+    let expires = claims.get("exp").and_then(Value::as_i64).unwrap_or(0);
+    if expires <= chrono::Utc::now().timestamp() {
+        return Err(OidcError::InvalidToken("token expired".to_string()));
+    }
+
+    if let Some(nonce) = nonce {
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(OidcError::InvalidToken("nonce mismatch".to_string()));
+        }
+    }
+
+    Ok(claims)
+}
+
+/// Map ID token claims onto a `V1UserProfile`.
+///
+/// The orgs claim may be a list of org ids, a map of org id to role, or a map
+/// of org id to `{"org_name", "org_role"}`. The primary organization is the
+/// one in the configured org claim, else the first listed org, or for a map
+/// the lowest org id.
+pub fn profile_from_claims(
+    config: &OidcConfig,
+    claims: &Value,
+) -> Result<V1UserProfile, OidcError> {
+    let claim = |name: &str| claims.get(name).and_then(Value::as_str).map(String::from);
+
+    let email =
+        claim("email").ok_or_else(|| OidcError::InvalidToken("missing email claim".to_string()))?;
+    if claims.get("email_verified") == Some(&Value::Bool(false)) {
+        return Err(OidcError::InvalidToken("email is not verified".to_string()));
+    }
+
+    let role = match claims.get(&config.role_claim) {
+        Some(Value::String(role)) => Some(role.clone()),
+        Some(Value::Array(roles)) => roles.first().and_then(Value::as_str).map(String::from),
+        _ => None,
+    };
+    let default_org_role = role.clone().unwrap_or_else(|| "member".to_string());
+
+    let org_entry = |name: &str, role: &str| {
+        HashMap::from([
+            ("org_name".to_string(), name.to_string()),
+            ("org_role".to_string(), role.to_string()),
+        ])
+    };
+    let organizations: Option<HashMap<String, HashMap<String, String>>> =
+        match claims.get(&config.orgs_claim) {
+            Some(Value::Array(orgs)) => Some(
+                orgs.iter()
+                    .filter_map(Value::as_str)
+                    .map(|org| (org.to_string(), org_entry(org, &default_org_role)))
+                    .collect(),
+            ),
+            Some(Value::Object(orgs)) => Some(
+                orgs.iter()
+                    .map(|(org, value)| {
+                        let entry = match value {
+                            Value::String(role) => org_entry(org, role),
+                            Value::Object(fields) => fields
+                                .iter()
+                                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                                .collect(),
+                            _ => org_entry(org, &default_org_role),
+                        };
+                        (org.clone(), entry)
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+    let organization = config.org_claim.as_deref().and_then(claim).or_else(|| {
+        match claims.get(&config.orgs_claim) {
+            Some(Value::Array(orgs)) => orgs.iter().find_map(Value::as_str).map(String::from),
+            Some(Value::Object(orgs)) => orgs.keys().min().cloned(),
+            _ => None,
+        }
+    });
+
+    Ok(V1UserProfile {
+        email,
+        display_name: claim("name"),
+        handle: claim("preferred_username"),
+        picture: claim("picture"),
+        organization,
+        role,
+        external_id: claim("sub"),
+        actor: None,
+        organizations,
+        created: None,
+        updated: claims.get("updated_at").and_then(Value::as_i64),
+        token: None,
+    })
+}
+
+/// Issue an API key that authenticates as `user_profile` for the session lifetime.
+pub async fn issue_session_key(
+    db: &DatabaseConnection,
+    config: &OidcConfig,
+    user_profile: V1UserProfile,
+) -> Result<OidcLoginResponse, Box<dyn std::error::Error>> {
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(config.session_ttl_secs as i64);
+    let options = ApiKeyOptions {
+        label: Some(format!("oidc:{}", user_profile.email)),
+        expires_at: Some(expires_at),
+        scope: None,
+        profile: Some(user_profile.clone()),
+    };
+    let api_key = generate_scoped_api_key(db, options).await?;
+    info!("[OIDC] Issued session key for {}", user_profile.email);
+
+    Ok(OidcLoginResponse {
+        api_key,
+        expires_at,
+        user_profile,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::get, routing::post, Json, Router};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    fn config(issuer: &str) -> OidcConfig {
+        OidcConfig {
+            issuer: issuer.to_string(),
+            client_id: "nebu".to_string(),
+            client_secret: None,
+            redirect_url: Some("http://localhost/auth/oidc/callback".to_string()),
+            scopes: "openid email profile".to_string(),
+            orgs_claim: "orgs".to_string(),
+            role_claim: "role".to_string(),
+            org_claim: None,
+            session_ttl_secs: 3600,
+        }
+    }
+
+    fn id_token(claims: &Value) -> String {
+        format!(
+            "{}.{}.signature",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn test_profile_from_claims() {
+        let claims = json!({
+            "sub": "user-1",
+            "email": "dev@example.com",
+            "email_verified": true,
+            "name": "Dev",
+            "preferred_username": "dev",
+            "role": ["admin", "member"],
+            "orgs": ["org-b", "org-a"],
+        });
+        let profile = profile_from_claims(&config("https://idp"), &claims).unwrap();
+        assert_eq!(profile.email, "dev@example.com");
+        assert_eq!(profile.handle.as_deref(), Some("dev"));
+        assert_eq!(profile.external_id.as_deref(), Some("user-1"));
+        assert_eq!(profile.role.as_deref(), Some("admin"));
+        // The first listed org is primary, whatever order the map iterates in
+        assert_eq!(profile.organization.as_deref(), Some("org-b"));
+        let orgs = profile.organizations.unwrap();
+        assert_eq!(orgs["org-a"]["org_role"], "admin");
+        assert_eq!(orgs["org-b"]["org_name"], "org-b");
+    }
+
+    #[test]
+    fn test_profile_org_maps_and_primary_org() {
+        let claims = json!({
+            "email": "dev@example.com",
+            "orgs": {
+                "org-z": "owner",
+                "org-m": {"org_name": "Team M", "org_role": "member"},
+            },
+            "org_id": "org-z",
+        });
+        let mut config = config("https://idp");
+        let profile = profile_from_claims(&config, &claims).unwrap();
+        assert_eq!(profile.organization.as_deref(), Some("org-m"));
+        let orgs = profile.organizations.unwrap();
+        assert_eq!(orgs["org-z"]["org_role"], "owner");
+        assert_eq!(orgs["org-m"]["org_name"], "Team M");
+
+        config.org_claim = Some("org_id".to_string());
+        let profile = profile_from_claims(&config, &claims).unwrap();
+        assert_eq!(profile.organization.as_deref(), Some("org-z"));
+    }
+
+    #[test]
+    fn test_profile_requires_verified_email() {
+        let config = config("https://idp");
+        assert!(profile_from_claims(&config, &json!({"sub": "user-1"})).is_err());
+        let unverified = json!({"email": "dev@example.com", "email_verified": false});
+        assert!(profile_from_claims(&config, &unverified).is_err());
+    }
+
+    #[derive(Clone)]
+    struct MockIdp {
+        issuer: String,
+        claims: Arc<Mutex<Value>>,
+    }
+
+    async fn discovery(State(idp): State<MockIdp>) -> Json<Value> {
+        Json(json!({
+            "issuer": idp.issuer,
+            "authorization_endpoint": format!("{}/authorize", idp.issuer),
+            "token_endpoint": format!("{}/token", idp.issuer),
+        }))
+    }
+
+    async fn token(State(idp): State<MockIdp>) -> Json<Value> {
+        let claims = idp.claims.lock().unwrap().clone();
+        Json(json!({"access_token": "access", "id_token": id_token(&claims)}))
+    }
+
+    /// Start a browser login against the mock provider, returning its state
+    /// and nonce.
+    async fn start_login(config: &OidcConfig) -> (String, String) {
+        let url = reqwest::Url::parse(&authorization_url(config).await.unwrap()).unwrap();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+                .unwrap()
+        };
+        (param("state"), param("nonce"))
+    }
+
+    #[tokio::test]
+    async fn test_login_validates_id_token_against_mock_provider() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let idp = MockIdp {
+            issuer: issuer.clone(),
+            claims: Arc::new(Mutex::new(Value::Null)),
+        };
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = config(&issuer);
+        let valid_claims = |nonce: &str| {
+            json!({
+                "iss": issuer,
+                "aud": ["other", "nebu"],
+                "exp": chrono::Utc::now().timestamp() + 300,
+                "nonce": nonce,
+                "sub": "user-1",
+                "email": "dev@example.com",
+            })
+        };
+        let login_with = |change: fn(&mut Value)| {
+            let config = config.clone();
+            let idp = idp.clone();
+            async move {
+                let (state, nonce) = start_login(&config).await;
+                let mut claims = valid_claims(&nonce);
+                change(&mut claims);
+                *idp.claims.lock().unwrap() = claims;
+                exchange_code(&config, "code", &state).await
+            }
+        };
+
+        let profile = login_with(|_| {}).await.unwrap();
+        assert_eq!(profile.email, "dev@example.com");
+
+        let rejected = [
+            (
+                "issuer mismatch",
+                login_with(|c| c["iss"] = json!("https://evil")).await,
+            ),
+            (
+                "audience mismatch",
+                login_with(|c| c["aud"] = json!("other")).await,
+            ),
+            (
+                "nonce mismatch",
+                login_with(|c| c["nonce"] = json!("replayed")).await,
+            ),
+            ("token expired", login_with(|c| c["exp"] = json!(1)).await),
+        ];
+        for (reason, result) in rejected {
+            match result {
+                Err(OidcError::InvalidToken(e)) => assert_eq!(e, reason),
+                other => panic!("expected {}, got {:?}", reason, other),
+            }
+        }
+
+        // A login state can only be used once
+        let (state, nonce) = start_login(&config).await;
+        *idp.claims.lock().unwrap() = valid_claims(&nonce);
+        assert!(exchange_code(&config, "code", &state).await.is_ok());
+        assert!(matches!(
+            exchange_code(&config, "code", &state).await,
+            Err(OidcError::InvalidState)
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use axum::http::Method;
+
+/// Kinds whose routes are `/v1/<group>/<kind>/...` rather than `/v1/<kind>/...`.
+const GROUPED_KINDS: &[&str] = &["auth", "billing"];
+
+/// Resource kinds as `(plural, singular)`. Scopes and policy rules may name a
+/// kind either way; both are matched as the plural used in request paths.
+const KINDS: &[(&str, &str)] = &[
+    ("admin", "admin"),
+    ("api-keys", "api-key"),
+    ("audit", "audit"),
+    ("budgets", "budget"),
+    ("cache", "cache"),
+    ("containers", "container"),
+    ("gc", "gc"),
+    ("invoices", "invoice"),
+    ("namespaces", "namespace"),
+    ("policies", "policy"),
+    ("price-books", "price-book"),
+    ("processors", "processor"),
+    ("rolebindings", "rolebinding"),
+    ("roles", "role"),
+    ("secrets", "secret"),
+    ("usage", "usage"),
+    ("users", "user"),
+    ("volumes", "volume"),
+    ("workload-token", "workload-token"),
+];
+
+/// Creates that take their namespace from the body's `metadata.namespace`,
+/// defaulting to the caller's own namespace.
+pub const NAMESPACED_CREATE_KINDS: &[&str] = &[
+    "containers",
+    "policies",
+    "processors",
+    "rolebindings",
+    "roles",
+    "secrets",
+    "volumes",
+];
+
+/// The plural form of a known kind, or `kind` itself if it is unknown.
+pub fn canonical_kind(kind: &str) -> &str {
+    KINDS
+        .iter()
+        .find(|(plural, singular)| kind == *plural || kind == *singular)
+        .map_or(kind, |(plural, _)| plural)
+}
+
+/// Whether `pattern` from a scope or rule names `kind`; `*` matches every kind.
+pub fn kind_matches(pattern: &str, kind: &str) -> bool {
+    pattern == "*" || canonical_kind(pattern) == canonical_kind(kind)
+}
+
+/// What an API request does, as checked against an API key's scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopedRequest {
+    pub verb: String,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Work out the verb, kind, namespace and name for a request path, e.g.
+/// `POST /v1/processors/ns/proc/messages` is `send` on `processors` `ns/proc`.
+///
+/// Namespaced routes are `/<kind>/<namespace>/<name>/...`; single id routes
+/// such as `/v1/containers/:id` have no namespace in the path.
+pub fn classify_request(method: &Method, path: &str) -> ScopedRequest {
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.first() == Some(&"v1") {
+        segments.remove(0);
+    }
+    if segments.len() > 1 && GROUPED_KINDS.contains(&segments[0]) {
+        segments.remove(0);
+    }
+
+    let kind = segments.first().copied().unwrap_or_default().to_string();
+    let rest = segments.get(1..).unwrap_or_default();
+
+    // Routes keyed by a single id, like `/containers/:id/logs` or `/invoices/:id`
+    let id_route = match rest {
+        [_] => kind != "namespaces",
+        [_, "logs", ..] => kind == "containers",
+        _ => false,
+    };
+
+    let (namespace, name, subresource) = match rest {
+        [] => (None, None, None),
+        ["search"] if kind == "containers" => (None, None, Some("search")),
+        ["dangling"] if kind == "gc" => (None, None, Some("dangling")),
+        [ns] if kind == "namespaces" => (Some(ns.to_string()), Some(ns.to_string()), None),
+        [ns, sub, ..] if kind == "namespaces" => {
+            (Some(ns.to_string()), Some(ns.to_string()), Some(*sub))
+        }
+        [id, sub @ ..] if id_route => (None, Some(id.to_string()), sub.first().copied()),
+        [ns, name, sub @ ..] => (
+            Some(ns.to_string()),
+            Some(name.to_string()),
+            sub.first().copied(),
+        ),
+        [id] => (None, Some(id.to_string()), None),
+    };
+
+    let verb = match (subresource, method) {
+        (Some("search"), _) => "list",
+        (Some("messages" | "ws"), _) => "send",
+        (Some("scale"), _) => "scale",
+        (Some("logs"), _) => "logs",
+        (Some("rollback"), _) => "update",
+        (Some("grants" | "transfer"), m) if *m != Method::GET => "update",
+        (_, &Method::GET) if name.is_none() => "list",
+        (_, &Method::POST) if name.is_none() => "create",
+        (_, &Method::PUT | &Method::PATCH) => "update",
+        (_, &Method::DELETE) => "delete",
+        _ => "get",
+    };
+
+    ScopedRequest {
+        verb: verb.to_string(),
+        kind,
+        namespace,
+        name,
+    }
+}
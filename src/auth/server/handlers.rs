@@ -1,5 +1,6 @@
 use crate::auth;
-use crate::auth::models::SanitizedApiKey;
+use crate::auth::models::{ApiKeyOptions, ApiKeyScope, SanitizedApiKey};
+use crate::models::V1RateLimit;
 use crate::state::AppState;
 use axum::extract::{Json, Path, State};
 use axum::http::StatusCode;
@@ -10,6 +11,20 @@ pub struct ApiKeyRequest {
     pub id: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ApiKeyRateLimitRequest {
+    pub id: String,
+    pub rate_limit: Option<V1RateLimit>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GenerateApiKeyRequest {
+    pub label: Option<String>,
+    /// How long the key is valid for, e.g. `30d`. Never expires when unset.
+    pub expires_in: Option<String>,
+    pub scope: Option<ApiKeyScope>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RawApiKeyResponse {
     pub api_key: String,
@@ -63,15 +78,77 @@ pub async fn generate_api_key(
     }
 }
 
+pub async fn generate_scoped_api_key(
+    State(state): State<AppState>,
+    Json(request): Json<GenerateApiKeyRequest>,
+) -> Result<Json<RawApiKeyResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let expires_at = match request.expires_in.as_deref().map(humantime::parse_duration) {
+        Some(Ok(duration)) => Some(
+            chrono::Utc::now()
+                + chrono::Duration::from_std(duration).map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": "expires_in is too large"})),
+                    )
+                })?,
+        ),
+        Some(Err(e)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("Invalid expires_in: {}", e)})),
+            ))
+        }
+        None => None,
+    };
+
+    let options = ApiKeyOptions {
+        label: request.label,
+        expires_at,
+        scope: request.scope,
+        ..Default::default()
+    };
+    match auth::api::generate_scoped_api_key(&state.db_pool, options).await {
+        Ok(api_key) => Ok(Json(RawApiKeyResponse::new(api_key))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Failed to generate API key"})),
+        )),
+    }
+}
+
 pub async fn revoke_api_key(
     State(state): State<AppState>,
     Json(api_key): Json<ApiKeyRequest>,
 ) -> Result<Json<SanitizedApiKey>, (StatusCode, Json<serde_json::Value>)> {
     match auth::api::revoke_api_key(&state.db_pool, &api_key.id).await {
-        Ok(api_key) => Ok(Json(SanitizedApiKey::from(api_key))),
+        Ok(api_key) => {
+            auth::cache::publish_revocation(&state.message_queue, &api_key.id).await;
+            Ok(Json(SanitizedApiKey::from(api_key)))
+        }
         Err(_) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({"error": "Failed to revoke API key"})),
         )),
     }
 }
+
+pub async fn set_api_key_rate_limit(
+    State(state): State<AppState>,
+    Json(request): Json<ApiKeyRateLimitRequest>,
+) -> Result<Json<SanitizedApiKey>, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(limit) = &request.rate_limit {
+        if limit.requests_per_second <= 0.0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "requests_per_second must be greater than 0"})),
+            ));
+        }
+    }
+    match auth::api::set_api_key_rate_limit(&state.db_pool, &request.id, request.rate_limit).await {
+        Ok(api_key) => Ok(Json(api_key)),
+        Err(_) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "API key not found"})),
+        )),
+    }
+}
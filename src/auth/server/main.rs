@@ -1,4 +1,8 @@
-use crate::auth::server::handlers::{generate_api_key, get_api_key, list_api_keys, revoke_api_key};
+use crate::auth::server::handlers::{
+    generate_api_key, generate_scoped_api_key, get_api_key, list_api_keys, revoke_api_key,
+    set_api_key_rate_limit,
+};
+use crate::auth::server::oidc::oidc_routes;
 use crate::state::AppState;
 use axum::routing::{get, post};
 use axum::Router;
@@ -12,8 +16,13 @@ pub async fn start_auth_server(app_state: AppState, port: u16) -> std::io::Resul
         .route("/health", get(health_check))
         .route("/api-keys", get(list_api_keys))
         .route("/api-key/:id", get(get_api_key))
-        .route("/api-key/generate", get(generate_api_key))
+        .route(
+            "/api-key/generate",
+            get(generate_api_key).post(generate_scoped_api_key),
+        )
         .route("/api-key/revoke", post(revoke_api_key))
+        .route("/api-key/rate-limit", post(set_api_key_rate_limit))
+        .merge(oidc_routes())
         .layer(TraceLayer::new_for_http())
         .with_state(app_state);
 
@@ -1,3 +1,5 @@
 pub mod handlers;
 
 pub mod main;
+
+pub mod oidc;
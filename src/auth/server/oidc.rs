@@ -0,0 +1,102 @@
+use crate::auth::oidc::{self, DeviceAuthorization, OidcError, OidcLoginResponse};
+use crate::state::AppState;
+use axum::extract::{Json, Query, State};
+use axum::http::StatusCode;
+use axum::response::Redirect;
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, warn};
+
+#[derive(Serialize, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceTokenRequest {
+    pub device_code: String,
+}
+
+fn oidc_error(e: OidcError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        OidcError::NotConfigured => StatusCode::NOT_FOUND,
+        // Follows RFC 8628: clients keep polling on these
+        OidcError::Pending(code) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": code })));
+        }
+        OidcError::InvalidState | OidcError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+        OidcError::Provider(_) => StatusCode::BAD_GATEWAY,
+    };
+    warn!("[OIDC] Login failed: {}", e);
+    (status, Json(json!({ "error": e.to_string() })))
+}
+
+async fn issue_session_key(
+    state: &AppState,
+    user_profile: crate::models::V1UserProfile,
+) -> Result<Json<OidcLoginResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let config = oidc::oidc_config().map_err(oidc_error)?;
+    let result = oidc::issue_session_key(&state.db_pool, config, user_profile)
+        .await
+        .map_err(|e| e.to_string());
+    match result {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            error!("[OIDC] Failed to issue session key: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to generate API key" })),
+            ))
+        }
+    }
+}
+
+pub async fn oidc_login() -> Result<Redirect, (StatusCode, Json<serde_json::Value>)> {
+    let config = oidc::oidc_config().map_err(oidc_error)?;
+    let url = oidc::authorization_url(config).await.map_err(oidc_error)?;
+    Ok(Redirect::to(&url))
+}
+
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Json<OidcLoginResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let config = oidc::oidc_config().map_err(oidc_error)?;
+    let user_profile = oidc::exchange_code(config, &query.code, &query.state)
+        .await
+        .map_err(oidc_error)?;
+    issue_session_key(&state, user_profile).await
+}
+
+pub async fn oidc_device_start(
+) -> Result<Json<DeviceAuthorization>, (StatusCode, Json<serde_json::Value>)> {
+    let config = oidc::oidc_config().map_err(oidc_error)?;
+    let authorization = oidc::start_device_authorization(config)
+        .await
+        .map_err(oidc_error)?;
+    Ok(Json(authorization))
+}
+
+pub async fn oidc_device_token(
+    State(state): State<AppState>,
+    Json(request): Json<DeviceTokenRequest>,
+) -> Result<Json<OidcLoginResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let config = oidc::oidc_config().map_err(oidc_error)?;
+    let user_profile = oidc::poll_device_token(config, &request.device_code)
+        .await
+        .map_err(oidc_error)?;
+    issue_session_key(&state, user_profile).await
+}
+
+/// Unauthenticated login routes, served by the auth server and under `/auth`
+/// on the API server.
+pub fn oidc_routes() -> Router<AppState> {
+    Router::new()
+        .route("/oidc/login", get(oidc_login))
+        .route("/oidc/callback", get(oidc_callback))
+        .route("/oidc/device", post(oidc_device_start))
+        .route("/oidc/device/token", post(oidc_device_token))
+}
@@ -0,0 +1,271 @@
+use crate::auth::models::ApiKeyScope;
+use crate::config::SERVER_CONFIG;
+use crate::entities::containers;
+use crate::models::V1UserProfile;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use once_cell::sync::Lazy;
+use ring::hmac;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Prefix of container workload tokens, `nwt.<claims>.<signature>`.
+pub const WORKLOAD_TOKEN_PREFIX: &str = "nwt.";
+
+/// Prefix of the identity a workload token authenticates as, `container:<id>`.
+const SUBJECT_PREFIX: &str = "container:";
+
+/// Where a container's startup script keeps its current workload token.
+pub const TOKEN_FILE: &str = "/nebu/credentials/api-key";
+
+/// The current workload token as an `Authorization` header value, read by the
+/// AWS container credentials provider.
+pub const AWS_AUTHORIZATION_FILE: &str = "/nebu/credentials/aws-authorization";
+
+static SIGNING_KEY: Lazy<Option<hmac::Key>> = Lazy::new(|| {
+    let secret = SERVER_CONFIG.auth.workload_token_secret.as_ref()?;
+    Some(signing_key(secret))
+});
+
+/// Derive a dedicated key rather than signing with the configured secret directly.
+fn signing_key(secret: &str) -> hmac::Key {
+    let root = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let derived = hmac::sign(&root, b"nebu-workload-token");
+    hmac::Key::new(hmac::HMAC_SHA256, derived.as_ref())
+}
+
+/// Claims carried by a workload token, binding it to one container.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkloadClaims {
+    /// Container id
+    pub sub: String,
+    pub namespace: String,
+    pub owner: String,
+    pub created_by: Option<String>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadToken {
+    pub token: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Whether containers get workload tokens instead of agent keys.
+pub fn enabled() -> bool {
+    SIGNING_KEY.is_some()
+}
+
+pub fn is_workload_token(token: &str) -> bool {
+    token.starts_with(WORKLOAD_TOKEN_PREFIX)
+}
+
+/// The identity a container's workload tokens are cached and revoked under.
+pub fn subject(container_id: &str) -> String {
+    format!("{}{}", SUBJECT_PREFIX, container_id)
+}
+
+pub fn is_subject(id: &str) -> bool {
+    id.starts_with(SUBJECT_PREFIX)
+}
+
+/// Issue a short-lived token bound to a container's id, namespace and owner.
+pub fn issue_workload_token(container: &containers::Model) -> Result<WorkloadToken, String> {
+    let key = SIGNING_KEY
+        .as_ref()
+        .ok_or_else(|| "Workload tokens are not configured".to_string())?;
+
+    let now = chrono::Utc::now();
+    let expires_at =
+        now + chrono::Duration::seconds(SERVER_CONFIG.auth.workload_token_ttl_secs as i64);
+    let claims = WorkloadClaims {
+        sub: container.id.clone(),
+        namespace: container.namespace.clone(),
+        owner: container.owner.clone(),
+        created_by: container.created_by.clone(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    Ok(WorkloadToken {
+        token: sign_claims(key, &claims)?,
+        expires_at,
+    })
+}
+
+fn sign_claims(key: &hmac::Key, claims: &WorkloadClaims) -> Result<String, String> {
+    let payload = serde_json::to_vec(claims).map_err(|e| e.to_string())?;
+    let payload = URL_SAFE_NO_PAD.encode(payload);
+    let signature = hmac::sign(key, payload.as_bytes());
+    Ok(format!(
+        "{}{}.{}",
+        WORKLOAD_TOKEN_PREFIX,
+        payload,
+        URL_SAFE_NO_PAD.encode(signature.as_ref())
+    ))
+}
+
+/// Check a workload token's signature and expiry and return its claims.
+pub fn verify_workload_token(token: &str) -> Result<WorkloadClaims, String> {
+    let key = SIGNING_KEY
+        .as_ref()
+        .ok_or_else(|| "Workload tokens are not configured".to_string())?;
+    verify_claims(key, token)
+}
+
+fn verify_claims(key: &hmac::Key, token: &str) -> Result<WorkloadClaims, String> {
+    let (payload, signature) = token
+        .strip_prefix(WORKLOAD_TOKEN_PREFIX)
+        .and_then(|rest| rest.split_once('.'))
+        .ok_or_else(|| "Malformed workload token".to_string())?;
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| "Malformed workload token".to_string())?;
+    hmac::verify(key, payload.as_bytes(), &signature)
+        .map_err(|_| "Invalid workload token signature".to_string())?;
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| "Malformed workload token".to_string())?;
+    let claims: WorkloadClaims =
+        serde_json::from_slice(&payload).map_err(|_| "Malformed workload token".to_string())?;
+
+    if claims.exp <= chrono::Utc::now().timestamp() {
+        return Err("Workload token expired".to_string());
+    }
+    Ok(claims)
+}
+
+/// Authenticate a workload token. Tokens stop working as soon as their
+/// container is deleted.
+pub async fn authenticate_workload_token(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<(WorkloadClaims, containers::Model), String> {
+    let claims = verify_workload_token(token)?;
+    let container = containers::Entity::find_by_id(claims.sub.clone())
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Container {} no longer exists", claims.sub))?;
+
+    if container.namespace != claims.namespace || container.owner != claims.owner {
+        return Err(format!("Container {} has changed owner", claims.sub));
+    }
+    Ok((claims, container))
+}
+
+/// The profile a workload acts as: the container's creator, on behalf of its owner.
+pub fn workload_profile(claims: &WorkloadClaims) -> V1UserProfile {
+    V1UserProfile {
+        actor: Some(subject(&claims.sub)),
+        ..delegated_profile(&claims.owner, claims.created_by.as_deref())
+    }
+}
+
+/// The profile of `created_by` acting on behalf of `owner`, for controllers
+/// that act for a resource's creator without one of their tokens.
+pub fn delegated_profile(owner: &str, created_by: Option<&str>) -> V1UserProfile {
+    let email = created_by.unwrap_or(owner).to_string();
+    let organizations = (email != owner).then(|| {
+        HashMap::from([(
+            owner.to_string(),
+            HashMap::from([
+                ("org_name".to_string(), owner.to_string()),
+                ("org_role".to_string(), "member".to_string()),
+            ]),
+        )])
+    });
+
+    V1UserProfile {
+        email,
+        display_name: None,
+        handle: None,
+        picture: None,
+        organization: organizations.as_ref().map(|_| owner.to_string()),
+        role: None,
+        external_id: None,
+        actor: None,
+        organizations,
+        created: None,
+        updated: None,
+        token: None,
+    }
+}
+
+/// Workloads may only act within their container's namespace.
+pub fn workload_scope(claims: &WorkloadClaims) -> ApiKeyScope {
+    ApiKeyScope {
+        namespaces: Some(vec![claims.namespace.clone()]),
+        permissions: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(exp: i64) -> WorkloadClaims {
+        WorkloadClaims {
+            sub: "container-1".to_string(),
+            namespace: "team".to_string(),
+            owner: "org-1".to_string(),
+            created_by: Some("dev@example.com".to_string()),
+            iat: 0,
+            exp,
+        }
+    }
+
+    fn valid_claims() -> WorkloadClaims {
+        claims(chrono::Utc::now().timestamp() + 300)
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let key = signing_key("secret");
+        let token = sign_claims(&key, &valid_claims()).unwrap();
+        assert!(is_workload_token(&token));
+        assert_eq!(verify_claims(&key, &token).unwrap(), valid_claims());
+    }
+
+    #[test]
+    fn test_rejects_other_key_and_tampering() {
+        let token = sign_claims(&signing_key("secret"), &valid_claims()).unwrap();
+        assert!(verify_claims(&signing_key("other"), &token).is_err());
+
+        // Swap in claims for another namespace, keeping the old signature
+        let mut forged = valid_claims();
+        forged.namespace = "victim".to_string();
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let forged = format!("{}{}.{}", WORKLOAD_TOKEN_PREFIX, forged_payload, signature);
+        assert!(verify_claims(&signing_key("secret"), &forged).is_err());
+
+        assert!(verify_claims(&signing_key("secret"), "nwt.garbage").is_err());
+    }
+
+    #[test]
+    fn test_rejects_expired_token() {
+        let key = signing_key("secret");
+        let token = sign_claims(&key, &claims(chrono::Utc::now().timestamp() - 1)).unwrap();
+        assert_eq!(
+            verify_claims(&key, &token).unwrap_err(),
+            "Workload token expired"
+        );
+    }
+
+    #[test]
+    fn test_workload_profile_and_scope() {
+        let claims = valid_claims();
+        let profile = workload_profile(&claims);
+        assert_eq!(profile.email, "dev@example.com");
+        assert_eq!(profile.organization.as_deref(), Some("org-1"));
+        assert_eq!(profile.actor, Some(subject("container-1")));
+
+        let scope = workload_scope(&claims);
+        assert!(scope.allows_namespace(Some("team")));
+        assert!(!scope.allows_namespace(Some("other")));
+    }
+}
@@ -0,0 +1,432 @@
+use crate::billing::invoice::build_line_items;
+use crate::billing::models::V1PriceBook;
+use crate::config::SERVER_CONFIG;
+use crate::entities::{budgets, containers, namespaces, price_books, processors};
+use crate::meters::ledger::{window_bounds, UsageGrouping};
+use crate::query::Query;
+use crate::resources::v1::containers::base::ContainerStatus;
+use crate::resources::v1::containers::factory::platform_factory;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use url::{Host, Url};
+
+/// Warning thresholds, as percentages of the budget, used when none are given.
+pub const DEFAULT_THRESHOLDS: &[f64] = &[50.0, 80.0, 100.0];
+
+/// Names of the namespaces a budget covers.
+async fn budget_namespaces(
+    db: &DatabaseConnection,
+    budget: &budgets::Model,
+) -> Result<Vec<String>, DbErr> {
+    if let Some(namespace) = &budget.namespace {
+        return Ok(vec![namespace.clone()]);
+    }
+    Ok(Query::find_owned_namespaces(db, &[budget.owner.as_str()])
+        .await?
+        .into_iter()
+        .map(|n| n.name)
+        .collect())
+}
+
+/// Spend against a budget over `[start, end)`, priced with the owner's price
+/// book so it matches what the owner is invoiced.
+pub async fn budget_spend(
+    db: &DatabaseConnection,
+    budget: &budgets::Model,
+    start: i64,
+    end: i64,
+) -> Result<f64, DbErr> {
+    let namespaces = budget_namespaces(db, budget).await?;
+    let namespace_refs: Vec<&str> = namespaces.iter().map(|s| s.as_str()).collect();
+
+    let price_book = price_books::Entity::find_by_id(budget.owner.clone())
+        .one(db)
+        .await?
+        .map(|b| b.to_v1())
+        .unwrap_or_else(|| V1PriceBook::at_cost(&budget.owner));
+
+    // Pricing only depends on the meter, so the database sums everything else
+    let grouping = UsageGrouping {
+        meter: true,
+        ..Default::default()
+    };
+    let totals = Query::sum_usage_records(
+        db,
+        &namespace_refs,
+        None,
+        None,
+        Some(start),
+        Some(end),
+        &grouping,
+    )
+    .await?;
+    Ok(build_line_items(&totals, &price_book)
+        .iter()
+        .map(|item| item.amount)
+        .sum())
+}
+
+/// The first exhausted budget that blocks new workloads in `namespace`, if any.
+pub async fn exhausted_budget(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Result<Option<budgets::Model>, DbErr> {
+    let owner = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(namespace))
+        .one(db)
+        .await?
+        .map(|n| n.owner);
+
+    let mut scope = Condition::any().add(budgets::Column::Namespace.eq(namespace));
+    if let Some(owner) = owner {
+        scope = scope.add(
+            Condition::all()
+                .add(budgets::Column::Namespace.is_null())
+                .add(budgets::Column::Owner.eq(owner)),
+        );
+    }
+
+    budgets::Entity::find()
+        .filter(budgets::Column::Exhausted.eq(true))
+        .filter(budgets::Column::Action.ne("notify"))
+        .filter(scope)
+        .one(db)
+        .await
+}
+
+/// Whether `ip` is routable on the public internet, so webhooks can't be
+/// pointed at the server's own network.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && (18..20).contains(&b))
+                // IETF protocol assignments, 192.0.0.0/24
+                || ip.octets()[..3] == [192, 0, 0])
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Check a budget webhook is an `https` URL on a public host.
+pub fn validate_webhook_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid webhook_url '{}': {}", url, e))?;
+    if parsed.scheme() != "https" {
+        return Err(format!("Invalid webhook_url '{}': must use https", url));
+    }
+    let public = match parsed.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost"
+                && !domain.ends_with(".localhost")
+                && !domain.ends_with(".local")
+                && !domain.ends_with(".internal")
+        }
+        Some(Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        None => false,
+    };
+    if !public {
+        return Err(format!(
+            "Invalid webhook_url '{}': host must be publicly reachable",
+            url
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Build a client for a budget's webhook that only connects to the public
+/// addresses its host resolves to now and doesn't follow redirects.
+async fn webhook_client(url: &str) -> Result<(reqwest::Client, Url), String> {
+    let url = validate_webhook_url(url)?;
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(10));
+    if let Some(Host::Domain(domain)) = url.host() {
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", domain, e))?
+            .collect();
+        if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+            return Err(format!("{} does not resolve to a public address", domain));
+        }
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    let client = builder
+        .build()
+        .map_err(|e| format!("Failed to build webhook client: {}", e))?;
+    Ok((client, url))
+}
+
+/// POST a warning to the budget's webhook, if it has one.
+async fn notify(budget: &budgets::Model, threshold: f64, spent: f64) {
+    let Some(url) = &budget.webhook_url else {
+        return;
+    };
+    let (client, url) = match webhook_client(url).await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "[Budgets] Not posting warning for budget {}: {}",
+                budget.id, e
+            );
+            return;
+        }
+    };
+
+    let body = serde_json::json!({
+        "kind": "BudgetWarning",
+        "budget": budget.to_v1(),
+        "threshold": threshold,
+        "spent": spent,
+    });
+    match client.post(url).json(&body).send().await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => warn!(
+            "[Budgets] Webhook for budget {} responded with status {}",
+            budget.id,
+            response.status()
+        ),
+        Err(e) => warn!(
+            "[Budgets] Failed to post warning for budget {}: {}",
+            budget.id, e
+        ),
+    }
+}
+
+/// Apply an exhausted budget's action to the workloads it covers. Safe to
+/// repeat: workloads already stopped or scaled down are left alone.
+async fn enforce_budget(db: &DatabaseConnection, budget: &budgets::Model) -> Result<(), DbErr> {
+    if budget.action == "notify" {
+        return Ok(());
+    }
+    let namespaces = budget_namespaces(db, budget).await?;
+
+    // Processors are held at their minimum so the autoscaler stops adding replicas
+    let processors = processors::Entity::find()
+        .filter(processors::Column::Namespace.is_in(namespaces.clone()))
+        .all(db)
+        .await?;
+    for processor in processors {
+        let min_replicas = processor.min_replicas.unwrap_or(1).max(1);
+        if processor.desired_replicas.unwrap_or(0) <= min_replicas {
+            continue;
+        }
+        info!(
+            "[Budgets] Budget {} exhausted, scaling processor {} down to {} replicas",
+            budget.name, processor.id, min_replicas
+        );
+        processors::Entity::update_many()
+            .col_expr(
+                processors::Column::DesiredReplicas,
+                Expr::value(min_replicas),
+            )
+            .filter(processors::Column::Id.eq(&processor.id))
+            .exec(db)
+            .await?;
+    }
+
+    if budget.action != "stop" {
+        return Ok(());
+    }
+
+    let running = Query::find_containers_by_status(db, ContainerStatus::Running).await?;
+    let reason = format!("Stopped after budget '{}' was exhausted", budget.name);
+    for container in running
+        .into_iter()
+        .filter(|c| namespaces.contains(&c.namespace))
+    {
+        stop_container(db, &container, &reason).await;
+    }
+    Ok(())
+}
+
+async fn stop_container(db: &DatabaseConnection, container: &containers::Model, reason: &str) {
+    info!(
+        "[Budgets] Stopping container {} in namespace {}",
+        container.id, container.namespace
+    );
+    let platform = container
+        .platform
+        .clone()
+        .unwrap_or_else(|| "runpod".to_string());
+    if let Err(e) = platform_factory(platform).stop(container, reason, db).await {
+        error!("[Budgets] Failed to stop container {}: {}", container.id, e);
+    }
+}
+
+/// Recompute a budget's spend for the current period, fire any warnings it
+/// has crossed since the last check and enforce it once exhausted.
+pub async fn evaluate_budget(
+    db: &DatabaseConnection,
+    budget: budgets::Model,
+) -> Result<budgets::Model, DbErr> {
+    let now = chrono::Utc::now();
+    let (start, end) = window_bounds(now.timestamp(), &budget.period)
+        .ok_or_else(|| DbErr::Custom(format!("Invalid budget period '{}'", budget.period)))?;
+
+    // Warnings start over every period
+    let new_period = budget.period_start.map(|t| t.timestamp()) != Some(start);
+    let mut last_threshold = if new_period {
+        None
+    } else {
+        budget.last_threshold
+    };
+
+    let spent = budget_spend(db, &budget, start, end).await?;
+    let percent = if budget.amount > 0.0 {
+        spent / budget.amount * 100.0
+    } else {
+        100.0
+    };
+    debug!(
+        "[Budgets] Budget {} has spent {:.2} of {:.2} {} ({:.1}%)",
+        budget.id, spent, budget.amount, budget.currency, percent
+    );
+
+    let crossed = budget
+        .parse_thresholds()
+        .unwrap_or_else(|_| DEFAULT_THRESHOLDS.to_vec())
+        .into_iter()
+        .filter(|t| percent >= *t && last_threshold.is_none_or(|last| *t > last))
+        .fold(None, |max: Option<f64>, t| {
+            Some(max.map_or(t, |m| m.max(t)))
+        });
+    if let Some(threshold) = crossed {
+        warn!(
+            "[Budgets] Budget {} for {} has reached {}% ({:.2} of {:.2} {})",
+            budget.name,
+            budget.namespace.as_deref().unwrap_or(&budget.owner),
+            threshold,
+            spent,
+            budget.amount,
+            budget.currency
+        );
+        notify(&budget, threshold, spent).await;
+        last_threshold = Some(threshold);
+    }
+
+    let exhausted = spent >= budget.amount;
+    if exhausted {
+        if !budget.exhausted {
+            warn!(
+                "[Budgets] Budget {} is exhausted, applying action '{}'",
+                budget.name, budget.action
+            );
+        }
+        enforce_budget(db, &budget).await?;
+    }
+
+    let mut active_model: budgets::ActiveModel = budget.into();
+    active_model.spent = Set(spent);
+    active_model.period_start = Set(chrono::DateTime::from_timestamp(start, 0).map(Into::into));
+    active_model.last_threshold = Set(last_threshold);
+    active_model.exhausted = Set(exhausted);
+    active_model.checked_at = Set(Some(now.into()));
+    active_model.update(db).await
+}
+
+/// Spawn a background task that periodically evaluates every budget.
+pub fn spawn_budget_enforcer(db: DatabaseConnection) -> JoinHandle<()> {
+    let interval_secs = SERVER_CONFIG.budgets.check_interval_secs.max(1);
+
+    tokio::spawn(async move {
+        info!(
+            "[Budgets] Budget enforcer started (interval={}s)",
+            interval_secs
+        );
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let budgets = match budgets::Entity::find().all(&db).await {
+                Ok(budgets) => budgets,
+                Err(e) => {
+                    error!("[Budgets] Failed to list budgets: {}", e);
+                    continue;
+                }
+            };
+            for budget in budgets {
+                let id = budget.id.clone();
+                if let Err(e) = evaluate_budget(&db, budget).await {
+                    error!("[Budgets] Failed to evaluate budget {}: {}", id, e);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_must_be_https() {
+        assert!(validate_webhook_url("https://hooks.example.com/budget").is_ok());
+        assert!(validate_webhook_url("http://hooks.example.com/budget").is_err());
+        assert!(validate_webhook_url("file:///etc/passwd").is_err());
+        assert!(validate_webhook_url("not a url").is_err());
+    }
+
+    #[test]
+    fn webhook_host_must_be_public() {
+        for url in [
+            "https://localhost/hook",
+            "https://api.localhost/hook",
+            "https://metadata.google.internal/computeMetadata",
+            "https://printer.local/hook",
+            "https://127.0.0.1/hook",
+            "https://10.0.0.5/hook",
+            "https://172.16.0.1/hook",
+            "https://192.168.1.1/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.100.100.100/hook",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(validate_webhook_url(url).is_err(), "{} was allowed", url);
+        }
+        assert!(validate_webhook_url("https://8.8.8.8/hook").is_ok());
+        assert!(validate_webhook_url("https://[2606:4700::1111]/hook").is_ok());
+    }
+
+    #[tokio::test]
+    async fn webhook_client_validates_the_url() {
+        assert!(webhook_client("https://127.0.0.1/hook").await.is_err());
+        assert!(webhook_client("https://8.8.8.8/hook").await.is_ok());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::billing::models::{V1Invoice, V1InvoiceLineItem, V1MeterPrice, V1PriceBook};
+use crate::meters::compute::COMPUTE_METER;
+use crate::meters::ledger::{window_bounds, UsageTotal};
+use chrono::{TimeZone, Utc};
+use std::collections::BTreeMap;
+
+/// Returns the `[start, end)` bounds of a `YYYY-MM` month in UTC.
+pub fn month_bounds(month: &str) -> Option<(i64, i64)> {
+    let (year, month) = month.split_once('-')?;
+    let year: i32 = year.parse().ok()?;
+    let month: u32 = month.parse().ok()?;
+    let first = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+    window_bounds(first.timestamp(), "month")
+}
+
+/// Find the price entry for a meter, preferring one that matches the unit exactly.
+fn find_price<'a>(
+    price_book: &'a V1PriceBook,
+    meter: &str,
+    unit: Option<&str>,
+) -> Option<&'a V1MeterPrice> {
+    price_book
+        .prices
+        .iter()
+        .filter(|p| p.meter == meter)
+        .find(|p| p.unit.as_deref() == unit)
+        .or_else(|| {
+            price_book
+                .prices
+                .iter()
+                .find(|p| p.meter == meter && p.unit.is_none())
+        })
+}
+
+/// Price ledger totals with a price book, producing one line item per
+/// namespace, container, meter, unit and currency.
+///
+/// Meters with an explicit `unit_price` are billed at that price; everything
+/// else is billed cost-plus, marking up the recorded cost by the meter's
+/// `markup_percent` or the book's compute/default markup.
+pub fn build_line_items(totals: &[UsageTotal], price_book: &V1PriceBook) -> Vec<V1InvoiceLineItem> {
+    type Key = (
+        Option<String>,
+        Option<String>,
+        String,
+        Option<String>,
+        Option<String>,
+    );
+    let mut items: BTreeMap<Key, V1InvoiceLineItem> = BTreeMap::new();
+
+    for record in totals {
+        let meter = record.meter.clone().unwrap_or_default();
+        let price = find_price(price_book, &meter, record.unit.as_deref());
+
+        let (base_cost, markup_percent) = match price.and_then(|p| p.unit_price) {
+            Some(unit_price) => (
+                record.quantity * unit_price,
+                price.and_then(|p| p.markup_percent).unwrap_or(0.0),
+            ),
+            None => {
+                let fallback = if meter == COMPUTE_METER {
+                    price_book.compute_markup_percent
+                } else {
+                    price_book.default_markup_percent
+                };
+                (
+                    record.amount,
+                    price.and_then(|p| p.markup_percent).unwrap_or(fallback),
+                )
+            }
+        };
+
+        let key: Key = (
+            record.namespace.clone(),
+            record.container_id.clone(),
+            meter.clone(),
+            record.unit.clone(),
+            record.currency.clone(),
+        );
+        let item = items.entry(key).or_insert_with(|| V1InvoiceLineItem {
+            namespace: record.namespace.clone(),
+            container_id: record.container_id.clone(),
+            meter: meter.clone(),
+            unit: record.unit.clone(),
+            currency: record.currency.clone(),
+            markup_percent,
+            ..Default::default()
+        });
+        item.quantity += record.quantity;
+        item.base_cost += base_cost;
+        item.amount += base_cost * (1.0 + markup_percent / 100.0);
+    }
+
+    items
+        .into_values()
+        .map(|mut item| {
+            item.unit_price = if item.quantity > 0.0 {
+                item.amount / item.quantity
+            } else {
+                0.0
+            };
+            item
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render an invoice as CSV with one row per line item and a trailing total row.
+pub fn invoice_to_csv(invoice: &V1Invoice) -> String {
+    let mut out = String::from(
+        "invoice_id,owner,period_start,period_end,namespace,container_id,meter,unit,quantity,unit_price,base_cost,markup_percent,amount,currency\n",
+    );
+
+    for item in &invoice.line_items {
+        let row = [
+            csv_field(&invoice.id),
+            csv_field(&invoice.owner),
+            invoice.period_start.to_string(),
+            invoice.period_end.to_string(),
+            csv_field(item.namespace.as_deref().unwrap_or("")),
+            csv_field(item.container_id.as_deref().unwrap_or("")),
+            csv_field(&item.meter),
+            csv_field(item.unit.as_deref().unwrap_or("")),
+            item.quantity.to_string(),
+            format!("{:.6}", item.unit_price),
+            format!("{:.6}", item.base_cost),
+            item.markup_percent.to_string(),
+            format!("{:.6}", item.amount),
+            csv_field(item.currency.as_deref().unwrap_or(&invoice.currency)),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out.push_str(&format!(
+        "{},{},{},{},,,total,,,,{:.6},,{:.6},{}\n",
+        csv_field(&invoice.id),
+        csv_field(&invoice.owner),
+        invoice.period_start,
+        invoice.period_end,
+        invoice.subtotal,
+        invoice.total,
+        csv_field(&invoice.currency),
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(meter: &str, quantity: f64, amount: f64) -> UsageTotal {
+        UsageTotal {
+            namespace: Some("ns".to_string()),
+            container_id: Some("c1".to_string()),
+            meter: Some(meter.to_string()),
+            unit: Some("unit".to_string()),
+            currency: Some("USD".to_string()),
+            quantity,
+            amount,
+            events: 1,
+            first_occurred_at: Utc::now().into(),
+        }
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(month_bounds("2024-02"), Some((1706745600, 1709251200)));
+        assert_eq!(month_bounds("2024-13"), None);
+        assert_eq!(month_bounds("feb"), None);
+    }
+
+    #[test]
+    fn test_build_line_items_cost_plus_and_fixed_price() {
+        let mut book = V1PriceBook::at_cost("org");
+        book.compute_markup_percent = 20.0;
+        book.prices.push(V1MeterPrice {
+            meter: "requests".to_string(),
+            unit: None,
+            unit_price: Some(0.01),
+            markup_percent: None,
+        });
+
+        let records = vec![
+            record(COMPUTE_METER, 60.0, 1.0),
+            record(COMPUTE_METER, 60.0, 1.0),
+            record("requests", 100.0, 0.0),
+            record("tokens", 10.0, 5.0),
+        ];
+
+        let items = build_line_items(&records, &book);
+        assert_eq!(items.len(), 3);
+
+        let compute = items.iter().find(|i| i.meter == COMPUTE_METER).unwrap();
+        assert_eq!(compute.quantity, 120.0);
+        assert_eq!(compute.base_cost, 2.0);
+        assert!((compute.amount - 2.4).abs() < 1e-9);
+
+        let requests = items.iter().find(|i| i.meter == "requests").unwrap();
+        assert!((requests.amount - 1.0).abs() < 1e-9);
+
+        let tokens = items.iter().find(|i| i.meter == "tokens").unwrap();
+        assert_eq!(tokens.amount, 5.0);
+    }
+
+    #[test]
+    fn test_build_line_items_splits_currencies() {
+        let book = V1PriceBook::at_cost("org");
+        let mut eur = record("tokens", 10.0, 4.0);
+        eur.currency = Some("EUR".to_string());
+
+        let items = build_line_items(&[record("tokens", 10.0, 5.0), eur], &book);
+        assert_eq!(items.len(), 2);
+        assert!(items
+            .iter()
+            .any(|i| i.currency.as_deref() == Some("USD") && i.amount == 5.0));
+        assert!(items
+            .iter()
+            .any(|i| i.currency.as_deref() == Some("EUR") && i.amount == 4.0));
+    }
+}
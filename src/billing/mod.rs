@@ -0,0 +1,6 @@
+pub mod budget;
+pub mod invoice;
+pub mod models;
+
+pub use invoice::{build_line_items, invoice_to_csv, month_bounds};
+pub use models::{V1Budget, V1Invoice, V1InvoiceLineItem, V1MeterPrice, V1PriceBook};
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+fn default_price_book_kind() -> String {
+    "PriceBook".to_string()
+}
+
+fn default_invoice_kind() -> String {
+    "Invoice".to_string()
+}
+
+fn default_budget_kind() -> String {
+    "Budget".to_string()
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Price override for a single meter. With `unit_price` set the meter is billed
+/// at that price per unit; otherwise the recorded cost is marked up by
+/// `markup_percent`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1MeterPrice {
+    pub meter: String,
+    pub unit: Option<String>,
+    pub unit_price: Option<f64>,
+    pub markup_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct V1PriceBook {
+    #[serde(default = "default_price_book_kind")]
+    pub kind: String,
+    pub owner: String,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Cost-plus markup applied to the `compute` meter (runtime × `resource_cost_per_hr`).
+    #[serde(default)]
+    pub compute_markup_percent: f64,
+    /// Cost-plus markup applied to every other meter without an explicit price.
+    #[serde(default)]
+    pub default_markup_percent: f64,
+    #[serde(default)]
+    pub prices: Vec<V1MeterPrice>,
+    pub created_by: Option<String>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl V1PriceBook {
+    /// Price book used when an owner has none: costs are billed as recorded.
+    pub fn at_cost(owner: &str) -> Self {
+        Self {
+            kind: default_price_book_kind(),
+            owner: owner.to_string(),
+            currency: default_currency(),
+            compute_markup_percent: 0.0,
+            default_markup_percent: 0.0,
+            prices: Vec::new(),
+            created_by: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1PriceBookRequest {
+    pub currency: Option<String>,
+    pub compute_markup_percent: Option<f64>,
+    pub default_markup_percent: Option<f64>,
+    pub prices: Option<Vec<V1MeterPrice>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1PriceBooks {
+    pub price_books: Vec<V1PriceBook>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1InvoiceLineItem {
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    pub meter: String,
+    pub unit: Option<String>,
+    /// Currency the usage was recorded in; the invoice currency when unset.
+    #[serde(default)]
+    pub currency: Option<String>,
+    pub quantity: f64,
+    /// Effective price per unit after markup.
+    pub unit_price: f64,
+    /// Cost before markup.
+    pub base_cost: f64,
+    pub markup_percent: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct V1Invoice {
+    #[serde(default = "default_invoice_kind")]
+    pub kind: String,
+    pub id: String,
+    pub owner: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub currency: String,
+    pub line_items: Vec<V1InvoiceLineItem>,
+    pub subtotal: f64,
+    pub markup: f64,
+    pub total: f64,
+    pub status: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+/// Request to generate an invoice. The period is either a calendar `month`
+/// (`YYYY-MM`, UTC) or explicit `start`/`end` unix timestamps.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1InvoiceRequest {
+    pub owner: Option<String>,
+    pub month: Option<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1Invoices {
+    pub invoices: Vec<V1Invoice>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1InvoiceFormatQuery {
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+}
+
+/// What happens once a budget is exhausted. `notify` only warns; the others
+/// also refuse new containers, processors and scale-ups in the budget's scope.
+pub const BUDGET_ACTIONS: &[&str] = &["notify", "scale_down", "stop"];
+
+/// Spend limit for a namespace, or for every namespace of an owner, over a
+/// recurring `period`. Spend is the ledger priced with the owner's price book,
+/// the same as an invoice would show.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct V1Budget {
+    #[serde(default = "default_budget_kind")]
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    pub namespace: Option<String>,
+    pub amount: f64,
+    pub currency: String,
+    /// `hour`, `day` or `month`
+    pub period: String,
+    /// Percentages of `amount` at which warnings fire
+    pub thresholds: Vec<f64>,
+    /// One of `BUDGET_ACTIONS`
+    pub action: String,
+    pub webhook_url: Option<String>,
+    pub spent: f64,
+    pub period_start: Option<i64>,
+    /// Highest threshold warned about this period
+    pub last_threshold: Option<f64>,
+    pub exhausted: bool,
+    pub checked_at: Option<i64>,
+    pub created_by: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1BudgetRequest {
+    pub name: String,
+    /// Defaults to the namespace's owner, or the user
+    pub owner: Option<String>,
+    pub namespace: Option<String>,
+    pub amount: f64,
+    pub currency: Option<String>,
+    pub period: Option<String>,
+    pub thresholds: Option<Vec<f64>>,
+    pub action: Option<String>,
+    pub webhook_url: Option<String>,
+}
+
+/// Changes to a budget's limits. Its name and scope are fixed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1BudgetUpdate {
+    pub amount: Option<f64>,
+    pub currency: Option<String>,
+    pub period: Option<String>,
+    pub thresholds: Option<Vec<f64>>,
+    pub action: Option<String>,
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1Budgets {
+    pub budgets: Vec<V1Budget>,
+}
@@ -29,6 +29,12 @@ pub enum Commands {
         command: DeleteCommands,
     },
 
+    /// Show a resource's spec, status and recent events.
+    Describe {
+        #[command(subcommand)]
+        command: DescribeCommands,
+    },
+
     /// Sync data
     Sync {
         #[command(subcommand)]
@@ -118,6 +124,10 @@ pub enum Commands {
         /// Address of the Hub
         #[arg(long, default_value = None)]
         hub: Option<String>,
+
+        /// Login through the server's OIDC provider using the device-code flow
+        #[arg(long)]
+        oidc: bool,
     },
 
     /// Auth commands.
@@ -126,6 +136,12 @@ pub enum Commands {
         command: AuthCommands,
     },
 
+    /// Server administration commands.
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommands,
+    },
+
     /// Execute a command inside a container.
     Exec(ExecArgs),
 
@@ -201,6 +217,11 @@ pub struct SecretCommands {
     /// Read the secret value from a file.
     #[arg(short = 'f', long)]
     pub file: Option<String>,
+
+    /// Reference an external value instead, as `provider:path[#key]`, e.g.
+    /// `vault:db/creds#password`. Providers are `vault`, `file` and `env`.
+    #[arg(long, conflicts_with_all = ["value", "file"])]
+    pub source: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -396,6 +417,18 @@ pub struct ContainerCommands {
     /// Proxy port
     #[arg(long)]
     pub proxy_port: Option<i16>,
+
+    /// Stop the container after this long without proxied requests, e.g. 15m
+    #[arg(long)]
+    pub idle_stop_after: Option<String>,
+
+    /// How long the proxy holds requests while an idle container wakes, e.g. 30s
+    #[arg(long)]
+    pub idle_wake_timeout: Option<String>,
+
+    /// Restart the container when a secret used in its env changes
+    #[arg(long)]
+    pub restart_on_secret_change: bool,
 }
 
 /// Parse a key-value pair in the format of KEY=VALUE
@@ -422,6 +455,10 @@ pub enum GetCommands {
     Containers {
         /// Platform to get containers for.
         id: Option<String>,
+
+        /// Keep running and print container changes as they happen.
+        #[arg(short, long, default_value_t = false, conflicts_with = "id")]
+        watch: bool,
     },
 
     /// Get processors.
@@ -476,6 +513,32 @@ pub enum DeleteCommands {
     },
 }
 
+/// Describe resources.
+#[derive(Subcommand)]
+pub enum DescribeCommands {
+    /// Describe a container.
+    #[command(aliases = ["container", "co"])]
+    Containers {
+        /// Container name.
+        name: String,
+
+        /// Container namespace.
+        #[arg(long, short)]
+        namespace: Option<String>,
+    },
+
+    /// Describe a processor.
+    #[command(aliases = ["processor", "proc"])]
+    Processors {
+        /// Processor name.
+        name: String,
+
+        /// Processor namespace.
+        #[arg(long, short)]
+        namespace: Option<String>,
+    },
+}
+
 #[derive(Args)]
 pub struct DeleteProcessorCommands {
     /// Processor name
@@ -532,6 +595,12 @@ pub enum AuthCommands {
     // TODO: Add auth for tailnet
 }
 
+#[derive(Subcommand)]
+pub enum AdminCommands {
+    /// Re-wrap every secret with the server's active encryption key.
+    RotateKeys,
+}
+
 #[derive(Subcommand)]
 pub enum ApiKeyActions {
     /// List API keys.
@@ -544,7 +613,23 @@ pub enum ApiKeyActions {
     },
 
     /// Generate a new API key.
-    Generate,
+    Generate {
+        /// A human readable label for the key.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// Restrict the key to a namespace; may be repeated.
+        #[arg(short = 'n', long = "namespace", action = ArgAction::Append)]
+        namespaces: Option<Vec<String>>,
+
+        /// Allow verbs on a kind as VERB[,VERB]:KIND[/NAME], e.g. get,list:containers; may be repeated.
+        #[arg(long, action = ArgAction::Append)]
+        allow: Option<Vec<String>>,
+
+        /// Expire the key after this long, e.g. 30d.
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
 
     /// Revoke an API key.
     Revoke {
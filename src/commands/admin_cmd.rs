@@ -0,0 +1,23 @@
+use crate::commands::request::server_request;
+use nebulous::resources::v1::secrets::models::V1KeyRotation;
+use std::error::Error;
+
+pub async fn rotate_keys() -> Result<(), Box<dyn Error>> {
+    let response = server_request("/v1/admin/rotate-keys", reqwest::Method::POST).await?;
+    let report = response.json::<V1KeyRotation>().await?;
+
+    println!("Active key: {}", report.active_key);
+    println!("Re-wrapped: {}", report.rewrapped);
+    println!("Already current: {}", report.unchanged);
+    if report.failed.is_empty() {
+        println!("\nAll secrets use the active key; old keys can be removed.");
+    } else {
+        println!("Failed: {}", report.failed.join(", "));
+        return Err(format!(
+            "{} secrets could not be re-wrapped, keep the old keys configured",
+            report.failed.len()
+        )
+        .into());
+    }
+    Ok(())
+}
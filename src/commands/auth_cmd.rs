@@ -1,6 +1,8 @@
 use crate::commands::request::server_request;
-use nebulous::auth::models::SanitizedApiKey;
-use nebulous::auth::server::handlers::{ApiKeyListResponse, ApiKeyRequest, RawApiKeyResponse};
+use nebulous::auth::models::{ApiKeyPermission, ApiKeyScope, SanitizedApiKey};
+use nebulous::auth::server::handlers::{
+    ApiKeyListResponse, ApiKeyRequest, GenerateApiKeyRequest, RawApiKeyResponse,
+};
 use std::error::Error;
 
 // TODO: Make the auth server's port configurable
@@ -8,6 +10,9 @@ const SERVER: &str = "http://localhost:8080";
 
 fn pretty_print_api_key(api_key: SanitizedApiKey) {
     println!("ID: {}", api_key.id);
+    if let Some(label) = &api_key.label {
+        println!("Label: {}", label);
+    }
     println!("Active: {}", api_key.is_active);
     println!("Created at: {}", api_key.created_at.to_string());
     println!(
@@ -22,6 +27,28 @@ fn pretty_print_api_key(api_key: SanitizedApiKey) {
             .revoked_at
             .map_or("N/A".to_string(), |dt| dt.to_string())
     );
+    println!(
+        "Expires at: {}",
+        api_key
+            .expires_at
+            .map_or("Never".to_string(), |dt| dt.to_string())
+    );
+    if let Some(scope) = &api_key.scope {
+        if let Some(namespaces) = &scope.namespaces {
+            println!("Namespaces: {}", namespaces.join(", "));
+        }
+        for permission in scope.permissions.iter().flatten() {
+            println!(
+                "Allows: {} on {}{}",
+                permission.verbs.join(","),
+                permission.kinds.join(","),
+                permission
+                    .names
+                    .as_ref()
+                    .map_or(String::new(), |names| format!(" ({})", names.join(", ")))
+            );
+        }
+    }
     println!();
 }
 
@@ -41,9 +68,43 @@ pub async fn get_api_key(id: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub async fn generate_api_key() -> Result<(), Box<dyn Error>> {
+pub async fn generate_api_key(
+    label: Option<String>,
+    namespaces: Option<Vec<String>>,
+    allow: Option<Vec<String>>,
+    expires_in: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let permissions = allow
+        .map(|allow| {
+            allow
+                .iter()
+                .map(|a| a.parse::<ApiKeyPermission>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let scope = (namespaces.is_some() || permissions.is_some()).then(|| ApiKeyScope {
+        namespaces,
+        permissions,
+    });
+    let payload = GenerateApiKeyRequest {
+        label,
+        expires_in,
+        scope,
+    };
+
     let url = format!("{}/api-key/generate", SERVER);
-    match reqwest::Client::new().get(&url).send().await {
+    match reqwest::Client::new()
+        .post(&url)
+        .json(&payload)
+        .send()
+        .await
+    {
+        Ok(response) if !response.status().is_success() => {
+            eprintln!(
+                "Failed to generate API key: {}",
+                response.text().await.unwrap_or_default()
+            );
+        }
         Ok(response) => {
             let api_key = response.json::<RawApiKeyResponse>().await?;
             println!("Generated a new API key:\n");
@@ -1,9 +1,9 @@
 use crate::commands::request::server_request_with_payload;
 use nebulous::models::{V1Meter, V1ResourceMetaRequest};
 use nebulous::resources::v1::containers::models::{
-    RestartPolicy, V1ContainerRequest, V1ContainerResources, V1EnvVar,
+    RestartPolicy, V1ContainerRequest, V1ContainerResources, V1EnvVar, V1IdlePolicy,
 };
-use nebulous::resources::v1::secrets::models::V1SecretRequest;
+use nebulous::resources::v1::secrets::models::{V1SecretRequest, V1SecretSource};
 use nebulous::resources::v1::volumes::models::{V1VolumeConfig, V1VolumeDriver, V1VolumePath};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -106,6 +106,13 @@ pub async fn create_container(
             proxy_port: command.proxy_port,
             authz: None,
             health_check: None,
+            idle: command.idle_stop_after.map(|stop_after| V1IdlePolicy {
+                stop_after,
+                wake_on_request: true,
+                wake_timeout: command.idle_wake_timeout,
+            }),
+            restart_on_secret_change: command.restart_on_secret_change.then_some(true),
+            secret_files: None,
         }
     };
 
@@ -142,7 +149,26 @@ pub async fn create_secret(
     };
 
     // Construct the request object depending on whether a file is provided
-    let secret_request = if let Some(file) = command.file {
+    let secret_request = if let Some(source) = command.source {
+        let (provider, reference) = source
+            .split_once(':')
+            .ok_or("Invalid source, expected provider:path[#key]")?;
+        let (path, key) = match reference.split_once('#') {
+            Some((path, key)) => (path, Some(key.to_string())),
+            None => (reference, None),
+        };
+
+        V1SecretRequest {
+            metadata,
+            value: String::new(),
+            expires_at: command.expires_at,
+            source: Some(V1SecretSource {
+                provider: provider.to_string(),
+                path: path.to_string(),
+                key,
+            }),
+        }
+    } else if let Some(file) = command.file {
         // If the user provided a file, read *raw* contents as the secret value
         println!("Reading secret file: {}", file);
         let file_content = std::fs::read_to_string(&file)?;
@@ -152,6 +178,7 @@ pub async fn create_secret(
             metadata,
             value: file_content,
             expires_at: command.expires_at,
+            source: None,
         }
     } else {
         // Otherwise, ensure a `--value` was provided on the CLI
@@ -164,6 +191,7 @@ pub async fn create_secret(
             metadata,
             value: command.value.clone().unwrap(),
             expires_at: command.expires_at,
+            source: None,
         }
     };
 
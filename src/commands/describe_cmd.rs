@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::commands::get_cmd::remove_null_values;
+use crate::commands::request::server_request;
+use nebulous::resources::v1::events::models::V1ResourceEvents;
+use serde_json::Value;
+use std::error::Error;
+
+pub async fn describe_container(
+    name: String,
+    namespace: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    describe("containers", name, namespace).await
+}
+
+pub async fn describe_processor(
+    name: String,
+    namespace: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    describe("processors", name, namespace).await
+}
+
+/// Print a resource's spec and status, followed by its recent events.
+async fn describe(
+    kind: &str,
+    name: String,
+    namespace: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let namespace = namespace.unwrap_or("-".to_string());
+    let path = format!("/v1/{}/{}/{}", kind, namespace, name);
+
+    let response = server_request(&path, reqwest::Method::GET).await?;
+    let mut resource: Value = response.json().await?;
+    remove_null_values(&mut resource);
+
+    let mut buf = Vec::new();
+    {
+        let mut serializer = serde_yaml::Serializer::new(&mut buf);
+        resource.serialize(&mut serializer)?;
+    }
+    println!("{}", String::from_utf8(buf)?);
+
+    let response = server_request(&format!("{}/events", path), reqwest::Method::GET).await?;
+    let events: V1ResourceEvents = response.json().await?;
+
+    println!("Events:");
+    if events.events.is_empty() {
+        println!("  <none>");
+        return Ok(());
+    }
+
+    let mut table = prettytable::Table::new();
+    table.add_row(prettytable::Row::new(vec![
+        prettytable::Cell::new("LAST SEEN"),
+        prettytable::Cell::new("TYPE"),
+        prettytable::Cell::new("REASON"),
+        prettytable::Cell::new("COUNT"),
+        prettytable::Cell::new("MESSAGE"),
+    ]));
+
+    // Oldest first, so the latest event is closest to the prompt
+    for event in events.events.iter().rev() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(&format_age(event.last_seen)),
+            prettytable::Cell::new(&event.event_type),
+            prettytable::Cell::new(&event.reason),
+            prettytable::Cell::new(&event.count.to_string()),
+            prettytable::Cell::new(&event.message),
+        ]));
+    }
+
+    table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+    table.printstd();
+
+    Ok(())
+}
+
+fn format_age(timestamp: i64) -> String {
+    let dt = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
+    let duration = Utc::now().signed_duration_since(dt);
+
+    if duration.num_days().abs() > 0 {
+        format!("{}d", duration.num_days())
+    } else if duration.num_hours().abs() > 0 {
+        format!("{}hr", duration.num_hours())
+    } else if duration.num_minutes().abs() > 0 {
+        format!("{}m", duration.num_minutes())
+    } else {
+        format!("{}s", duration.num_seconds())
+    }
+}
@@ -1,14 +1,21 @@
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
-use crate::commands::request::server_request;
+use crate::commands::request::{prepare_request, server_request};
+use futures::StreamExt;
 use nebulous::config::ClientConfig;
 use nebulous::resources::v1::containers::models::{V1Container, V1Containers};
+use nebulous::resources::v1::watch::models::V1WatchEvent;
 use serde_json::Value;
 use std::error::Error;
+use std::time::Duration;
 use tracing::debug;
 
-pub async fn get_containers(id: Option<String>) -> Result<(), Box<dyn Error>> {
+pub async fn get_containers(id: Option<String>, watch: bool) -> Result<(), Box<dyn Error>> {
+    if watch {
+        return watch_containers().await;
+    }
+
     let containers: Vec<V1Container> = match id {
         Some(id) => {
             let url = format!("/v1/containers/{}", id);
@@ -125,6 +132,80 @@ pub async fn get_containers(id: Option<String>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Print one line per container change until interrupted. A dropped stream is
+/// resumed from the last token; if that token has expired the containers are
+/// listed again.
+async fn watch_containers() -> Result<(), Box<dyn Error>> {
+    let mut resume_from: Option<String> = None;
+
+    println!(
+        "{:<10} {:<30} {:<20} {:<12} {:<12}",
+        "EVENT", "NAME", "NAMESPACE", "STATUS", "ACCELERATOR"
+    );
+
+    loop {
+        let path = match &resume_from {
+            Some(token) => format!("/v1/containers?watch=true&resume_from={}", token),
+            None => "/v1/containers?watch=true".to_string(),
+        };
+        let response = prepare_request(&path, reqwest::Method::GET)?.send().await?;
+
+        if response.status() == reqwest::StatusCode::GONE {
+            debug!("Resume token expired, listing containers again");
+            resume_from = None;
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(format!("Request to server failed: {}", response.status()).into());
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut buf: Vec<u8> = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    debug!("Watch stream interrupted: {}", e);
+                    break;
+                }
+            };
+            buf.extend_from_slice(&chunk);
+
+            // Server-sent events are separated by a blank line
+            while let Some(pos) = buf.windows(2).position(|w| w == b"\n\n") {
+                let frame: Vec<u8> = buf.drain(..pos + 2).collect();
+                for line in String::from_utf8_lossy(&frame).lines() {
+                    let Some(data) = line.strip_prefix("data:") else {
+                        continue;
+                    };
+                    let event: V1WatchEvent = serde_json::from_str(data.trim())?;
+                    resume_from = Some(event.resume_token.clone());
+                    print_container_change(&event)?;
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+fn print_container_change(event: &V1WatchEvent) -> Result<(), Box<dyn Error>> {
+    let container: V1Container = serde_json::from_value(event.object.clone())?;
+    let status = container.status.unwrap_or_default();
+    let change_type = event.change_type.to_string();
+
+    println!(
+        "{:<10} {:<30} {:<20} {:<12} {:<12}",
+        change_type,
+        container.metadata.name,
+        container.metadata.namespace,
+        status.status.unwrap_or("N/A".to_string()),
+        status.accelerator.unwrap_or("N/A".to_string()),
+    );
+
+    Ok(())
+}
+
 pub async fn get_secrets(id: Option<String>) -> Result<(), Box<dyn Error>> {
     let secrets = match id {
         Some(id) => {
@@ -148,8 +229,10 @@ pub async fn get_secrets(id: Option<String>) -> Result<(), Box<dyn Error>> {
         prettytable::Cell::new("ID"),
         prettytable::Cell::new("NAME"),
         prettytable::Cell::new("NAMESPACE"),
+        prettytable::Cell::new("VERSION"),
         prettytable::Cell::new("CREATED"),
         prettytable::Cell::new("UPDATED"),
+        prettytable::Cell::new("STATUS"),
     ]));
 
     // Process each secret in the array
@@ -201,13 +284,27 @@ pub async fn get_secrets(id: Option<String>) -> Result<(), Box<dyn Error>> {
                 })
                 .unwrap_or("N/A".to_string());
 
+            let version = secret_obj
+                .get("version")
+                .and_then(Value::as_i64)
+                .unwrap_or(1)
+                .to_string();
+
+            let status = if secret_obj.get("expired").and_then(Value::as_bool) == Some(true) {
+                "Expired"
+            } else {
+                "Active"
+            };
+
             // Finally, add the row
             table.add_row(prettytable::Row::new(vec![
                 prettytable::Cell::new(id),
                 prettytable::Cell::new(name),
                 prettytable::Cell::new(namespace),
+                prettytable::Cell::new(&version),
                 prettytable::Cell::new(&created),
                 prettytable::Cell::new(&updated),
+                prettytable::Cell::new(status),
             ]));
         }
     }
@@ -455,7 +552,7 @@ pub async fn get_processors(
 }
 
 // Function to recursively remove null values from serde_json::Value
-fn remove_null_values(value: &mut Value) {
+pub fn remove_null_values(value: &mut Value) {
     match value {
         Value::Object(map) => {
             // Collect keys with null values
@@ -1,6 +1,8 @@
 use std::error::Error;
 use std::io::{self, Write};
+use std::time::{Duration, Instant};
 
+use nebulous::auth::oidc::{DeviceAuthorization, OidcLoginResponse};
 use nebulous::config::{ClientConfig, ClientServerConfig};
 use open;
 use rpassword;
@@ -76,3 +78,73 @@ When you're running nebulous on Kubernetes, use:
     println!("\nLogin successful!");
     Ok(())
 }
+
+/// Login through the server's OIDC provider with the device-code flow.
+pub async fn execute_oidc(nebu_url: String) -> Result<(), Box<dyn Error>> {
+    let nebu_url = nebu_url.trim().trim_end_matches("/").to_string();
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/auth/oidc/device", nebu_url))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let body = response.text().await?;
+        return Err(format!("Failed to start OIDC login: {}", body).into());
+    }
+    let device: DeviceAuthorization = response.json().await?;
+
+    let verification_url = device
+        .verification_uri_complete
+        .clone()
+        .unwrap_or_else(|| device.verification_uri.clone());
+    println!(
+        "\nVisit {} and enter the code: {}\n",
+        device.verification_uri, device.user_code
+    );
+    if let Err(e) = open::that(&verification_url) {
+        eprintln!("Failed to open browser: {}", e);
+    }
+
+    let mut interval = Duration::from_secs(device.interval.unwrap_or(5));
+    let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+    let login = loop {
+        if Instant::now() >= deadline {
+            return Err("Login timed out, please try again".into());
+        }
+        tokio::time::sleep(interval).await;
+
+        let response = client
+            .post(format!("{}/auth/oidc/device/token", nebu_url))
+            .json(&serde_json::json!({ "device_code": device.device_code }))
+            .send()
+            .await?;
+        if response.status().is_success() {
+            break response.json::<OidcLoginResponse>().await?;
+        }
+
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        match body["error"].as_str() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += Duration::from_secs(5),
+            Some(error) => return Err(format!("Login failed: {}", error).into()),
+            None => return Err("Login failed".into()),
+        }
+    };
+
+    let mut client_config = ClientConfig::read()?;
+    client_config.add_server(ClientServerConfig {
+        name: "nebu".to_string(),
+        server: Some(nebu_url),
+        api_key: Some(login.api_key),
+        auth_server: None,
+    }, true);
+    client_config.write()?;
+
+    println!(
+        "\nLogged in as {} (session expires {})",
+        login.user_profile.email,
+        login.expires_at.format("%Y-%m-%d")
+    );
+    Ok(())
+}
@@ -1,8 +1,10 @@
+pub mod admin_cmd;
 pub mod auth_cmd;
 pub mod configure_cmd;
 pub mod create_cmd;
 pub mod daemon_cmd;
 pub mod delete_cmd;
+pub mod describe_cmd;
 pub mod exec_cmd;
 pub mod get_cmd;
 pub mod log_cmd;
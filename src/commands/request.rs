@@ -1,7 +1,7 @@
 use serde_json::Value;
 use nebulous::config::ClientConfig;
 
-fn prepare_request(
+pub fn prepare_request(
     path: &str,
     method: reqwest::Method,
 ) -> Result<reqwest::RequestBuilder, Box<dyn std::error::Error>> {
@@ -22,9 +22,24 @@ pub async fn execute(
     let app_state = create_app_state().await?;
     let app = create_app(app_state.clone()).await;
 
+    println!("Starting metering pipeline");
+    nebulous::meters::init_meter_pipeline(app_state.db_pool.clone())?;
+    nebulous::meters::compute::spawn_compute_meter(app_state.db_pool.clone());
+    println!("Metering pipeline started");
+
+    nebulous::auth::cache::spawn_revocation_listener(app_state.message_queue.clone());
+    nebulous::auth::api::spawn_last_used_flusher(app_state.db_pool.clone());
+    nebulous::resources::v1::secrets::expiry::spawn_expiry_sweeper(app_state.db_pool.clone());
+    nebulous::billing::budget::spawn_budget_enforcer(app_state.db_pool.clone());
+    nebulous::resources::v1::namespaces::finalizer::spawn_namespace_finalizer(app_state.clone());
+    nebulous::resources::v1::gc::collector::spawn_garbage_collector(app_state.clone());
+    nebulous::resources::v1::events::recorder::spawn_event_pruner(app_state.db_pool.clone());
+    nebulous::resources::v1::watch::feed::init_change_feed(&app_state.message_queue);
+
     println!("Starting container controller");
     let controller = ContainerController::new(std::sync::Arc::new(app_state.clone()));
     controller.spawn_reconciler();
+    nebulous::resources::v1::containers::idle::spawn_idle_reaper(app_state.db_pool.clone());
     println!("Container controller started");
 
     println!("Starting processor controller");
@@ -65,7 +80,11 @@ pub async fn execute(
     let addr = format!("{}:{}", host, port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     println!("Server running at http://{}", addr);
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
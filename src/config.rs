@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -48,9 +49,15 @@ impl ClientConfig {
     }
 
     fn create_config_from_environment(&mut self) {
-        let env_api_key = env::var("NEBU_API_KEY")
-            .or_else(|_| env::var("AGENTSEA_API_KEY"))
-            .ok();
+        // Containers keep their current workload token in a file that is
+        // refreshed while they run
+        let env_api_key = env::var("NEBU_API_KEY_FILE")
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .or_else(|| env::var("NEBU_API_KEY").ok())
+            .or_else(|| env::var("AGENTSEA_API_KEY").ok());
         let env_server = env::var("NEBU_SERVER")
             .or_else(|_| env::var("AGENTSEA_SERVER"))
             .ok();
@@ -154,10 +161,22 @@ pub struct ServerConfig {
     pub kafka: KafkaConfig,
     pub vpn: VpnConfig,
     pub auth: ServerAuthConfig,
+    pub meters: MeterConfig,
+    pub rate_limits: RateLimitConfig,
+    pub encryption: EncryptionConfig,
+    pub secret_providers: SecretProviderConfig,
+    pub secret_expiry: SecretExpiryConfig,
+    pub budgets: BudgetConfig,
+    pub gc: GcConfig,
+    pub events: EventConfig,
+    pub watch: WatchConfig,
     pub bucket_name: String,
     pub bucket_region: String,
     pub root_owner: String,
     pub publish_url: Option<String>,
+    /// Proxies whose `X-Forwarded-For` header is believed, as IPs or CIDR
+    /// ranges from `NEBU_TRUSTED_PROXIES` (comma separated)
+    pub trusted_proxies: Vec<(IpAddr, u8)>,
 }
 
 #[derive(Debug, Clone)]
@@ -251,6 +270,64 @@ impl KafkaConfig {
 pub struct ServerAuthConfig {
     pub internal: bool,
     pub url: String,
+    /// How long an authenticated token is trusted before it is checked again.
+    pub cache_ttl_secs: u64,
+    pub cache_max_entries: usize,
+    /// How often batched API key `last_used_at` updates are written.
+    pub last_used_flush_secs: u64,
+    /// OIDC provider to federate logins with, when configured.
+    pub oidc: Option<OidcConfig>,
+    /// Key for signing container workload tokens, from
+    /// `NEBU_WORKLOAD_TOKEN_SECRET` only. Containers fall back to long-lived
+    /// agent keys when unset.
+    pub workload_token_secret: Option<String>,
+    /// How long a container workload token is valid.
+    pub workload_token_ttl_secs: u64,
+}
+
+/// OIDC provider settings for the internal auth server. Set `NEBU_OIDC_ISSUER`
+/// and `NEBU_OIDC_CLIENT_ID` to enable login.
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: Option<String>,
+    pub scopes: String,
+    /// ID token claim listing the user's organizations.
+    pub orgs_claim: String,
+    /// ID token claim holding the user's role.
+    pub role_claim: String,
+    /// ID token claim naming the user's primary organization. Without it, the
+    /// first org in the orgs claim is used.
+    pub org_claim: Option<String>,
+    /// How long API keys issued at login are valid.
+    pub session_ttl_secs: u64,
+}
+
+impl OidcConfig {
+    pub fn new() -> Option<Self> {
+        dotenv().ok();
+
+        let issuer = env::var("NEBU_OIDC_ISSUER").ok()?;
+        let client_id = env::var("NEBU_OIDC_CLIENT_ID").ok()?;
+
+        Some(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: env::var("NEBU_OIDC_CLIENT_SECRET").ok(),
+            redirect_url: env::var("NEBU_OIDC_REDIRECT_URL").ok(),
+            scopes: env::var("NEBU_OIDC_SCOPES")
+                .unwrap_or_else(|_| "openid email profile".to_string()),
+            orgs_claim: env::var("NEBU_OIDC_ORGS_CLAIM").unwrap_or_else(|_| "orgs".to_string()),
+            role_claim: env::var("NEBU_OIDC_ROLE_CLAIM").unwrap_or_else(|_| "role".to_string()),
+            org_claim: env::var("NEBU_OIDC_ORG_CLAIM").ok(),
+            session_ttl_secs: env::var("NEBU_OIDC_SESSION_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30 * 24 * 60 * 60),
+        })
+    }
 }
 
 impl ServerAuthConfig {
@@ -266,6 +343,346 @@ impl ServerAuthConfig {
         Self {
             internal: true,
             url,
+            cache_ttl_secs: env::var("NEBU_AUTH_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            cache_max_entries: env::var("NEBU_AUTH_CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            last_used_flush_secs: env::var("NEBU_AUTH_LAST_USED_FLUSH_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            oidc: OidcConfig::new(),
+            workload_token_secret: env::var("NEBU_WORKLOAD_TOKEN_SECRET").ok(),
+            workload_token_ttl_secs: env::var("NEBU_WORKLOAD_TOKEN_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MeterConfig {
+    pub sinks: Vec<String>,
+    pub openmeter_url: Option<String>,
+    pub openmeter_token: Option<String>,
+    pub webhook_url: Option<String>,
+    pub webhook_token: Option<String>,
+    pub batch_size: usize,
+    pub flush_interval_ms: u64,
+    pub max_retries: u32,
+    /// Replays of a spooled event before it is dead-lettered and left for an operator
+    pub max_spool_attempts: i32,
+    pub queue_capacity: usize,
+    pub compute_interval_secs: u64,
+}
+
+impl MeterConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        let openmeter_url = env::var("OPENMETER_URL").ok();
+        let openmeter_token = env::var("OPENMETER_TOKEN").ok();
+
+        // Default to the built-in ledger, plus OpenMeter when it is configured
+        let sinks = match env::var("NEBU_METER_SINKS") {
+            Ok(sinks) => sinks
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Err(_) => {
+                let mut sinks = vec!["ledger".to_string()];
+                if openmeter_url.is_some() && openmeter_token.is_some() {
+                    sinks.push("openmeter".to_string());
+                }
+                sinks
+            }
+        };
+
+        Self {
+            sinks,
+            openmeter_url,
+            openmeter_token,
+            webhook_url: env::var("NEBU_METER_WEBHOOK_URL").ok(),
+            webhook_token: env::var("NEBU_METER_WEBHOOK_TOKEN").ok(),
+            batch_size: env::var("NEBU_METER_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(100),
+            flush_interval_ms: env::var("NEBU_METER_FLUSH_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(5000),
+            max_retries: env::var("NEBU_METER_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(3),
+            max_spool_attempts: env::var("NEBU_METER_MAX_SPOOL_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse::<i32>().ok())
+                .unwrap_or(20),
+            queue_capacity: env::var("NEBU_METER_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(10_000),
+            compute_interval_secs: env::var("NEBU_COMPUTE_METER_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(60),
+        }
+    }
+}
+
+/// Default token bucket limits applied when a namespace or API key has no
+/// limit of its own. Unset means unlimited.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub namespace: Option<crate::models::V1RateLimit>,
+    pub api_key: Option<crate::models::V1RateLimit>,
+}
+
+impl RateLimitConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        let read_limit = |rps_var: &str, burst_var: &str| {
+            let rps = env::var(rps_var).ok()?.parse::<f64>().ok()?;
+            if rps <= 0.0 {
+                return None;
+            }
+            let burst = env::var(burst_var)
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok());
+            Some(crate::models::V1RateLimit::new(rps, burst))
+        };
+
+        Self {
+            namespace: read_limit(
+                "NEBU_RATE_LIMIT_NAMESPACE_RPS",
+                "NEBU_RATE_LIMIT_NAMESPACE_BURST",
+            ),
+            api_key: read_limit("NEBU_RATE_LIMIT_KEY_RPS", "NEBU_RATE_LIMIT_KEY_BURST"),
+        }
+    }
+}
+
+/// Key-encryption keys for secrets. Each secret's data key is wrapped by the
+/// active key; older keys stay listed until `neb admin rotate-keys` re-wraps
+/// every secret.
+#[derive(Clone)]
+pub struct EncryptionConfig {
+    /// `id=key` pairs from `NEBU_ENCRYPTION_KEYS` (comma separated) and
+    /// `NEBU_ENCRYPTION_KEYS_FILE` (one per line). Keys are 32 raw bytes or
+    /// base64 of 32 bytes.
+    pub keys: Vec<(String, String)>,
+    /// Key id new secrets are wrapped with. Defaults to the first key.
+    pub active_key: Option<String>,
+    /// The original single key, `NEBU_ENCRYPTION_KEY`, available as key id `legacy`.
+    pub legacy_key: Option<String>,
+    /// External command that wraps and unwraps data keys, e.g. a KMS client.
+    pub kms_plugin: Option<String>,
+}
+
+impl std::fmt::Debug for EncryptionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionConfig")
+            .field(
+                "keys",
+                &self.keys.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            )
+            .field("active_key", &self.active_key)
+            .field("legacy_key", &self.legacy_key.as_ref().map(|_| "<redacted>"))
+            .field("kms_plugin", &self.kms_plugin)
+            .finish()
+    }
+}
+
+impl EncryptionConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        let parse_pair = |pair: &str| {
+            let (id, key) = pair.trim().split_once('=')?;
+            Some((id.trim().to_string(), key.trim().to_string()))
+        };
+
+        let mut keys: Vec<(String, String)> = env::var("NEBU_ENCRYPTION_KEYS")
+            .map(|v| v.split(',').filter_map(parse_pair).collect())
+            .unwrap_or_default();
+        if let Ok(path) = env::var("NEBU_ENCRYPTION_KEYS_FILE") {
+            let contents = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Failed to read NEBU_ENCRYPTION_KEYS_FILE {}: {}", path, e));
+            keys.extend(
+                contents
+                    .lines()
+                    .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+                    .filter_map(parse_pair),
+            );
+        }
+
+        Self {
+            keys,
+            active_key: env::var("NEBU_ENCRYPTION_ACTIVE_KEY").ok(),
+            legacy_key: env::var("NEBU_ENCRYPTION_KEY").ok(),
+            kms_plugin: env::var("NEBU_KMS_PLUGIN").ok(),
+        }
+    }
+}
+
+/// External backends secrets can reference instead of storing their value.
+/// References are always resolved inside the secret's namespace.
+#[derive(Clone)]
+pub struct SecretProviderConfig {
+    pub vault_addr: Option<String>,
+    pub vault_token: Option<String>,
+    pub vault_namespace: Option<String>,
+    /// KV v2 mount holding `<namespace>/<path>` secrets.
+    pub vault_mount: String,
+    /// Directory holding `<namespace>/<path>` secret files.
+    pub file_root: Option<String>,
+    /// Prefix of env vars that can be referenced, as `<prefix><NAMESPACE>_<PATH>`.
+    pub env_prefix: String,
+}
+
+impl std::fmt::Debug for SecretProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretProviderConfig")
+            .field("vault_addr", &self.vault_addr)
+            .field("vault_token", &self.vault_token.as_ref().map(|_| "<redacted>"))
+            .field("vault_namespace", &self.vault_namespace)
+            .field("vault_mount", &self.vault_mount)
+            .field("file_root", &self.file_root)
+            .field("env_prefix", &self.env_prefix)
+            .finish()
+    }
+}
+
+impl SecretProviderConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            vault_addr: env::var("VAULT_ADDR")
+                .ok()
+                .map(|addr| addr.trim_end_matches('/').to_string()),
+            vault_token: env::var("VAULT_TOKEN").ok(),
+            vault_namespace: env::var("VAULT_NAMESPACE").ok(),
+            vault_mount: env::var("NEBU_VAULT_MOUNT").unwrap_or_else(|_| "secret".to_string()),
+            file_root: env::var("NEBU_SECRET_FILE_ROOT").ok(),
+            env_prefix: env::var("NEBU_SECRET_ENV_PREFIX")
+                .unwrap_or_else(|_| "NEBU_SECRET_".to_string()),
+        }
+    }
+}
+
+/// Expired secrets are refused immediately and deleted once the grace period
+/// has passed.
+#[derive(Debug, Clone)]
+pub struct SecretExpiryConfig {
+    pub grace_secs: u64,
+    pub sweep_interval_secs: u64,
+}
+
+impl SecretExpiryConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            grace_secs: env::var("NEBU_SECRET_EXPIRY_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(7 * 24 * 3600),
+            sweep_interval_secs: env::var("NEBU_SECRET_EXPIRY_SWEEP_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(3600),
+        }
+    }
+}
+
+/// Budgets are re-evaluated against the usage ledger on this interval.
+#[derive(Debug, Clone)]
+pub struct BudgetConfig {
+    pub check_interval_secs: u64,
+}
+
+impl BudgetConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            check_interval_secs: env::var("NEBU_BUDGET_CHECK_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(300),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GcConfig {
+    pub interval_secs: u64,
+    /// How long a dependent must have existed before it is collected, so
+    /// resources created just ahead of their owner are left alone
+    pub grace_secs: u64,
+}
+
+impl GcConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            interval_secs: env::var("NEBU_GC_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(60),
+            grace_secs: env::var("NEBU_GC_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(300),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventConfig {
+    /// How long container and processor events are kept after they were last seen
+    pub retention_secs: u64,
+}
+
+impl EventConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            retention_secs: env::var("NEBU_EVENT_RETENTION_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(7 * 24 * 3600),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Changes kept in the change feed; watches resuming from an older token must list again
+    pub history: u64,
+}
+
+impl WatchConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            history: env::var("NEBU_WATCH_HISTORY")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(10000),
         }
     }
 }
@@ -329,6 +746,15 @@ impl ServerConfig {
         let vpn = VpnConfig::new();
         
         let auth = ServerAuthConfig::new();
+        let meters = MeterConfig::new();
+        let rate_limits = RateLimitConfig::new();
+        let encryption = EncryptionConfig::new();
+        let secret_providers = SecretProviderConfig::new();
+        let secret_expiry = SecretExpiryConfig::new();
+        let budgets = BudgetConfig::new();
+        let gc = GcConfig::new();
+        let events = EventConfig::new();
+        let watch = WatchConfig::new();
 
         Self {
             database_url,
@@ -337,6 +763,15 @@ impl ServerConfig {
             kafka,
             vpn,
             auth,
+            meters,
+            rate_limits,
+            encryption,
+            secret_providers,
+            secret_expiry,
+            budgets,
+            gc,
+            events,
+            watch,
             bucket_name: env::var("NEBU_BUCKET_NAME")
                 .unwrap_or_else(|_| panic!("NEBU_BUCKET_NAME environment variable must be set")),
             bucket_region: env::var("NEBU_BUCKET_REGION")
@@ -346,9 +781,33 @@ impl ServerConfig {
             publish_url: env::var("NEBU_PUBLISH_URL")
                 .or_else(|_| env::var("NEBULOUS_PUBLISH_URL"))
                 .ok(),
+            trusted_proxies: env::var("NEBU_TRUSTED_PROXIES")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|range| !range.is_empty())
+                        .map(|range| {
+                            parse_ip_range(range).unwrap_or_else(|| {
+                                panic!("Invalid entry '{}' in NEBU_TRUSTED_PROXIES", range)
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
         }
     }
 }
 
+/// Parse an IP address or CIDR range into its address and prefix length.
+pub fn parse_ip_range(range: &str) -> Option<(IpAddr, u8)> {
+    let (ip, prefix) = match range.split_once('/') {
+        Some((ip, prefix)) => (ip.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+        None => (range.parse::<IpAddr>().ok()?, None),
+    };
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then_some((ip, prefix))
+}
+
 // Global static CONFIG instance
 pub static SERVER_CONFIG: Lazy<ServerConfig> = Lazy::new(ServerConfig::new);
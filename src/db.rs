@@ -106,6 +106,15 @@ async fn create_tables(db: &DbPool) -> Result<(), DbErr> {
     )
     .await?;
 
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::secret_versions::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
     db.execute(
         db.get_database_backend().build(
             schema
@@ -142,5 +151,104 @@ async fn create_tables(db: &DbPool) -> Result<(), DbErr> {
     )
     .await?;
 
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::meter_events::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::usage_records::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::price_books::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::invoices::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::budgets::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::roles::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::role_bindings::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::namespace_grants::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::admission_policies::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::audit_events::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
+    db.execute(
+        db.get_database_backend().build(
+            schema
+                .create_table_from_entity(crate::entities::resource_events::Entity)
+                .if_not_exists(),
+        ),
+    )
+    .await?;
+
     Ok(())
 }
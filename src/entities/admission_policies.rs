@@ -0,0 +1,67 @@
+use crate::resources::v1::policies::models::{
+    V1AdmissionPolicy, V1AdmissionPolicySpec, V1PolicyViolation,
+};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Checks and mutations applied to resources created in a namespace, or in
+/// every namespace when `namespace` is `*`.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "admission_policies")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub namespace: String,
+    pub name: String,
+    #[sea_orm(unique, column_type = "Text")]
+    pub full_name: String,
+    pub owner: String,
+    pub spec: Json,
+    pub violations: Option<Json>,
+    pub labels: Option<Json>,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `spec` into a `V1AdmissionPolicySpec`.
+    pub fn parse_spec(&self) -> Result<V1AdmissionPolicySpec, serde_json::Error> {
+        serde_json::from_value(self.spec.clone())
+    }
+
+    /// Attempt to parse `violations` into a list of `V1PolicyViolation`.
+    pub fn parse_violations(&self) -> Vec<V1PolicyViolation> {
+        self.violations
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn to_v1(&self) -> V1AdmissionPolicy {
+        V1AdmissionPolicy {
+            kind: "AdmissionPolicy".to_string(),
+            metadata: crate::models::V1ResourceMeta {
+                id: self.id.clone(),
+                name: self.name.clone(),
+                namespace: self.namespace.clone(),
+                labels: self
+                    .labels
+                    .as_ref()
+                    .and_then(|json| serde_json::from_value(json.clone()).ok()),
+                owner: self.owner.clone(),
+                owner_ref: None,
+                created_by: self.created_by.clone(),
+                created_at: self.created_at.timestamp(),
+                updated_at: self.updated_at.timestamp(),
+            },
+            spec: self.parse_spec().unwrap_or_default(),
+            violations: self.parse_violations(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::audit::models::V1AuditEvent;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An append-only record of an API call. Rows are never updated or deleted
+/// through the API.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub actor: String,
+    pub api_key_id: Option<String>,
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub request_id: String,
+    pub status: i32,
+    pub changes: Option<Json>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn to_v1(&self) -> V1AuditEvent {
+        V1AuditEvent {
+            id: self.id.clone(),
+            actor: self.actor.clone(),
+            api_key_id: self.api_key_id.clone(),
+            action: self.action.clone(),
+            method: self.method.clone(),
+            path: self.path.clone(),
+            kind: self.kind.clone(),
+            namespace: self.namespace.clone(),
+            name: self.name.clone(),
+            request_id: self.request_id.clone(),
+            status: self.status,
+            changes: self
+                .changes
+                .as_ref()
+                .and_then(|json| serde_json::from_value(json.clone()).ok()),
+            source_ip: self.source_ip.clone(),
+            user_agent: self.user_agent.clone(),
+            created_at: self.created_at.timestamp(),
+        }
+    }
+}
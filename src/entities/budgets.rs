@@ -0,0 +1,65 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A spend limit on a namespace, or on all of an owner's namespaces when
+/// `namespace` is unset. `spent`, `last_threshold` and `exhausted` are kept
+/// current by the budget enforcer for the period starting at `period_start`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "budgets")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    pub namespace: Option<String>,
+    pub amount: f64,
+    pub currency: String,
+    pub period: String,
+    pub thresholds: Json,
+    pub action: String,
+    pub webhook_url: Option<String>,
+    pub spent: f64,
+    pub period_start: Option<DateTimeWithTimeZone>,
+    pub last_threshold: Option<f64>,
+    pub exhausted: bool,
+    pub checked_at: Option<DateTimeWithTimeZone>,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `thresholds` into a list of percentages.
+    pub fn parse_thresholds(&self) -> Result<Vec<f64>, serde_json::Error> {
+        serde_json::from_value(self.thresholds.clone())
+    }
+
+    pub fn to_v1(&self) -> crate::billing::models::V1Budget {
+        crate::billing::models::V1Budget {
+            kind: "Budget".to_string(),
+            id: self.id.clone(),
+            name: self.name.clone(),
+            owner: self.owner.clone(),
+            namespace: self.namespace.clone(),
+            amount: self.amount,
+            currency: self.currency.clone(),
+            period: self.period.clone(),
+            thresholds: self.parse_thresholds().unwrap_or_default(),
+            action: self.action.clone(),
+            webhook_url: self.webhook_url.clone(),
+            spent: self.spent,
+            period_start: self.period_start.map(|t| t.timestamp()),
+            last_threshold: self.last_threshold,
+            exhausted: self.exhausted,
+            checked_at: self.checked_at.map(|t| t.timestamp()),
+            created_by: self.created_by.clone(),
+            created_at: self.created_at.timestamp(),
+            updated_at: self.updated_at.timestamp(),
+        }
+    }
+}
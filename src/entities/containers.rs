@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use crate::models::{V1AuthzConfig, V1Meter};
 use crate::resources::v1::containers::models::{
     V1Container, V1ContainerHealthCheck, V1ContainerResources, V1ContainerStatus, V1EnvVar,
-    V1PortRequest, V1SSHKey,
+    V1IdlePolicy, V1PortRequest, V1SSHKey, V1SecretFile,
 };
 use crate::resources::v1::volumes::models::V1VolumePath;
 
@@ -55,6 +55,12 @@ pub struct Model {
     pub controller_data: Option<Json>,
     pub container_user: Option<String>,
     pub ssh_keys: Option<Json>,
+    pub idle: Option<Json>,
+    pub restart_on_secret_change: Option<bool>,
+    pub secret_files: Option<Json>,
+    /// Version or hash of each secret in `env` when the container was started
+    pub secret_fingerprints: Option<Json>,
+    pub last_active_at: Option<DateTimeWithTimeZone>,
     pub updated_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
 }
@@ -160,6 +166,35 @@ impl Model {
         }
     }
 
+    /// Attempt to parse `idle` into a `V1IdlePolicy`.
+    pub fn parse_idle(&self) -> Result<Option<V1IdlePolicy>, serde_json::Error> {
+        if let Some(json_value) = &self.idle {
+            serde_json::from_value(json_value.clone()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Attempt to parse `secret_files` into a vector of `V1SecretFile`.
+    pub fn parse_secret_files(&self) -> Result<Option<Vec<V1SecretFile>>, serde_json::Error> {
+        if let Some(json_value) = &self.secret_files {
+            serde_json::from_value(json_value.clone()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Attempt to parse `secret_fingerprints` into a map of secret name to fingerprint.
+    pub fn parse_secret_fingerprints(
+        &self,
+    ) -> Result<Option<HashMap<String, String>>, serde_json::Error> {
+        if let Some(json_value) = &self.secret_fingerprints {
+            serde_json::from_value(json_value.clone()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Attempt to parse `health_check` into a `V1ContainerHealthCheck`.
     pub fn parse_health_check(&self) -> Result<Option<V1ContainerHealthCheck>, serde_json::Error> {
         if let Some(json_value) = &self.health_check {
@@ -182,6 +217,8 @@ impl Model {
         let ports = self.parse_ports()?;
         let authz = self.parse_authz()?;
         let health_check = self.parse_health_check()?;
+        let idle = self.parse_idle()?;
+        let secret_files = self.parse_secret_files()?;
 
         // Build metadata; fill with defaults or unwrap as needed
         let metadata = crate::models::V1ResourceMeta {
@@ -218,6 +255,9 @@ impl Model {
             ports: ports.clone(),
             proxy_port: self.proxy_port.clone(),
             authz,
+            idle,
+            restart_on_secret_change: self.restart_on_secret_change,
+            secret_files,
         };
 
         Ok(container)
@@ -0,0 +1,54 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An invoice generated for one owner over a billing period. Line items are
+/// frozen at generation time so later price book changes do not alter it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "invoices")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub owner: String,
+    pub period_start: DateTimeWithTimeZone,
+    pub period_end: DateTimeWithTimeZone,
+    pub currency: String,
+    pub subtotal: f64,
+    pub markup: f64,
+    pub total: f64,
+    pub line_items: Json,
+    pub status: String,
+    pub created_by: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `line_items` into a list of `V1InvoiceLineItem`.
+    pub fn parse_line_items(
+        &self,
+    ) -> Result<Vec<crate::billing::models::V1InvoiceLineItem>, serde_json::Error> {
+        serde_json::from_value(self.line_items.clone())
+    }
+
+    pub fn to_v1(&self) -> crate::billing::models::V1Invoice {
+        crate::billing::models::V1Invoice {
+            kind: "Invoice".to_string(),
+            id: self.id.clone(),
+            owner: self.owner.clone(),
+            period_start: self.period_start.timestamp(),
+            period_end: self.period_end.timestamp(),
+            currency: self.currency.clone(),
+            line_items: self.parse_line_items().unwrap_or_default(),
+            subtotal: self.subtotal,
+            markup: self.markup,
+            total: self.total,
+            status: self.status.clone(),
+            created_by: self.created_by.clone(),
+            created_at: self.created_at.timestamp(),
+        }
+    }
+}
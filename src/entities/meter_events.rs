@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Spool of meter events that could not be delivered to a metering sink.
+/// Rows are replayed by the metering pipeline and deleted once delivered.
+/// Rows the sink keeps rejecting are dead-lettered and no longer replayed.
+/// The primary key is `<sink>:<event id>` so one event can be spooled per sink.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "meter_events")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub sink: String,
+    pub event: Json,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub dead_lettered_at: Option<DateTimeWithTimeZone>,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `event` into a `MeterEvent`.
+    pub fn parse_event(&self) -> Result<crate::meters::MeterEvent, serde_json::Error> {
+        serde_json::from_value(self.event.clone())
+    }
+}
@@ -1,6 +1,18 @@
 // src/entities/mod.rs
+pub mod admission_policies;
+pub mod audit_events;
+pub mod budgets;
 pub mod containers;
+pub mod invoices;
+pub mod meter_events;
+pub mod namespace_grants;
 pub mod namespaces;
+pub mod price_books;
 pub mod processors;
+pub mod resource_events;
+pub mod role_bindings;
+pub mod roles;
+pub mod secret_versions;
 pub mod secrets;
+pub mod usage_records;
 pub mod volumes;
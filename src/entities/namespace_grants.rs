@@ -0,0 +1,41 @@
+use crate::resources::v1::namespaces::models::V1NamespaceGrant;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Shares a namespace with a user or organization that doesn't own it,
+/// granting them a built-in or custom role within it.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "namespace_grants")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub namespace: String,
+    /// A user's email or an organization id
+    pub subject: String,
+    /// `User` or `Org`
+    pub subject_kind: String,
+    pub role: String,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn to_v1(&self) -> V1NamespaceGrant {
+        V1NamespaceGrant {
+            id: self.id.clone(),
+            namespace: self.namespace.clone(),
+            subject: self.subject.clone(),
+            subject_kind: self.subject_kind.clone(),
+            role: self.role.clone(),
+            created_by: self.created_by.clone(),
+            updated_at: self.updated_at.timestamp(),
+            created_at: self.created_at.timestamp(),
+        }
+    }
+}
@@ -12,6 +12,13 @@ pub struct Model {
     pub owner: String,
     pub owner_ref: Option<String>,
     pub labels: Option<Json>,
+    pub rate_limit: Option<Json>,
+    pub quota: Option<Json>,
+    pub container_defaults: Option<Json>,
+    pub container_enforced: Option<Json>,
+    pub finalizers: Option<Json>,
+    pub deletion_started_at: Option<DateTimeWithTimeZone>,
+    pub deletion_status: Option<Json>,
     pub created_by: String,
     pub updated_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
@@ -39,6 +46,13 @@ impl Model {
             owner,
             owner_ref: None,
             labels,
+            rate_limit: None,
+            quota: None,
+            container_defaults: None,
+            container_enforced: None,
+            finalizers: None,
+            deletion_started_at: None,
+            deletion_status: None,
             created_by,
             updated_at: now,
             created_at: now,
@@ -62,6 +76,75 @@ impl Model {
                 created_at: self.created_at.timestamp(),
                 updated_at: self.updated_at.timestamp(),
             },
+            rate_limit: self.parse_rate_limit(),
+            quota: self.parse_quota(),
+            usage: None,
+            container_defaults: self.parse_container_defaults(),
+            container_enforced: self.parse_container_enforced(),
+            status: Some(self.status()),
         }
     }
+
+    /// Attempt to parse `rate_limit` into a `V1RateLimit`.
+    pub fn parse_rate_limit(&self) -> Option<crate::models::V1RateLimit> {
+        self.rate_limit
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+    }
+
+    /// Attempt to parse `quota` into a `V1NamespaceQuota`.
+    pub fn parse_quota(
+        &self,
+    ) -> Option<crate::resources::v1::namespaces::models::V1NamespaceQuota> {
+        self.quota
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+    }
+
+    /// Attempt to parse `container_defaults` into a `V1ContainerDefaults`.
+    pub fn parse_container_defaults(
+        &self,
+    ) -> Option<crate::resources::v1::namespaces::models::V1ContainerDefaults> {
+        self.container_defaults
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+    }
+
+    /// Attempt to parse `container_enforced` into a `V1ContainerEnforced`.
+    pub fn parse_container_enforced(
+        &self,
+    ) -> Option<crate::resources::v1::namespaces::models::V1ContainerEnforced> {
+        self.container_enforced
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+    }
+
+    /// Attempt to parse `finalizers` into a list of finalizer names.
+    pub fn parse_finalizers(&self) -> Vec<String> {
+        self.finalizers
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_terminating(&self) -> bool {
+        self.deletion_started_at.is_some()
+    }
+
+    /// The namespace's phase, with deletion progress while it is terminating.
+    pub fn status(&self) -> crate::resources::v1::namespaces::models::V1NamespaceStatus {
+        let mut status: crate::resources::v1::namespaces::models::V1NamespaceStatus = self
+            .deletion_status
+            .as_ref()
+            .and_then(|json| serde_json::from_value(json.clone()).ok())
+            .unwrap_or_default();
+        status.phase = if self.is_terminating() {
+            "Terminating".to_string()
+        } else {
+            "Active".to_string()
+        };
+        status.deletion_started_at = self.deletion_started_at.map(|t| t.timestamp());
+        status.finalizers = self.parse_finalizers();
+        status
+    }
 }
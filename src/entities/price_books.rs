@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Per-owner price book used when generating invoices. The owner is the
+/// organization or user being billed, so there is at most one book per owner.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "price_books")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub owner: String,
+    pub currency: String,
+    pub compute_markup_percent: f64,
+    pub default_markup_percent: f64,
+    pub prices: Json,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `prices` into a list of `V1MeterPrice`.
+    pub fn parse_prices(
+        &self,
+    ) -> Result<Vec<crate::billing::models::V1MeterPrice>, serde_json::Error> {
+        serde_json::from_value(self.prices.clone())
+    }
+
+    pub fn to_v1(&self) -> crate::billing::models::V1PriceBook {
+        crate::billing::models::V1PriceBook {
+            kind: "PriceBook".to_string(),
+            owner: self.owner.clone(),
+            currency: self.currency.clone(),
+            compute_markup_percent: self.compute_markup_percent,
+            default_markup_percent: self.default_markup_percent,
+            prices: self.parse_prices().unwrap_or_default(),
+            created_by: Some(self.created_by.clone()),
+            created_at: Some(self.created_at.timestamp()),
+            updated_at: Some(self.updated_at.timestamp()),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::resources::v1::events::models::V1ResourceEvent;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An event in the history of a container or processor. Events outlive the
+/// resource and are pruned by age.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "resource_events")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    pub resource_id: String,
+    pub event_type: String,
+    pub reason: String,
+    pub message: String,
+    pub count: i32,
+    pub last_seen_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn to_v1(&self) -> V1ResourceEvent {
+        V1ResourceEvent {
+            id: self.id.clone(),
+            kind: self.kind.clone(),
+            namespace: self.namespace.clone(),
+            name: self.name.clone(),
+            resource_id: self.resource_id.clone(),
+            event_type: self.event_type.clone(),
+            reason: self.reason.clone(),
+            message: self.message.clone(),
+            count: self.count,
+            first_seen: self.created_at.timestamp(),
+            last_seen: self.last_seen_at.timestamp(),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::resources::v1::roles::models::{V1RoleBinding, V1RoleSubject};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Grants a built-in or custom role to users, org roles or API keys within
+/// one namespace.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "role_bindings")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub namespace: String,
+    pub name: String,
+    #[sea_orm(unique, column_type = "Text")]
+    pub full_name: String,
+    pub owner: String,
+    pub role: String,
+    pub subjects: Json,
+    pub labels: Option<Json>,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `subjects` into a list of `V1RoleSubject`.
+    pub fn parse_subjects(&self) -> Result<Vec<V1RoleSubject>, serde_json::Error> {
+        serde_json::from_value(self.subjects.clone())
+    }
+
+    pub fn to_v1(&self) -> V1RoleBinding {
+        V1RoleBinding {
+            kind: "RoleBinding".to_string(),
+            metadata: crate::models::V1ResourceMeta {
+                id: self.id.clone(),
+                name: self.name.clone(),
+                namespace: self.namespace.clone(),
+                labels: self
+                    .labels
+                    .as_ref()
+                    .and_then(|json| serde_json::from_value(json.clone()).ok()),
+                owner: self.owner.clone(),
+                owner_ref: None,
+                created_by: self.created_by.clone(),
+                created_at: self.created_at.timestamp(),
+                updated_at: self.updated_at.timestamp(),
+            },
+            role: self.role.clone(),
+            subjects: self.parse_subjects().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::resources::v1::roles::models::{V1PolicyRule, V1Role};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A custom set of verbs on resource kinds, scoped to one namespace.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "roles")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub namespace: String,
+    pub name: String,
+    #[sea_orm(unique, column_type = "Text")]
+    pub full_name: String,
+    pub owner: String,
+    pub rules: Json,
+    pub labels: Option<Json>,
+    pub created_by: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Attempt to parse `rules` into a list of `V1PolicyRule`.
+    pub fn parse_rules(&self) -> Result<Vec<V1PolicyRule>, serde_json::Error> {
+        serde_json::from_value(self.rules.clone())
+    }
+
+    pub fn to_v1(&self) -> V1Role {
+        V1Role {
+            kind: "Role".to_string(),
+            metadata: crate::models::V1ResourceMeta {
+                id: self.id.clone(),
+                name: self.name.clone(),
+                namespace: self.namespace.clone(),
+                labels: self
+                    .labels
+                    .as_ref()
+                    .and_then(|json| serde_json::from_value(json.clone()).ok()),
+                owner: self.owner.clone(),
+                owner_ref: None,
+                created_by: self.created_by.clone(),
+                created_at: self.created_at.timestamp(),
+                updated_at: self.updated_at.timestamp(),
+            },
+            rules: self.parse_rules().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::resources::v1::secrets::keyring::keyring;
+use crate::resources::v1::secrets::models::V1SecretSource;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A previous value of a secret, kept when the secret is updated.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "secret_versions")]
+pub struct Model {
+    /// `<secret_id>-v<version>`
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub secret_id: String,
+    pub version: i32,
+    pub encrypted_value: String,
+    pub nonce: String,
+    pub key_id: Option<String>,
+    pub encrypted_key: Option<String>,
+    pub source: Option<Json>,
+    pub expires_at: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn version_id(secret_id: &str, version: i32) -> String {
+        format!("{}-v{}", secret_id, version)
+    }
+
+    /// Snapshot a secret's current value as a version.
+    pub fn from_secret(secret: &super::secrets::Model) -> Self {
+        let version = secret.current_version();
+        Self {
+            id: Self::version_id(&secret.id, version),
+            secret_id: secret.id.clone(),
+            version,
+            encrypted_value: secret.encrypted_value.clone(),
+            nonce: secret.nonce.clone(),
+            key_id: secret.key_id.clone(),
+            encrypted_key: secret.encrypted_key.clone(),
+            source: secret.source.clone(),
+            expires_at: secret.expires_at,
+            created_at: secret.updated_at,
+        }
+    }
+
+    pub fn decrypt_value(&self) -> Result<String, String> {
+        keyring()?.decrypt(
+            &self.encrypted_value,
+            &self.nonce,
+            self.key_id.as_deref(),
+            self.encrypted_key.as_deref(),
+        )
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| (expires_at as i64) <= chrono::Utc::now().timestamp())
+    }
+
+    pub fn parse_source(&self) -> Option<V1SecretSource> {
+        self.source
+            .as_ref()
+            .and_then(|source| serde_json::from_value(source.clone()).ok())
+    }
+}
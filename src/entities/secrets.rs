@@ -1,12 +1,7 @@
-use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce,
-};
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use rand::{rngs::OsRng, RngCore};
+use crate::resources::v1::secrets::keyring::{keyring, EncryptedValue};
+use crate::resources::v1::secrets::models::V1SecretSource;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::env;
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "secrets")]
@@ -21,11 +16,21 @@ pub struct Model {
     pub owner_ref: Option<String>,
     pub encrypted_value: String,
     pub nonce: String, // Store the nonce used for encryption
+    /// Key-encryption key that wrapped `encrypted_key`; unset for secrets
+    /// encrypted directly with the legacy key.
+    pub key_id: Option<String>,
+    /// The secret's data key, wrapped by `key_id`.
+    pub encrypted_key: Option<String>,
+    /// External `V1SecretSource` the value is resolved from; the stored value
+    /// is empty when set.
+    pub source: Option<Json>,
     pub labels: Option<Json>,
     pub created_by: Option<String>,
     pub updated_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
     pub expires_at: Option<i32>,
+    /// Current version, bumped on every update. Unset means 1.
+    pub version: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -34,71 +39,45 @@ pub enum Relation {}
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Model {
-    // Get encryption key from environment
-    fn get_encryption_key() -> Result<[u8; 32], String> {
-        let key = env::var("NEBU_ENCRYPTION_KEY")
-            .map_err(|_| "NEBU_ENCRYPTION_KEY environment variable not set".to_string())?;
-
-        // Ensure the key is exactly 32 bytes (256 bits)
-        if key.len() != 32 {
-            return Err("NEBU_ENCRYPTION_KEY must be exactly 32 bytes".to_string());
-        }
-
-        let mut result = [0u8; 32];
-        result.copy_from_slice(key.as_bytes());
-        Ok(result)
-    }
-
-    // Encrypt a value
-    pub fn encrypt_value(value: &str) -> Result<(String, String), String> {
-        let key = Self::get_encryption_key()?;
-        let cipher = Aes256Gcm::new_from_slice(&key)
-            .map_err(|e| format!("Failed to create cipher: {}", e))?;
-
-        // Generate a random nonce
-        let mut nonce_bytes = [0u8; 12]; // 96 bits
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-
-        // Encrypt the value
-        let ciphertext = cipher
-            .encrypt(nonce, value.as_bytes())
-            .map_err(|e| format!("Encryption failed: {}", e))?;
-
-        // Encode as base64 for storage
-        let encrypted_value = BASE64.encode(ciphertext);
-        let nonce_value = BASE64.encode(nonce_bytes);
-
-        Ok((encrypted_value, nonce_value))
+    // Encrypt a value under a new data key wrapped by the active key
+    pub fn encrypt_value(value: &str) -> Result<EncryptedValue, String> {
+        keyring()?.encrypt(value)
     }
 
     // Decrypt a value
     pub fn decrypt_value(&self) -> Result<String, String> {
-        let key = Self::get_encryption_key()?;
-        let cipher = Aes256Gcm::new_from_slice(&key)
-            .map_err(|e| format!("Failed to create cipher: {}", e))?;
-
-        // Decode the nonce and ciphertext from base64
-        let nonce_bytes = BASE64
-            .decode(self.nonce.as_bytes())
-            .map_err(|e| format!("Failed to decode nonce: {}", e))?;
-        let ciphertext = BASE64
-            .decode(self.encrypted_value.as_bytes())
-            .map_err(|e| format!("Failed to decode ciphertext: {}", e))?;
+        keyring()?.decrypt(
+            &self.encrypted_value,
+            &self.nonce,
+            self.key_id.as_deref(),
+            self.encrypted_key.as_deref(),
+        )
+    }
 
-        if nonce_bytes.len() != 12 {
-            return Err("Invalid nonce length".to_string());
+    // Decrypt the value stored in nebu; external values are only resolved
+    // when containers are declared
+    pub fn stored_value(&self) -> Option<String> {
+        if self.source.is_some() {
+            return None;
         }
+        self.decrypt_value().ok()
+    }
 
-        let nonce = Nonce::from_slice(&nonce_bytes);
+    pub fn current_version(&self) -> i32 {
+        self.version.unwrap_or(1)
+    }
 
-        // Decrypt the value
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext.as_ref())
-            .map_err(|e| format!("Decryption failed: {}", e))?;
+    // Whether `expires_at` (seconds since the epoch) has passed
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| (expires_at as i64) <= chrono::Utc::now().timestamp())
+    }
 
-        String::from_utf8(plaintext)
-            .map_err(|e| format!("Failed to convert decrypted bytes to string: {}", e))
+    // Parse the external source, if the value lives outside nebu
+    pub fn parse_source(&self) -> Option<V1SecretSource> {
+        self.source
+            .as_ref()
+            .and_then(|source| serde_json::from_value(source.clone()).ok())
     }
 
     // Create a new secret with encrypted value
@@ -112,7 +91,7 @@ impl Model {
         labels: Option<Json>,
         expires_at: Option<i32>,
     ) -> Result<Self, String> {
-        let (encrypted_value, nonce) = Self::encrypt_value(value)?;
+        let encrypted = Self::encrypt_value(value)?;
         let now = chrono::Utc::now().into();
 
         Ok(Self {
@@ -122,13 +101,17 @@ impl Model {
             full_name: format!("{namespace}/{name}"),
             owner,
             owner_ref: None,
-            encrypted_value,
-            nonce,
+            encrypted_value: encrypted.encrypted_value,
+            nonce: encrypted.nonce,
+            key_id: Some(encrypted.key_id),
+            encrypted_key: Some(encrypted.encrypted_key),
+            source: None,
             labels,
             created_by,
             updated_at: now,
             created_at: now,
             expires_at,
+            version: Some(1),
         })
     }
 }
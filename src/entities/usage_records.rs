@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Built-in usage ledger. One row per delivered meter event.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "usage_records")]
+pub struct Model {
+    #[sea_orm(primary_key, column_type = "Text", auto_increment = false)]
+    pub id: String,
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    pub subject: String,
+    pub meter: String,
+    pub source: String,
+    pub quantity: f64,
+    pub unit: Option<String>,
+    pub currency: Option<String>,
+    pub unit_price: Option<f64>,
+    pub amount: f64,
+    pub data: Option<Json>,
+    pub occurred_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
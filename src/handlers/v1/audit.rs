@@ -0,0 +1,137 @@
+use crate::audit::models::{V1AuditEvents, V1AuditQuery};
+use crate::entities::audit_events;
+use crate::models::V1UserProfile;
+use crate::query::Query;
+use crate::rbac::is_root_owner;
+use crate::state::AppState;
+use axum::{
+    extract::{Extension, Query as QueryParam, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde_json::json;
+
+/// Events returned when no `limit` is given.
+const DEFAULT_AUDIT_LIMIT: u64 = 100;
+
+/// Most events returned by one request.
+const MAX_AUDIT_LIMIT: u64 = 10_000;
+
+fn db_error(e: sea_orm::DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) })),
+    )
+}
+
+/// Handler: Query the audit log, newest first, as JSON or with
+/// `?format=jsonl` as one event per line for export.
+///
+/// The root owner sees every event. Everyone else sees their own calls and
+/// the calls made in namespaces they own.
+pub async fn list_audit_events(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    QueryParam(params): QueryParam<V1AuditQuery>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+
+    let mut query = audit_events::Entity::find();
+    if !is_root_owner(&user_profile) {
+        let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
+        let namespaces: Vec<String> = Query::find_owned_namespaces(db_pool, &owner_id_refs)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|n| n.name)
+            .collect();
+        query = query.filter(
+            Condition::any()
+                .add(audit_events::Column::Actor.eq(&user_profile.email))
+                .add(audit_events::Column::Namespace.is_in(namespaces)),
+        );
+    }
+
+    if let Some(actor) = &params.actor {
+        query = query.filter(audit_events::Column::Actor.eq(actor));
+    }
+    if let Some(api_key_id) = &params.api_key_id {
+        query = query.filter(audit_events::Column::ApiKeyId.eq(api_key_id));
+    }
+    if let Some(action) = &params.action {
+        query = query.filter(audit_events::Column::Action.eq(action));
+    }
+    if let Some(kind) = &params.kind {
+        query = query.filter(audit_events::Column::Kind.eq(kind));
+    }
+    if let Some(namespace) = &params.namespace {
+        query = query.filter(audit_events::Column::Namespace.eq(namespace));
+    }
+    if let Some(name) = &params.name {
+        query = query.filter(audit_events::Column::Name.eq(name));
+    }
+    if let Some(request_id) = &params.request_id {
+        query = query.filter(audit_events::Column::RequestId.eq(request_id));
+    }
+    if let Some(since) = params
+        .since
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+    {
+        query = query.filter(audit_events::Column::CreatedAt.gte(since));
+    }
+    if let Some(until) = params
+        .until
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+    {
+        query = query.filter(audit_events::Column::CreatedAt.lt(until));
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .min(MAX_AUDIT_LIMIT);
+    let events: Vec<_> = query
+        .order_by_desc(audit_events::Column::CreatedAt)
+        .limit(limit)
+        .all(db_pool)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|e| e.to_v1())
+        .collect();
+
+    match params.format.as_deref().unwrap_or("json") {
+        "json" => Ok(Json(V1AuditEvents { events }).into_response()),
+        "jsonl" => {
+            let body: String = events
+                .iter()
+                .filter_map(|event| serde_json::to_string(event).ok())
+                .map(|line| line + "\n")
+                .collect();
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        "attachment; filename=\"audit.jsonl\"".to_string(),
+                    ),
+                ],
+                body,
+            )
+                .into_response())
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid format '{}': must be json or jsonl", other) })),
+        )),
+    }
+}
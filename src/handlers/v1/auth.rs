@@ -1,10 +1,118 @@
 // src/handlers/auth.rs
 
+use crate::agent::aws::generate_temporary_s3_credentials;
+use crate::auth::workload::{self, WorkloadClaims, WorkloadToken};
+use crate::config::SERVER_CONFIG;
+use crate::entities::containers;
 use crate::models::V1UserProfile;
-use axum::{extract::Extension, extract::Json, http::StatusCode};
+use crate::state::AppState;
+use axum::{extract::Extension, extract::Json, extract::State, http::HeaderMap, http::StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use tracing::{debug, error};
+
+/// Shortest and longest lifetime STS allows for federation tokens.
+const S3_CREDENTIALS_MIN_SECS: i64 = 15 * 60;
+const S3_CREDENTIALS_MAX_SECS: i64 = 60 * 60;
+
+/// Temporary S3 credentials in the format of the AWS container credentials
+/// provider, so SDKs and rclone can fetch them through
+/// `AWS_CONTAINER_CREDENTIALS_FULL_URI`.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct V1ContainerCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: Option<String>,
+}
 
 pub async fn get_user_profile(
     Extension(user_profile): Extension<V1UserProfile>,
 ) -> Result<Json<V1UserProfile>, (StatusCode, Json<serde_json::Value>)> {
     Ok(Json(user_profile))
 }
+
+/// Authenticate the request's workload token, re-checking the container
+/// rather than trusting the identity cache.
+async fn authenticate_workload(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(WorkloadClaims, containers::Model), (StatusCode, Json<serde_json::Value>)> {
+    let token = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|token| workload::is_workload_token(token))
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Only workload tokens are accepted"})),
+            )
+        })?;
+
+    workload::authenticate_workload_token(&state.db_pool, token)
+        .await
+        .map_err(|e| {
+            debug!("Workload token rejected: {}", e);
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({"error": "Invalid workload token"})),
+            )
+        })
+}
+
+/// Exchange a container's current workload token for a fresh one.
+pub async fn refresh_workload_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<WorkloadToken>, (StatusCode, Json<serde_json::Value>)> {
+    let (_, container) = authenticate_workload(&state, &headers).await?;
+
+    let token = workload::issue_workload_token(&container).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Failed to issue workload token: {}", e)})),
+        )
+    })?;
+    Ok(Json(token))
+}
+
+/// Exchange a container's workload token for temporary S3 credentials limited
+/// to its namespace's data prefix. They last as long as the token, within the
+/// limits STS allows.
+pub async fn get_workload_s3_credentials(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<V1ContainerCredentials>, (StatusCode, Json<serde_json::Value>)> {
+    let (claims, _) = authenticate_workload(&state, &headers).await?;
+
+    let duration_secs = (claims.exp - chrono::Utc::now().timestamp())
+        .clamp(S3_CREDENTIALS_MIN_SECS, S3_CREDENTIALS_MAX_SECS);
+    let credentials = generate_temporary_s3_credentials(
+        &SERVER_CONFIG.bucket_name,
+        &claims.namespace,
+        duration_secs as i32,
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            "Failed to generate S3 credentials for container {}: {}",
+            claims.sub, e
+        );
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to generate S3 credentials"})),
+        )
+    })?;
+
+    Ok(Json(V1ContainerCredentials {
+        access_key_id: credentials.access_key_id,
+        secret_access_key: credentials.secret_access_key,
+        token: credentials.session_token,
+        expiration: credentials
+            .expiration
+            .and_then(|dt| chrono::DateTime::from_timestamp(dt.secs(), 0))
+            .map(|dt| dt.to_rfc3339()),
+    }))
+}
@@ -0,0 +1,633 @@
+use crate::agent::ns::NamespaceTerminating;
+use crate::billing::budget::{evaluate_budget, validate_webhook_url, DEFAULT_THRESHOLDS};
+use crate::billing::models::{
+    V1Budget, V1BudgetRequest, V1BudgetUpdate, V1Budgets, V1Invoice, V1InvoiceFormatQuery,
+    V1InvoiceRequest, V1Invoices, V1PriceBook, V1PriceBookRequest, V1PriceBooks, BUDGET_ACTIONS,
+};
+use crate::billing::{build_line_items, invoice_to_csv, month_bounds};
+use crate::entities::{budgets, invoices, namespaces, price_books};
+use crate::meters::ledger::{is_valid_window, UsageGrouping};
+use crate::models::V1UserProfile;
+use crate::query::Query;
+use crate::rbac::is_root_owner;
+use crate::state::AppState;
+use axum::{
+    extract::{Extension, Path, Query as QueryParam, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+use serde_json::json;
+use tracing::{debug, error, info};
+
+fn owner_ids_for(user_profile: &V1UserProfile) -> Vec<String> {
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+    owner_ids
+}
+
+fn db_error(e: sea_orm::DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    error!("Billing database error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) })),
+    )
+}
+
+/// Handler: List price books. The root owner sees every book, others only their own.
+pub async fn list_price_books(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1PriceBooks>, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+
+    let mut query = price_books::Entity::find();
+    if !is_root_owner(&user_profile) {
+        query = query.filter(price_books::Column::Owner.is_in(owner_ids.clone()));
+    }
+    let books = query.all(&state.db_pool).await.map_err(db_error)?;
+
+    Ok(Json(V1PriceBooks {
+        price_books: books.iter().map(|b| b.to_v1()).collect(),
+    }))
+}
+
+/// Handler: Get the price book for an owner.
+pub async fn get_price_book(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(owner): Path<String>,
+) -> Result<Json<V1PriceBook>, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+    if !is_root_owner(&user_profile) && !owner_ids.contains(&owner) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Price book for '{}' not found", owner) })),
+        ));
+    }
+
+    let book = price_books::Entity::find_by_id(owner.clone())
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Price book for '{}' not found", owner) })),
+            )
+        })?;
+
+    Ok(Json(book.to_v1()))
+}
+
+/// Handler: Create or replace the price book for an owner. Only the root owner
+/// may set prices.
+pub async fn put_price_book(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(owner): Path<String>,
+    Json(request): Json<V1PriceBookRequest>,
+) -> Result<Json<V1PriceBook>, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+    if !is_root_owner(&user_profile) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only the root owner can manage price books" })),
+        ));
+    }
+
+    let prices = request.prices.unwrap_or_default();
+    if let Some(price) = prices.iter().find(|p| {
+        p.unit_price.is_some_and(|v| v < 0.0) || p.markup_percent.is_some_and(|v| v < -100.0)
+    }) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Invalid price for meter '{}': unit_price must be >= 0 and markup_percent >= -100", price.meter)
+            })),
+        ));
+    }
+    let prices_json = serde_json::to_value(&prices).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid prices: {}", e) })),
+        )
+    })?;
+
+    let now = chrono::Utc::now();
+    let existing = price_books::Entity::find_by_id(owner.clone())
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    let saved = match existing {
+        Some(book) => {
+            let mut active: price_books::ActiveModel = book.clone().into();
+            active.currency = Set(request.currency.unwrap_or(book.currency));
+            active.compute_markup_percent = Set(request
+                .compute_markup_percent
+                .unwrap_or(book.compute_markup_percent));
+            active.default_markup_percent = Set(request
+                .default_markup_percent
+                .unwrap_or(book.default_markup_percent));
+            active.prices = Set(prices_json);
+            active.updated_at = Set(now.into());
+            active.update(&state.db_pool).await.map_err(db_error)?
+        }
+        None => {
+            let active = price_books::ActiveModel {
+                owner: Set(owner.clone()),
+                currency: Set(request.currency.unwrap_or_else(|| "USD".to_string())),
+                compute_markup_percent: Set(request.compute_markup_percent.unwrap_or(0.0)),
+                default_markup_percent: Set(request.default_markup_percent.unwrap_or(0.0)),
+                prices: Set(prices_json),
+                created_by: Set(user_profile.email.clone()),
+                updated_at: Set(now.into()),
+                created_at: Set(now.into()),
+            };
+            active.insert(&state.db_pool).await.map_err(db_error)?
+        }
+    };
+    info!("Price book for {} saved by {}", owner, user_profile.email);
+
+    Ok(Json(saved.to_v1()))
+}
+
+/// Handler: Delete the price book for an owner. Only the root owner may do this.
+pub async fn delete_price_book(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(owner): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+    if !is_root_owner(&user_profile) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only the root owner can manage price books" })),
+        ));
+    }
+
+    let result = price_books::Entity::delete_by_id(owner.clone())
+        .exec(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Price book for '{}' not found", owner) })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler: Generate an invoice for an owner over a billing period.
+///
+/// Usage is read from the ledger for every namespace the owner has and priced
+/// with the owner's price book, or at cost if they have none.
+pub async fn create_invoice(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Json(request): Json<V1InvoiceRequest>,
+) -> Result<Json<V1Invoice>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let owner_ids = owner_ids_for(&user_profile);
+    let owner = request
+        .owner
+        .clone()
+        .unwrap_or_else(|| user_profile.email.clone());
+
+    if !is_root_owner(&user_profile) && !owner_ids.contains(&owner) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": format!("Not authorized to bill '{}'", owner) })),
+        ));
+    }
+
+    let (start, end) = match (&request.month, request.start, request.end) {
+        (Some(month), None, None) => month_bounds(month).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Invalid month '{}': expected YYYY-MM", month) })),
+            )
+        })?,
+        (None, Some(start), Some(end)) if start < end => (start, end),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Specify either 'month' (YYYY-MM) or 'start' and 'end' with start < end"
+                })),
+            ))
+        }
+    };
+
+    let price_book = price_books::Entity::find_by_id(owner.clone())
+        .one(db_pool)
+        .await
+        .map_err(db_error)?
+        .map(|b| b.to_v1())
+        .unwrap_or_else(|| V1PriceBook::at_cost(&owner));
+
+    let namespaces: Vec<String> = Query::find_owned_namespaces(db_pool, &[owner.as_str()])
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|n| n.name)
+        .collect();
+    let namespace_refs: Vec<&str> = namespaces.iter().map(|s| s.as_str()).collect();
+    debug!(
+        "Generating invoice for {} over namespaces {:?}",
+        owner, namespace_refs
+    );
+
+    let totals = Query::sum_usage_records(
+        db_pool,
+        &namespace_refs,
+        None,
+        None,
+        Some(start),
+        Some(end),
+        &UsageGrouping::all(),
+    )
+    .await
+    .map_err(db_error)?;
+
+    let line_items = build_line_items(&totals, &price_book);
+    let subtotal: f64 = line_items.iter().map(|i| i.base_cost).sum();
+    let total: f64 = line_items.iter().map(|i| i.amount).sum();
+
+    let line_items_json = serde_json::to_value(&line_items).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Failed to serialize line items: {}", e) })),
+        )
+    })?;
+
+    let now = chrono::Utc::now();
+    let invoice = invoices::ActiveModel {
+        id: Set(short_uuid::ShortUuid::generate().to_string()),
+        owner: Set(owner.clone()),
+        period_start: Set(chrono::DateTime::from_timestamp(start, 0)
+            .unwrap_or(now)
+            .into()),
+        period_end: Set(chrono::DateTime::from_timestamp(end, 0)
+            .unwrap_or(now)
+            .into()),
+        currency: Set(price_book.currency.clone()),
+        subtotal: Set(subtotal),
+        markup: Set(total - subtotal),
+        total: Set(total),
+        line_items: Set(line_items_json),
+        status: Set("issued".to_string()),
+        created_by: Set(user_profile.email.clone()),
+        created_at: Set(now.into()),
+    }
+    .insert(db_pool)
+    .await
+    .map_err(db_error)?;
+
+    info!(
+        "Generated invoice {} for {} ({} line items, total {:.2} {})",
+        invoice.id,
+        owner,
+        line_items.len(),
+        total,
+        invoice.currency
+    );
+
+    Ok(Json(invoice.to_v1()))
+}
+
+/// Handler: List invoices for the user's organizations.
+pub async fn list_invoices(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1Invoices>, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+
+    let rows = invoices::Entity::find()
+        .filter(invoices::Column::Owner.is_in(owner_ids))
+        .order_by_desc(invoices::Column::PeriodStart)
+        .all(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(V1Invoices {
+        invoices: rows.iter().map(|i| i.to_v1()).collect(),
+    }))
+}
+
+/// Handler: Get an invoice as JSON, or as a CSV download with `?format=csv`.
+pub async fn get_invoice(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(id): Path<String>,
+    QueryParam(params): QueryParam<V1InvoiceFormatQuery>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+
+    let mut query = invoices::Entity::find_by_id(id.clone());
+    if !is_root_owner(&user_profile) {
+        query = query.filter(invoices::Column::Owner.is_in(owner_ids));
+    }
+    let invoice = query
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Invoice '{}' not found", id) })),
+            )
+        })?
+        .to_v1();
+
+    match params.format.as_deref().unwrap_or("json") {
+        "json" => Ok(Json(invoice).into_response()),
+        "csv" => {
+            let disposition = format!("attachment; filename=\"invoice-{}.csv\"", invoice.id);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                invoice_to_csv(&invoice),
+            )
+                .into_response())
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid format '{}': must be json or csv", other) })),
+        )),
+    }
+}
+
+/// Check a budget's limits before saving them.
+fn validate_budget(
+    amount: f64,
+    period: &str,
+    thresholds: &[f64],
+    action: &str,
+    webhook_url: Option<&str>,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let webhook_error = webhook_url.and_then(|url| validate_webhook_url(url).err());
+    let error = if amount <= 0.0 {
+        Some("amount must be greater than 0".to_string())
+    } else if !is_valid_window(period) {
+        Some(format!(
+            "Invalid period '{}': must be one of hour, day, month",
+            period
+        ))
+    } else if thresholds.iter().any(|t| *t <= 0.0) {
+        Some("thresholds must be percentages greater than 0".to_string())
+    } else if !BUDGET_ACTIONS.contains(&action) {
+        Some(format!(
+            "Invalid action '{}': must be one of {}",
+            action,
+            BUDGET_ACTIONS.join(", ")
+        ))
+    } else {
+        webhook_error
+    };
+    match error {
+        Some(error) => Err((StatusCode::BAD_REQUEST, Json(json!({ "error": error })))),
+        None => Ok(()),
+    }
+}
+
+/// Find a budget the user may manage.
+async fn find_owned_budget(
+    state: &AppState,
+    user_profile: &V1UserProfile,
+    id: &str,
+) -> Result<budgets::Model, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(user_profile);
+
+    let mut query = budgets::Entity::find_by_id(id.to_string());
+    if !is_root_owner(user_profile) {
+        query = query.filter(budgets::Column::Owner.is_in(owner_ids));
+    }
+    query
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Budget '{}' not found", id) })),
+            )
+        })
+}
+
+/// Handler: List budgets for the user's organizations.
+pub async fn list_budgets(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1Budgets>, (StatusCode, Json<serde_json::Value>)> {
+    let owner_ids = owner_ids_for(&user_profile);
+
+    let mut query = budgets::Entity::find();
+    if !is_root_owner(&user_profile) {
+        query = query.filter(budgets::Column::Owner.is_in(owner_ids));
+    }
+    let rows = query
+        .order_by_asc(budgets::Column::Name)
+        .all(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(V1Budgets {
+        budgets: rows.iter().map(|b| b.to_v1()).collect(),
+    }))
+}
+
+/// Handler: Get a budget with its spend for the current period.
+pub async fn get_budget(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(id): Path<String>,
+) -> Result<Json<V1Budget>, (StatusCode, Json<serde_json::Value>)> {
+    let budget = find_owned_budget(&state, &user_profile, &id).await?;
+    Ok(Json(budget.to_v1()))
+}
+
+/// Handler: Create a budget for a namespace, or for every namespace of an owner.
+pub async fn create_budget(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Json(request): Json<V1BudgetRequest>,
+) -> Result<Json<V1Budget>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let owner_ids = owner_ids_for(&user_profile);
+
+    crate::validate::validate_name(&request.name).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid name: {}", e) })),
+        )
+    })?;
+
+    // Namespace budgets belong to the namespace's owner
+    let owner = match &request.namespace {
+        Some(name) => {
+            let mut query = namespaces::Entity::find().filter(namespaces::Column::Name.eq(name));
+            if !is_root_owner(&user_profile) {
+                query = query.filter(namespaces::Column::Owner.is_in(owner_ids.clone()));
+            }
+            let ns = query.one(db_pool).await.map_err(db_error)?.ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": format!("Namespace '{}' not found", name) })),
+                )
+            })?;
+            if ns.is_terminating() {
+                return Err((
+                    StatusCode::CONFLICT,
+                    Json(json!({ "error": NamespaceTerminating(name.clone()).to_string() })),
+                ));
+            }
+            ns.owner
+        }
+        None => request
+            .owner
+            .clone()
+            .unwrap_or_else(|| user_profile.email.clone()),
+    };
+    if !is_root_owner(&user_profile) && !owner_ids.contains(&owner) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": format!("Not authorized to budget for '{}'", owner) })),
+        ));
+    }
+
+    let period = request.period.unwrap_or_else(|| "month".to_string());
+    let thresholds = request
+        .thresholds
+        .unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec());
+    let action = request.action.unwrap_or_else(|| "notify".to_string());
+    validate_budget(
+        request.amount,
+        &period,
+        &thresholds,
+        &action,
+        request.webhook_url.as_deref(),
+    )?;
+
+    let existing = budgets::Entity::find()
+        .filter(budgets::Column::Owner.eq(&owner))
+        .filter(budgets::Column::Name.eq(&request.name))
+        .one(db_pool)
+        .await
+        .map_err(db_error)?;
+    if existing.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!("Budget '{}' already exists for '{}'", request.name, owner)
+            })),
+        ));
+    }
+
+    let now = chrono::Utc::now();
+    let budget = budgets::ActiveModel {
+        id: Set(short_uuid::ShortUuid::generate().to_string()),
+        name: Set(request.name.clone()),
+        owner: Set(owner.clone()),
+        namespace: Set(request.namespace.clone()),
+        amount: Set(request.amount),
+        currency: Set(request.currency.unwrap_or_else(|| "USD".to_string())),
+        period: Set(period),
+        thresholds: Set(json!(thresholds)),
+        action: Set(action),
+        webhook_url: Set(request.webhook_url),
+        spent: Set(0.0),
+        period_start: Set(None),
+        last_threshold: Set(None),
+        exhausted: Set(false),
+        checked_at: Set(None),
+        created_by: Set(user_profile.email.clone()),
+        updated_at: Set(now.into()),
+        created_at: Set(now.into()),
+    }
+    .insert(db_pool)
+    .await
+    .map_err(db_error)?;
+    info!(
+        "Budget {} for {} created by {}",
+        budget.name,
+        budget.namespace.as_deref().unwrap_or(&owner),
+        user_profile.email
+    );
+
+    let budget = evaluate_budget(db_pool, budget).await.map_err(db_error)?;
+    Ok(Json(budget.to_v1()))
+}
+
+/// Handler: Change a budget's limits and re-evaluate it.
+pub async fn update_budget(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(id): Path<String>,
+    Json(request): Json<V1BudgetUpdate>,
+) -> Result<Json<V1Budget>, (StatusCode, Json<serde_json::Value>)> {
+    let budget = find_owned_budget(&state, &user_profile, &id).await?;
+
+    let amount = request.amount.unwrap_or(budget.amount);
+    let period = request.period.unwrap_or_else(|| budget.period.clone());
+    let thresholds = match request.thresholds {
+        Some(thresholds) => thresholds,
+        None => budget
+            .parse_thresholds()
+            .unwrap_or_else(|_| DEFAULT_THRESHOLDS.to_vec()),
+    };
+    let action = request.action.unwrap_or_else(|| budget.action.clone());
+    validate_budget(
+        amount,
+        &period,
+        &thresholds,
+        &action,
+        request.webhook_url.as_deref(),
+    )?;
+
+    let mut active: budgets::ActiveModel = budget.clone().into();
+    active.amount = Set(amount);
+    active.currency = Set(request.currency.unwrap_or(budget.currency));
+    active.period = Set(period);
+    active.thresholds = Set(json!(thresholds));
+    active.action = Set(action);
+    if request.webhook_url.is_some() {
+        active.webhook_url = Set(request.webhook_url);
+    }
+    active.updated_at = Set(chrono::Utc::now().into());
+    let budget = active.update(&state.db_pool).await.map_err(db_error)?;
+    info!("Budget {} updated by {}", budget.id, user_profile.email);
+
+    let budget = evaluate_budget(&state.db_pool, budget)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(budget.to_v1()))
+}
+
+/// Handler: Delete a budget.
+pub async fn delete_budget(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let budget = find_owned_budget(&state, &user_profile, &id).await?;
+
+    budgets::Entity::delete_by_id(budget.id)
+        .exec(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
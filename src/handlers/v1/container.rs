@@ -6,18 +6,30 @@ use crate::resources::v1::containers::models::{
     V1Container, V1ContainerHealthCheck, V1ContainerRequest, V1ContainerResources,
     V1ContainerSearch, V1Containers, V1EnvVar, V1UpdateContainer,
 };
+use crate::resources::v1::gc::collector::{
+    prepare_owner_deletion, spawn_dependent_deletion, OwnerRef,
+};
+use crate::resources::v1::gc::models::{PropagationPolicy, V1DeleteOptions};
+use crate::resources::v1::namespaces::defaults::{
+    apply_namespace_defaults, apply_namespace_enforced,
+};
+use crate::resources::v1::namespaces::quota::{check_quota, QuotaRequest};
+use crate::resources::v1::policies::admission::{admit, AdmissionObject};
 use crate::resources::v1::volumes::models::V1VolumePath;
+use crate::resources::v1::watch::feed::{resume_token, Watch};
+use crate::resources::v1::watch::models::V1WatchQuery;
 // Adjust the crate paths below to match your own project structure:
-use crate::agent::ns::auth_ns;
+use crate::agent::ns::{auth_ns, auth_ns_for_create, NamespaceTerminating};
 use crate::entities::containers;
 use crate::mutation::Mutation;
 use crate::query::Query;
+use crate::rbac::{self, Access};
 use crate::state::AppState;
 use crate::utils::namespace::resolve_namespace;
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::{
-    extract::Extension, extract::Json, extract::Path, extract::State, http::StatusCode,
-    response::IntoResponse,
+    extract::Extension, extract::Json, extract::Path, extract::Query as QueryParam, extract::State,
+    http::HeaderMap, http::StatusCode, response::IntoResponse, response::Response,
 };
 use futures::{SinkExt, StreamExt};
 use sea_orm::sea_query::extension::postgres::PgExpr;
@@ -33,17 +45,13 @@ use tracing::{debug, error};
 pub async fn get_container(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1Container>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
     let owner = auth_ns(db_pool, &owner_ids, &resolved_namespace)
         .await
@@ -81,32 +89,33 @@ pub async fn get_container(
         "Getting container by id: {}",
         container.clone().id.to_string()
     );
-    _get_container_by_id(db_pool, &container.clone().id.to_string(), &user_profile).await
+    _get_container_by_id(
+        db_pool,
+        &container.clone().id.to_string(),
+        &user_profile,
+        &access,
+    )
+    .await
 }
 
 pub async fn get_container_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(id): Path<String>,
 ) -> Result<Json<V1Container>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    _get_container_by_id(db_pool, &id, &user_profile).await
+    _get_container_by_id(db_pool, &id, &user_profile, &access).await
 }
 
 pub async fn _get_container_by_id(
     db_pool: &DatabaseConnection,
     id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<Json<V1Container>, (StatusCode, Json<serde_json::Value>)> {
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let container = Query::find_container_by_id_and_owners(db_pool, &id, &owner_id_refs)
@@ -178,26 +187,69 @@ pub async fn _get_container_by_id(
         ports: container.ports.and_then(|v| serde_json::from_value(v).ok()),
         proxy_port: container.proxy_port,
         authz: container.authz.and_then(|v| serde_json::from_value(v).ok()),
+        idle: container.idle.and_then(|v| serde_json::from_value(v).ok()),
+        restart_on_secret_change: container.restart_on_secret_change,
+        secret_files: container
+            .secret_files
+            .and_then(|v| serde_json::from_value(v).ok()),
     };
 
     Ok(Json(out_container))
 }
 
+/// Check a container's secret files and `restart_on_secret_change`. Run it
+/// after namespace defaults and admission, which can change both.
+pub(crate) fn validate_secret_settings(
+    request: &V1ContainerRequest,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if let Some(secret_files) = &request.secret_files {
+        crate::validate::validate_secret_files(secret_files).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Invalid secret files: {}", e) })),
+            )
+        })?;
+    }
+    crate::validate::validate_restart_on_secret_change(
+        request.platform.as_deref().unwrap_or("runpod"),
+        request.restart_on_secret_change,
+    )
+    .map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": e.to_string() })),
+        )
+    })
+}
+
 #[axum::debug_handler]
 pub async fn list_containers(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-) -> Result<Json<V1Containers>, (StatusCode, Json<serde_json::Value>)> {
+    Extension(access): Extension<Access>,
+    QueryParam(params): QueryParam<V1WatchQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
+    // Start the watch before listing, so no change made in between is missed
+    let watch = if params.watch {
+        let resume_from = resume_token(&params, &headers);
+        Some(
+            Watch::start(
+                db_pool,
+                access.principal(&user_profile),
+                "Container",
+                resume_from,
+            )
+            .await
+            .map_err(|e| (e.status(), Json(json!({"error": e.to_string()}))))?,
+        )
     } else {
-        Vec::new()
+        None
     };
 
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
@@ -211,6 +263,23 @@ pub async fn list_containers(
             )
         })?;
 
+    // Role bindings can narrow access below the owning org
+    let container_models = rbac::retain_authorized(
+        db_pool,
+        access.principal(&user_profile),
+        "list",
+        "containers",
+        container_models,
+        |c| c.namespace.as_str(),
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", e)})),
+        )
+    })?;
+
     // Convert database models to API response models
     let containers = container_models
         .into_iter()
@@ -251,16 +320,28 @@ pub async fn list_containers(
             ports: c.ports.and_then(|v| serde_json::from_value(v).ok()),
             proxy_port: c.proxy_port,
             authz: c.authz.and_then(|v| serde_json::from_value(v).ok()),
+            idle: c.idle.and_then(|v| serde_json::from_value(v).ok()),
+            restart_on_secret_change: c.restart_on_secret_change,
+            secret_files: c.secret_files.and_then(|v| serde_json::from_value(v).ok()),
         })
-        .collect();
+        .collect::<Vec<V1Container>>();
+
+    if let Some(watch) = watch {
+        let current = containers
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap_or_default())
+            .collect();
+        return Ok(watch.into_response(current));
+    }
 
-    Ok(Json(V1Containers { containers }))
+    Ok(Json(V1Containers { containers }).into_response())
 }
 
 pub async fn create_container(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-    Json(container_request): Json<V1ContainerRequest>,
+    Extension(access): Extension<Access>,
+    Json(mut container_request): Json<V1ContainerRequest>,
 ) -> Result<Json<V1Container>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
@@ -328,23 +409,49 @@ pub async fn create_container(
     })?;
     debug!("Validated namespace");
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
     debug!("Authorizing namespace");
-    let owner = auth_ns(db_pool, &owner_ids, &namespace)
+    let owner = auth_ns_for_create(db_pool, &owner_ids, &namespace)
         .await
-        .map_err(|e| {
-            (
+        .map_err(|e| match e.downcast_ref::<NamespaceTerminating>() {
+            Some(terminating) => (
+                StatusCode::CONFLICT,
+                Json(json!({"error": terminating.to_string()})),
+            ),
+            None => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": format!("Authorization error: {}", e)})),
-            )
+            ),
         })?;
     debug!("Authorized namespace");
+
+    apply_namespace_defaults(db_pool, &namespace, &mut container_request)
+        .await
+        .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+    admit(
+        db_pool,
+        &namespace,
+        &mut AdmissionObject::Container(&mut container_request),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
+    // Defaults and mutating policies can set these, so check the final request
+    validate_secret_settings(&container_request)?;
+
+    let _quota_lease = check_quota(
+        db_pool,
+        &namespace,
+        &QuotaRequest {
+            containers: 1,
+            accelerators: container_request.accelerators.clone().unwrap_or_default(),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
     let platform = platform_factory(
         container_request
             .clone()
@@ -376,19 +483,14 @@ pub async fn create_container(
 pub async fn delete_container(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
+    QueryParam(options): QueryParam<V1DeleteOptions>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let container = match Query::find_container_by_namespace_name_and_owners(
@@ -408,32 +510,76 @@ pub async fn delete_container(
         }
     };
 
-    _delete_container_by_id(db_pool, &container.clone().id.to_string(), &user_profile).await
+    let owner = OwnerRef::new("Container", &container.namespace, &container.name);
+    let policy = options.propagation_policy.unwrap_or_default();
+    prepare_owner_deletion(&state, &owner, policy)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to handle dependents: {}", e)})),
+            )
+        })?;
+
+    let response = _delete_container_by_id(
+        db_pool,
+        &container.clone().id.to_string(),
+        &user_profile,
+        &access,
+    )
+    .await?;
+    if policy == PropagationPolicy::Background {
+        spawn_dependent_deletion(state.clone(), owner);
+    }
+    Ok(response)
 }
 
 pub async fn delete_container_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(id): Path<String>,
+    QueryParam(options): QueryParam<V1DeleteOptions>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    _delete_container_by_id(db_pool, &id, &user_profile).await
+    let owner_ids = access.owner_ids(&user_profile);
+    let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
+
+    let container = Query::find_container_by_id_and_owners(db_pool, &id, &owner_id_refs)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", e)})),
+            )
+        })?;
+
+    let owner = OwnerRef::new("Container", &container.namespace, &container.name);
+    let policy = options.propagation_policy.unwrap_or_default();
+    prepare_owner_deletion(&state, &owner, policy)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to handle dependents: {}", e)})),
+            )
+        })?;
+
+    let response = _delete_container_by_id(db_pool, &id, &user_profile, &access).await?;
+    if policy == PropagationPolicy::Background {
+        spawn_dependent_deletion(state.clone(), owner);
+    }
+    Ok(response)
 }
 
 pub async fn _delete_container_by_id(
     db_pool: &DatabaseConnection,
     id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let container = Query::find_container_by_id_and_owners(db_pool, &id, &owner_id_refs)
@@ -477,29 +623,24 @@ pub async fn _delete_container_by_id(
 pub async fn fetch_container_logs_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(id): Path<String>,
 ) -> Result<Json<String>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    _fetch_container_logs_by_id(db_pool, &id, &user_profile).await
+    _fetch_container_logs_by_id(db_pool, &id, &user_profile, &access).await
 }
 
 pub async fn fetch_container_logs(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<String>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let container = Query::find_container_by_namespace_name_and_owners(
@@ -516,23 +657,23 @@ pub async fn fetch_container_logs(
         )
     })?;
 
-    _fetch_container_logs_by_id(db_pool, &container.clone().id.to_string(), &user_profile).await
+    _fetch_container_logs_by_id(
+        db_pool,
+        &container.clone().id.to_string(),
+        &user_profile,
+        &access,
+    )
+    .await
 }
 
 pub async fn _fetch_container_logs_by_id(
     db_pool: &DatabaseConnection,
     id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<Json<String>, (StatusCode, Json<serde_json::Value>)> {
     // Collect owner IDs from user_profile to use in your `Query` call
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-
-    // Add user email if necessary for ownership checks
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the container in the DB, ensuring the user has permission
@@ -565,21 +706,24 @@ pub async fn _fetch_container_logs_by_id(
 pub async fn patch_container(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(update_request): Json<V1UpdateContainer>,
 ) -> Result<Json<V1Container>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    // Collect owner IDs from user_profile to use in your `Query` call
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
+    if let Some(secret_files) = &update_request.secret_files {
+        crate::validate::validate_secret_files(secret_files).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Invalid secret files: {}", e) })),
+            )
+        })?;
+    }
 
-    // Add user email if necessary for ownership checks
-    owner_ids.push(user_profile.email.clone());
+    // Collect owner IDs from user_profile to use in your `Query` call
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the container in the DB, ensuring the user has permission
@@ -703,6 +847,29 @@ pub async fn patch_container(
         .authz
         .clone()
         .unwrap_or_else(|| container_authz.clone());
+    let container_idle = container.parse_idle().unwrap_or(None);
+    let updated_idle = update_request
+        .idle
+        .clone()
+        .or_else(|| container_idle.clone());
+    let updated_restart_on_secret_change = update_request
+        .restart_on_secret_change
+        .or(container.restart_on_secret_change);
+    let container_secret_files = container.parse_secret_files().unwrap_or(None);
+    let updated_secret_files = update_request
+        .secret_files
+        .clone()
+        .or_else(|| container_secret_files.clone());
+    crate::validate::validate_restart_on_secret_change(
+        &updated_platform,
+        updated_restart_on_secret_change,
+    )
+    .map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": e.to_string() })),
+        )
+    })?;
 
     // Log changes in debug
     {
@@ -890,6 +1057,9 @@ pub async fn patch_container(
             || Some(updated_proxy_port.clone()) != container.proxy_port
             || Some(updated_health_check.clone()) != Some(container_health_check)
             || Some(updated_authz.clone()) != Some(container_authz)
+            || updated_idle != container_idle
+            || updated_restart_on_secret_change != container.restart_on_secret_change
+            || updated_secret_files != container_secret_files
     };
 
     // If anything changed, we may need to delete+recreate the container unless no_delete = true.
@@ -904,23 +1074,21 @@ pub async fn patch_container(
             ));
         }
 
-        debug!("Deleting old container");
-        if let Err(e) = _delete_container_by_id(db_pool, &container.id, &user_profile).await {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to delete container: {:?}", e)})),
-            ));
-        }
-
         let request_meta = V1ResourceMetaRequest {
             name: Some(container.name.clone()),
             namespace: Some(container.namespace.clone()),
+            labels: update_request.labels.clone().or_else(|| {
+                container
+                    .labels
+                    .clone()
+                    .and_then(|json_value| serde_json::from_value(json_value).ok())
+            }),
             ..Default::default()
         };
 
         // Now we create the new container with merged (old + new) values
         debug!("Creating new container with updated fields");
-        let to_create = V1ContainerRequest {
+        let mut to_create = V1ContainerRequest {
             kind: "Container".to_string(),
             platform: Some(updated_platform),
             image: updated_image,
@@ -940,7 +1108,31 @@ pub async fn patch_container(
             proxy_port: Some(updated_proxy_port),
             health_check: Some(updated_health_check),
             authz: Some(updated_authz),
+            idle: updated_idle,
+            restart_on_secret_change: updated_restart_on_secret_change,
+            secret_files: updated_secret_files,
         };
+        apply_namespace_enforced(db_pool, &container.namespace, &mut to_create)
+            .await
+            .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+        admit(
+            db_pool,
+            &container.namespace,
+            &mut AdmissionObject::Container(&mut to_create),
+        )
+        .await
+        .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+        validate_secret_settings(&to_create)?;
+
+        debug!("Deleting old container");
+        if let Err(e) =
+            _delete_container_by_id(db_pool, &container.id, &user_profile, &access).await
+        {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to delete container: {:?}", e)})),
+            ));
+        }
 
         let platform = platform_factory(
             update_request
@@ -978,23 +1170,21 @@ pub async fn _search_containers(
     db_pool: &DatabaseConnection,
     search: &V1ContainerSearch,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<Vec<V1Container>, (StatusCode, Json<serde_json::Value>)> {
     debug!("Searching for containers: {:?}", search);
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-
-    // Add user's email to owner IDs
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let mut conditions = Condition::all();
 
     // Add owner condition first
-    conditions = conditions.add(containers::Column::Owner.is_in(owner_id_refs));
+    conditions = conditions.add(Query::owned_or_shared(
+        containers::Column::Owner,
+        containers::Column::Namespace,
+        &owner_id_refs,
+    ));
 
     // Rest of the search conditions remain the same
     if let Some(namespace) = &search.namespace {
@@ -1147,6 +1337,20 @@ pub async fn _search_containers(
             )
         })?;
 
+    // Role bindings can narrow access below the owning org
+    let principal = access.principal(user_profile);
+    let containers =
+        rbac::retain_authorized(db_pool, principal, "list", "containers", containers, |c| {
+            c.namespace.as_str()
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", e)})),
+            )
+        })?;
+
     debug!("Found {} containers", containers.len());
 
     // Convert the database models to V1Container
@@ -1163,12 +1367,13 @@ pub async fn _search_containers(
 pub async fn search_containers(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Json(search): Json<V1ContainerSearch>,
 ) -> Result<Json<V1Containers>, (StatusCode, Json<serde_json::Value>)> {
     debug!("Searching for containers: {:?}", search);
     let db_pool = &state.db_pool;
 
-    let containers = _search_containers(db_pool, &search, &user_profile).await?;
+    let containers = _search_containers(db_pool, &search, &user_profile, &access).await?;
 
     Ok(Json(V1Containers { containers }))
 }
@@ -1178,11 +1383,19 @@ pub async fn stream_logs_ws(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> impl IntoResponse {
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
     ws.on_upgrade(move |socket| {
-        handle_socket(socket, state, user_profile, resolved_namespace, name)
+        handle_socket(
+            socket,
+            state,
+            user_profile,
+            access,
+            resolved_namespace,
+            name,
+        )
     })
 }
 
@@ -1190,15 +1403,17 @@ pub async fn stream_logs_ws_by_id(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket_by_id(socket, state, user_profile, id))
+    ws.on_upgrade(move |socket| handle_socket_by_id(socket, state, user_profile, access, id))
 }
 
 async fn handle_socket_by_id(
     socket: WebSocket,
     state: AppState,
     user_profile: V1UserProfile,
+    access: Access,
     id: String,
 ) {
     debug!(
@@ -1210,12 +1425,7 @@ async fn handle_socket_by_id(
     let (sender, _receiver) = socket.split(); // Receiver is not used anymore
 
     // Fetch container info
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     match Query::find_container_by_id_and_owners(db_pool, &id, &owner_id_refs).await {
@@ -1241,6 +1451,7 @@ async fn handle_socket(
     socket: WebSocket,
     state: AppState,
     user_profile: V1UserProfile,
+    access: Access,
     namespace: String,
     name: String,
 ) {
@@ -1253,12 +1464,7 @@ async fn handle_socket(
     let (sender, _receiver) = socket.split(); // Receiver is not used anymore
 
     // Fetch container info
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     match Query::find_container_by_namespace_name_and_owners(
@@ -0,0 +1,116 @@
+use crate::agent::ns::auth_ns;
+use crate::entities::resource_events;
+use crate::models::V1UserProfile;
+use crate::resources::v1::events::models::{V1ResourceEventQuery, V1ResourceEvents};
+use crate::state::AppState;
+use crate::utils::namespace::resolve_namespace;
+use axum::{
+    extract::{Extension, Json, Path, Query as QueryParam, State},
+    http::StatusCode,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde_json::json;
+
+/// Events returned when no `limit` is given.
+const DEFAULT_EVENT_LIMIT: u64 = 100;
+
+/// Most events returned by one request.
+const MAX_EVENT_LIMIT: u64 = 1000;
+
+/// Handler: List the events of a container, newest first.
+pub async fn list_container_events(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+    QueryParam(params): QueryParam<V1ResourceEventQuery>,
+) -> Result<Json<V1ResourceEvents>, (StatusCode, Json<serde_json::Value>)> {
+    list_events(
+        &state,
+        &user_profile,
+        "Container",
+        &namespace,
+        &name,
+        &params,
+    )
+    .await
+}
+
+/// Handler: List the events of a processor, newest first.
+pub async fn list_processor_events(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+    QueryParam(params): QueryParam<V1ResourceEventQuery>,
+) -> Result<Json<V1ResourceEvents>, (StatusCode, Json<serde_json::Value>)> {
+    list_events(
+        &state,
+        &user_profile,
+        "Processor",
+        &namespace,
+        &name,
+        &params,
+    )
+    .await
+}
+
+/// Events are looked up by name rather than through the resource, so the
+/// history of a deleted resource can still be read.
+async fn list_events(
+    state: &AppState,
+    user_profile: &V1UserProfile,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    params: &V1ResourceEventQuery,
+) -> Result<Json<V1ResourceEvents>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace = resolve_namespace(namespace, user_profile);
+
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+
+    auth_ns(db_pool, &owner_ids, &namespace)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": format!("Authorization error: {}", e) })),
+            )
+        })?;
+
+    let mut query = resource_events::Entity::find()
+        .filter(resource_events::Column::Kind.eq(kind))
+        .filter(resource_events::Column::Namespace.eq(&namespace))
+        .filter(resource_events::Column::Name.eq(name));
+    if let Some(since) = params
+        .since
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+    {
+        query = query.filter(resource_events::Column::LastSeenAt.gte(since));
+    }
+
+    let events = query
+        .order_by_desc(resource_events::Column::LastSeenAt)
+        .limit(
+            params
+                .limit
+                .unwrap_or(DEFAULT_EVENT_LIMIT)
+                .min(MAX_EVENT_LIMIT),
+        )
+        .all(db_pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", e) })),
+            )
+        })?;
+
+    Ok(Json(V1ResourceEvents {
+        events: events.iter().map(|e| e.to_v1()).collect(),
+    }))
+}
@@ -0,0 +1,43 @@
+use crate::models::V1UserProfile;
+use crate::rbac::is_root_owner;
+use crate::resources::v1::gc::collector::find_dangling;
+use crate::resources::v1::gc::models::V1DanglingReferences;
+use crate::state::AppState;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+
+/// Handler: List resources whose `owner_ref` points at an owner that no
+/// longer exists.
+///
+/// The root owner sees every dangling reference; everyone else only sees
+/// resources they or their organizations own.
+pub async fn list_dangling_references(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1DanglingReferences>, (StatusCode, Json<serde_json::Value>)> {
+    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
+        orgs.keys().cloned().collect()
+    } else {
+        Vec::new()
+    };
+    owner_ids.push(user_profile.email.clone());
+    let is_root = is_root_owner(&user_profile);
+
+    let references = find_dangling(&state.db_pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", e)})),
+            )
+        })?
+        .into_iter()
+        .filter(|r| is_root || owner_ids.contains(&r.owner))
+        .collect();
+
+    Ok(Json(V1DanglingReferences { references }))
+}
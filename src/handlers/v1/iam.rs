@@ -2,9 +2,10 @@ use crate::agent::aws::{
     create_s3_scoped_user, delete_s3_scoped_user, generate_temporary_s3_credentials,
     IamCredentials, StsCredentials,
 };
-use crate::agent::ns::auth_ns;
+use crate::agent::ns::{auth_ns, auth_ns_for_create, NamespaceTerminating};
 use crate::config::SERVER_CONFIG;
 use crate::models::{V1ResourceMeta, V1UserProfile};
+use crate::rbac::Access;
 use crate::state::AppState;
 use aws_config::{self, BehaviorVersion, Region};
 use aws_sdk_iam::Client as IamClient;
@@ -43,6 +44,7 @@ pub struct V1StsCredentialsResponse {
 pub async fn create_scoped_s3_token(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1IamCredentialsResponse>, (StatusCode, Json<serde_json::Value>)> {
     debug!(?namespace, ?name, "Entered create_scoped_s3_token handler");
@@ -50,11 +52,7 @@ pub async fn create_scoped_s3_token(
 
     // --- Authorization ---
     debug!("Starting authorization step");
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
+    let mut owner_ids = access.owner_ids(&user_profile);
     // Also allow authorization if the namespace matches the user's handle
     if let Some(handle) = &user_profile.handle {
         owner_ids.push(handle.clone());
@@ -78,15 +76,17 @@ pub async fn create_scoped_s3_token(
             }
         }
     }
-    owner_ids.push(user_profile.email.clone());
     debug!(?owner_ids, "Constructed owner_ids for authorization check");
 
     debug!("Calling auth_ns");
-    let owner = match auth_ns(db_pool, &owner_ids, &namespace).await {
+    let owner = match auth_ns_for_create(db_pool, &owner_ids, &namespace).await {
         Ok(owner) => {
             debug!(?owner, "auth_ns successful");
             owner
         }
+        Err(e) if e.is::<NamespaceTerminating>() => {
+            return Err((StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))));
+        }
         Err(e) => {
             error!("Authorization failed for namespace {}: {}", namespace, e);
             debug!("Returning 403 Forbidden due to auth_ns failure");
@@ -156,21 +156,17 @@ pub async fn create_scoped_s3_token(
 pub async fn delete_scoped_s3_token(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
     // --- Authorization ---
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
+    let mut owner_ids = access.owner_ids(&user_profile);
     // Also allow authorization if the namespace matches the user's handle
     if let Some(handle) = &user_profile.handle {
         owner_ids.push(handle.clone());
     }
-    owner_ids.push(user_profile.email.clone());
 
     match auth_ns(db_pool, &owner_ids, &namespace).await {
         Ok(_) => (),
@@ -218,6 +214,7 @@ pub async fn delete_scoped_s3_token(
 pub async fn generate_temp_s3_credentials(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1StsCredentialsResponse>, (StatusCode, Json<serde_json::Value>)> {
     debug!(
@@ -228,11 +225,7 @@ pub async fn generate_temp_s3_credentials(
     let db_pool = &state.db_pool;
 
     // --- Authorization ---
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
+    let mut owner_ids = access.owner_ids(&user_profile);
     if let Some(handle) = &user_profile.handle {
         owner_ids.push(handle.clone());
 
@@ -255,7 +248,6 @@ pub async fn generate_temp_s3_credentials(
             }
         }
     }
-    owner_ids.push(user_profile.email.clone());
 
     let owner = match auth_ns(db_pool, &owner_ids, &namespace).await {
         Ok(owner) => owner,
@@ -1,29 +1,53 @@
+pub mod audit;
 pub mod auth;
+pub mod billing;
 pub mod cache;
 pub mod container;
+pub mod events;
+pub mod gc;
 pub mod iam;
 pub mod namespaces;
+pub mod policies;
 pub mod processors;
+pub mod roles;
 pub mod secrets;
+pub mod usage;
 pub mod volumes;
-pub use auth::get_user_profile;
+pub use audit::list_audit_events;
+pub use auth::{get_user_profile, get_workload_s3_credentials, refresh_workload_token};
+pub use billing::{
+    create_budget, create_invoice, delete_budget, delete_price_book, get_budget, get_invoice,
+    get_price_book, list_budgets, list_invoices, list_price_books, put_price_book, update_budget,
+};
 pub use cache::{delete_cache_key, get_cache_key, list_cache_keys};
 pub use container::{
     create_container, delete_container, delete_container_by_id, fetch_container_logs,
     fetch_container_logs_by_id, get_container, get_container_by_id, list_containers,
     patch_container, search_containers, stream_logs_ws, stream_logs_ws_by_id,
 };
+pub use events::{list_container_events, list_processor_events};
+pub use gc::list_dangling_references;
 pub use iam::{create_scoped_s3_token, delete_scoped_s3_token, generate_temp_s3_credentials};
 pub use namespaces::{
-    create_namespace, delete_namespace, ensure_namespace, get_namespace, list_namespaces,
+    create_namespace, create_namespace_grant, delete_namespace, delete_namespace_grant,
+    ensure_namespace, get_namespace, list_namespace_grants, list_namespaces, transfer_namespace,
+    update_namespace_container_defaults, update_namespace_container_enforced,
+    update_namespace_quota,
 };
+pub use policies::{create_policy, delete_policy, get_policy, list_policies};
 pub use processors::{
     check_processor_health, create_processor, delete_processor, get_processor, get_processor_logs,
     list_processors, processor_websocket, read_processor_stream, read_return_message,
     scale_processor, send_processor, stream_processor_return_ws, update_processor,
 };
+pub use roles::{
+    create_role, create_role_binding, delete_role, delete_role_binding, get_role, get_role_binding,
+    list_role_bindings, list_roles,
+};
 pub use secrets::{
-    create_secret, delete_secret, delete_secret_by_id, get_secret, get_secret_by_id, list_secrets,
+    create_secret, delete_secret, delete_secret_by_id, get_secret, get_secret_by_id,
+    get_secret_version, list_secret_versions, list_secrets, rollback_secret, rotate_secret_keys,
     update_secret, update_secret_by_id,
 };
+pub use usage::get_usage;
 pub use volumes::{create_volume, delete_volume, get_volume, list_volumes};
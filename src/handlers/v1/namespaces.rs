@@ -2,18 +2,38 @@
 
 use crate::config::SERVER_CONFIG;
 use crate::entities::namespaces::{self, ActiveModel as NamespaceActiveModel};
+use crate::entities::{
+    admission_policies, budgets, containers, namespace_grants, processors, role_bindings, roles,
+    secrets, volumes,
+};
 use crate::handlers::v1::volumes::ensure_volume;
 use crate::models::V1UserProfile;
-use crate::resources::v1::namespaces::models::{V1Namespace, V1NamespaceRequest, V1Namespaces};
+use crate::query::Query;
+use crate::rbac::{is_root_owner, Principal};
+use crate::resources::v1::namespaces::defaults::validate_container_enforced;
+use crate::resources::v1::namespaces::finalizer::{
+    begin_namespace_deletion, finalize_namespace, namespace_resources,
+};
+use crate::resources::v1::namespaces::models::{
+    V1ContainerDefaults, V1ContainerEnforced, V1Namespace, V1NamespaceDeleteQuery,
+    V1NamespaceDeletion, V1NamespaceGrant, V1NamespaceGrantRequest, V1NamespaceGrants,
+    V1NamespaceQuota, V1NamespaceRequest, V1NamespaceTransferRequest, V1Namespaces,
+};
+use crate::resources::v1::namespaces::quota::{namespace_usage, validate_quota};
+use crate::resources::v1::roles::models::builtin_role_rules;
 use crate::state::AppState;
-use axum::{extract::Extension, extract::Json, extract::Path, extract::State, http::StatusCode};
+use axum::{
+    extract::Extension, extract::Json, extract::Path, extract::Query as QueryParam, extract::State,
+    http::StatusCode,
+};
+use sea_orm::sea_query::Expr;
 use sea_orm::DbErr;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
 };
 use serde_json::json;
 use short_uuid;
-use tracing::debug;
+use tracing::{debug, error};
 
 pub async fn get_namespace(
     State(state): State<AppState>,
@@ -34,7 +54,11 @@ pub async fn get_namespace(
 
     let namespace_entity = namespaces::Entity::find()
         .filter(namespaces::Column::Name.eq(name.clone()))
-        .filter(namespaces::Column::Owner.is_in(owner_id_refs))
+        .filter(Query::owned_or_shared(
+            namespaces::Column::Owner,
+            namespaces::Column::Name,
+            &owner_id_refs,
+        ))
         .one(db_pool)
         .await
         .map_err(|err| {
@@ -54,7 +78,18 @@ pub async fn get_namespace(
         })),
     ))?;
 
-    Ok(Json(namespace_entity.to_v1()))
+    let usage = namespace_usage(db_pool, &namespace_entity.name)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+
+    let mut namespace = namespace_entity.to_v1();
+    namespace.usage = Some(usage);
+    Ok(Json(namespace))
 }
 
 pub async fn create_namespace(
@@ -135,6 +170,21 @@ pub async fn create_namespace(
         )
     })?;
 
+    let rate_limit = match &namespace.rate_limit {
+        Some(limit) if limit.requests_per_second <= 0.0 => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "rate_limit.requests_per_second must be greater than 0"})),
+            ));
+        }
+        Some(limit) => Some(serde_json::to_value(limit).unwrap_or_default()),
+        None => None,
+    };
+
+    if let Some(quota) = &namespace.quota {
+        validate_quota(quota).map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e}))))?;
+    }
+
     // Insert the namespace into the database
     let namespace_entity = NamespaceActiveModel {
         id: Set(namespace_entity.id),
@@ -142,6 +192,19 @@ pub async fn create_namespace(
         owner: Set(namespace_entity.owner),
         owner_ref: Set(namespace_entity.owner_ref),
         labels: Set(namespace_entity.labels),
+        rate_limit: Set(rate_limit),
+        quota: Set(namespace
+            .quota
+            .as_ref()
+            .map(|quota| serde_json::to_value(quota).unwrap_or_default())),
+        container_defaults: Set(namespace
+            .container_defaults
+            .as_ref()
+            .map(|defaults| serde_json::to_value(defaults).unwrap_or_default())),
+        container_enforced: Set(None),
+        finalizers: Set(None),
+        deletion_started_at: Set(None),
+        deletion_status: Set(None),
         created_by: Set(namespace_entity.created_by),
         updated_at: Set(namespace_entity.updated_at),
         created_at: Set(namespace_entity.created_at),
@@ -185,7 +248,8 @@ pub async fn delete_namespace(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
     Path(name): Path<String>,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    QueryParam(query): QueryParam<V1NamespaceDeleteQuery>,
+) -> Result<(StatusCode, Json<V1NamespaceDeletion>), (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
     // Get owner IDs from organizations and email
@@ -220,9 +284,30 @@ pub async fn delete_namespace(
         })),
     ))?;
 
-    // Delete the namespace
-    namespaces::Entity::delete_by_id(namespace_entity.id)
-        .exec(db_pool)
+    let resources = namespace_resources(db_pool, &namespace_entity.name)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+
+    if query.dry_run {
+        return Ok((
+            StatusCode::OK,
+            Json(V1NamespaceDeletion {
+                namespace: namespace_entity.name.clone(),
+                dry_run: true,
+                resources,
+                status: Some(namespace_entity.status()),
+            }),
+        ));
+    }
+
+    // Children are torn down by the namespace finalizer, which deletes the
+    // namespace itself once they are gone
+    let namespace_entity = begin_namespace_deletion(db_pool, namespace_entity)
         .await
         .map_err(|err| {
             (
@@ -231,7 +316,424 @@ pub async fn delete_namespace(
             )
         })?;
 
-    Ok(())
+    tokio::spawn({
+        let state = state.clone();
+        let namespace = namespace_entity.clone();
+        async move {
+            let name = namespace.name.clone();
+            if let Err(e) = finalize_namespace(&state, namespace).await {
+                error!("Failed to finalize namespace {}: {}", name, e);
+            }
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(V1NamespaceDeletion {
+            namespace: namespace_entity.name.clone(),
+            dry_run: false,
+            resources,
+            status: Some(namespace_entity.status()),
+        }),
+    ))
+}
+
+/// Handler: Set or clear a namespace's quota. Root only, so tenants cannot
+/// lift their own limits.
+pub async fn update_namespace_quota(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+    Json(quota): Json<Option<V1NamespaceQuota>>,
+) -> Result<Json<V1Namespace>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+
+    if !is_root_owner(&user_profile) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Only the root owner can change namespace quotas"})),
+        ));
+    }
+    if let Some(quota) = &quota {
+        validate_quota(quota).map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e}))))?;
+    }
+
+    let namespace_entity = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(name.clone()))
+        .one(db_pool)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("Namespace with name '{}' not found", name)})),
+        ))?;
+
+    let mut active_model: NamespaceActiveModel = namespace_entity.into();
+    active_model.quota = Set(quota.map(|quota| serde_json::to_value(quota).unwrap_or_default()));
+    active_model.updated_at = Set(chrono::Utc::now().into());
+    let namespace_entity = active_model.update(db_pool).await.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", err)})),
+        )
+    })?;
+
+    Ok(Json(namespace_entity.to_v1()))
+}
+
+/// Handler: Set or clear the defaults filled into containers created in a
+/// namespace.
+pub async fn update_namespace_container_defaults(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+    Json(defaults): Json<Option<V1ContainerDefaults>>,
+) -> Result<Json<V1Namespace>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    let mut active_model: NamespaceActiveModel = namespace_entity.into();
+    active_model.container_defaults =
+        Set(defaults.map(|defaults| serde_json::to_value(defaults).unwrap_or_default()));
+    active_model.updated_at = Set(chrono::Utc::now().into());
+    let namespace_entity = active_model.update(db_pool).await.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", err)})),
+        )
+    })?;
+
+    Ok(Json(namespace_entity.to_v1()))
+}
+
+/// Handler: Set or clear the values every container in a namespace must use.
+/// Only namespace admins may change them, so members cannot lift them.
+pub async fn update_namespace_container_enforced(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+    Json(enforced): Json<Option<V1ContainerEnforced>>,
+) -> Result<Json<V1Namespace>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    if !Principal::new(&user_profile, None).administers(&namespace_entity) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Only namespace admins can change enforced container values"})),
+        ));
+    }
+    if let Some(enforced) = &enforced {
+        validate_container_enforced(enforced)
+            .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e}))))?;
+    }
+
+    let mut active_model: NamespaceActiveModel = namespace_entity.into();
+    active_model.container_enforced =
+        Set(enforced.map(|enforced| serde_json::to_value(enforced).unwrap_or_default()));
+    active_model.updated_at = Set(chrono::Utc::now().into());
+    let namespace_entity = active_model.update(db_pool).await.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", err)})),
+        )
+    })?;
+
+    Ok(Json(namespace_entity.to_v1()))
+}
+
+/// Find a namespace the user may share or transfer: one they own, or any
+/// namespace for the root owner.
+async fn find_administered_namespace(
+    db_pool: &DatabaseConnection,
+    user_profile: &V1UserProfile,
+    name: &str,
+) -> Result<namespaces::Model, (StatusCode, Json<serde_json::Value>)> {
+    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
+        orgs.keys().cloned().collect()
+    } else {
+        Vec::new()
+    };
+    owner_ids.push(user_profile.email.clone());
+
+    let namespace_entity = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(name))
+        .one(db_pool)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?
+        .filter(|ns| owner_ids.contains(&ns.owner) || is_root_owner(user_profile))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("Namespace with name '{}' not found", name)})),
+        ))?;
+    Ok(namespace_entity)
+}
+
+/// Handler: List who a namespace is shared with.
+pub async fn list_namespace_grants(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+) -> Result<Json<V1NamespaceGrants>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    let grants = Query::find_namespace_grants(db_pool, &namespace_entity.name)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+
+    Ok(Json(V1NamespaceGrants {
+        grants: grants.iter().map(|grant| grant.to_v1()).collect(),
+    }))
+}
+
+/// Handler: Share a namespace with a user or org. Granting a subject that
+/// already has a grant replaces its role.
+pub async fn create_namespace_grant(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+    Json(request): Json<V1NamespaceGrantRequest>,
+) -> Result<Json<V1NamespaceGrant>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    if request.subject.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "subject is required"})),
+        ));
+    }
+    if !matches!(request.subject_kind.as_str(), "User" | "Org") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "subject_kind must be User or Org"})),
+        ));
+    }
+    if request.subject == namespace_entity.owner {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "The namespace owner already has full access"})),
+        ));
+    }
+
+    let custom_role = roles::Entity::find()
+        .filter(roles::Column::Namespace.eq(&namespace_entity.name))
+        .filter(roles::Column::Name.eq(&request.role))
+        .one(db_pool)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+    if builtin_role_rules(&request.role).is_none() && custom_role.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!(
+                    "Role '{}' is neither built in nor defined in namespace '{}'",
+                    request.role, namespace_entity.name
+                )
+            })),
+        ));
+    }
+
+    let existing = namespace_grants::Entity::find()
+        .filter(namespace_grants::Column::Namespace.eq(&namespace_entity.name))
+        .filter(namespace_grants::Column::Subject.eq(&request.subject))
+        .one(db_pool)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+
+    let now = chrono::Utc::now();
+    let grant = match existing {
+        Some(existing) => {
+            let mut active_model: namespace_grants::ActiveModel = existing.into();
+            active_model.subject_kind = Set(request.subject_kind.clone());
+            active_model.role = Set(request.role.clone());
+            active_model.updated_at = Set(now.into());
+            active_model.update(db_pool).await
+        }
+        None => {
+            namespace_grants::ActiveModel {
+                id: Set(short_uuid::ShortUuid::generate().to_string()),
+                namespace: Set(namespace_entity.name.clone()),
+                subject: Set(request.subject.clone()),
+                subject_kind: Set(request.subject_kind.clone()),
+                role: Set(request.role.clone()),
+                created_by: Set(user_profile.email.clone()),
+                updated_at: Set(now.into()),
+                created_at: Set(now.into()),
+            }
+            .insert(db_pool)
+            .await
+        }
+    }
+    .map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", err)})),
+        )
+    })?;
+
+    Ok(Json(grant.to_v1()))
+}
+
+/// Handler: Revoke a namespace grant.
+pub async fn delete_namespace_grant(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((name, id)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    let result = namespace_grants::Entity::delete_many()
+        .filter(namespace_grants::Column::Namespace.eq(&namespace_entity.name))
+        .filter(namespace_grants::Column::Id.eq(&id))
+        .exec(db_pool)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Database error: {}", err)})),
+            )
+        })?;
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("Grant '{}' not found", id)})),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Handler: Transfer a namespace, and everything in it, to a new owner.
+pub async fn transfer_namespace(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path(name): Path<String>,
+    Json(request): Json<V1NamespaceTransferRequest>,
+) -> Result<Json<V1Namespace>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let namespace_entity = find_administered_namespace(db_pool, &user_profile, &name).await?;
+
+    let new_owner = request.owner.trim().to_string();
+    if new_owner.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "owner is required"})),
+        ));
+    }
+    if namespace_entity.name == "root" {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "The root namespace cannot be transferred"})),
+        ));
+    }
+
+    let db_err = |err: DbErr| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Failed to transfer namespace: {}", err)})),
+        )
+    };
+    let ns = namespace_entity.name.clone();
+
+    // Resources are owned by the namespace owner, so they move with it
+    containers::Entity::update_many()
+        .col_expr(containers::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(containers::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    processors::Entity::update_many()
+        .col_expr(processors::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(processors::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    secrets::Entity::update_many()
+        .col_expr(secrets::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(secrets::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    volumes::Entity::update_many()
+        .col_expr(volumes::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(volumes::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    roles::Entity::update_many()
+        .col_expr(roles::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(roles::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    role_bindings::Entity::update_many()
+        .col_expr(role_bindings::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(role_bindings::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    admission_policies::Entity::update_many()
+        .col_expr(
+            admission_policies::Column::Owner,
+            Expr::value(new_owner.clone()),
+        )
+        .filter(admission_policies::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+    budgets::Entity::update_many()
+        .col_expr(budgets::Column::Owner, Expr::value(new_owner.clone()))
+        .filter(budgets::Column::Namespace.eq(&ns))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+
+    // The new owner no longer needs a grant
+    namespace_grants::Entity::delete_many()
+        .filter(namespace_grants::Column::Namespace.eq(&ns))
+        .filter(namespace_grants::Column::Subject.eq(&new_owner))
+        .exec(db_pool)
+        .await
+        .map_err(db_err)?;
+
+    debug!(
+        "Transferring namespace {} from {} to {}",
+        ns, namespace_entity.owner, new_owner
+    );
+    let mut active_model: NamespaceActiveModel = namespace_entity.into();
+    active_model.owner = Set(new_owner);
+    active_model.updated_at = Set(chrono::Utc::now().into());
+    let namespace_entity = active_model.update(db_pool).await.map_err(db_err)?;
+
+    Ok(Json(namespace_entity.to_v1()))
 }
 
 /// Internal helper function to ensure a namespace exists with the given parameters.
@@ -269,6 +771,13 @@ pub async fn ensure_namespace(
         owner: Set(owner.to_string()),
         owner_ref: Set(None),
         labels: Set(labels),
+        rate_limit: Set(None),
+        quota: Set(None),
+        container_defaults: Set(None),
+        container_enforced: Set(None),
+        finalizers: Set(None),
+        deletion_started_at: Set(None),
+        deletion_status: Set(None),
         created_by: Set(created_by.to_string()),
         updated_at: Set(chrono::Utc::now().into()),
         created_at: Set(chrono::Utc::now().into()),
@@ -280,7 +789,8 @@ pub async fn ensure_namespace(
     Ok((namespace_entity, true))
 }
 
-/// Handler: List namespaces for the current user (and their organizations)
+/// Handler: List namespaces for the current user (and their organizations),
+/// including namespaces shared with them
 pub async fn list_namespaces(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
@@ -297,9 +807,7 @@ pub async fn list_namespaces(
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Retrieve namespaces
-    let namespaces_list = namespaces::Entity::find()
-        .filter(namespaces::Column::Owner.is_in(owner_id_refs))
-        .all(db_pool)
+    let namespaces_list = Query::find_namespaces_by_owners(db_pool, &owner_id_refs)
         .await
         .map_err(|err| {
             (
@@ -0,0 +1,217 @@
+use crate::agent::ns::NamespaceTerminating;
+use crate::config::SERVER_CONFIG;
+use crate::entities::{admission_policies, namespaces};
+use crate::models::V1UserProfile;
+use crate::rbac::is_root_owner;
+use crate::resources::v1::policies::admission::validate_policy_spec;
+use crate::resources::v1::policies::models::{
+    V1AdmissionPolicies, V1AdmissionPolicy, V1AdmissionPolicyRequest, GLOBAL_POLICY_NAMESPACE,
+};
+use crate::state::AppState;
+use crate::utils::namespace::resolve_namespace;
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde_json::json;
+use short_uuid::ShortUuid;
+use tracing::{error, info};
+
+fn owner_ids(user_profile: &V1UserProfile) -> Vec<String> {
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+    owner_ids
+}
+
+fn db_error(e: sea_orm::DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    error!("Admission policies database error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) })),
+    )
+}
+
+fn bad_request(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message })))
+}
+
+fn not_found() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Admission policy not found" })),
+    )
+}
+
+/// Only the root owner may manage policies that apply to every namespace.
+fn check_global_access(
+    user_profile: &V1UserProfile,
+    namespace: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if namespace == GLOBAL_POLICY_NAMESPACE && !is_root_owner(user_profile) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only the root owner can manage global admission policies" })),
+        ));
+    }
+    Ok(())
+}
+
+/// Handler: List the policies in the user's namespaces, along with the global
+/// policies that apply to them.
+pub async fn list_policies(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1AdmissionPolicies>, (StatusCode, Json<serde_json::Value>)> {
+    let policies = admission_policies::Entity::find()
+        .filter(
+            Condition::any()
+                .add(admission_policies::Column::Owner.is_in(owner_ids(&user_profile)))
+                .add(admission_policies::Column::Namespace.eq(GLOBAL_POLICY_NAMESPACE)),
+        )
+        .order_by_asc(admission_policies::Column::FullName)
+        .all(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(V1AdmissionPolicies {
+        policies: policies.iter().map(|p| p.to_v1()).collect(),
+    }))
+}
+
+/// Handler: Create an admission policy, or replace the spec of an existing
+/// one with the same name.
+pub async fn create_policy(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Json(request): Json<V1AdmissionPolicyRequest>,
+) -> Result<Json<V1AdmissionPolicy>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+
+    let name = request
+        .metadata
+        .name
+        .clone()
+        .ok_or_else(|| bad_request("metadata.name is required".to_string()))?;
+    crate::validate::validate_name(&name).map_err(|e| bad_request(e.to_string()))?;
+    validate_policy_spec(&request.spec).map_err(bad_request)?;
+
+    let namespace = resolve_namespace(
+        request.metadata.namespace.as_deref().unwrap_or("-"),
+        &user_profile,
+    );
+    check_global_access(&user_profile, &namespace)?;
+    let owner = if namespace == GLOBAL_POLICY_NAMESPACE {
+        SERVER_CONFIG.root_owner.clone()
+    } else {
+        let ns = namespaces::Entity::find()
+            .filter(namespaces::Column::Name.eq(&namespace))
+            .one(db_pool)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": format!("Namespace '{}' not found", namespace) })),
+                )
+            })?;
+        if ns.is_terminating() {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": NamespaceTerminating(namespace).to_string() })),
+            ));
+        }
+        ns.owner
+    };
+
+    let full_name = format!("{}/{}", namespace, name);
+    let spec = serde_json::to_value(&request.spec).unwrap_or_default();
+    let labels = request
+        .metadata
+        .labels
+        .as_ref()
+        .map(|labels| serde_json::to_value(labels).unwrap_or_default());
+    let now = chrono::Utc::now();
+
+    let existing = admission_policies::Entity::find()
+        .filter(admission_policies::Column::FullName.eq(&full_name))
+        .one(db_pool)
+        .await
+        .map_err(db_error)?;
+
+    let policy = match existing {
+        Some(existing) => {
+            let mut active = existing.into_active_model();
+            active.spec = Set(spec);
+            active.labels = Set(labels);
+            active.violations = Set(None);
+            active.updated_at = Set(now.into());
+            active.update(db_pool).await.map_err(db_error)?
+        }
+        None => admission_policies::ActiveModel {
+            id: Set(ShortUuid::generate().to_string()),
+            namespace: Set(namespace),
+            name: Set(name),
+            full_name: Set(full_name),
+            owner: Set(owner),
+            spec: Set(spec),
+            violations: Set(None),
+            labels: Set(labels),
+            created_by: Set(user_profile.email.clone()),
+            updated_at: Set(now.into()),
+            created_at: Set(now.into()),
+        }
+        .insert(db_pool)
+        .await
+        .map_err(db_error)?,
+    };
+
+    info!(
+        "Saved admission policy {} by {}",
+        policy.full_name, user_profile.email
+    );
+    Ok(Json(policy.to_v1()))
+}
+
+pub async fn get_policy(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Json<V1AdmissionPolicy>, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    let policy = admission_policies::Entity::find()
+        .filter(admission_policies::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(policy.to_v1()))
+}
+
+pub async fn delete_policy(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    check_global_access(&user_profile, &namespace)?;
+
+    let result = admission_policies::Entity::delete_many()
+        .filter(admission_policies::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .exec(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected == 0 {
+        return Err(not_found());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
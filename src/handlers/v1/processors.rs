@@ -1,24 +1,38 @@
-use crate::agent::ns::auth_ns;
+use crate::agent::ns::{auth_ns_for_create, NamespaceTerminating};
 use crate::config::SERVER_CONFIG;
 use crate::entities::processors;
+use crate::handlers::v1::container::validate_secret_settings;
 use crate::middleware::get_user_profile_from_token;
 use crate::models::{V1ResourceMetaRequest, V1StreamData, V1StreamMessage, V1UserProfile};
+use crate::mutation::Mutation;
 use crate::query::Query;
+use crate::rbac::{self, Access};
+use crate::resources::v1::gc::collector::{
+    prepare_owner_deletion, spawn_dependent_deletion, OwnerRef,
+};
+use crate::resources::v1::gc::models::{PropagationPolicy, V1DeleteOptions};
+use crate::resources::v1::namespaces::defaults::{
+    apply_namespace_defaults, apply_namespace_enforced,
+};
+use crate::resources::v1::namespaces::quota::{check_quota, check_replicas_quota, QuotaRequest};
+use crate::resources::v1::policies::admission::{admit, AdmissionObject};
 use crate::resources::v1::processors::base::ProcessorPlatform;
 use crate::resources::v1::processors::models::{
     V1Processor, V1ProcessorHealthResponse, V1ProcessorRequest, V1ProcessorScaleRequest,
     V1Processors, V1ReadStreamRequest, V1UpdateProcessor,
 };
 use crate::resources::v1::processors::standard::StandardProcessor;
+use crate::resources::v1::watch::feed::{resume_token, Watch};
+use crate::resources::v1::watch::models::V1WatchQuery;
 use crate::state::AppState;
 use crate::utils::namespace::resolve_namespace;
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::{
-    extract::Extension, extract::Json, extract::Path, extract::State, http::StatusCode,
-    response::IntoResponse,
+    extract::Extension, extract::Json, extract::Path, extract::Query as QueryParam, extract::State,
+    http::HeaderMap, http::StatusCode, response::IntoResponse, response::Response,
 };
 use futures::{SinkExt, StreamExt};
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection};
+use sea_orm::{ActiveValue, DatabaseConnection};
 use serde_json::json;
 use short_uuid::ShortUuid;
 use std::collections::HashMap;
@@ -29,7 +43,8 @@ use tracing::{debug, error, warn};
 pub async fn create_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-    Json(processor_request): Json<V1ProcessorRequest>,
+    Extension(access): Extension<Access>,
+    Json(mut processor_request): Json<V1ProcessorRequest>,
 ) -> Result<Json<V1Processor>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
@@ -88,27 +103,53 @@ pub async fn create_processor(
     })?;
     debug!("Validated namespace");
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
     debug!(
         "Authorizing namespace {:?} with owner_ids {:?}",
         namespace, owner_ids
     );
-    let owner = auth_ns(db_pool, &owner_ids, &namespace)
+    let owner = auth_ns_for_create(db_pool, &owner_ids, &namespace)
         .await
-        .map_err(|e| {
-            (
+        .map_err(|e| match e.downcast_ref::<NamespaceTerminating>() {
+            Some(terminating) => (
+                StatusCode::CONFLICT,
+                Json(json!({"error": terminating.to_string()})),
+            ),
+            None => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": format!("Authorization error: {}", e)})),
-            )
+            ),
         })?;
     debug!("Authorized namespace");
 
+    if let Some(container) = processor_request.container.as_mut() {
+        apply_namespace_defaults(db_pool, &namespace, container)
+            .await
+            .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+    }
+    admit(
+        db_pool,
+        &namespace,
+        &mut AdmissionObject::Processor(&mut processor_request),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+    if let Some(container) = &processor_request.container {
+        validate_secret_settings(container)?;
+    }
+
+    let _quota_lease = check_quota(
+        db_pool,
+        &namespace,
+        &QuotaRequest {
+            processors: 1,
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
     // Create the standard processor platform
     let app_state = Arc::new(AppState {
         db_pool: db_pool.clone(),
@@ -143,6 +184,7 @@ pub async fn create_processor(
 pub async fn scale_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(scale_request): Json<V1ProcessorScaleRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
@@ -151,6 +193,7 @@ pub async fn scale_processor(
         &namespace,
         &name,
         &user_profile,
+        &access,
         scale_request,
     )
     .await?;
@@ -164,6 +207,7 @@ async fn _scale_processor(
     namespace: &str,
     name: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
     scale_request: V1ProcessorScaleRequest,
 ) -> Result<V1Processor, (StatusCode, Json<serde_json::Value>)> {
     // Validate we have at least one parameter
@@ -175,12 +219,7 @@ async fn _scale_processor(
     }
 
     // Collect owner IDs
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the processor
@@ -201,7 +240,7 @@ async fn _scale_processor(
         }
     };
 
-    let mut active_model = processors::ActiveModel::from(processor);
+    let mut active_model = processors::ActiveModel::from(processor.clone());
 
     // Handle min_replicas update if provided
     if let Some(min_replicas) = scale_request.min_replicas {
@@ -249,13 +288,27 @@ async fn _scale_processor(
         }
     }
 
+    // Replicas are created by the controller, which checks again. The lease
+    // is held until the new target is stored, so concurrent scale-ups of the
+    // namespace are checked one at a time.
+    let _replicas_lease = match active_model.desired_replicas {
+        ActiveValue::Set(Some(target)) => Some(
+            check_replicas_quota(db_pool, &processor, target)
+                .await
+                .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?,
+        ),
+        _ => None,
+    };
+
     // Update the processor in the database
-    let updated_processor = active_model.update(db_pool).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": format!("Failed to update processor: {}", e)})),
-        )
-    })?;
+    let updated_processor = Mutation::update_processor(db_pool, active_model)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update processor: {}", e)})),
+            )
+        })?;
 
     // Convert the updated processor model to V1Processor for the response
     let processor_v1 = updated_processor.to_v1_processor().map_err(|e| {
@@ -272,6 +325,7 @@ async fn _scale_processor(
 pub async fn check_processor_health(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1ProcessorHealthResponse>, (StatusCode, Json<serde_json::Value>)> {
     // Changed return type
@@ -287,14 +341,7 @@ pub async fn check_processor_health(
     );
 
     // --- Authorization and Processor Fetching ---
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        debug!("User organizations found: {:?}", orgs.keys());
-        orgs.keys().cloned().collect()
-    } else {
-        debug!("No organizations found for user.");
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     debug!("Collected owner_ids: {:?}", owner_ids);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
@@ -687,17 +734,30 @@ pub async fn check_processor_health(
 pub async fn list_processors(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-) -> Result<Json<V1Processors>, (StatusCode, Json<serde_json::Value>)> {
+    Extension(access): Extension<Access>,
+    QueryParam(params): QueryParam<V1WatchQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
+    // Start the watch before listing, so no change made in between is missed
+    let watch = if params.watch {
+        let resume_from = resume_token(&params, &headers);
+        Some(
+            Watch::start(
+                db_pool,
+                access.principal(&user_profile),
+                "Processor",
+                resume_from,
+            )
+            .await
+            .map_err(|e| (e.status(), Json(json!({"error": e.to_string()}))))?,
+        )
     } else {
-        Vec::new()
+        None
     };
 
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
@@ -711,6 +771,23 @@ pub async fn list_processors(
             )
         })?;
 
+    // Role bindings can narrow access below the owning org
+    let processor_models = rbac::retain_authorized(
+        db_pool,
+        access.principal(&user_profile),
+        "list",
+        "processors",
+        processor_models,
+        |p| p.namespace.as_str(),
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Database error: {}", e)})),
+        )
+    })?;
+
     // Convert database models to API response models
     let processors_result: Result<Vec<V1Processor>, _> = processor_models
         .into_iter()
@@ -724,23 +801,27 @@ pub async fn list_processors(
         )
     })?;
 
-    Ok(Json(V1Processors { processors }))
+    if let Some(watch) = watch {
+        let current = processors
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap_or_default())
+            .collect();
+        return Ok(watch.into_response(current));
+    }
+
+    Ok(Json(V1Processors { processors }).into_response())
 }
 
 pub async fn get_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1Processor>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let processor = match Query::find_processor_by_namespace_name_and_owners(
@@ -796,6 +877,7 @@ pub async fn get_processor(
 pub async fn send_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(stream_data): Json<V1StreamData>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
@@ -809,12 +891,7 @@ pub async fn send_processor(
     debug!("Resolved namespace: {}", resolved_namespace);
 
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
     debug!("Owner IDs: {:?}", owner_ids);
 
@@ -1248,18 +1325,15 @@ pub async fn send_processor(
 pub async fn delete_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
+    QueryParam(options): QueryParam<V1DeleteOptions>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     debug!("Deleting processor: {} in namespace: {}", name, namespace);
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     debug!(
@@ -1297,6 +1371,17 @@ pub async fn delete_processor(
         }
     };
 
+    let owner = OwnerRef::new("Processor", &processor.namespace, &processor.name);
+    let policy = options.propagation_policy.unwrap_or_default();
+    prepare_owner_deletion(&state, &owner, policy)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to handle dependents: {}", e)})),
+            )
+        })?;
+
     platform
         .delete(&processor.id, db_pool, redis)
         .await
@@ -1309,12 +1394,17 @@ pub async fn delete_processor(
 
     debug!("Deleted processor: {}", processor.id);
 
+    if policy == PropagationPolicy::Background {
+        spawn_dependent_deletion(state.clone(), owner);
+    }
+
     Ok(StatusCode::OK)
 }
 
 pub async fn update_processor(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(update_request): Json<V1UpdateProcessor>,
 ) -> Result<Json<V1Processor>, (StatusCode, Json<serde_json::Value>)> {
@@ -1322,12 +1412,7 @@ pub async fn update_processor(
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the processor
@@ -1581,36 +1666,24 @@ pub async fn update_processor(
             ));
         }
 
-        debug!("Deleting old processor");
-        let app_state = Arc::new(AppState {
-            db_pool: db_pool.clone(),
-            message_queue: state.message_queue.clone(),
-        });
-        let platform = StandardProcessor::new(app_state);
-
-        let redis = match &state.message_queue {
-            crate::state::MessageQueue::Redis { client } => client,
-            _ => {
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({"error": "Kafka streams are not currently supported"})),
-                ))
-            }
-        };
-
-        platform
-            .delete(&processor.id, db_pool, redis)
-            .await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({"error": format!("Failed to delete processor: {}", e)})),
-                )
-            })?;
+        // A new container spec gets the namespace defaults; the existing one
+        // was defaulted when the processor was created
+        let mut merged_container = update_request
+            .container
+            .clone()
+            .or(processor_v1.container.clone());
+        if let Some(container) = merged_container.as_mut() {
+            let applied = if update_request.container.is_some() {
+                apply_namespace_defaults(db_pool, &processor.namespace, container).await
+            } else {
+                apply_namespace_enforced(db_pool, &processor.namespace, container).await
+            };
+            applied.map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+        }
 
         // --- Start: Create the potential final processor state by merging updates ---
         // This is needed for the declare call if recreation happens.
-        let merged_processor_request = V1ProcessorRequest {
+        let mut merged_processor_request = V1ProcessorRequest {
             kind: update_request
                 .kind
                 .clone()
@@ -1626,10 +1699,7 @@ pub async fn update_processor(
                 owner: None,     // Usually set during creation/retrieval, not update
                 owner_ref: None, // Usually set during creation/retrieval, not update
             },
-            container: update_request
-                .container
-                .clone()
-                .or(processor_v1.container.clone()), // Merge container
+            container: merged_container, // Merge container
             schema: update_request
                 .schema
                 .clone()
@@ -1643,6 +1713,43 @@ pub async fn update_processor(
             scale: update_request.scale.clone().or(processor_v1.scale.clone()),      // Merge scale
         };
         // --- End: Create the potential final processor state ---
+        admit(
+            db_pool,
+            &processor.namespace,
+            &mut AdmissionObject::Processor(&mut merged_processor_request),
+        )
+        .await
+        .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+        if let Some(container) = &merged_processor_request.container {
+            validate_secret_settings(container)?;
+        }
+
+        debug!("Deleting old processor");
+        let app_state = Arc::new(AppState {
+            db_pool: db_pool.clone(),
+            message_queue: state.message_queue.clone(),
+        });
+        let platform = StandardProcessor::new(app_state);
+
+        let redis = match &state.message_queue {
+            crate::state::MessageQueue::Redis { client } => client,
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error": "Kafka streams are not currently supported"})),
+                ))
+            }
+        };
+
+        platform
+            .delete(&processor.id, db_pool, redis)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"error": format!("Failed to delete processor: {}", e)})),
+                )
+            })?;
 
         // Create the new processor with merged values
         debug!("Creating new processor with updated fields");
@@ -1679,12 +1786,32 @@ pub async fn update_processor(
         // Check metadata labels
         if let Some(metadata_req) = &update_request.metadata {
             if let Some(labels) = &metadata_req.labels {
+                // Only the labels change, so only they are admitted
+                let mut labels_request = V1ProcessorRequest {
+                    kind: processor_v1.kind.clone(),
+                    metadata: V1ResourceMetaRequest {
+                        name: Some(processor.name.clone()),
+                        namespace: Some(processor.namespace.clone()),
+                        labels: Some(labels.clone()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                admit(
+                    db_pool,
+                    &processor.namespace,
+                    &mut AdmissionObject::Processor(&mut labels_request),
+                )
+                .await
+                .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+                let labels = labels_request.metadata.labels.unwrap_or_default();
+
                 let current_labels_json = processor_active_model
                     .labels
                     .as_ref()
                     .clone()
                     .unwrap_or(serde_json::Value::Null);
-                let new_labels_json = serde_json::to_value(labels).map_err(|e| {
+                let new_labels_json = serde_json::to_value(&labels).map_err(|e| {
                     (
                         StatusCode::INTERNAL_SERVER_ERROR,
                         Json(json!({"error": format!("Failed to serialize labels: {}", e)})),
@@ -1781,12 +1908,14 @@ pub async fn update_processor(
         if model_updated {
             debug!("Applying updates to processor.");
             let updated_processor_model =
-                processor_active_model.update(db_pool).await.map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({"error": format!("Failed to update processor: {}", e)})),
-                    )
-                })?;
+                Mutation::update_processor(db_pool, processor_active_model)
+                    .await
+                    .map_err(|e| {
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(json!({"error": format!("Failed to update processor: {}", e)})),
+                        )
+                    })?;
             let updated_processor_v1 = updated_processor_model.to_v1_processor().map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -1806,6 +1935,7 @@ pub async fn update_processor(
 pub async fn get_processor_logs(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     debug!(
@@ -1816,12 +1946,7 @@ pub async fn get_processor_logs(
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
     // --- Authorization and Processor Fetching (similar to get_processor) ---
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let processor = Query::find_processor_by_namespace_name_and_owners(
@@ -1895,6 +2020,7 @@ pub async fn get_processor_logs(
             db_pool,
             &container_id,
             &user_profile,
+            &access,
         )
         .await
         {
@@ -1937,6 +2063,7 @@ pub async fn get_processor_logs(
 pub async fn read_processor_stream(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(read_request): Json<V1ReadStreamRequest>,
 ) -> Result<Json<Vec<V1StreamMessage>>, (StatusCode, Json<serde_json::Value>)> {
@@ -1951,12 +2078,7 @@ pub async fn read_processor_stream(
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let processor = Query::find_processor_by_namespace_name_and_owners(
@@ -2106,6 +2228,7 @@ pub async fn read_processor_stream(
 pub async fn read_return_message(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name, message_id)): Path<(String, String, String)>,
     Json(read_request): Json<V1ReadStreamRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
@@ -2118,12 +2241,7 @@ pub async fn read_return_message(
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the processor to get its stream name
@@ -2366,11 +2484,19 @@ pub async fn processor_websocket(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> impl IntoResponse {
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
     ws.on_upgrade(move |socket| {
-        handle_bidirectional_processor_socket(socket, state, user_profile, resolved_namespace, name)
+        handle_bidirectional_processor_socket(
+            socket,
+            state,
+            user_profile,
+            access,
+            resolved_namespace,
+            name,
+        )
     })
 }
 
@@ -2397,6 +2523,7 @@ pub async fn stream_processor_return_ws(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name, message_id)): Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
@@ -2405,6 +2532,7 @@ pub async fn stream_processor_return_ws(
             socket,
             state,
             user_profile,
+            access,
             resolved_namespace,
             name,
             message_id,
@@ -2417,6 +2545,7 @@ async fn handle_processor_return_socket(
     socket: WebSocket,
     state: AppState,
     user_profile: V1UserProfile,
+    access: Access,
     namespace: String,
     name: String,
     message_id: String,
@@ -2430,12 +2559,7 @@ async fn handle_processor_return_socket(
     let (sender, _receiver) = socket.split();
 
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the processor to get its stream name
@@ -2772,6 +2896,7 @@ async fn handle_bidirectional_processor_socket(
     socket: WebSocket,
     state: AppState,
     user_profile: V1UserProfile,
+    access: Access,
     namespace: String,
     name: String,
 ) {
@@ -2785,12 +2910,7 @@ async fn handle_bidirectional_processor_socket(
     let ws_sender = Arc::new(Mutex::new(ws_sender));
 
     // Collect owner IDs from user_profile
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Find the processor
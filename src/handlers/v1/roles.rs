@@ -0,0 +1,358 @@
+use crate::agent::ns::NamespaceTerminating;
+use crate::entities::{namespaces, role_bindings, roles};
+use crate::models::V1UserProfile;
+use crate::resources::v1::roles::models::{
+    builtin_role_rules, V1Role, V1RoleBinding, V1RoleBindingRequest, V1RoleBindings, V1RoleRequest,
+    V1Roles,
+};
+use crate::state::AppState;
+use crate::utils::namespace::resolve_namespace;
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use serde_json::json;
+use short_uuid::ShortUuid;
+use tracing::{error, info};
+
+const SUBJECT_KINDS: &[&str] = &["User", "OrgRole", "ApiKey"];
+
+fn owner_ids(user_profile: &V1UserProfile) -> Vec<String> {
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+    owner_ids
+}
+
+fn db_error(e: sea_orm::DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    error!("Roles database error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) })),
+    )
+}
+
+fn bad_request(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message })))
+}
+
+/// Resolve the namespace and name of a role or role binding request and
+/// return them along with the namespace's owner.
+async fn resolve_target(
+    db: &DatabaseConnection,
+    user_profile: &V1UserProfile,
+    namespace: Option<&str>,
+    name: Option<&str>,
+) -> Result<(String, String, String), (StatusCode, Json<serde_json::Value>)> {
+    let name = name
+        .ok_or_else(|| bad_request("metadata.name is required".to_string()))?
+        .to_string();
+    crate::validate::validate_name(&name).map_err(|e| bad_request(e.to_string()))?;
+
+    let namespace = resolve_namespace(namespace.unwrap_or("-"), user_profile);
+    let ns = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(&namespace))
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Namespace '{}' not found", namespace) })),
+            )
+        })?;
+    if ns.is_terminating() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": NamespaceTerminating(namespace.clone()).to_string() })),
+        ));
+    }
+
+    Ok((namespace, name, ns.owner))
+}
+
+pub async fn list_roles(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1Roles>, (StatusCode, Json<serde_json::Value>)> {
+    let roles = roles::Entity::find()
+        .filter(roles::Column::Owner.is_in(owner_ids(&user_profile)))
+        .all(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(V1Roles {
+        roles: roles.iter().map(|r| r.to_v1()).collect(),
+    }))
+}
+
+pub async fn create_role(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Json(request): Json<V1RoleRequest>,
+) -> Result<Json<V1Role>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let (namespace, name, owner) = resolve_target(
+        db_pool,
+        &user_profile,
+        request.metadata.namespace.as_deref(),
+        request.metadata.name.as_deref(),
+    )
+    .await?;
+
+    if builtin_role_rules(&name).is_some() {
+        return Err(bad_request(format!(
+            "'{}' is a built-in role and cannot be redefined",
+            name
+        )));
+    }
+    if request.rules.is_empty() {
+        return Err(bad_request("A role needs at least one rule".to_string()));
+    }
+
+    let full_name = format!("{}/{}", namespace, name);
+    let rules = serde_json::to_value(&request.rules).unwrap_or_default();
+    let labels = request
+        .metadata
+        .labels
+        .as_ref()
+        .map(|labels| serde_json::to_value(labels).unwrap_or_default());
+    let now = chrono::Utc::now();
+
+    // Creating an existing role replaces its rules
+    let existing = roles::Entity::find()
+        .filter(roles::Column::FullName.eq(&full_name))
+        .one(db_pool)
+        .await
+        .map_err(db_error)?;
+
+    let role = match existing {
+        Some(existing) => {
+            let mut active = existing.into_active_model();
+            active.rules = Set(rules);
+            active.labels = Set(labels);
+            active.updated_at = Set(now.into());
+            active.update(db_pool).await.map_err(db_error)?
+        }
+        None => roles::ActiveModel {
+            id: Set(ShortUuid::generate().to_string()),
+            namespace: Set(namespace),
+            name: Set(name),
+            full_name: Set(full_name),
+            owner: Set(owner),
+            rules: Set(rules),
+            labels: Set(labels),
+            created_by: Set(user_profile.email.clone()),
+            updated_at: Set(now.into()),
+            created_at: Set(now.into()),
+        }
+        .insert(db_pool)
+        .await
+        .map_err(db_error)?,
+    };
+
+    info!("Saved role {} by {}", role.full_name, user_profile.email);
+    Ok(Json(role.to_v1()))
+}
+
+pub async fn get_role(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Json<V1Role>, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    let role = roles::Entity::find()
+        .filter(roles::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Role not found" })),
+            )
+        })?;
+
+    Ok(Json(role.to_v1()))
+}
+
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    let result = roles::Entity::delete_many()
+        .filter(roles::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .exec(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Role not found" })),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_role_bindings(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1RoleBindings>, (StatusCode, Json<serde_json::Value>)> {
+    let bindings = role_bindings::Entity::find()
+        .filter(role_bindings::Column::Owner.is_in(owner_ids(&user_profile)))
+        .all(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(V1RoleBindings {
+        role_bindings: bindings.iter().map(|b| b.to_v1()).collect(),
+    }))
+}
+
+pub async fn create_role_binding(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Json(request): Json<V1RoleBindingRequest>,
+) -> Result<Json<V1RoleBinding>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let (namespace, name, owner) = resolve_target(
+        db_pool,
+        &user_profile,
+        request.metadata.namespace.as_deref(),
+        request.metadata.name.as_deref(),
+    )
+    .await?;
+
+    if request.subjects.is_empty() {
+        return Err(bad_request(
+            "A role binding needs at least one subject".to_string(),
+        ));
+    }
+    if let Some(subject) = request
+        .subjects
+        .iter()
+        .find(|s| !SUBJECT_KINDS.contains(&s.kind.as_str()))
+    {
+        return Err(bad_request(format!(
+            "Invalid subject kind '{}', expected one of {}",
+            subject.kind,
+            SUBJECT_KINDS.join(", ")
+        )));
+    }
+
+    if builtin_role_rules(&request.role).is_none() {
+        let role_exists = roles::Entity::find()
+            .filter(roles::Column::FullName.eq(format!("{}/{}", namespace, request.role)))
+            .one(db_pool)
+            .await
+            .map_err(db_error)?
+            .is_some();
+        if !role_exists {
+            return Err(bad_request(format!(
+                "Role '{}' is not built-in and does not exist in namespace '{}'",
+                request.role, namespace
+            )));
+        }
+    }
+
+    let full_name = format!("{}/{}", namespace, name);
+    let subjects = serde_json::to_value(&request.subjects).unwrap_or_default();
+    let labels = request
+        .metadata
+        .labels
+        .as_ref()
+        .map(|labels| serde_json::to_value(labels).unwrap_or_default());
+    let now = chrono::Utc::now();
+
+    // Creating an existing binding replaces its role and subjects
+    let existing = role_bindings::Entity::find()
+        .filter(role_bindings::Column::FullName.eq(&full_name))
+        .one(db_pool)
+        .await
+        .map_err(db_error)?;
+
+    let binding = match existing {
+        Some(existing) => {
+            let mut active = existing.into_active_model();
+            active.role = Set(request.role.clone());
+            active.subjects = Set(subjects);
+            active.labels = Set(labels);
+            active.updated_at = Set(now.into());
+            active.update(db_pool).await.map_err(db_error)?
+        }
+        None => role_bindings::ActiveModel {
+            id: Set(ShortUuid::generate().to_string()),
+            namespace: Set(namespace),
+            name: Set(name),
+            full_name: Set(full_name),
+            owner: Set(owner),
+            role: Set(request.role.clone()),
+            subjects: Set(subjects),
+            labels: Set(labels),
+            created_by: Set(user_profile.email.clone()),
+            updated_at: Set(now.into()),
+            created_at: Set(now.into()),
+        }
+        .insert(db_pool)
+        .await
+        .map_err(db_error)?,
+    };
+
+    info!(
+        "Saved role binding {} by {}",
+        binding.full_name, user_profile.email
+    );
+    Ok(Json(binding.to_v1()))
+}
+
+pub async fn get_role_binding(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Json<V1RoleBinding>, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    let binding = role_bindings::Entity::find()
+        .filter(role_bindings::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .one(&state.db_pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Role binding not found" })),
+            )
+        })?;
+
+    Ok(Json(binding.to_v1()))
+}
+
+pub async fn delete_role_binding(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let namespace = resolve_namespace(&namespace, &user_profile);
+    let result = role_bindings::Entity::delete_many()
+        .filter(role_bindings::Column::FullName.eq(format!("{}/{}", namespace, name)))
+        .exec(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Role binding not found" })),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
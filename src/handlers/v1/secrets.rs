@@ -1,6 +1,11 @@
-use crate::agent::ns::auth_ns;
+use crate::agent::ns::{auth_ns_for_create, NamespaceTerminating};
 use crate::models::V1ResourceMeta;
-use crate::resources::v1::secrets::models::{V1Secret, V1SecretRequest};
+use crate::rbac::{self, Access};
+use crate::resources::v1::policies::admission::{admit, AdmissionObject};
+use crate::resources::v1::secrets::models::{
+    V1KeyRotation, V1Secret, V1SecretRequest, V1SecretRollbackRequest, V1SecretVersion,
+};
+use crate::resources::v1::secrets::{providers, rotation};
 use crate::utils::namespace::resolve_namespace;
 use crate::{
     entities::secrets, models::V1UserProfile, mutation::Mutation, query::Query, state::AppState,
@@ -13,22 +18,24 @@ use axum::{
 use sea_orm::*;
 use serde_json::json;
 use short_uuid::ShortUuid;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 /// Handler: List secrets for the current user (and their organizations)
 pub async fn list_secrets(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
 ) -> Result<Json<Vec<V1Secret>>, (StatusCode, Json<serde_json::Value>)> {
-    let db_pool = &state.db_pool;
+    _list_secrets(&state.db_pool, &user_profile, &access).await
+}
 
+pub async fn _list_secrets(
+    db_pool: &DatabaseConnection,
+    user_profile: &V1UserProfile,
+    access: &Access,
+) -> Result<Json<Vec<V1Secret>>, (StatusCode, Json<serde_json::Value>)> {
     // Gather all possible owner IDs from user + organizations
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     info!("Listing secrets for user: {}", owner_ids.join(", "));
@@ -43,13 +50,34 @@ pub async fn list_secrets(
             )
         })?;
 
+    // Role bindings can narrow access below the owning org
+    let principal = access.principal(user_profile);
+    let secrets_list = rbac::retain_authorized(
+        db_pool,
+        principal,
+        "list",
+        "secrets",
+        secrets_list,
+        |secret| secret.namespace.as_str(),
+    )
+    .await
+    .map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Database error: {}", err) })),
+        )
+    })?;
+
     info!("Found {} secrets", secrets_list.len());
 
     // Transform them into our V1Secret response (decrypted if needed)
     let response = secrets_list
         .into_iter()
         .map(|secret| {
-            let decrypted_value = secret.decrypt_value().ok(); // returns Result; ignore errors
+            let decrypted_value = secret.stored_value();
+            let source = secret.parse_source();
+            let version = secret.current_version();
+            let expired = secret.is_expired();
             V1Secret {
                 kind: "Secret".to_string(),
                 metadata: V1ResourceMeta {
@@ -69,6 +97,9 @@ pub async fn list_secrets(
                 },
                 value: decrypted_value,
                 expires_at: secret.expires_at,
+                source,
+                version,
+                expired,
             }
         })
         .collect();
@@ -108,7 +139,10 @@ pub async fn get_secret(
     };
 
     // Decrypt the value if needed
-    let decrypted_value = secret_model.decrypt_value().ok();
+    let decrypted_value = secret_model.stored_value();
+    let source = secret_model.parse_source();
+    let version = secret_model.current_version();
+    let expired = secret_model.is_expired();
 
     // Build and return the V1Secret response
     let secret_response = V1Secret {
@@ -130,6 +164,9 @@ pub async fn get_secret(
         },
         value: decrypted_value,
         expires_at: secret_model.expires_at,
+        source,
+        version,
+        expired,
     };
 
     Ok(Json(secret_response))
@@ -138,25 +175,22 @@ pub async fn get_secret(
 pub async fn get_secret_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(id): Path<String>,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
-    _get_secret_by_id(db_pool, &id, &user_profile).await
+    _get_secret_by_id(db_pool, &id, &user_profile, &access).await
 }
 
 pub async fn _get_secret_by_id(
     db_pool: &DatabaseConnection,
     id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
     // Gather owners
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     info!("Getting secret for user: {}", owner_ids.join(", "));
@@ -174,7 +208,10 @@ pub async fn _get_secret_by_id(
     info!("Found secret: {}", secret_model.id);
 
     // Decrypt
-    let decrypted_value = secret_model.decrypt_value().ok();
+    let decrypted_value = secret_model.stored_value();
+    let source = secret_model.parse_source();
+    let version = secret_model.current_version();
+    let expired = secret_model.is_expired();
 
     let secret_response = V1Secret {
         kind: "Secret".to_string(),
@@ -195,16 +232,41 @@ pub async fn _get_secret_by_id(
         },
         value: decrypted_value,
         expires_at: secret_model.expires_at,
+        source,
+        version,
+        expired,
     };
 
     Ok(Json(secret_response))
 }
 
+/// Check an external source is usable and not combined with a stored value.
+fn validate_secret_source(
+    payload: &V1SecretRequest,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let Some(source) = &payload.source else {
+        return Ok(());
+    };
+    if !payload.value.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "A secret takes either a value or a source, not both" })),
+        ));
+    }
+    providers::validate_source(source).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid secret source: {}", err) })),
+        )
+    })
+}
+
 /// Handler: Create a new secret
 pub async fn create_secret(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-    Json(payload): Json<V1SecretRequest>,
+    Extension(access): Extension<Access>,
+    Json(mut payload): Json<V1SecretRequest>,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
@@ -224,8 +286,9 @@ pub async fn create_secret(
             Json(json!({ "error": format!("Invalid name: {}", err) })),
         )
     })?;
+    validate_secret_source(&payload)?;
 
-    let namespace_opt = payload.metadata.namespace;
+    let namespace_opt = payload.metadata.namespace.clone();
 
     let handle = match user_profile.handle.clone() {
         Some(handle) => handle,
@@ -264,24 +327,31 @@ pub async fn create_secret(
         )
     })?;
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
 
-    let owner = auth_ns(db_pool, &owner_ids, &namespace)
+    let owner = auth_ns_for_create(db_pool, &owner_ids, &namespace)
         .await
-        .map_err(|e| {
-            (
+        .map_err(|e| match e.downcast_ref::<NamespaceTerminating>() {
+            Some(terminating) => (
+                StatusCode::CONFLICT,
+                Json(json!({"error": terminating.to_string()})),
+            ),
+            None => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": format!("Authorization error: {}", e)})),
-            )
+            ),
         })?;
 
+    admit(
+        db_pool,
+        &namespace,
+        &mut AdmissionObject::Secret(&mut payload),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
     // Create the new Model, which will auto-encrypt the secret value
-    let secret_model = secrets::Model::new(
+    let mut secret_model = secrets::Model::new(
         secret_id.clone(),
         name,
         namespace,
@@ -297,6 +367,7 @@ pub async fn create_secret(
             Json(json!({ "error": format!("Failed to encrypt secret: {}", err) })),
         )
     })?;
+    secret_model.source = payload.source.as_ref().map(|source| json!(source));
 
     // Insert into DB
     let inserted = secrets::ActiveModel::from(secret_model)
@@ -317,7 +388,10 @@ pub async fn create_secret(
         .unwrap_or_default();
 
     // Now decrypt using `inserted`, leaving the prior fields intact
-    let decrypted_value = inserted.decrypt_value().ok();
+    let decrypted_value = inserted.stored_value();
+    let source = inserted.parse_source();
+    let version = inserted.current_version();
+    let expired = inserted.is_expired();
 
     // Finally build the response
     let response = V1Secret {
@@ -335,6 +409,9 @@ pub async fn create_secret(
         },
         value: decrypted_value,
         expires_at: inserted.expires_at,
+        source,
+        version,
+        expired,
     };
 
     Ok(Json(response))
@@ -344,6 +421,7 @@ pub async fn create_secret(
 pub async fn update_secret(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
     Json(payload): Json<V1SecretRequest>,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
@@ -372,33 +450,32 @@ pub async fn update_secret(
     };
 
     // 3) Call the shared helper, passing the existing secret's ID
-    _update_secret_by_id(db_pool, &secret_model.id, &user_profile, &payload).await
+    _update_secret_by_id(db_pool, &secret_model.id, &user_profile, &access, &payload).await
 }
 
 /// Handler: Update a secret by ID
 pub async fn update_secret_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(secret_id): Path<String>,
     Json(payload): Json<V1SecretRequest>,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
-    _update_secret_by_id(db_pool, &secret_id, &user_profile, &payload).await
+    _update_secret_by_id(db_pool, &secret_id, &user_profile, &access, &payload).await
 }
 
 pub async fn _update_secret_by_id(
     db_pool: &DatabaseConnection,
     secret_id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
     payload: &V1SecretRequest,
 ) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
+    validate_secret_source(payload)?;
+
     // Gather owners
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Fetch the model to ensure it exists and user can access
@@ -411,13 +488,28 @@ pub async fn _update_secret_by_id(
             )
         })?;
 
+    let mut payload = payload.clone();
+    admit(
+        db_pool,
+        &existing_secret.namespace,
+        &mut AdmissionObject::Secret(&mut payload),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
+    // Only a changed value or source creates a new version
+    let value_changed = payload.source != existing_secret.parse_source()
+        || (payload.source.is_none()
+            && existing_secret.stored_value().as_deref() != Some(payload.value.as_str()));
+
     // Perform the update
     let updated_secret = Mutation::update_secret(
         db_pool,
         existing_secret,
         payload.metadata.name.clone(),
-        // Provide new_value if you want to re-encrypt. If you want partial updates, handle Option.
-        Some(payload.value.clone()),
+        value_changed.then(|| payload.value.clone()),
+        payload.source.as_ref().map(|source| json!(source)),
+        payload.expires_at,
         Some(json!(payload.metadata.labels)),
     )
     .await
@@ -429,7 +521,10 @@ pub async fn _update_secret_by_id(
     })?;
 
     // Decrypt the newly updated secret
-    let decrypted_value = updated_secret.decrypt_value().ok();
+    let decrypted_value = updated_secret.stored_value();
+    let source = updated_secret.parse_source();
+    let version = updated_secret.current_version();
+    let expired = updated_secret.is_expired();
 
     // Build response
     let response = V1Secret {
@@ -451,6 +546,9 @@ pub async fn _update_secret_by_id(
         },
         value: decrypted_value,
         expires_at: updated_secret.expires_at,
+        source,
+        version,
+        expired,
     };
 
     Ok(Json(response))
@@ -460,6 +558,7 @@ pub async fn _update_secret_by_id(
 pub async fn delete_secret(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
@@ -487,18 +586,19 @@ pub async fn delete_secret(
     };
 
     // 3) Call the shared helper to delete by ID
-    _delete_secret_by_id(db_pool, &secret_model.id, &user_profile).await
+    _delete_secret_by_id(db_pool, &secret_model.id, &user_profile, &access).await
 }
 
 /// Handler: Delete a secret by ID
 pub async fn delete_secret_by_id(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path(secret_id): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     // Just call our shared helper directly
-    _delete_secret_by_id(db_pool, &secret_id, &user_profile).await
+    _delete_secret_by_id(db_pool, &secret_id, &user_profile, &access).await
 }
 
 /// Handler: Delete a secret
@@ -506,14 +606,10 @@ pub async fn _delete_secret_by_id(
     db_pool: &DatabaseConnection,
     secret_id: &str,
     user_profile: &V1UserProfile,
+    access: &Access,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     // Gather owners
-    let mut owner_ids: Vec<String> = user_profile
-        .organizations
-        .as_ref()
-        .map(|orgs| orgs.keys().cloned().collect())
-        .unwrap_or_default();
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Make sure the secret is accessible
@@ -547,3 +643,325 @@ pub async fn _delete_secret_by_id(
     // Return a 200 OK
     Ok(StatusCode::OK)
 }
+
+/// Look up a secret by namespace/name that the user can reach
+async fn find_owned_secret(
+    db_pool: &DatabaseConnection,
+    namespace: &str,
+    name: &str,
+    user_profile: &V1UserProfile,
+    access: &Access,
+) -> Result<secrets::Model, (StatusCode, Json<serde_json::Value>)> {
+    let resolved_namespace = resolve_namespace(namespace, user_profile);
+    let secret = Query::find_secret_by_namespace_and_name(db_pool, &resolved_namespace, name)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", err) })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Secret not found" })),
+            )
+        })?;
+
+    let owner_ids = access.owner_ids(user_profile);
+    let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
+
+    Query::find_secret_by_id_and_owners(db_pool, &secret.id, &owner_id_refs)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Secret not found: {}", err) })),
+            )
+        })
+}
+
+/// Handler: List a secret's versions, newest first, without their values
+pub async fn list_secret_versions(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Json<Vec<V1SecretVersion>>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let secret = find_owned_secret(db_pool, &namespace, &name, &user_profile, &access).await?;
+
+    let previous = Query::find_secret_versions(db_pool, &secret.id)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", err) })),
+            )
+        })?;
+
+    let mut versions = vec![V1SecretVersion {
+        version: secret.current_version(),
+        value: None,
+        source: secret.parse_source(),
+        expires_at: secret.expires_at,
+        expired: secret.is_expired(),
+        current: true,
+        created_at: secret.updated_at.timestamp(),
+    }];
+    versions.extend(previous.into_iter().map(|version| V1SecretVersion {
+        version: version.version,
+        value: None,
+        source: version.parse_source(),
+        expires_at: version.expires_at,
+        expired: version.is_expired(),
+        current: false,
+        created_at: version.created_at.timestamp(),
+    }));
+
+    Ok(Json(versions))
+}
+
+/// Handler: Get one version of a secret, including its value
+pub async fn get_secret_version(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
+    Path((namespace, name, version)): Path<(String, String, i32)>,
+) -> Result<Json<V1SecretVersion>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let secret = find_owned_secret(db_pool, &namespace, &name, &user_profile, &access).await?;
+
+    if version == secret.current_version() {
+        return Ok(Json(V1SecretVersion {
+            version,
+            value: secret.stored_value(),
+            source: secret.parse_source(),
+            expires_at: secret.expires_at,
+            expired: secret.is_expired(),
+            current: true,
+            created_at: secret.updated_at.timestamp(),
+        }));
+    }
+
+    let snapshot = Query::find_secret_version(db_pool, &secret.id, version)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", err) })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Secret version {} not found", version) })),
+            )
+        })?;
+
+    let source = snapshot.parse_source();
+    let value = match source {
+        Some(_) => None,
+        None => snapshot.decrypt_value().ok(),
+    };
+    Ok(Json(V1SecretVersion {
+        version,
+        value,
+        source,
+        expires_at: snapshot.expires_at,
+        expired: snapshot.is_expired(),
+        current: false,
+        created_at: snapshot.created_at.timestamp(),
+    }))
+}
+
+/// Handler: Roll a secret back to a previous version. The old content
+/// becomes a new version, so the rollback itself can be undone.
+pub async fn rollback_secret(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
+    Path((namespace, name)): Path<(String, String)>,
+    Json(payload): Json<V1SecretRollbackRequest>,
+) -> Result<Json<V1Secret>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+    let secret = find_owned_secret(db_pool, &namespace, &name, &user_profile, &access).await?;
+
+    if payload.version == secret.current_version() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Version {} is already current", payload.version) })),
+        ));
+    }
+
+    let target = Query::find_secret_version(db_pool, &secret.id, payload.version)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", err) })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Secret version {} not found", payload.version) })),
+            )
+        })?;
+
+    info!(
+        "Rolling back secret {} to version {}",
+        secret.full_name, payload.version
+    );
+    let secret_id = secret.id.clone();
+    Mutation::rollback_secret(db_pool, secret, target)
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Failed to roll back secret: {}", err) })),
+            )
+        })?;
+
+    _get_secret_by_id(db_pool, &secret_id, &user_profile, &access).await
+}
+
+/// Handler: Re-wrap every secret with the active encryption key. Root only.
+pub async fn rotate_secret_keys(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+) -> Result<Json<V1KeyRotation>, (StatusCode, Json<serde_json::Value>)> {
+    if !rbac::is_root_owner(&user_profile) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only the root owner can rotate encryption keys" })),
+        ));
+    }
+
+    info!("Key rotation requested by {}", user_profile.email);
+    match rotation::rotate_secret_keys(&state.db_pool).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("Key rotation failed: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Key rotation failed: {}", e) })),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{namespace_grants, namespaces, role_bindings, roles};
+    use std::collections::HashMap;
+
+    /// Namespaces `team-a` and `team-b`, both owned by org `acme` and each
+    /// holding one secret. `member@example.com` is a viewer in `team-a`
+    /// only; `team-b` binds someone else.
+    async fn secrets_db() -> DatabaseConnection {
+        for (var, value) in [
+            ("NEBU_BUCKET_NAME", "test"),
+            ("NEBU_BUCKET_REGION", "us-east-1"),
+            ("NEBU_ROOT_OWNER", "root@example.com"),
+        ] {
+            if std::env::var(var).is_err() {
+                std::env::set_var(var, value);
+            }
+        }
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for statement in [
+            schema.create_table_from_entity(namespaces::Entity),
+            schema.create_table_from_entity(namespace_grants::Entity),
+            schema.create_table_from_entity(role_bindings::Entity),
+            schema.create_table_from_entity(roles::Entity),
+            schema.create_table_from_entity(secrets::Entity),
+        ] {
+            db.execute(backend.build(&statement)).await.unwrap();
+        }
+
+        let now = chrono::Utc::now().into();
+        for (namespace, subject) in [
+            ("team-a", "member@example.com"),
+            ("team-b", "other@example.com"),
+        ] {
+            namespaces::ActiveModel {
+                id: Set(format!("ns-{}", namespace)),
+                name: Set(namespace.to_string()),
+                owner: Set("acme".to_string()),
+                created_by: Set("owner@example.com".to_string()),
+                updated_at: Set(now),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+            role_bindings::ActiveModel {
+                id: Set(format!("rb-{}", namespace)),
+                namespace: Set(namespace.to_string()),
+                name: Set("viewers".to_string()),
+                full_name: Set(format!("{}/viewers", namespace)),
+                owner: Set("acme".to_string()),
+                role: Set("viewer".to_string()),
+                subjects: Set(json!([{"kind": "User", "name": subject}])),
+                created_by: Set("owner@example.com".to_string()),
+                updated_at: Set(now),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+            secrets::ActiveModel {
+                id: Set(format!("sec-{}", namespace)),
+                name: Set("token".to_string()),
+                namespace: Set(namespace.to_string()),
+                full_name: Set(format!("{}/token", namespace)),
+                owner: Set("acme".to_string()),
+                encrypted_value: Set(String::new()),
+                nonce: Set(String::new()),
+                updated_at: Set(now),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    fn member(org_role: &str) -> V1UserProfile {
+        let role = HashMap::from([("org_role".to_string(), org_role.to_string())]);
+        V1UserProfile {
+            email: "member@example.com".to_string(),
+            organizations: Some(HashMap::from([("acme".to_string(), role)])),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_secrets_filters_by_role_binding() {
+        let db = secrets_db().await;
+
+        let Json(listed) = _list_secrets(&db, &member("member"), &Access::default())
+            .await
+            .unwrap();
+        let namespaces: Vec<&str> = listed
+            .iter()
+            .map(|s| s.metadata.namespace.as_str())
+            .collect();
+        assert_eq!(namespaces, vec!["team-a"]);
+
+        // Org admins administer every namespace the org owns
+        let Json(listed) = _list_secrets(&db, &member("admin"), &Access::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::meters::ledger::{aggregate_usage, is_valid_window, UsageGrouping};
+use crate::meters::models::{V1Usage, V1UsageQuery};
+use crate::models::V1UserProfile;
+use crate::query::Query;
+use crate::state::AppState;
+use crate::utils::namespace::resolve_namespace;
+use axum::{
+    extract::{Extension, Query as QueryParam, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use tracing::{debug, error};
+
+/// Handler: Aggregate usage from the built-in ledger.
+///
+/// Results only cover namespaces owned by the user or their organizations.
+pub async fn get_usage(
+    State(state): State<AppState>,
+    Extension(user_profile): Extension<V1UserProfile>,
+    QueryParam(params): QueryParam<V1UsageQuery>,
+) -> Result<Json<V1Usage>, (StatusCode, Json<serde_json::Value>)> {
+    let db_pool = &state.db_pool;
+
+    if let Some(window) = &params.window {
+        if !is_valid_window(window) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Invalid window '{}': must be one of hour, day, month", window)
+                })),
+            ));
+        }
+    }
+
+    let group_by: Vec<String> = params
+        .group_by
+        .as_deref()
+        .unwrap_or("namespace,container,meter")
+        .split(',')
+        .map(|g| g.trim().to_lowercase())
+        .filter(|g| !g.is_empty())
+        .collect();
+    if let Some(invalid) = group_by
+        .iter()
+        .find(|g| !matches!(g.as_str(), "namespace" | "container" | "meter"))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!(
+                    "Invalid group_by '{}': must be namespace, container or meter",
+                    invalid
+                )
+            })),
+        ));
+    }
+
+    let mut owner_ids: Vec<String> = user_profile
+        .organizations
+        .as_ref()
+        .map(|orgs| orgs.keys().cloned().collect())
+        .unwrap_or_default();
+    owner_ids.push(user_profile.email.clone());
+    let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
+
+    let accessible = Query::find_namespaces_by_owners(db_pool, &owner_id_refs)
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to query namespaces for owners {:?}: {}",
+                owner_id_refs, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", e) })),
+            )
+        })?;
+    let mut namespaces: Vec<String> = accessible.into_iter().map(|n| n.name).collect();
+
+    if let Some(requested) = &params.namespace {
+        let requested = resolve_namespace(requested, &user_profile);
+        if !namespaces.contains(&requested) {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Namespace '{}' not found", requested) })),
+            ));
+        }
+        namespaces = vec![requested];
+    }
+    let namespace_refs: Vec<&str> = namespaces.iter().map(|s| s.as_str()).collect();
+    debug!("Aggregating usage for namespaces: {:?}", namespace_refs);
+
+    let grouping = UsageGrouping {
+        namespace: group_by.iter().any(|g| g == "namespace"),
+        container: group_by.iter().any(|g| g == "container"),
+        meter: group_by.iter().any(|g| g == "meter"),
+        window: params.window.clone(),
+    };
+
+    let totals = Query::sum_usage_records(
+        db_pool,
+        &namespace_refs,
+        params.container_id.as_deref(),
+        params.meter.as_deref(),
+        params.start,
+        params.end,
+        &grouping,
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Database error: {}", e) })),
+        )
+    })?;
+
+    let buckets = aggregate_usage(&totals, params.window.as_deref());
+
+    Ok(Json(V1Usage {
+        start: params.start,
+        end: params.end,
+        window: params.window,
+        buckets,
+    }))
+}
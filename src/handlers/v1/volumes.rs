@@ -1,7 +1,9 @@
 // src/handlers/containers.rs
 
-use crate::agent::ns::auth_ns;
+use crate::agent::ns::{auth_ns_for_create, NamespaceTerminating};
 use crate::models::V1ResourceMeta;
+use crate::rbac::{self, Access};
+use crate::resources::v1::policies::admission::{admit, AdmissionObject};
 use crate::resources::v1::volumes::models::{V1Volume, V1VolumeRequest};
 use crate::utils::namespace::resolve_namespace;
 use crate::{
@@ -27,19 +29,13 @@ use short_uuid;
 pub async fn get_volume(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<Json<V1Volume>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-
-    // Include user's email (assuming owner_id is user's email)
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let volume = Query::find_volume_by_namespace_name_and_owners(
@@ -62,17 +58,13 @@ pub async fn get_volume(
 pub async fn create_volume(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
-    Json(volume): Json<V1VolumeRequest>,
+    Extension(access): Extension<Access>,
+    Json(mut volume): Json<V1VolumeRequest>,
 ) -> Result<Json<V1Volume>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
     // Get owner IDs from organizations and email
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let namespace_opt = volume.clone().metadata.namespace;
@@ -113,15 +105,27 @@ pub async fn create_volume(
         .clone()
         .unwrap_or_else(|| petname::petname(2, "-").unwrap());
 
-    let owner = auth_ns(db_pool, &owner_ids, &namespace)
+    let owner = auth_ns_for_create(db_pool, &owner_ids, &namespace)
         .await
-        .map_err(|e| {
-            (
+        .map_err(|e| match e.downcast_ref::<NamespaceTerminating>() {
+            Some(terminating) => (
+                StatusCode::CONFLICT,
+                Json(json!({"error": terminating.to_string()})),
+            ),
+            None => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": format!("Authorization error: {}", e)})),
-            )
+            ),
         })?;
 
+    admit(
+        db_pool,
+        &namespace,
+        &mut AdmissionObject::Volume(&mut volume),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
     // Check if a volume with the same namespace and name already exists
     let existing_volume =
         Query::find_volume_by_namespace_name_and_owners(db_pool, &namespace, &name, &owner_id_refs)
@@ -177,18 +181,14 @@ pub async fn create_volume(
 pub async fn delete_volume(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
     // Collect owner IDs
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // 1) Look up volume by namespace + name
@@ -290,21 +290,21 @@ pub async fn ensure_volume(
 pub async fn list_volumes(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
 ) -> Result<Json<Vec<V1Volume>>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
 
     // Gather all possible owner IDs from user + organizations
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     // Retrieve volumes
     let volumes_list = volumes::Entity::find()
-        .filter(volumes::Column::Owner.is_in(owner_id_refs))
+        .filter(Query::owned_or_shared(
+            volumes::Column::Owner,
+            volumes::Column::Namespace,
+            &owner_id_refs,
+        ))
         .all(db_pool)
         .await
         .map_err(|err| {
@@ -314,6 +314,23 @@ pub async fn list_volumes(
             )
         })?;
 
+    // Role bindings can narrow access below the owning org
+    let volumes_list = rbac::retain_authorized(
+        db_pool,
+        access.principal(&user_profile),
+        "list",
+        "volumes",
+        volumes_list,
+        |volume| volume.namespace.as_str(),
+    )
+    .await
+    .map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Database error: {}", err) })),
+        )
+    })?;
+
     // Transform them into V1Volume responses
     let volumes = volumes_list
         .into_iter()
@@ -326,18 +343,14 @@ pub async fn list_volumes(
 pub async fn update_volume(
     State(state): State<AppState>,
     Extension(user_profile): Extension<V1UserProfile>,
+    Extension(access): Extension<Access>,
     Path((namespace, name)): Path<(String, String)>,
-    Json(payload): Json<V1VolumeRequest>,
+    Json(mut payload): Json<V1VolumeRequest>,
 ) -> Result<Json<V1Volume>, (StatusCode, Json<serde_json::Value>)> {
     let db_pool = &state.db_pool;
     let resolved_namespace = resolve_namespace(&namespace, &user_profile);
 
-    let mut owner_ids: Vec<String> = if let Some(orgs) = &user_profile.organizations {
-        orgs.keys().cloned().collect()
-    } else {
-        Vec::new()
-    };
-    owner_ids.push(user_profile.email.clone());
+    let owner_ids = access.owner_ids(&user_profile);
     let owner_id_refs: Vec<&str> = owner_ids.iter().map(|s| s.as_str()).collect();
 
     let volume = Query::find_volume_by_namespace_name_and_owners(
@@ -354,6 +367,21 @@ pub async fn update_volume(
         )
     })?;
 
+    // Policies check the labels the volume ends up with
+    if payload.metadata.labels.is_none() {
+        payload.metadata.labels = volume
+            .labels
+            .clone()
+            .and_then(|labels| serde_json::from_value(labels).ok());
+    }
+    admit(
+        db_pool,
+        &volume.namespace,
+        &mut AdmissionObject::Volume(&mut payload),
+    )
+    .await
+    .map_err(|e| (e.status(), Json(json!({ "error": e.to_string() }))))?;
+
     // Create an ActiveModel from the existing volume
     let mut volume_active_model = volumes::ActiveModel::from(volume);
 
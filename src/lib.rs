@@ -2,7 +2,9 @@
 
 pub mod accelerator;
 pub mod agent;
+pub mod audit;
 pub mod auth;
+pub mod billing;
 pub mod cli;
 pub mod client;
 pub mod config;
@@ -11,6 +13,7 @@ pub mod dns;
 pub mod entities;
 pub mod errors;
 pub mod handlers;
+pub mod meters;
 pub mod middleware;
 pub mod models;
 pub mod mutation;
@@ -20,6 +23,8 @@ pub mod org;
 pub mod orign;
 pub mod proxy;
 pub mod query;
+pub mod ratelimit;
+pub mod rbac;
 pub mod resources;
 pub mod routes;
 pub mod select;
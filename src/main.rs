@@ -4,8 +4,9 @@ mod models;
 use std::path::Path;
 
 use crate::cli::{
-    ApiKeyActions, AuthCommands, Cli, Commands, CreateCommands, DeleteCommands, GetCommands,
-    ProxyCommands, SelectCommands, SendCommands, SetCommands, ShowCommands, SyncCommands,
+    AdminCommands, ApiKeyActions, AuthCommands, Cli, Commands, CreateCommands, DeleteCommands,
+    DescribeCommands, GetCommands, ProxyCommands, SelectCommands, SendCommands, SetCommands,
+    ShowCommands, SyncCommands,
 };
 use clap::Parser;
 use nebulous::select::checkpoint::select_checkpoint;
@@ -89,8 +90,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
             GetCommands::Accelerators { platform } => {
                 commands::get_cmd::get_accelerators(platform).await?;
             }
-            GetCommands::Containers { id } => {
-                commands::get_cmd::get_containers(id).await?;
+            GetCommands::Containers { id, watch } => {
+                commands::get_cmd::get_containers(id, watch).await?;
             }
             GetCommands::Platforms => {
                 commands::get_cmd::get_platforms().await?;
@@ -114,6 +115,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 commands::delete_cmd::delete_processor(name, namespace).await?;
             }
         },
+        Commands::Describe { command } => match command {
+            DescribeCommands::Containers { name, namespace } => {
+                commands::describe_cmd::describe_container(name, namespace).await?;
+            }
+            DescribeCommands::Processors { name, namespace } => {
+                commands::describe_cmd::describe_processor(name, namespace).await?;
+            }
+        },
         Commands::Proxy { command } => match command {
             ProxyCommands::Shell { host, port } => {
                 commands::proxy_cmd::run_sync_cmd_server(&host, port).await?;
@@ -149,8 +158,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         } => {
             commands::log_cmd::fetch_container_logs(name, namespace, follow).await?;
         }
-        Commands::Login { url, auth, hub } => {
-            commands::login_cmd::execute(url, auth, hub).await?;
+        Commands::Login {
+            url,
+            auth,
+            hub,
+            oidc,
+        } => {
+            if oidc {
+                commands::login_cmd::execute_oidc(url).await?;
+            } else {
+                commands::login_cmd::execute(url, auth, hub).await?;
+            }
         }
         Commands::Exec(args) => {
             commands::exec_cmd::exec_cmd(args).await?;
@@ -163,14 +181,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 ApiKeyActions::Get { id } => {
                     commands::auth_cmd::get_api_key(&id).await?;
                 }
-                ApiKeyActions::Generate => {
-                    commands::auth_cmd::generate_api_key().await?;
+                ApiKeyActions::Generate {
+                    label,
+                    namespaces,
+                    allow,
+                    expires_in,
+                } => {
+                    commands::auth_cmd::generate_api_key(label, namespaces, allow, expires_in)
+                        .await?;
                 }
                 ApiKeyActions::Revoke { id } => {
                     commands::auth_cmd::revoke_api_key(&id).await?;
                 }
             },
         },
+        Commands::Admin { command } => match command {
+            AdminCommands::RotateKeys => {
+                commands::admin_cmd::rotate_keys().await?;
+            }
+        },
         Commands::Show { command } => match command {
             ShowCommands::Config => {
                 commands::show_cmd::show_config().await?;
@@ -0,0 +1,89 @@
+use crate::config::SERVER_CONFIG;
+use crate::meters::models::MeterEvent;
+use crate::meters::record_meter_event;
+use crate::query::Query;
+use crate::resources::v1::containers::base::ContainerStatus;
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+/// Name of the meter recording raw compute time for every running container.
+pub const COMPUTE_METER: &str = "compute";
+
+/// Spawn a background task that periodically records a `compute` meter event for
+/// each running container, priced at the container's `resource_cost_per_hr`.
+///
+/// Unlike user-defined meters this runs for every container regardless of
+/// platform, so billing and budgets always have a record of compute spend.
+pub fn spawn_compute_meter(db_pool: DatabaseConnection) -> JoinHandle<()> {
+    let interval_secs = SERVER_CONFIG.meters.compute_interval_secs.max(1);
+
+    tokio::spawn(async move {
+        info!(
+            "[Metering] Compute meter started (interval={}s)",
+            interval_secs
+        );
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        // The first tick fires immediately; skip it so we only bill elapsed time.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            record_compute_usage(&db_pool, interval_secs).await;
+        }
+    })
+}
+
+async fn record_compute_usage(db_pool: &DatabaseConnection, seconds: u64) {
+    let containers = match Query::find_containers_by_status(db_pool, ContainerStatus::Running).await
+    {
+        Ok(containers) => containers,
+        Err(e) => {
+            error!("[Metering] Failed to list running containers: {}", e);
+            return;
+        }
+    };
+
+    let tick = chrono::Utc::now().timestamp() / seconds as i64;
+
+    for container in containers {
+        let Some(cost_per_hr) = container.resource_cost_per_hr else {
+            continue;
+        };
+
+        let accelerator = container
+            .parse_status()
+            .ok()
+            .flatten()
+            .and_then(|s| s.accelerator);
+
+        let data = serde_json::json!({
+            "value": seconds as f64,
+            "metric": COMPUTE_METER,
+            "container_id": container.id,
+            "namespace": container.namespace,
+            "currency": "USD",
+            "cost": cost_per_hr / 3600.0,
+            "unit": "second",
+            "kind": "Container",
+            "service": "Nebulous",
+            "platform": container.platform,
+            "gpu_type": accelerator,
+        });
+
+        // Stable ids per container and tick so replays never double bill
+        let event = MeterEvent::new(
+            "nebulous-compute-meter",
+            COMPUTE_METER,
+            &container.owner,
+            data,
+        )
+        .with_id(format!("compute-{}-{}", container.id, tick));
+        debug!(
+            "[Metering] Recording {}s of compute for container {}",
+            seconds, container.id
+        );
+        record_meter_event(event).await;
+    }
+}
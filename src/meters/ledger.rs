@@ -0,0 +1,265 @@
+use crate::entities::usage_records;
+use crate::meters::models::{MeterEvent, V1UsageBucket};
+use crate::meters::sink::MeterSink;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait, FromQueryResult};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// Sink that records every event in the `usage_records` table so usage can be
+/// queried through `GET /v1/usage` without an external metering service.
+pub struct LedgerSink {
+    db_pool: DatabaseConnection,
+}
+
+impl LedgerSink {
+    pub fn new(db_pool: DatabaseConnection) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl MeterSink for LedgerSink {
+    fn name(&self) -> &str {
+        "ledger"
+    }
+
+    async fn send(&self, events: &[MeterEvent]) -> Result<(), String> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let rows: Vec<usage_records::ActiveModel> = events.iter().map(to_usage_record).collect();
+        debug!("[Metering] Writing {} events to the usage ledger", rows.len());
+
+        // Replayed events keep their id, so conflicts are duplicates and safe to skip.
+        usage_records::Entity::insert_many(rows)
+            .on_conflict(
+                OnConflict::column(usage_records::Column::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db_pool)
+            .await
+            .map_err(|e| format!("Failed to write usage records: {}", e))?;
+
+        Ok(())
+    }
+}
+
+fn to_usage_record(event: &MeterEvent) -> usage_records::ActiveModel {
+    let data = &event.data;
+    let quantity = data.get("value").and_then(value_as_f64).unwrap_or_else(|| {
+        warn!(
+            "[Metering] Event {} has no numeric value, recording quantity 0",
+            event.id
+        );
+        0.0
+    });
+    let unit_price = data.get("cost").and_then(value_as_f64);
+    let occurred_at = DateTime::parse_from_rfc3339(&event.time)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+
+    usage_records::ActiveModel {
+        id: Set(event.id.clone()),
+        namespace: Set(string_field(data, "namespace")),
+        container_id: Set(string_field(data, "container_id")),
+        subject: Set(event.subject.clone()),
+        meter: Set(event.event_type.clone()),
+        source: Set(event.source.clone()),
+        quantity: Set(quantity),
+        unit: Set(string_field(data, "unit")),
+        currency: Set(string_field(data, "currency")),
+        unit_price: Set(unit_price),
+        amount: Set(quantity * unit_price.unwrap_or(0.0)),
+        data: Set(Some(data.clone())),
+        occurred_at: Set(occurred_at.into()),
+        created_at: Set(Utc::now().into()),
+    }
+}
+
+fn string_field(data: &Value, key: &str) -> Option<String> {
+    data.get(key).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+/// Meter values extracted from JSON bodies may be numbers or numeric strings.
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Returns true if `window` is a supported aggregation window.
+pub fn is_valid_window(window: &str) -> bool {
+    matches!(window, "hour" | "day" | "month")
+}
+
+/// Returns the `[start, end)` bounds of the window containing `ts`.
+pub fn window_bounds(ts: i64, window: &str) -> Option<(i64, i64)> {
+    match window {
+        "hour" => {
+            let start = ts - ts.rem_euclid(3600);
+            Some((start, start + 3600))
+        }
+        "day" => {
+            let start = ts - ts.rem_euclid(86400);
+            Some((start, start + 86400))
+        }
+        "month" => {
+            let dt = Utc.timestamp_opt(ts, 0).single()?;
+            let start = Utc
+                .with_ymd_and_hms(dt.year(), dt.month(), 1, 0, 0, 0)
+                .single()?;
+            let (next_year, next_month) = if dt.month() == 12 {
+                (dt.year() + 1, 1)
+            } else {
+                (dt.year(), dt.month() + 1)
+            };
+            let end = Utc
+                .with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0)
+                .single()?;
+            Some((start.timestamp(), end.timestamp()))
+        }
+        _ => None,
+    }
+}
+
+/// Dimensions ledger rows are summed by. Rows are always split by unit and
+/// currency so quantities of different kinds are never summed together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageGrouping {
+    pub namespace: bool,
+    pub container: bool,
+    pub meter: bool,
+    /// `hour`, `day` or `month`
+    pub window: Option<String>,
+}
+
+impl UsageGrouping {
+    /// Group by every dimension, without windows.
+    pub fn all() -> Self {
+        Self {
+            namespace: true,
+            container: true,
+            meter: true,
+            window: None,
+        }
+    }
+}
+
+/// Ledger rows summed by the database for one group. Dimensions that are not
+/// grouped on are `None`.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct UsageTotal {
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    pub meter: Option<String>,
+    pub unit: Option<String>,
+    pub currency: Option<String>,
+    pub quantity: f64,
+    pub amount: f64,
+    pub events: i64,
+    /// Earliest event in the group, which places it in its window
+    pub first_occurred_at: DateTimeWithTimeZone,
+}
+
+/// Turn usage totals into buckets, attaching window bounds when `window` is set.
+pub fn aggregate_usage(totals: &[UsageTotal], window: Option<&str>) -> Vec<V1UsageBucket> {
+    type Key = (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<(i64, i64)>,
+        Option<String>,
+        Option<String>,
+    );
+    let mut buckets: BTreeMap<Key, V1UsageBucket> = BTreeMap::new();
+
+    for total in totals {
+        let bounds = window.and_then(|w| window_bounds(total.first_occurred_at.timestamp(), w));
+        let key: Key = (
+            total.namespace.clone(),
+            total.container_id.clone(),
+            total.meter.clone(),
+            bounds,
+            total.unit.clone(),
+            total.currency.clone(),
+        );
+
+        let bucket = buckets.entry(key.clone()).or_insert_with(|| V1UsageBucket {
+            namespace: key.0.clone(),
+            container_id: key.1.clone(),
+            meter: key.2.clone(),
+            window_start: bounds.map(|b| b.0),
+            window_end: bounds.map(|b| b.1),
+            unit: key.4.clone(),
+            currency: key.5.clone(),
+            quantity: 0.0,
+            cost: 0.0,
+            events: 0,
+        });
+        bucket.quantity += total.quantity;
+        bucket.cost += total.amount;
+        bucket.events += total.events.max(0) as u64;
+    }
+
+    buckets.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(namespace: &str, ts: i64, quantity: f64, events: i64) -> UsageTotal {
+        UsageTotal {
+            namespace: Some(namespace.to_string()),
+            container_id: None,
+            meter: None,
+            unit: Some("second".to_string()),
+            currency: Some("USD".to_string()),
+            quantity,
+            amount: quantity * 0.5,
+            events,
+            first_occurred_at: Utc.timestamp_opt(ts, 0).unwrap().into(),
+        }
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        assert_eq!(window_bounds(3700, "hour"), Some((3600, 7200)));
+        assert_eq!(window_bounds(90000, "day"), Some((86400, 172800)));
+        // 2024-02-15T00:00:00Z falls in February 2024
+        assert_eq!(
+            window_bounds(1707955200, "month"),
+            Some((1706745600, 1709251200))
+        );
+        assert_eq!(window_bounds(0, "week"), None);
+    }
+
+    #[test]
+    fn test_aggregate_usage_by_namespace_and_window() {
+        let totals = vec![
+            total("ns1", 10, 90.0, 2),
+            total("ns2", 30, 10.0, 1),
+            total("ns1", 4000, 5.0, 1),
+        ];
+
+        let buckets = aggregate_usage(&totals, Some("hour"));
+
+        assert_eq!(buckets.len(), 3);
+        let first = &buckets[0];
+        assert_eq!(first.namespace.as_deref(), Some("ns1"));
+        assert_eq!(first.window_start, Some(0));
+        assert_eq!(first.quantity, 90.0);
+        assert_eq!(first.cost, 45.0);
+        assert_eq!(first.events, 2);
+        assert_eq!(first.container_id, None);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod compute;
+pub mod ledger;
+pub mod models;
+pub mod openmeter;
+pub mod pipeline;
+pub mod sink;
+pub mod webhook;
+
+pub use models::MeterEvent;
+pub use pipeline::{get_meter_pipeline, init_meter_pipeline, record_meter_event, MeterPipeline};
+pub use sink::MeterSink;
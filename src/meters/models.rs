@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use short_uuid::ShortUuid;
+
+/// A single billable usage event, shaped after the CloudEvents spec.
+///
+/// Events are what flows through the metering pipeline; sinks convert them
+/// into whatever wire format they need.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MeterEvent {
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub subject: String,
+    pub time: String,
+    pub data: Value,
+}
+
+impl MeterEvent {
+    pub fn new(source: &str, event_type: &str, subject: &str, data: Value) -> Self {
+        Self {
+            id: ShortUuid::generate().to_string(),
+            source: source.to_string(),
+            event_type: event_type.to_string(),
+            subject: subject.to_string(),
+            time: chrono::Utc::now().to_rfc3339(),
+            data,
+        }
+    }
+
+    /// Override the generated id, e.g. to keep ids stable for deduplication.
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Serialize this event as a structured-mode CloudEvents JSON object.
+    pub fn to_cloud_event_json(&self) -> Value {
+        serde_json::json!({
+            "specversion": "1.0",
+            "id": self.id,
+            "source": self.source,
+            "type": self.event_type,
+            "subject": self.subject,
+            "time": self.time,
+            "datacontenttype": "application/json",
+            "data": self.data,
+        })
+    }
+
+    /// Convert this event into an OpenMeter `CloudEvent`.
+    pub fn to_cloud_event(&self) -> openmeter::CloudEvent {
+        openmeter::CloudEvent {
+            id: self.id.clone(),
+            source: self.source.clone(),
+            specversion: "1.0".to_string(),
+            r#type: self.event_type.clone(),
+            subject: self.subject.clone(),
+            time: Some(self.time.clone()),
+            dataschema: None,
+            datacontenttype: Some("application/json".to_string()),
+            data: Some(self.data.clone()),
+        }
+    }
+}
+
+/// Query parameters accepted by `GET /v1/usage`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1UsageQuery {
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    pub meter: Option<String>,
+    /// Start of the time range, as a unix timestamp in seconds (inclusive).
+    pub start: Option<i64>,
+    /// End of the time range, as a unix timestamp in seconds (exclusive).
+    pub end: Option<i64>,
+    /// Bucket size for the time series: `hour`, `day`, `month`, or omitted for a single total.
+    pub window: Option<String>,
+    /// Comma separated dimensions to group by: `namespace`, `container`, `meter`.
+    pub group_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1UsageBucket {
+    pub namespace: Option<String>,
+    pub container_id: Option<String>,
+    pub meter: Option<String>,
+    pub window_start: Option<i64>,
+    pub window_end: Option<i64>,
+    pub unit: Option<String>,
+    pub currency: Option<String>,
+    pub quantity: f64,
+    pub cost: f64,
+    pub events: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1Usage {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub window: Option<String>,
+    pub buckets: Vec<V1UsageBucket>,
+}
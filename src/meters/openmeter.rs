@@ -0,0 +1,49 @@
+use crate::config::SERVER_CONFIG;
+use crate::meters::models::MeterEvent;
+use crate::meters::sink::MeterSink;
+use async_trait::async_trait;
+use openmeter::{CloudEvent, MeterClient};
+use tracing::debug;
+
+/// Sink that ingests events into OpenMeter.
+pub struct OpenMeterSink {
+    client: MeterClient,
+}
+
+impl OpenMeterSink {
+    pub fn from_config() -> Result<Self, String> {
+        let openmeter_url = SERVER_CONFIG
+            .meters
+            .openmeter_url
+            .clone()
+            .ok_or_else(|| "OPENMETER_URL environment variable not set".to_string())?;
+        let openmeter_token = SERVER_CONFIG
+            .meters
+            .openmeter_token
+            .clone()
+            .ok_or_else(|| "OPENMETER_TOKEN environment variable not set".to_string())?;
+
+        Ok(Self {
+            client: MeterClient::new(openmeter_url, openmeter_token),
+        })
+    }
+}
+
+#[async_trait]
+impl MeterSink for OpenMeterSink {
+    fn name(&self) -> &str {
+        "openmeter"
+    }
+
+    async fn send(&self, events: &[MeterEvent]) -> Result<(), String> {
+        let cloud_events: Vec<CloudEvent> = events.iter().map(|e| e.to_cloud_event()).collect();
+        debug!("[Metering] Ingesting {} events into OpenMeter", cloud_events.len());
+
+        self.client
+            .ingest_events(&cloud_events)
+            .await
+            .map_err(|e| format!("Failed to send events to OpenMeter: {}", e))?;
+
+        Ok(())
+    }
+}
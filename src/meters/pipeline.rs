@@ -0,0 +1,445 @@
+use crate::config::SERVER_CONFIG;
+use crate::entities::meter_events;
+use crate::meters::models::MeterEvent;
+use crate::meters::sink::{build_sinks, MeterSink};
+use once_cell::sync::OnceCell;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Global metering pipeline, initialized once by the server at startup.
+static METER_PIPELINE: OnceCell<MeterPipeline> = OnceCell::new();
+
+type Sinks = Arc<Vec<Arc<dyn MeterSink>>>;
+
+/// A background pipeline that batches meter events, delivers them to every
+/// configured sink with retries, and spools undeliverable events to the
+/// database for later replay.
+#[derive(Clone)]
+pub struct MeterPipeline {
+    sender: mpsc::Sender<MeterEvent>,
+    sinks: Sinks,
+    db_pool: DatabaseConnection,
+}
+
+impl MeterPipeline {
+    /// Enqueue an event for delivery. If the in-memory queue is full the event
+    /// is written straight to the spool so it is never dropped.
+    pub async fn record(&self, event: MeterEvent) {
+        match self.sender.try_send(event) {
+            Ok(_) => {}
+            Err(mpsc::error::TrySendError::Full(event))
+            | Err(mpsc::error::TrySendError::Closed(event)) => {
+                warn!(
+                    "[Metering] Queue unavailable, spooling event {} directly",
+                    event.id
+                );
+                for sink in self.sinks.iter() {
+                    spool_events(
+                        &self.db_pool,
+                        sink.name(),
+                        &[event.clone()],
+                        "metering queue full",
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    /// Names of the sinks this pipeline delivers to.
+    pub fn sink_names(&self) -> Vec<String> {
+        self.sinks.iter().map(|s| s.name().to_string()).collect()
+    }
+}
+
+/// Start the metering pipeline worker and register it globally.
+pub fn init_meter_pipeline(db_pool: DatabaseConnection) -> Result<JoinHandle<()>, String> {
+    let (sender, receiver) = mpsc::channel(SERVER_CONFIG.meters.queue_capacity);
+    let sinks: Sinks = Arc::new(build_sinks(&db_pool));
+
+    let pipeline = MeterPipeline {
+        sender,
+        sinks: sinks.clone(),
+        db_pool: db_pool.clone(),
+    };
+    METER_PIPELINE
+        .set(pipeline)
+        .map_err(|_| "Metering pipeline already initialized".to_string())?;
+
+    let sink_queues = sinks
+        .iter()
+        .map(|sink| spawn_sink_worker(sink.clone(), db_pool.clone()))
+        .collect();
+
+    Ok(tokio::spawn(run_worker(receiver, sink_queues, db_pool)))
+}
+
+/// Get the global metering pipeline, if it has been initialized.
+pub fn get_meter_pipeline() -> Option<&'static MeterPipeline> {
+    METER_PIPELINE.get()
+}
+
+/// Record a meter event through the global pipeline.
+pub async fn record_meter_event(event: MeterEvent) {
+    match get_meter_pipeline() {
+        Some(pipeline) => pipeline.record(event).await,
+        None => {
+            error!(
+                "[Metering] Pipeline not initialized, dropping event {} ({})",
+                event.id, event.event_type
+            );
+        }
+    }
+}
+
+/// Batches waiting for one sink before new batches for it are spooled.
+const SINK_QUEUE_BATCHES: usize = 16;
+
+/// Queue of batches for one sink's worker.
+struct SinkQueue {
+    name: String,
+    sender: mpsc::Sender<Vec<MeterEvent>>,
+}
+
+/// Batch events and hand each batch to every sink's worker. This never waits
+/// on a sink, so a slow sink cannot hold up delivery to the others.
+async fn run_worker(
+    mut receiver: mpsc::Receiver<MeterEvent>,
+    sink_queues: Vec<SinkQueue>,
+    db_pool: DatabaseConnection,
+) {
+    let batch_size = SERVER_CONFIG.meters.batch_size.max(1);
+    let mut interval = tokio::time::interval(Duration::from_millis(
+        SERVER_CONFIG.meters.flush_interval_ms,
+    ));
+    let mut buffer: Vec<MeterEvent> = Vec::with_capacity(batch_size);
+
+    info!("[Metering] Pipeline started (batch_size={})", batch_size);
+
+    loop {
+        tokio::select! {
+            maybe_event = receiver.recv() => {
+                match maybe_event {
+                    Some(event) => {
+                        buffer.push(event);
+                        if buffer.len() >= batch_size {
+                            flush(&db_pool, &sink_queues, &mut buffer).await;
+                        }
+                    }
+                    None => {
+                        // All senders are gone; deliver what we have and stop.
+                        flush(&db_pool, &sink_queues, &mut buffer).await;
+                        info!("[Metering] Pipeline channel closed, worker exiting");
+                        return;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                flush(&db_pool, &sink_queues, &mut buffer).await;
+            }
+        }
+    }
+}
+
+/// Queue the buffered events for each sink, spooling them for any sink whose
+/// worker is too far behind to take them.
+async fn flush(
+    db_pool: &DatabaseConnection,
+    sink_queues: &[SinkQueue],
+    buffer: &mut Vec<MeterEvent>,
+) {
+    if buffer.is_empty() {
+        return;
+    }
+    let batch: Vec<MeterEvent> = buffer.drain(..).collect();
+
+    if sink_queues.is_empty() {
+        debug!(
+            "[Metering] No metering sink configured, discarding {} events",
+            batch.len()
+        );
+        return;
+    }
+
+    for queue in sink_queues {
+        match queue.sender.try_send(batch.clone()) {
+            Ok(_) => {}
+            Err(mpsc::error::TrySendError::Full(batch))
+            | Err(mpsc::error::TrySendError::Closed(batch)) => {
+                warn!(
+                    "[Metering] Sink {} is behind, spooling {} events",
+                    queue.name,
+                    batch.len()
+                );
+                spool_events(db_pool, &queue.name, &batch, "sink queue full").await;
+            }
+        }
+    }
+}
+
+/// Start the worker delivering to one sink. It retries each batch with
+/// backoff, spools batches that still fail, and replays the spool on every
+/// flush interval.
+fn spawn_sink_worker(sink: Arc<dyn MeterSink>, db_pool: DatabaseConnection) -> SinkQueue {
+    let (sender, mut receiver) = mpsc::channel::<Vec<MeterEvent>>(SINK_QUEUE_BATCHES);
+    let name = sink.name().to_string();
+
+    tokio::spawn(async move {
+        let batch_size = SERVER_CONFIG.meters.batch_size.max(1);
+        let mut interval = tokio::time::interval(Duration::from_millis(
+            SERVER_CONFIG.meters.flush_interval_ms,
+        ));
+
+        loop {
+            tokio::select! {
+                maybe_batch = receiver.recv() => {
+                    let Some(batch) = maybe_batch else {
+                        return;
+                    };
+                    if let Err(e) = deliver_with_retry(sink.as_ref(), &batch).await {
+                        error!(
+                            "[Metering] Failed to deliver {} events to {}, spooling: {}",
+                            batch.len(),
+                            sink.name(),
+                            e
+                        );
+                        spool_events(&db_pool, sink.name(), &batch, &e).await;
+                    }
+                }
+                _ = interval.tick() => {
+                    replay_spool(&db_pool, sink.as_ref(), batch_size).await;
+                }
+            }
+        }
+    });
+
+    SinkQueue { name, sender }
+}
+
+async fn deliver_with_retry(sink: &dyn MeterSink, batch: &[MeterEvent]) -> Result<(), String> {
+    let max_retries = SERVER_CONFIG.meters.max_retries;
+    let mut attempt = 0;
+
+    loop {
+        match sink.send(batch).await {
+            Ok(_) => {
+                debug!(
+                    "[Metering] Delivered {} events to {}",
+                    batch.len(),
+                    sink.name()
+                );
+                return Ok(());
+            }
+            Err(e) if attempt < max_retries => {
+                let delay = backoff_delay(attempt);
+                warn!(
+                    "[Metering] Delivery attempt {} to {} failed: {}. Retrying in {:?}",
+                    attempt + 1,
+                    sink.name(),
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Exponential backoff starting at 500ms and capped at 30s.
+fn backoff_delay(attempt: u32) -> Duration {
+    let millis = 500u64.saturating_mul(1u64 << attempt.min(16));
+    Duration::from_millis(millis.min(30_000))
+}
+
+async fn spool_events(
+    db_pool: &DatabaseConnection,
+    sink_name: &str,
+    events: &[MeterEvent],
+    reason: &str,
+) {
+    let now = chrono::Utc::now();
+
+    for event in events {
+        let event_json = match serde_json::to_value(event) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[Metering] Failed to serialize event {}: {}", event.id, e);
+                continue;
+            }
+        };
+
+        let row = meter_events::ActiveModel {
+            id: Set(format!("{}:{}", sink_name, event.id)),
+            sink: Set(sink_name.to_string()),
+            event: Set(event_json),
+            attempts: Set(1),
+            last_error: Set(Some(reason.to_string())),
+            dead_lettered_at: Set(None),
+            updated_at: Set(now.into()),
+            created_at: Set(now.into()),
+        };
+
+        if let Err(e) = meter_events::Entity::insert(row).exec(db_pool).await {
+            error!(
+                "[Metering] Failed to spool event {} for {}; usage is lost: {}",
+                event.id, sink_name, e
+            );
+        }
+    }
+}
+
+/// Replay one batch of events spooled for `sink`, oldest first. If the sink
+/// rejects the batch, each event is sent on its own so one bad event does not
+/// hold back the rest. Events still failing after `max_spool_attempts` are
+/// dead-lettered: kept in the spool but no longer replayed.
+async fn replay_spool(db_pool: &DatabaseConnection, sink: &dyn MeterSink, batch_size: usize) {
+    let rows = match meter_events::Entity::find()
+        .filter(meter_events::Column::Sink.eq(sink.name()))
+        .filter(meter_events::Column::DeadLetteredAt.is_null())
+        .order_by_asc(meter_events::Column::CreatedAt)
+        .limit(batch_size as u64)
+        .all(db_pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!("[Metering] Failed to read spooled events: {}", e);
+            return;
+        }
+    };
+
+    if rows.is_empty() {
+        return;
+    }
+
+    let mut events = Vec::with_capacity(rows.len());
+    let mut readable_rows = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row.parse_event() {
+            Ok(event) => {
+                events.push(event);
+                readable_rows.push(row);
+            }
+            Err(e) => {
+                error!(
+                    "[Metering] Dropping unreadable spooled event {}: {}",
+                    row.id, e
+                );
+                let _ = meter_events::Entity::delete_by_id(row.id.clone())
+                    .exec(db_pool)
+                    .await;
+            }
+        }
+    }
+
+    if sink.send(&events).await.is_ok() {
+        info!(
+            "[Metering] Replayed {} spooled events to {}",
+            events.len(),
+            sink.name()
+        );
+        for row in readable_rows {
+            remove_spooled(db_pool, &row.id).await;
+        }
+        return;
+    }
+
+    let mut replayed = 0;
+    for (row, event) in readable_rows.into_iter().zip(events) {
+        match sink.send(std::slice::from_ref(&event)).await {
+            Ok(_) => {
+                replayed += 1;
+                remove_spooled(db_pool, &row.id).await;
+            }
+            Err(e) => record_replay_failure(db_pool, sink.name(), row, e).await,
+        }
+    }
+    if replayed > 0 {
+        info!(
+            "[Metering] Replayed {} spooled events to {} one at a time",
+            replayed,
+            sink.name()
+        );
+    }
+}
+
+async fn remove_spooled(db_pool: &DatabaseConnection, id: &str) {
+    if let Err(e) = meter_events::Entity::delete_by_id(id.to_string())
+        .exec(db_pool)
+        .await
+    {
+        error!("[Metering] Failed to remove replayed event {}: {}", id, e);
+    }
+}
+
+async fn record_replay_failure(
+    db_pool: &DatabaseConnection,
+    sink_name: &str,
+    row: meter_events::Model,
+    error: String,
+) {
+    let attempts = row.attempts + 1;
+    let now = chrono::Utc::now();
+    let mut active: meter_events::ActiveModel = row.clone().into();
+    active.attempts = Set(attempts);
+    active.last_error = Set(Some(error.clone()));
+    active.updated_at = Set(now.into());
+
+    if is_dead_letter(attempts, SERVER_CONFIG.meters.max_spool_attempts) {
+        error!(
+            "[Metering] Dead-lettering event {} after {} failed deliveries to {}: {}",
+            row.id, attempts, sink_name, error
+        );
+        active.dead_lettered_at = Set(Some(now.into()));
+    } else {
+        warn!(
+            "[Metering] Spool replay of {} to {} failed, will retry: {}",
+            row.id, sink_name, error
+        );
+    }
+
+    if let Err(e) = active.update(db_pool).await {
+        error!(
+            "[Metering] Failed to update spooled event {}: {}",
+            row.id, e
+        );
+    }
+}
+
+/// Whether a spooled event has failed often enough to stop replaying it.
+fn is_dead_letter(attempts: i32, max_attempts: i32) -> bool {
+    attempts >= max_attempts.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(3), Duration::from_millis(4000));
+        assert_eq!(backoff_delay(10), Duration::from_secs(30));
+        assert_eq!(backoff_delay(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_is_dead_letter() {
+        assert!(!is_dead_letter(1, 20));
+        assert!(!is_dead_letter(19, 20));
+        assert!(is_dead_letter(20, 20));
+        assert!(is_dead_letter(21, 20));
+        // A zero or negative limit still allows one attempt
+        assert!(is_dead_letter(1, 0));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::config::SERVER_CONFIG;
+use crate::meters::ledger::LedgerSink;
+use crate::meters::models::MeterEvent;
+use crate::meters::openmeter::OpenMeterSink;
+use crate::meters::webhook::WebhookSink;
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// A destination for meter events. Every configured sink receives every event;
+/// delivery to one sink failing does not affect the others.
+#[async_trait]
+pub trait MeterSink: Send + Sync {
+    /// Stable name of the sink, used to route spooled events back to it.
+    fn name(&self) -> &str;
+
+    /// Deliver a batch of events. Implementations should be idempotent on event id,
+    /// since batches may be retried or replayed from the spool.
+    async fn send(&self, events: &[MeterEvent]) -> Result<(), String>;
+}
+
+/// Build the sinks named in `NEBU_METER_SINKS`.
+pub fn build_sinks(db_pool: &DatabaseConnection) -> Vec<Arc<dyn MeterSink>> {
+    let mut sinks: Vec<Arc<dyn MeterSink>> = Vec::new();
+
+    for name in &SERVER_CONFIG.meters.sinks {
+        match name.as_str() {
+            "openmeter" => match OpenMeterSink::from_config() {
+                Ok(sink) => sinks.push(Arc::new(sink)),
+                Err(e) => error!("[Metering] Cannot enable openmeter sink: {}", e),
+            },
+            "ledger" => sinks.push(Arc::new(LedgerSink::new(db_pool.clone()))),
+            "webhook" => match WebhookSink::from_config() {
+                Ok(sink) => sinks.push(Arc::new(sink)),
+                Err(e) => error!("[Metering] Cannot enable webhook sink: {}", e),
+            },
+            other => error!("[Metering] Unknown meter sink '{}', ignoring", other),
+        }
+    }
+
+    info!(
+        "[Metering] Enabled sinks: {:?}",
+        sinks.iter().map(|s| s.name()).collect::<Vec<_>>()
+    );
+    sinks
+}
@@ -0,0 +1,65 @@
+use crate::config::SERVER_CONFIG;
+use crate::meters::models::MeterEvent;
+use crate::meters::sink::MeterSink;
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::debug;
+
+/// Sink that POSTs batches of CloudEvents to an arbitrary HTTP endpoint
+/// using the `application/cloudevents-batch+json` content type.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn from_config() -> Result<Self, String> {
+        let url = SERVER_CONFIG
+            .meters
+            .webhook_url
+            .clone()
+            .ok_or_else(|| "NEBU_METER_WEBHOOK_URL environment variable not set".to_string())?;
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            token: SERVER_CONFIG.meters.webhook_token.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl MeterSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, events: &[MeterEvent]) -> Result<(), String> {
+        let body: Vec<Value> = events.iter().map(|e| e.to_cloud_event_json()).collect();
+        debug!("[Metering] Posting {} events to {}", body.len(), self.url);
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/cloudevents-batch+json")
+            .json(&body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Webhook request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Webhook responded with status {}",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+}
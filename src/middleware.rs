@@ -1,17 +1,31 @@
+use crate::audit::{self, models::V1AuditEvent};
 use crate::auth;
+use crate::auth::cache::identity_cache;
+use crate::auth::models::{ApiKey, ApiKeyScope};
+use crate::auth::scope::{classify_request, ScopedRequest, NAMESPACED_CREATE_KINDS};
+use crate::auth::workload;
 use crate::config::{ClientConfig, ServerConfig, SERVER_CONFIG};
+use crate::entities::namespaces;
 use crate::models::V1UserProfile;
+use crate::query::Query;
+use crate::ratelimit::{api_key_rate_limit, enforce_rate_limits, namespace_rate_limit};
+use crate::rbac::{self, Principal};
+use crate::utils::namespace::resolve_namespace;
 use crate::AppState;
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
+    body::Body,
+    extract::{ConnectInfo, RawPathParams, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
-use sea_orm::DatabaseConnection;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde_json::json;
-use tracing::debug;
+use short_uuid::ShortUuid;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{debug, error};
 
 pub async fn auth_middleware(
     State(state): State<AppState>,
@@ -35,9 +49,13 @@ pub async fn auth_middleware(
             println!("Bearer token is empty");
             unauthorized_response()
         } else {
-            match get_user_profile_from_token(db_pool, token).await {
-                Ok(user_profile) => {
+            match authenticate_token(db_pool, token).await {
+                Ok((user_profile, scope)) => {
                     let mut req = request;
+                    // Checked by `scope_middleware`, once the audit layer can see denials
+                    if let Some(scope) = scope {
+                        req.extensions_mut().insert(scope);
+                    }
                     req.extensions_mut().insert(user_profile);
                     next.run(req).await
                 }
@@ -50,6 +68,419 @@ pub async fn auth_middleware(
     }
 }
 
+/// Enforce the scope of the API key or workload token a request was made with.
+///
+/// Must run after `auth_middleware`, and inside `audit_middleware` so that
+/// scope denials are audited.
+pub async fn scope_middleware(request: Request, next: Next) -> Response {
+    let (Some(scope), Some(user_profile)) = (
+        request.extensions().get::<ApiKeyScope>().cloned(),
+        request.extensions().get::<V1UserProfile>().cloned(),
+    ) else {
+        return next.run(request).await;
+    };
+    match check_api_key_scope(&scope, request, &user_profile).await {
+        Ok(request) => next.run(request).await,
+        Err(response) => response,
+    }
+}
+
+/// Routes about the caller itself, which every scope may use.
+const SELF_SERVICE_PATHS: &[&str] = &[
+    "/v1/users/me",
+    "/v1/auth/workload-token",
+    "/v1/auth/s3-credentials",
+];
+
+/// Reject requests outside a scoped API key's namespaces, verbs and kinds.
+///
+/// Creates carry their namespace in the body, so for namespace-restricted
+/// keys their body is buffered to check it.
+async fn check_api_key_scope(
+    scope: &ApiKeyScope,
+    request: Request,
+    user_profile: &V1UserProfile,
+) -> Result<Request, Response> {
+    if SELF_SERVICE_PATHS.contains(&request.uri().path()) {
+        return Ok(request);
+    }
+    let scoped = classify_request(request.method(), request.uri().path());
+
+    let (request, namespace) = if is_namespaced_create(&scoped) && scope.namespaces.is_some() {
+        let (request, body) = buffer_json_body(request).await?;
+        (request, Some(create_namespace(body.as_deref())))
+    } else {
+        (request, scoped.namespace.clone())
+    };
+    let namespace = namespace.map(|ns| resolve_namespace(&ns, user_profile));
+
+    if !scope.allows_namespace(namespace.as_deref()) {
+        debug!(
+            "API key scope denies namespace {:?} for {} {}",
+            namespace, scoped.verb, scoped.kind
+        );
+        return Err(forbidden_response(match namespace {
+            Some(ns) => format!("API key is not allowed in namespace '{}'", ns),
+            None => "API key is restricted to namespaces; use a namespaced route".to_string(),
+        }));
+    }
+
+    if !scope.allows(&scoped.verb, &scoped.kind, scoped.name.as_deref()) {
+        debug!(
+            "API key scope denies {} on {} {:?}",
+            scoped.verb, scoped.kind, scoped.name
+        );
+        return Err(forbidden_response(format!(
+            "API key is not allowed to {} {}",
+            scoped.verb, scoped.kind
+        )));
+    }
+
+    Ok(request)
+}
+
+/// Largest request body buffered for authorization and auditing.
+const MAX_BUFFERED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// A request's JSON body, buffered by the first middleware that needs it and
+/// shared with later ones through the request extensions. `None` if the body
+/// is not JSON.
+#[derive(Clone, Debug)]
+pub struct BufferedBody(pub Option<Arc<serde_json::Value>>);
+
+/// Read the request body as JSON, or reuse it if an earlier middleware already
+/// did, returning the request with its body restored.
+async fn buffer_json_body(
+    request: Request,
+) -> Result<(Request, Option<Arc<serde_json::Value>>), Response> {
+    if let Some(BufferedBody(body)) = request.extensions().get::<BufferedBody>() {
+        let body = body.clone();
+        return Ok((request, body));
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_BUFFERED_BODY_BYTES)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({"error": "Request body too large"})),
+            )
+                .into_response()
+        })?;
+    let body = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .map(Arc::new);
+    parts.extensions.insert(BufferedBody(body.clone()));
+    Ok((Request::from_parts(parts, Body::from(bytes)), body))
+}
+
+fn is_namespaced_create(scoped: &ScopedRequest) -> bool {
+    scoped.namespace.is_none()
+        && scoped.verb == "create"
+        && NAMESPACED_CREATE_KINDS.contains(&scoped.kind.as_str())
+}
+
+/// The namespace a create targets: the body's `metadata.namespace`, or the
+/// caller's own namespace (`-`) when it is not set.
+fn create_namespace(body: Option<&serde_json::Value>) -> String {
+    body.and_then(|body| body.pointer("/metadata/namespace")?.as_str())
+        .unwrap_or("-")
+        .to_string()
+}
+
+/// Enforce namespace roles and role bindings for every routed API request.
+///
+/// Must run after `auth_middleware`. Creates carry their namespace in the
+/// body's `metadata.namespace`, so JSON create bodies are buffered here if an
+/// earlier middleware has not already done so; creates without one act in the
+/// caller's own namespace. Id routes are checked in their target's namespace.
+pub async fn rbac_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(user_profile) = request.extensions().get::<V1UserProfile>().cloned() else {
+        return next.run(request).await;
+    };
+    let scoped = classify_request(request.method(), request.uri().path());
+    let api_key_id = request
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(auth::api::api_key_id)
+        .map(|id| id.to_string());
+
+    let (mut request, namespace) = match rbac_namespace(&state.db_pool, request, &scoped).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+
+    let mut access = rbac::Access {
+        api_key_id,
+        namespace: None,
+    };
+    if let Some(namespace) = namespace {
+        let namespace = resolve_namespace(&namespace, &user_profile);
+        let principal = access.principal(&user_profile);
+        if let Err(response) = check_rbac(&state.db_pool, principal, &scoped, &namespace).await {
+            return response;
+        }
+        // Handlers trust this decision and look up objects in the namespace
+        // on behalf of its owner
+        let found = namespaces::Entity::find()
+            .filter(namespaces::Column::Name.eq(namespace.as_str()))
+            .one(&state.db_pool)
+            .await;
+        match found {
+            Ok(ns) => access.namespace = ns.map(|ns| (ns.name, ns.owner)),
+            Err(e) => {
+                error!("RBAC namespace lookup failed: {}", e);
+                return authorization_failed_response();
+            }
+        }
+    }
+
+    request.extensions_mut().insert(access);
+    next.run(request).await
+}
+
+/// Kinds with `/<kind>/:id` routes whose namespace is read from the target.
+const ID_ROUTE_KINDS: &[&str] = &["containers", "secrets"];
+
+/// The namespace a request acts in for RBAC: from the path, from the body of
+/// a create, or from the row an id route targets. `None` for requests that
+/// are not namespaced. Id routes whose target cannot be found are denied.
+async fn rbac_namespace(
+    db: &DatabaseConnection,
+    request: Request,
+    scoped: &ScopedRequest,
+) -> Result<(Request, Option<String>), Response> {
+    if let Some(namespace) = &scoped.namespace {
+        return Ok((request, Some(namespace.clone())));
+    }
+    if is_namespaced_create(scoped) {
+        let (request, body) = buffer_json_body(request).await?;
+        return Ok((request, Some(create_namespace(body.as_deref()))));
+    }
+    let Some(id) = scoped
+        .name
+        .as_deref()
+        .filter(|_| ID_ROUTE_KINDS.contains(&scoped.kind.as_str()))
+    else {
+        return Ok((request, None));
+    };
+
+    let found = match scoped.kind.as_str() {
+        "containers" => Query::find_container_by_id(db, id.to_string())
+            .await
+            .map(|c| c.map(|c| c.namespace)),
+        _ => Query::find_secret_by_id(db, id)
+            .await
+            .map(|s| s.map(|s| s.namespace)),
+    };
+    match found {
+        Ok(Some(namespace)) => Ok((request, Some(namespace))),
+        Ok(None) => Err(forbidden_response(format!(
+            "Not allowed to {} {} '{}'",
+            scoped.verb, scoped.kind, id
+        ))),
+        Err(e) => {
+            error!("RBAC namespace lookup failed: {}", e);
+            Err(authorization_failed_response())
+        }
+    }
+}
+
+/// Deny the request unless `principal` may do it in `namespace`.
+async fn check_rbac(
+    db: &DatabaseConnection,
+    principal: Principal<'_>,
+    scoped: &ScopedRequest,
+    namespace: &str,
+) -> Result<(), Response> {
+    match rbac::authorize(db, principal, &scoped.verb, &scoped.kind, namespace).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(forbidden_response(format!(
+            "Not allowed to {} {} in namespace '{}'",
+            scoped.verb, scoped.kind, namespace
+        ))),
+        Err(e) => {
+            error!("RBAC check failed: {}", e);
+            Err(authorization_failed_response())
+        }
+    }
+}
+
+fn authorization_failed_response() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": "Authorization check failed"})),
+    )
+        .into_response()
+}
+
+/// Record mutating API calls, and reads of secrets, in the audit log.
+///
+/// Must run after `auth_middleware`. Mutating requests have their JSON body
+/// buffered, or reuse the one an earlier middleware buffered, to note which
+/// fields they set. The request id, taken from
+/// `X-Request-Id` or generated, is echoed on the response.
+pub async fn audit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(user_profile) = request.extensions().get::<V1UserProfile>().cloned() else {
+        return next.run(request).await;
+    };
+    let scoped = classify_request(request.method(), request.uri().path());
+    if !audit::is_audited(request.method(), &scoped) {
+        return next.run(request).await;
+    }
+
+    let request_id = request
+        .headers()
+        .get(audit::REQUEST_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| ShortUuid::generate().to_string());
+    let api_key_id = request
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(auth::api::api_key_id)
+        .map(|id| id.to_string());
+    let source_ip = audit::source_ip(
+        request.headers(),
+        request.extensions().get::<ConnectInfo<SocketAddr>>(),
+        &SERVER_CONFIG.trusted_proxies,
+    );
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|ua| ua.to_string());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let (request, body) = if matches!(method, Method::POST | Method::PUT | Method::PATCH) {
+        match buffer_json_body(request).await {
+            Ok(buffered) => buffered,
+            Err(response) => return response,
+        }
+    } else {
+        (request, None)
+    };
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(audit::REQUEST_ID_HEADER, value);
+    }
+
+    // Creates name their target in the body rather than the path
+    let namespace = scoped
+        .namespace
+        .clone()
+        .or_else(|| {
+            body.as_ref()?
+                .pointer("/metadata/namespace")?
+                .as_str()
+                .map(|ns| ns.to_string())
+        })
+        .map(|ns| resolve_namespace(&ns, &user_profile));
+    let name = scoped.name.clone().or_else(|| {
+        body.as_ref()?
+            .pointer("/metadata/name")?
+            .as_str()
+            .map(|name| name.to_string())
+    });
+
+    audit::record_event(
+        &state.db_pool,
+        V1AuditEvent {
+            id: ShortUuid::generate().to_string(),
+            // Workloads are recorded as themselves, not the user they act for
+            actor: user_profile
+                .actor
+                .clone()
+                .unwrap_or_else(|| user_profile.email.clone()),
+            api_key_id,
+            action: scoped.verb,
+            method: method.to_string(),
+            path,
+            kind: scoped.kind,
+            namespace,
+            name,
+            request_id,
+            status: response.status().as_u16() as i32,
+            changes: body.as_deref().map(audit::changed_fields),
+            source_ip,
+            user_agent,
+            created_at: chrono::Utc::now().timestamp(),
+        },
+    )
+    .await;
+
+    response
+}
+
+/// Apply per-API-key and per-namespace rate limits to routed API requests.
+///
+/// Must run after `auth_middleware`, and as a route layer so the `:namespace`
+/// path parameter is available.
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    path_params: Option<RawPathParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let db_pool = &state.db_pool;
+    let user_profile = request.extensions().get::<V1UserProfile>().cloned();
+
+    let namespace = path_params.as_ref().and_then(|params| {
+        params
+            .iter()
+            .find(|(key, _)| *key == "namespace")
+            .map(|(_, value)| match &user_profile {
+                Some(profile) => resolve_namespace(value, profile),
+                None => value.to_string(),
+            })
+    });
+
+    let mut targets = Vec::new();
+    if let Some(target) = api_key_rate_limit(db_pool, request.headers()).await {
+        targets.push(target);
+    }
+    if let Some(namespace) = &namespace {
+        if let Some(target) = namespace_rate_limit(db_pool, namespace).await {
+            targets.push(target);
+        }
+    }
+
+    let subject = user_profile
+        .as_ref()
+        .map(|p| p.email.clone())
+        .unwrap_or_default();
+    if let Err(response) = enforce_rate_limits(
+        &state.message_queue,
+        &targets,
+        &subject,
+        namespace.as_deref(),
+        None,
+    )
+    .await
+    {
+        return response;
+    }
+
+    next.run(request).await
+}
+
 pub async fn internal_auth(
     db_conn: &DatabaseConnection,
     token: &str,
@@ -81,10 +512,58 @@ pub async fn get_user_profile_from_token(
     db_conn: &DatabaseConnection,
     token: &str,
 ) -> Result<V1UserProfile, StatusCode> {
-    if token.starts_with("nebu-") {
-        get_user_profile_from_internal_token(db_conn, token).await
+    authenticate_token(db_conn, token)
+        .await
+        .map(|(user_profile, _)| user_profile)
+}
+
+/// Get a user profile and, for scoped API keys, the key's scope from any token.
+/// Successful results are served from the identity cache until they expire.
+pub async fn authenticate_token(
+    db_conn: &DatabaseConnection,
+    token: &str,
+) -> Result<(V1UserProfile, Option<ApiKeyScope>), StatusCode> {
+    let cache = identity_cache();
+    if let Some(identity) = cache.get(token) {
+        if let Some(id) = &identity.api_key_id {
+            if !workload::is_subject(id) {
+                auth::api::record_api_key_use(id);
+            }
+        }
+        return Ok((identity.user_profile, identity.scope));
+    }
+
+    if workload::is_workload_token(token) {
+        let (claims, _) = workload::authenticate_workload_token(db_conn, token)
+            .await
+            .map_err(|e| {
+                debug!("Workload token rejected: {}", e);
+                StatusCode::UNAUTHORIZED
+            })?;
+        let user_profile = workload::workload_profile(&claims);
+        let scope = workload::workload_scope(&claims);
+        cache.insert(
+            token,
+            user_profile.clone(),
+            Some(scope.clone()),
+            Some(workload::subject(&claims.sub)),
+            chrono::DateTime::from_timestamp(claims.exp, 0),
+        );
+        Ok((user_profile, Some(scope)))
+    } else if token.starts_with("nebu-") {
+        let (user_profile, api_key) = authenticate_internal_token(db_conn, token).await?;
+        cache.insert(
+            token,
+            user_profile.clone(),
+            api_key.scope.clone(),
+            Some(api_key.id),
+            api_key.expires_at,
+        );
+        Ok((user_profile, api_key.scope))
     } else {
-        get_user_profile_from_external_token(token).await
+        let user_profile = get_user_profile_from_external_token(token).await?;
+        cache.insert(token, user_profile.clone(), None, None, None);
+        Ok((user_profile, None))
     }
 }
 
@@ -93,14 +572,27 @@ pub async fn get_user_profile_from_internal_token(
     db_conn: &DatabaseConnection,
     token: &str,
 ) -> Result<V1UserProfile, StatusCode> {
+    if !token.starts_with("nebu-") {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    authenticate_token(db_conn, token)
+        .await
+        .map(|(user_profile, _)| user_profile)
+}
+
+async fn authenticate_internal_token(
+    db_conn: &DatabaseConnection,
+    token: &str,
+) -> Result<(V1UserProfile, ApiKey), StatusCode> {
     debug!("Validating internal token: {}", token);
-    let is_valid = auth::api::validate_api_key(db_conn, token).await;
-    match is_valid {
-        Ok(is_valid) => {
-            if is_valid {
+    let api_key = auth::api::authenticate_api_key(db_conn, token).await;
+    match api_key {
+        Ok(api_key) => {
+            if let Some(api_key) = api_key {
                 println!("✅ Internal token is valid");
 
-                let user_profile = V1UserProfile {
+                // Keys issued at login carry the user's identity
+                let user_profile = api_key.profile.clone().unwrap_or(V1UserProfile {
                     email: "dummy@example.com".to_string(),
                     display_name: None,
                     handle: None,
@@ -113,8 +605,8 @@ pub async fn get_user_profile_from_internal_token(
                     created: None,
                     updated: None,
                     token: None,
-                };
-                Ok(user_profile)
+                });
+                Ok((user_profile, api_key))
             } else {
                 println!("❌ Internal token is invalid");
                 Err(StatusCode::UNAUTHORIZED)
@@ -180,6 +672,18 @@ pub async fn get_user_profile_from_external_token(
     }
 }
 
+fn forbidden_response(message: String) -> Response {
+    let error_response = json!({
+        "error": {
+            "message": message,
+            "type": "permission_error",
+            "param": null,
+            "code": null
+        }
+    });
+    (StatusCode::FORBIDDEN, Json(error_response)).into_response()
+}
+
 fn unauthorized_response() -> Response {
     let error_response = json!({
         "error": {
@@ -191,3 +695,245 @@ fn unauthorized_response() -> Response {
     });
     (StatusCode::UNAUTHORIZED, Json(error_response)).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{namespace_grants, namespaces, role_bindings, roles, secrets};
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, Schema, Set};
+
+    fn profile() -> V1UserProfile {
+        V1UserProfile {
+            email: "dev@example.com".to_string(),
+            handle: Some("dev".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn team_scope() -> ApiKeyScope {
+        ApiKeyScope {
+            namespaces: Some(vec!["team".to_string()]),
+            permissions: Some(vec!["create:containers".parse().unwrap()]),
+        }
+    }
+
+    fn create_request(body: serde_json::Value) -> Request {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/v1/containers")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scope_checks_create_body_namespace() {
+        let allowed = create_request(json!({"metadata": {"namespace": "team"}}));
+        let request = check_api_key_scope(&team_scope(), allowed, &profile())
+            .await
+            .unwrap();
+
+        // The body is still there for the handler, and shared with later middleware
+        let buffered = request.extensions().get::<BufferedBody>().cloned();
+        let bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["metadata"]["namespace"], "team");
+        assert_eq!(buffered.and_then(|b| b.0).as_deref(), Some(&body));
+
+        let denied = create_request(json!({"metadata": {"namespace": "other"}}));
+        let response = check_api_key_scope(&team_scope(), denied, &profile())
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_body_is_parsed_once() {
+        let (request, first) = buffer_json_body(create_request(json!({"value": "x"})))
+            .await
+            .unwrap();
+        let (request, second) = buffer_json_body(request).await.unwrap();
+        assert!(Arc::ptr_eq(
+            first.as_ref().unwrap(),
+            second.as_ref().unwrap()
+        ));
+
+        let bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(bytes, json!({"value": "x"}).to_string());
+    }
+
+    #[tokio::test]
+    async fn test_scope_create_without_namespace_uses_callers_namespace() {
+        let request = create_request(json!({"metadata": {"name": "web"}}));
+        let response = check_api_key_scope(&team_scope(), request, &profile())
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let own = ApiKeyScope {
+            namespaces: Some(vec!["dev".to_string()]),
+            ..team_scope()
+        };
+        let request = create_request(json!({"metadata": {"name": "web"}}));
+        assert!(check_api_key_scope(&own, request, &profile()).await.is_ok());
+    }
+
+    fn user(email: &str) -> V1UserProfile {
+        V1UserProfile {
+            email: email.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A namespace `team` owned by `owner@example.com`, where
+    /// `viewer@example.com` is bound to the `viewer` role, holding secret `sec-1`.
+    async fn rbac_db() -> DatabaseConnection {
+        for (var, value) in [
+            ("NEBU_BUCKET_NAME", "test"),
+            ("NEBU_BUCKET_REGION", "us-east-1"),
+            ("NEBU_ROOT_OWNER", "root@example.com"),
+        ] {
+            if std::env::var(var).is_err() {
+                std::env::set_var(var, value);
+            }
+        }
+
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for statement in [
+            schema.create_table_from_entity(namespaces::Entity),
+            schema.create_table_from_entity(namespace_grants::Entity),
+            schema.create_table_from_entity(role_bindings::Entity),
+            schema.create_table_from_entity(roles::Entity),
+            schema.create_table_from_entity(secrets::Entity),
+        ] {
+            db.execute(backend.build(&statement)).await.unwrap();
+        }
+
+        let now = chrono::Utc::now().into();
+        namespaces::ActiveModel {
+            id: Set("ns-1".to_string()),
+            name: Set("team".to_string()),
+            owner: Set("owner@example.com".to_string()),
+            created_by: Set("owner@example.com".to_string()),
+            updated_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        role_bindings::ActiveModel {
+            id: Set("rb-1".to_string()),
+            namespace: Set("team".to_string()),
+            name: Set("viewers".to_string()),
+            full_name: Set("team/viewers".to_string()),
+            owner: Set("owner@example.com".to_string()),
+            role: Set("viewer".to_string()),
+            subjects: Set(json!([{"kind": "User", "name": "viewer@example.com"}])),
+            created_by: Set("owner@example.com".to_string()),
+            updated_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        secrets::ActiveModel {
+            id: Set("sec-1".to_string()),
+            name: Set("token".to_string()),
+            namespace: Set("team".to_string()),
+            full_name: Set("team/token".to_string()),
+            owner: Set("owner@example.com".to_string()),
+            encrypted_value: Set(String::new()),
+            nonce: Set(String::new()),
+            updated_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        db
+    }
+
+    async fn check_id_route(
+        db: &DatabaseConnection,
+        user_profile: &V1UserProfile,
+        method: Method,
+        path: &str,
+    ) -> Result<(), Response> {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        let scoped = classify_request(request.method(), request.uri().path());
+        let (_, namespace) = rbac_namespace(db, request, &scoped).await?;
+        let namespace = namespace.expect("id routes are namespaced");
+        check_rbac(db, Principal::new(user_profile, None), &scoped, &namespace).await
+    }
+
+    #[tokio::test]
+    async fn test_rbac_viewer_denied_delete_by_id() {
+        let db = rbac_db().await;
+        let viewer = user("viewer@example.com");
+
+        assert!(
+            check_id_route(&db, &viewer, Method::GET, "/v1/secrets/sec-1")
+                .await
+                .is_ok()
+        );
+        let response = check_id_route(&db, &viewer, Method::DELETE, "/v1/secrets/sec-1")
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let owner = user("owner@example.com");
+        assert!(
+            check_id_route(&db, &owner, Method::DELETE, "/v1/secrets/sec-1")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rbac_viewer_denied_update_by_id() {
+        let db = rbac_db().await;
+        let viewer = user("viewer@example.com");
+
+        let response = check_id_route(&db, &viewer, Method::PUT, "/v1/secrets/sec-1")
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let outsider = user("outsider@example.com");
+        let response = check_id_route(&db, &outsider, Method::PUT, "/v1/secrets/sec-1")
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_denies_unresolvable_id() {
+        let db = rbac_db().await;
+        let owner = user("owner@example.com");
+        let response = check_id_route(&db, &owner, Method::DELETE, "/v1/secrets/missing")
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_create_without_namespace_uses_callers_namespace() {
+        let db = rbac_db().await;
+        let request = create_request(json!({"metadata": {"name": "web"}}));
+        let scoped = classify_request(request.method(), request.uri().path());
+        let (_, namespace) = rbac_namespace(&db, request, &scoped).await.unwrap();
+        assert_eq!(namespace.as_deref(), Some("-"));
+    }
+}
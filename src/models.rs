@@ -71,6 +71,32 @@ pub struct V1AuthzConfig {
     pub auth_type: String,
     pub jwt: Option<V1AuthzJwt>,
     pub rules: Option<Vec<V1AuthzRule>>,
+    pub rate_limit: Option<V1RateLimit>,
+}
+
+/// Token bucket rate limit: `requests_per_second` refill rate with room for
+/// `burst` requests at once (defaults to one second's worth).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RateLimit {
+    pub requests_per_second: f64,
+    pub burst: Option<u32>,
+}
+
+impl V1RateLimit {
+    pub fn new(requests_per_second: f64, burst: Option<u32>) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+        }
+    }
+
+    /// Bucket capacity, never less than a single request.
+    pub fn capacity(&self) -> f64 {
+        match self.burst {
+            Some(burst) => (burst as f64).max(1.0),
+            None => self.requests_per_second.ceil().max(1.0),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
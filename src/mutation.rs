@@ -1,11 +1,18 @@
+use crate::auth::cache::broadcast_revocation;
+use crate::auth::workload;
 use crate::entities::containers;
 use crate::entities::processors;
+use crate::entities::secret_versions;
 use crate::entities::secrets;
 use crate::resources::v1::containers::models::{V1Port, V1UpdateContainer};
+use crate::resources::v1::events::recorder::{record_status_change, ResourceRef};
 use crate::resources::v1::processors::models::V1ProcessorStatus;
+use crate::resources::v1::watch::feed::{publish_container_change, publish_processor_change};
+use crate::resources::v1::watch::models::ChangeType;
 use sea_orm::*;
 use serde_json::json;
 use short_uuid::ShortUuid;
+use std::collections::HashMap;
 use tracing::{debug, error, info};
 
 pub struct Mutation;
@@ -15,7 +22,9 @@ impl Mutation {
         db: &DatabaseConnection,
         form_data: containers::ActiveModel,
     ) -> Result<containers::Model, DbErr> {
-        form_data.insert(db).await
+        let container = form_data.insert(db).await?;
+        publish_container_change(ChangeType::Added, &container).await;
+        Ok(container)
     }
 
     /// Mutation to update the resource_name field in a container
@@ -93,6 +102,62 @@ impl Mutation {
         }
     }
 
+    /// Mutation to update just the `desired_status` of a container.
+    pub async fn update_container_desired_status(
+        db: &DatabaseConnection,
+        id: String,
+        new_desired_status: Option<String>,
+    ) -> Result<containers::Model, DbErr> {
+        let container = containers::Entity::find_by_id(id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| DbErr::Custom(format!("Container '{}' not found", id)))?;
+
+        let mut container_am: containers::ActiveModel = container.into();
+        container_am.desired_status = Set(new_desired_status);
+        container_am.updated_at = Set(chrono::Utc::now().into());
+
+        info!(
+            "[Mutation] Updating container '{}' desired_status to: {:?}",
+            id, container_am.desired_status
+        );
+        container_am.update(db).await
+    }
+
+    /// Mutation to record that a container just served a proxied request.
+    /// Leaves `updated_at` alone since this is not a change to the container.
+    pub async fn update_container_last_active(
+        db: &DatabaseConnection,
+        id: String,
+    ) -> Result<(), DbErr> {
+        containers::Entity::update_many()
+            .col_expr(
+                containers::Column::LastActiveAt,
+                sea_query::Expr::value(chrono::Utc::now()),
+            )
+            .filter(containers::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Record the version or hash of each secret a container was started with.
+    pub async fn update_container_secret_fingerprints(
+        db: &DatabaseConnection,
+        id: String,
+        fingerprints: HashMap<String, String>,
+    ) -> Result<(), DbErr> {
+        containers::Entity::update_many()
+            .col_expr(
+                containers::Column::SecretFingerprints,
+                sea_query::Expr::value(json!(fingerprints)),
+            )
+            .filter(containers::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
     // Mutation to update only the container status
     pub async fn update_container_status(
         db: &DatabaseConnection,
@@ -131,6 +196,9 @@ impl Mutation {
             existing_status
         );
 
+        let previous_status = existing_status.status.clone();
+        let status_before = json!(existing_status);
+        let status_message = message.clone();
         let mut container: containers::ActiveModel = container.into();
 
         // 1. Parse any existing status from the database
@@ -190,7 +258,26 @@ impl Mutation {
         );
 
         // 4. Update in the database
-        container.update(db).await
+        let container = container.update(db).await?;
+
+        // 5. Keep status transitions in the container's event history
+        if let Some(current) = &existing_status.status {
+            record_status_change(
+                db,
+                &ResourceRef::from(&container),
+                previous_status.as_deref(),
+                current,
+                status_message.as_deref(),
+            )
+            .await;
+        }
+
+        // 6. Only tell watchers when something other than the timestamp moved
+        if json!(existing_status) != status_before {
+            publish_container_change(ChangeType::Modified, &container).await;
+        }
+
+        Ok(container)
     }
 
     // Mutation to update multiple container fields
@@ -245,7 +332,9 @@ impl Mutation {
         // Always update the updated_at timestamp
         container.updated_at = Set(chrono::Utc::now().into());
 
-        container.update(db).await
+        let container = container.update(db).await?;
+        publish_container_change(ChangeType::Modified, &container).await;
+        Ok(container)
     }
 
     // Mutation to delete a container by ID
@@ -253,7 +342,7 @@ impl Mutation {
         db: &DatabaseConnection,
         id: String,
     ) -> Result<DeleteResult, DbErr> {
-        let result = containers::Entity::delete_by_id(id).exec(db).await?;
+        let result = Self::delete_container_if_exists(db, id).await?;
 
         // Check if any row was actually deleted
         if result.rows_affected == 0 {
@@ -263,6 +352,23 @@ impl Mutation {
         Ok(result)
     }
 
+    /// Mutation to delete a container by ID that may already be gone
+    pub async fn delete_container_if_exists(
+        db: &DatabaseConnection,
+        id: String,
+    ) -> Result<DeleteResult, DbErr> {
+        let container = containers::Entity::find_by_id(id.clone()).one(db).await?;
+        let result = containers::Entity::delete_by_id(id).exec(db).await?;
+
+        if let Some(container) = container.filter(|_| result.rows_affected > 0) {
+            // Workload tokens of a deleted container stop working on every replica
+            broadcast_revocation(&workload::subject(&container.id)).await;
+            publish_container_change(ChangeType::Deleted, &container).await;
+        }
+
+        Ok(result)
+    }
+
     /// Mutation to update the container user
     pub async fn update_container_user(
         db: &DatabaseConnection,
@@ -355,13 +461,22 @@ impl Mutation {
     }
 
     /// Update an existing secret by re-encrypting if `new_value` is provided.
+    /// A new value creates a new version, keeping the previous one in
+    /// `secret_versions`, and replaces the secret's `source`. `expires_at` is
+    /// only changed when one is given.
     pub async fn update_secret(
         db: &DatabaseConnection,
         secret: secrets::Model,
         new_name: Option<String>,
         new_value: Option<String>,
+        new_source: Option<serde_json::Value>,
+        new_expires_at: Option<i32>,
         new_labels: Option<serde_json::Value>,
     ) -> Result<secrets::Model, DbErr> {
+        if new_value.is_some() {
+            Self::snapshot_secret_version(db, &secret).await?;
+        }
+        let next_version = secret.current_version() + 1;
         let mut active_model = secrets::ActiveModel::from(secret);
 
         // If a new name is provided
@@ -371,10 +486,17 @@ impl Mutation {
 
         // If a new value is provided, re-encrypt
         if let Some(value) = new_value {
-            let (encrypted_value, nonce) =
-                secrets::Model::encrypt_value(&value).map_err(|e| DbErr::Custom(e))?;
-            active_model.encrypted_value = Set(encrypted_value);
-            active_model.nonce = Set(nonce);
+            let encrypted = secrets::Model::encrypt_value(&value).map_err(|e| DbErr::Custom(e))?;
+            active_model.encrypted_value = Set(encrypted.encrypted_value);
+            active_model.nonce = Set(encrypted.nonce);
+            active_model.key_id = Set(Some(encrypted.key_id));
+            active_model.encrypted_key = Set(Some(encrypted.encrypted_key));
+            active_model.source = Set(new_source);
+            active_model.version = Set(Some(next_version));
+        }
+
+        if let Some(expires_at) = new_expires_at {
+            active_model.expires_at = Set(Some(expires_at));
         }
 
         // If new labels are provided
@@ -388,11 +510,55 @@ impl Mutation {
         active_model.update(db).await
     }
 
+    /// Restore a previous version's content as a new version of the secret.
+    pub async fn rollback_secret(
+        db: &DatabaseConnection,
+        secret: secrets::Model,
+        target: secret_versions::Model,
+    ) -> Result<secrets::Model, DbErr> {
+        Self::snapshot_secret_version(db, &secret).await?;
+
+        let next_version = secret.current_version() + 1;
+        let mut active_model = secrets::ActiveModel::from(secret);
+        active_model.encrypted_value = Set(target.encrypted_value);
+        active_model.nonce = Set(target.nonce);
+        active_model.key_id = Set(target.key_id);
+        active_model.encrypted_key = Set(target.encrypted_key);
+        active_model.source = Set(target.source);
+        active_model.expires_at = Set(target.expires_at);
+        active_model.version = Set(Some(next_version));
+        active_model.updated_at = Set(chrono::Utc::now().into());
+
+        active_model.update(db).await
+    }
+
+    /// Keep a secret's current value in `secret_versions` before it is replaced.
+    async fn snapshot_secret_version(
+        db: &DatabaseConnection,
+        secret: &secrets::Model,
+    ) -> Result<(), DbErr> {
+        let previous = secret_versions::Model::from_secret(secret);
+        secret_versions::Entity::insert(secret_versions::ActiveModel::from(previous))
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::column(secret_versions::Column::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
     /// Delete a secret by ID
     pub async fn delete_secret(
         db: &DatabaseConnection,
         id: String,
     ) -> Result<sea_orm::DeleteResult, DbErr> {
+        secret_versions::Entity::delete_many()
+            .filter(secret_versions::Column::SecretId.eq(id.clone()))
+            .exec(db)
+            .await?;
         secrets::Entity::delete_by_id(id).exec(db).await
     }
 
@@ -439,6 +605,10 @@ impl Mutation {
             id, existing_status
         );
 
+        let previous_status = existing_status.status.clone();
+        let status_before = json!(existing_status);
+        let status_message = new_message.clone();
+
         // 4) Merge in the new optional fields if provided
         if let Some(s) = new_status {
             existing_status.status = Some(s);
@@ -458,7 +628,26 @@ impl Mutation {
         );
 
         // 6) Write it back to the database
-        processor_am.update(db).await
+        let processor = processor_am.update(db).await?;
+
+        // 7) Keep status transitions in the processor's event history
+        if let Some(current) = &existing_status.status {
+            record_status_change(
+                db,
+                &ResourceRef::from(&processor),
+                previous_status.as_deref(),
+                current,
+                status_message.as_deref(),
+            )
+            .await;
+        }
+
+        // 8) Only tell watchers when something other than the timestamp moved
+        if json!(existing_status) != status_before {
+            publish_processor_change(ChangeType::Modified, &processor).await;
+        }
+
+        Ok(processor)
     }
 
     /// Mutation to update just the `desired_status` of a processor.
@@ -489,4 +678,38 @@ impl Mutation {
         // 4) Write it back to the database
         processor_am.update(db).await
     }
+
+    pub async fn create_processor(
+        db: &DatabaseConnection,
+        form_data: processors::ActiveModel,
+    ) -> Result<processors::Model, DbErr> {
+        let processor = form_data.insert(db).await?;
+        publish_processor_change(ChangeType::Added, &processor).await;
+        Ok(processor)
+    }
+
+    /// Mutation to save changes to a processor's spec
+    pub async fn update_processor(
+        db: &DatabaseConnection,
+        form_data: processors::ActiveModel,
+    ) -> Result<processors::Model, DbErr> {
+        let processor = form_data.update(db).await?;
+        publish_processor_change(ChangeType::Modified, &processor).await;
+        Ok(processor)
+    }
+
+    /// Mutation to delete a processor by ID that may already be gone
+    pub async fn delete_processor(
+        db: &DatabaseConnection,
+        id: String,
+    ) -> Result<DeleteResult, DbErr> {
+        let processor = processors::Entity::find_by_id(id.clone()).one(db).await?;
+        let result = processors::Entity::delete_by_id(id).exec(db).await?;
+
+        if let Some(processor) = processor.filter(|_| result.rows_affected > 0) {
+            publish_processor_change(ChangeType::Deleted, &processor).await;
+        }
+
+        Ok(result)
+    }
 }
@@ -255,6 +255,7 @@ mod tests {
             auth_type: "jwt".to_string(),
             jwt: None,
             rules: Some(rules),
+            rate_limit: None,
         };
 
         // Act
@@ -310,6 +311,7 @@ mod tests {
             auth_type: "jwt".to_string(),
             jwt: None,
             rules: Some(rules),
+            rate_limit: None,
         };
 
         let request_path = "/api/other"; // won't matter
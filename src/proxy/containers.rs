@@ -1,21 +1,40 @@
+use crate::billing::budget::exhausted_budget;
 use crate::models::V1AuthzConfig;
 use crate::models::V1UserProfile;
 use crate::proxy::authz::evaluate_authorization_rules;
-use crate::proxy::meters::{send_request_metrics, send_response_metrics};
+use crate::proxy::meters::{record_request_metrics, record_response_metrics};
 use crate::query::Query;
+use crate::ratelimit::{
+    api_key_rate_limit, enforce_rate_limits, namespace_rate_limit, RateLimitTarget,
+};
 use crate::resources::v1::containers::base::get_vpn_device_name;
+use crate::resources::v1::containers::idle::{
+    is_container_ready, is_scaled_to_zero, record_proxied_request, wait_until_ready, wake_container,
+};
 use crate::AppState;
 use axum::body::Body;
 use axum::http::Uri;
 use axum::{
     body::Bytes,
     extract::State,
-    http::{HeaderMap, Method, StatusCode},
+    http::{header, HeaderMap, Method, StatusCode},
     response::{IntoResponse, Response},
 };
 use serde_json::Value;
 use tracing::{debug, error};
 
+/// Seconds a client should wait before retrying a container that is starting.
+const WAKE_RETRY_AFTER_SECS: u64 = 10;
+
+fn container_unavailable(container_id: &str, reason: &str) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, WAKE_RETRY_AFTER_SECS.to_string())],
+        format!("Container {} {}", container_id, reason),
+    )
+        .into_response()
+}
+
 #[allow(dead_code)]
 pub async fn forward_container(
     State(_app_state): State<AppState>, // replace with actual state usage if needed
@@ -90,28 +109,89 @@ pub async fn forward_container(
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     }
 
-    // ---------------------------------------------------
-    //  Send request_value metrics to openmeter
-    // ---------------------------------------------------
-    let maybe_meters = container_model.parse_meters().unwrap_or(None);
+    // Rate limits, most specific first: container, namespace, then API key
+    let mut rate_limit_targets = Vec::new();
+    if let Some(limit) = authz_config.rate_limit.clone() {
+        rate_limit_targets.push(RateLimitTarget::new(
+            format!("container:{}", container_model.id),
+            limit,
+        ));
+    }
+    if let Some(target) = namespace_rate_limit(&_app_state.db_pool, &namespace).await {
+        rate_limit_targets.push(target);
+    }
+    if let Some(target) = api_key_rate_limit(&_app_state.db_pool, &headers).await {
+        rate_limit_targets.push(target);
+    }
+    if let Err(response) = enforce_rate_limits(
+        &_app_state.message_queue,
+        &rate_limit_targets,
+        &user_profile.email,
+        Some(&namespace),
+        Some(&container_model.id),
+    )
+    .await
+    {
+        return response;
+    }
 
-    // Only iterate if we actually have some meters
-    if let Some(ref meters) = maybe_meters.clone() {
-        match send_request_metrics(&container_model.id, meters, &json_body_opt).await {
-            Ok(_) => {
-                debug!("[Proxy] Successfully sent metrics to OpenMeter");
+    // Idle scale-to-zero: record activity and wake the container if it was stopped
+    let mut container_model = container_model;
+    if let Some(idle) = container_model.parse_idle().ok().flatten() {
+        record_proxied_request(&_app_state.db_pool, &container_model.id).await;
+
+        if !is_container_ready(&container_model) {
+            if !(is_scaled_to_zero(&container_model) && idle.wake_on_request) {
+                return container_unavailable(&container_model.id, "is not ready");
             }
-            Err(e) => {
-                error!("[Proxy] Failed to send metrics to OpenMeter: {}", e);
+
+            if let Ok(Some(budget)) = exhausted_budget(&_app_state.db_pool, &namespace).await {
+                return container_unavailable(
+                    &container_model.id,
+                    &format!("is stopped: budget '{}' is exhausted", budget.name),
+                );
+            }
+
+            if let Err(e) = wake_container(&_app_state.db_pool, &container_model).await {
+                error!(
+                    "[PROXY] Failed to wake container {}: {e}",
+                    container_model.id
+                );
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("OpenMeter error: {}", e),
+                    "Failed to wake container",
                 )
                     .into_response();
             }
+
+            let wake_timeout = idle
+                .wake_timeout
+                .as_deref()
+                .and_then(|t| humantime::parse_duration(t).ok())
+                .unwrap_or_default();
+            match wait_until_ready(&_app_state.db_pool, &container_model.id, wake_timeout).await {
+                Some(ready) => container_model = ready,
+                None => return container_unavailable(&container_model.id, "is waking up"),
+            }
         }
     }
 
+    // ---------------------------------------------------
+    //  Queue request_value metrics on the metering pipeline
+    // ---------------------------------------------------
+    let maybe_meters = container_model.parse_meters().unwrap_or(None);
+
+    // Only iterate if we actually have some meters
+    if let Some(ref meters) = maybe_meters.clone() {
+        record_request_metrics(
+            &container_model.id,
+            &container_model.namespace,
+            meters,
+            &json_body_opt,
+        )
+        .await;
+    }
+
     // If this is a JSON request, parse and print the JSON body
     if let Some(content_type) = headers.get("content-type") {
         if content_type
@@ -184,11 +264,13 @@ pub async fn forward_container(
 
                         // Replace response metrics logic with a call to the new function
                         if let Some(ref meters) = maybe_meters.clone() {
-                            if let Err(e) =
-                                send_response_metrics(&container_model.id, meters, &json_resp).await
-                            {
-                                error!("[Proxy] Failed to send response metrics: {}", e);
-                            }
+                            record_response_metrics(
+                                &container_model.id,
+                                &container_model.namespace,
+                                meters,
+                                &json_resp,
+                            )
+                            .await;
                         }
                     }
                 }
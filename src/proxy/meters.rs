@@ -1,23 +1,15 @@
+use crate::meters::{record_meter_event, MeterEvent};
 use crate::proxy::authz::extract_json_path;
-use openmeter::{CloudEvent, MeterClient};
 use serde_json::Value;
-use short_uuid::ShortUuid;
-use tracing::{debug, error, warn};
+use tracing::{debug, warn};
 
-pub async fn send_request_metrics(
+/// Queue request meter events on the metering pipeline.
+pub async fn record_request_metrics(
     container_id: &str,
+    namespace: &str,
     meters: &[crate::models::V1Meter],
     json_body_opt: &Option<Value>,
-) -> Result<(), String> {
-    // Get OpenMeter configuration from environment
-    let openmeter_url = std::env::var("OPENMETER_URL")
-        .map_err(|_| "OPENMETER_URL environment variable not set".to_string())?;
-
-    let openmeter_token = std::env::var("OPENMETER_TOKEN")
-        .map_err(|_| "OPENMETER_TOKEN environment variable not set".to_string())?;
-
-    let meter_client = MeterClient::new(openmeter_url, openmeter_token);
-
+) {
     for meter in meters {
         // Skip meters that are not for request processing
         if meter.metric == "response_value" {
@@ -55,6 +47,7 @@ pub async fn send_request_metrics(
             "value": value,
             "metric": meter.metric,
             "container_id": container_id,
+            "namespace": namespace,
             "currency": meter.currency,
             "cost": meter.cost,
             "unit": meter.unit,
@@ -63,51 +56,23 @@ pub async fn send_request_metrics(
         });
         debug!("[PROXY] request metrics data: {data:?}");
 
-        // Create CloudEvent
-        let cloud_event = CloudEvent {
-            id: ShortUuid::generate().to_string(),
-            source: "nebulous-proxy".to_string(),
-            specversion: "1.0".to_string(),
-            r#type: meter.metric.clone(),
-            subject: container_id.to_string(),
-            time: Some(chrono::Utc::now().to_rfc3339()),
-            dataschema: None,
-            datacontenttype: Some("application/json".to_string()),
-            data: Some(data),
-        };
-
-        // Send the event to OpenMeter
-        if let Err(e) = meter_client.ingest_events(&[cloud_event]).await {
-            error!(
-                "[Metrics] Failed to report meter {:?} for container {}: {}",
-                meter, container_id, e
-            );
-            return Err(format!("Failed to send metrics: {}", e));
-        }
+        let event = MeterEvent::new("nebulous-proxy", &meter.metric, container_id, data);
+        record_meter_event(event).await;
 
         debug!(
-            "[Metrics] Successfully reported meter {:?} for container {}",
+            "[Metrics] Queued meter {:?} for container {}",
             meter, container_id
         );
     }
-
-    Ok(())
 }
 
-pub async fn send_response_metrics(
+/// Queue response meter events on the metering pipeline.
+pub async fn record_response_metrics(
     container_id: &str,
+    namespace: &str,
     meters: &[crate::models::V1Meter],
     json_response: &Value,
-) -> Result<(), String> {
-    // Get OpenMeter configuration from environment
-    let openmeter_url = std::env::var("OPENMETER_URL")
-        .map_err(|_| "OPENMETER_URL environment variable not set".to_string())?;
-
-    let openmeter_token = std::env::var("OPENMETER_TOKEN")
-        .map_err(|_| "OPENMETER_TOKEN environment variable not set".to_string())?;
-
-    let meter_client = MeterClient::new(openmeter_url, openmeter_token);
-
+) {
     for meter in meters {
         // Only process response_value meters
         if meter.metric != "response_value" {
@@ -142,6 +107,7 @@ pub async fn send_response_metrics(
             "value": value,
             "metric": meter.metric,
             "container_id": container_id,
+            "namespace": namespace,
             "currency": meter.currency,
             "cost": meter.cost,
             "unit": meter.unit,
@@ -150,33 +116,12 @@ pub async fn send_response_metrics(
         });
         debug!("[PROXY] response metrics data: {data:?}");
 
-        // Create CloudEvent
-        let cloud_event = CloudEvent {
-            id: ShortUuid::generate().to_string(),
-            source: "nebulous-proxy".to_string(),
-            specversion: "1.0".to_string(),
-            r#type: meter.metric.clone(),
-            subject: container_id.to_string(),
-            time: Some(chrono::Utc::now().to_rfc3339()),
-            dataschema: None,
-            datacontenttype: Some("application/json".to_string()),
-            data: Some(data),
-        };
-
-        // Send the event to OpenMeter
-        if let Err(e) = meter_client.ingest_events(&[cloud_event]).await {
-            error!(
-                "[Metrics] Failed to report response meter {:?} for container {}: {}",
-                meter, container_id, e
-            );
-            return Err(format!("Failed to send response metrics: {}", e));
-        }
+        let event = MeterEvent::new("nebulous-proxy", &meter.metric, container_id, data);
+        record_meter_event(event).await;
 
         debug!(
-            "[Metrics] Successfully reported response meter {:?} for container {}",
+            "[Metrics] Queued response meter {:?} for container {}",
             meter, container_id
         );
     }
-
-    Ok(())
 }
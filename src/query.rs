@@ -1,11 +1,15 @@
 // src/query.rs
 use crate::entities::containers;
+use crate::entities::namespace_grants;
 use crate::entities::namespaces;
 use crate::entities::processors;
+use crate::entities::secret_versions;
 use crate::entities::secrets;
+use crate::entities::usage_records;
+use crate::meters::ledger::{UsageGrouping, UsageTotal};
 use crate::resources::v1::containers::base::ContainerStatus;
 use crate::resources::v1::containers::models::V1ContainerStatus;
-use sea_orm::sea_query::Expr;
+use sea_orm::sea_query::{Expr, SimpleExpr};
 use sea_orm::Value;
 use sea_orm::*;
 use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
@@ -18,7 +22,11 @@ impl Query {
         owners: &[&str],
     ) -> Result<Vec<containers::Model>, DbErr> {
         containers::Entity::find()
-            .filter(containers::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                containers::Column::Owner,
+                containers::Column::Namespace,
+                owners,
+            ))
             .all(db)
             .await
     }
@@ -50,7 +58,11 @@ impl Query {
         let result = containers::Entity::find()
             .filter(containers::Column::Namespace.eq(namespace))
             .filter(containers::Column::Name.eq(name))
-            .filter(containers::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                containers::Column::Owner,
+                containers::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -66,7 +78,11 @@ impl Query {
     ) -> Result<containers::Model, DbErr> {
         let result = containers::Entity::find()
             .filter(containers::Column::Id.eq(id))
-            .filter(containers::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                containers::Column::Owner,
+                containers::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -299,6 +315,13 @@ impl Query {
     }
 
     /// Find a single secret by ID and ensure that the user is an owner
+    pub async fn find_secret_by_id(
+        db: &DatabaseConnection,
+        id: &str,
+    ) -> Result<Option<secrets::Model>, DbErr> {
+        secrets::Entity::find_by_id(id).one(db).await
+    }
+
     pub async fn find_secret_by_id_and_owners(
         db: &DatabaseConnection,
         id: &str,
@@ -306,7 +329,11 @@ impl Query {
     ) -> Result<secrets::Model, DbErr> {
         let result = secrets::Entity::find()
             .filter(secrets::Column::Id.eq(id))
-            .filter(secrets::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                secrets::Column::Owner,
+                secrets::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -334,18 +361,48 @@ impl Query {
         owners: &[&str],
     ) -> Result<Vec<secrets::Model>, DbErr> {
         secrets::Entity::find()
-            .filter(secrets::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                secrets::Column::Owner,
+                secrets::Column::Namespace,
+                owners,
+            ))
             .all(db)
             .await
     }
 
+    /// Fetch a secret's previous versions, newest first
+    pub async fn find_secret_versions(
+        db: &DatabaseConnection,
+        secret_id: &str,
+    ) -> Result<Vec<secret_versions::Model>, DbErr> {
+        secret_versions::Entity::find()
+            .filter(secret_versions::Column::SecretId.eq(secret_id))
+            .order_by_desc(secret_versions::Column::Version)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_secret_version(
+        db: &DatabaseConnection,
+        secret_id: &str,
+        version: i32,
+    ) -> Result<Option<secret_versions::Model>, DbErr> {
+        secret_versions::Entity::find_by_id(secret_versions::Model::version_id(secret_id, version))
+            .one(db)
+            .await
+    }
+
     /// Fetch all processors for a given list of owners
     pub async fn find_processors_by_owners(
         db: &DatabaseConnection,
         owners: &[&str],
     ) -> Result<Vec<processors::Model>, DbErr> {
         processors::Entity::find()
-            .filter(processors::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                processors::Column::Owner,
+                processors::Column::Namespace,
+                owners,
+            ))
             .all(db)
             .await
     }
@@ -360,7 +417,11 @@ impl Query {
         let result = processors::Entity::find()
             .filter(processors::Column::Namespace.eq(namespace))
             .filter(processors::Column::Name.eq(name))
-            .filter(processors::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                processors::Column::Owner,
+                processors::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -377,7 +438,11 @@ impl Query {
     ) -> Result<processors::Model, DbErr> {
         let result = processors::Entity::find()
             .filter(processors::Column::Id.eq(id))
-            .filter(processors::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                processors::Column::Owner,
+                processors::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -443,7 +508,11 @@ impl Query {
         let result = volumes::Entity::find()
             .filter(volumes::Column::Namespace.eq(namespace))
             .filter(volumes::Column::Name.eq(name))
-            .filter(volumes::Column::Owner.is_in(owners.iter().copied()))
+            .filter(Self::owned_or_shared(
+                volumes::Column::Owner,
+                volumes::Column::Namespace,
+                owners,
+            ))
             .one(db)
             .await?;
 
@@ -503,14 +572,156 @@ impl Query {
         Ok(count)
     }
 
-    /// Fetch all namespaces for a given list of owners
+    /// Sum ledger rows in the database, split by `grouping` plus unit and currency.
+    pub async fn sum_usage_records(
+        db: &DatabaseConnection,
+        namespaces: &[&str],
+        container_id: Option<&str>,
+        meter: Option<&str>,
+        start: Option<i64>,
+        end: Option<i64>,
+        grouping: &UsageGrouping,
+    ) -> Result<Vec<UsageTotal>, DbErr> {
+        let mut query = usage_records::Entity::find()
+            .select_only()
+            .filter(usage_records::Column::Namespace.is_in(namespaces.iter().copied()));
+
+        if let Some(container_id) = container_id {
+            query = query.filter(usage_records::Column::ContainerId.eq(container_id));
+        }
+        if let Some(meter) = meter {
+            query = query.filter(usage_records::Column::Meter.eq(meter));
+        }
+        if let Some(start) = start.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)) {
+            query = query.filter(usage_records::Column::OccurredAt.gte(start));
+        }
+        if let Some(end) = end.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)) {
+            query = query.filter(usage_records::Column::OccurredAt.lt(end));
+        }
+
+        for (grouped, column, alias) in [
+            (
+                grouping.namespace,
+                usage_records::Column::Namespace,
+                "namespace",
+            ),
+            (
+                grouping.container,
+                usage_records::Column::ContainerId,
+                "container_id",
+            ),
+            (grouping.meter, usage_records::Column::Meter, "meter"),
+            (true, usage_records::Column::Unit, "unit"),
+            (true, usage_records::Column::Currency, "currency"),
+        ] {
+            query = if grouped {
+                query.column_as(column, alias).group_by(column)
+            } else {
+                query.column_as(Expr::cust("CAST(NULL AS TEXT)"), alias)
+            };
+        }
+
+        if let Some(window) = &grouping.window {
+            let key = Self::usage_window_key(db.get_database_backend(), window)
+                .ok_or_else(|| DbErr::Custom(format!("Unsupported usage window '{}'", window)))?;
+            query = query.group_by(key);
+        }
+
+        query
+            .column_as(Expr::col(usage_records::Column::Quantity).sum(), "quantity")
+            .column_as(Expr::col(usage_records::Column::Amount).sum(), "amount")
+            .column_as(Expr::col(usage_records::Column::Id).count(), "events")
+            .column_as(
+                Expr::col(usage_records::Column::OccurredAt).min(),
+                "first_occurred_at",
+            )
+            .into_model::<UsageTotal>()
+            .all(db)
+            .await
+    }
+
+    /// Expression that is equal for all ledger rows in the same UTC window.
+    fn usage_window_key(backend: DbBackend, window: &str) -> Option<SimpleExpr> {
+        let expr = match (backend, window) {
+            (DbBackend::Postgres, "hour") => {
+                "to_char(occurred_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24')"
+            }
+            (DbBackend::Postgres, "day") => "to_char(occurred_at AT TIME ZONE 'UTC', 'YYYY-MM-DD')",
+            (DbBackend::Postgres, "month") => "to_char(occurred_at AT TIME ZONE 'UTC', 'YYYY-MM')",
+            (DbBackend::Sqlite, "hour") => "strftime('%Y-%m-%d %H', occurred_at)",
+            (DbBackend::Sqlite, "day") => "strftime('%Y-%m-%d', occurred_at)",
+            (DbBackend::Sqlite, "month") => "strftime('%Y-%m', occurred_at)",
+            _ => return None,
+        };
+        Some(Expr::cust(expr))
+    }
+
+    /// Fetch all namespaces owned by, or shared with, a given list of owners
     pub async fn find_namespaces_by_owners(
         db: &DatabaseConnection,
         owners: &[&str],
+    ) -> Result<Vec<namespaces::Model>, DbErr> {
+        namespaces::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(namespaces::Column::Owner.is_in(owners.iter().copied()))
+                    .add(namespaces::Column::Name.in_subquery(Self::shared_namespaces(owners))),
+            )
+            .all(db)
+            .await
+    }
+
+    /// Fetch the namespaces owned by `owners`, leaving out ones only shared
+    /// with them. Used for billing, which always follows ownership.
+    pub async fn find_owned_namespaces(
+        db: &DatabaseConnection,
+        owners: &[&str],
     ) -> Result<Vec<namespaces::Model>, DbErr> {
         namespaces::Entity::find()
             .filter(namespaces::Column::Owner.is_in(owners.iter().copied()))
             .all(db)
             .await
     }
+
+    /// Select the namespaces shared with any of `owners` through a grant
+    pub fn shared_namespaces(owners: &[&str]) -> sea_orm::sea_query::SelectStatement {
+        sea_orm::sea_query::Query::select()
+            .column(namespace_grants::Column::Namespace)
+            .from(namespace_grants::Entity)
+            .and_where(namespace_grants::Column::Subject.is_in(owners.iter().copied()))
+            .to_owned()
+    }
+
+    /// Match rows owned by one of `owners`, or in a namespace shared with one
+    /// of them
+    pub fn owned_or_shared<C: ColumnTrait>(owner: C, namespace: C, owners: &[&str]) -> Condition {
+        Condition::any()
+            .add(owner.is_in(owners.iter().copied()))
+            .add(namespace.in_subquery(Self::shared_namespaces(owners)))
+    }
+
+    /// Find the grant sharing `namespace` with any of `owners`, if there is one
+    pub async fn find_namespace_grant(
+        db: &DatabaseConnection,
+        namespace: &str,
+        owners: &[&str],
+    ) -> Result<Option<namespace_grants::Model>, DbErr> {
+        namespace_grants::Entity::find()
+            .filter(namespace_grants::Column::Namespace.eq(namespace))
+            .filter(namespace_grants::Column::Subject.is_in(owners.iter().copied()))
+            .one(db)
+            .await
+    }
+
+    /// Fetch the grants on a namespace
+    pub async fn find_namespace_grants(
+        db: &DatabaseConnection,
+        namespace: &str,
+    ) -> Result<Vec<namespace_grants::Model>, DbErr> {
+        namespace_grants::Entity::find()
+            .filter(namespace_grants::Column::Namespace.eq(namespace))
+            .order_by_asc(namespace_grants::Column::CreatedAt)
+            .all(db)
+            .await
+    }
 }
@@ -0,0 +1,150 @@
+use crate::models::V1RateLimit;
+use std::time::Duration;
+
+/// Stored state of a token bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketState {
+    pub tokens: f64,
+    pub updated_ms: i64,
+}
+
+impl BucketState {
+    /// A full bucket as of `now_ms`.
+    pub fn full(limit: &V1RateLimit, now_ms: i64) -> Self {
+        Self {
+            tokens: limit.capacity(),
+            updated_ms: now_ms,
+        }
+    }
+}
+
+/// Refill the bucket up to `now_ms` and try to take one token.
+///
+/// Returns the new bucket state and, if the request was rejected, how long
+/// until a token becomes available. A missing state starts as a full bucket.
+pub fn take_token(
+    state: Option<BucketState>,
+    now_ms: i64,
+    limit: &V1RateLimit,
+) -> (BucketState, Option<Duration>) {
+    let capacity = limit.capacity();
+    let rate = limit.requests_per_second;
+
+    let BucketState { tokens, updated_ms } =
+        state.unwrap_or_else(|| BucketState::full(limit, now_ms));
+    let elapsed_secs = (now_ms - updated_ms).max(0) as f64 / 1000.0;
+    let tokens = (tokens + elapsed_secs * rate).min(capacity);
+
+    if tokens >= 1.0 {
+        (
+            BucketState {
+                tokens: tokens - 1.0,
+                updated_ms: now_ms,
+            },
+            None,
+        )
+    } else {
+        let wait_secs = if rate > 0.0 {
+            (1.0 - tokens) / rate
+        } else {
+            f64::from(u32::MAX)
+        };
+        (
+            BucketState {
+                tokens,
+                updated_ms: now_ms,
+            },
+            Some(Duration::from_secs_f64(wait_secs)),
+        )
+    }
+}
+
+/// When the bucket will have refilled to capacity if no more tokens are taken.
+/// A full bucket is the same as a missing one, so it can be dropped after this.
+pub fn full_at_ms(state: &BucketState, limit: &V1RateLimit) -> i64 {
+    let missing = (limit.capacity() - state.tokens).max(0.0);
+    if missing == 0.0 {
+        return state.updated_ms;
+    }
+    if limit.requests_per_second <= 0.0 {
+        return i64::MAX;
+    }
+    let refill_ms = (missing / limit.requests_per_second * 1000.0).ceil();
+    state.updated_ms.saturating_add(refill_ms as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_state_starts_full() {
+        let limit = V1RateLimit::new(2.0, Some(3));
+        let (state, retry_after) = take_token(None, 1_000, &limit);
+        assert!(retry_after.is_none());
+        assert_eq!(state.tokens, 2.0);
+        assert_eq!(state.updated_ms, 1_000);
+    }
+
+    #[test]
+    fn test_burst_then_reject_with_retry_after() {
+        let limit = V1RateLimit::new(2.0, Some(3));
+        let mut state = None;
+        for _ in 0..3 {
+            let (next, retry_after) = take_token(state, 0, &limit);
+            assert!(retry_after.is_none());
+            state = Some(next);
+        }
+
+        let (next, retry_after) = take_token(state, 0, &limit);
+        assert_eq!(next.tokens, 0.0);
+        assert_eq!(retry_after, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_refills_over_time_up_to_capacity() {
+        let limit = V1RateLimit::new(2.0, Some(3));
+        let empty = BucketState {
+            tokens: 0.0,
+            updated_ms: 0,
+        };
+
+        // Half a second refills one token, which is taken
+        let (state, retry_after) = take_token(Some(empty), 500, &limit);
+        assert!(retry_after.is_none());
+        assert!(state.tokens.abs() < 1e-9);
+
+        // A long idle period refills only to capacity
+        let (state, retry_after) = take_token(Some(empty), 60_000, &limit);
+        assert!(retry_after.is_none());
+        assert_eq!(state.tokens, 2.0);
+    }
+
+    #[test]
+    fn test_default_capacity_is_one_second() {
+        let limit = V1RateLimit::new(1.5, None);
+        let (state, _) = take_token(None, 0, &limit);
+        assert_eq!(state.tokens, 1.0);
+    }
+
+    #[test]
+    fn test_full_at_ms() {
+        let limit = V1RateLimit::new(2.0, Some(3));
+        let state = BucketState {
+            tokens: 1.0,
+            updated_ms: 1_000,
+        };
+        assert_eq!(full_at_ms(&state, &limit), 2_000);
+
+        let full = BucketState {
+            tokens: 3.0,
+            updated_ms: 1_000,
+        };
+        assert_eq!(full_at_ms(&full, &limit), 1_000);
+
+        assert_eq!(
+            full_at_ms(&state, &V1RateLimit::new(0.0, Some(3))),
+            i64::MAX
+        );
+    }
+}
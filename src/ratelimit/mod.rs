@@ -0,0 +1,315 @@
+pub mod bucket;
+
+use crate::auth;
+use crate::config::SERVER_CONFIG;
+use crate::entities::namespaces;
+use crate::models::V1RateLimit;
+use crate::state::MessageQueue;
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bucket::{full_at_ms, take_token, BucketState};
+use dashmap::DashMap;
+use once_cell::sync::{Lazy, OnceCell};
+use redis::aio::MultiplexedConnection;
+use redis::Client as RedisClient;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::json;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Global rate limiter, created on first use from the server's message queue.
+static RATE_LIMITER: OnceCell<RateLimiter> = OnceCell::new();
+
+/// How long a namespace or API key limit read from the database is reused.
+/// Changes to a limit take effect within this time.
+const LIMIT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// How often idle local buckets and expired cached limits are swept.
+const SWEEP_INTERVAL_MS: i64 = 60_000;
+
+/// Limits read from the database, keyed by bucket scope. `None` means the
+/// namespace or key has no limit of its own.
+static LIMIT_CACHE: Lazy<DashMap<String, (Option<V1RateLimit>, Instant)>> = Lazy::new(DashMap::new);
+
+/// Atomically refill and take from a token bucket stored as a Redis hash.
+/// Uses the Redis clock so every server replica agrees on elapsed time.
+/// Returns `{allowed, retry_after_ms}`.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local rate = tonumber(ARGV[1])
+local capacity = tonumber(ARGV[2])
+local t = redis.call('TIME')
+local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+local data = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(data[1])
+local ts = tonumber(data[2])
+if tokens == nil or ts == nil then
+  tokens = capacity
+  ts = now
+end
+tokens = math.min(capacity, tokens + math.max(0, now - ts) / 1000 * rate)
+local allowed = 0
+local retry_ms = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+else
+  retry_ms = math.ceil((1 - tokens) / rate * 1000)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate * 1000) + 1000)
+return {allowed, retry_ms}
+"#;
+
+/// A limit to check for one request, e.g. `namespace:foo` at 10 req/s.
+#[derive(Debug, Clone)]
+pub struct RateLimitTarget {
+    pub scope: String,
+    pub limit: V1RateLimit,
+}
+
+impl RateLimitTarget {
+    pub fn new(scope: String, limit: V1RateLimit) -> Self {
+        Self { scope, limit }
+    }
+}
+
+/// Token bucket rate limiter. Buckets live in Redis when the message queue is
+/// Redis so limits are shared across replicas; otherwise, or if Redis is
+/// unreachable, they are kept in process.
+pub struct RateLimiter {
+    redis: Option<Arc<RedisClient>>,
+    /// Shared connection to `redis`, reopened after an error.
+    conn: Mutex<Option<MultiplexedConnection>>,
+    /// Local buckets with the time each will be full again if left idle.
+    local: DashMap<String, (BucketState, i64)>,
+    last_sweep_ms: AtomicI64,
+}
+
+impl RateLimiter {
+    pub fn new(message_queue: &MessageQueue) -> Self {
+        let redis = match message_queue {
+            MessageQueue::Redis { client } => Some(client.clone()),
+            MessageQueue::Kafka { .. } => None,
+        };
+        Self {
+            redis,
+            conn: Mutex::new(None),
+            local: DashMap::new(),
+            last_sweep_ms: AtomicI64::new(0),
+        }
+    }
+
+    /// Take one token from the bucket for `scope`. Returns how long to wait
+    /// if the request should be rejected.
+    pub async fn check(&self, scope: &str, limit: &V1RateLimit) -> Option<Duration> {
+        if limit.requests_per_second <= 0.0 {
+            return None;
+        }
+        self.sweep(chrono::Utc::now().timestamp_millis());
+
+        if let Some(client) = &self.redis {
+            match self.check_redis(client, scope, limit).await {
+                Ok(retry_after) => return retry_after,
+                Err(e) => {
+                    warn!(
+                        "[RateLimit] Redis unavailable, using local bucket for {}: {}",
+                        scope, e
+                    );
+                    *self.conn.lock().await = None;
+                }
+            }
+        }
+        self.check_local(scope, limit)
+    }
+
+    async fn connection(
+        &self,
+        client: &RedisClient,
+    ) -> Result<MultiplexedConnection, redis::RedisError> {
+        let mut slot = self.conn.lock().await;
+        if let Some(conn) = slot.as_ref() {
+            return Ok(conn.clone());
+        }
+        let conn = client.get_multiplexed_async_connection().await?;
+        *slot = Some(conn.clone());
+        Ok(conn)
+    }
+
+    async fn check_redis(
+        &self,
+        client: &RedisClient,
+        scope: &str,
+        limit: &V1RateLimit,
+    ) -> Result<Option<Duration>, redis::RedisError> {
+        let mut conn = self.connection(client).await?;
+        let (allowed, retry_ms): (i64, i64) = redis::Script::new(TOKEN_BUCKET_SCRIPT)
+            .key(format!("nebu:ratelimit:{}", scope))
+            .arg(limit.requests_per_second)
+            .arg(limit.capacity())
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok((allowed == 0).then(|| Duration::from_millis(retry_ms.max(0) as u64)))
+    }
+
+    fn check_local(&self, scope: &str, limit: &V1RateLimit) -> Option<Duration> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut entry = self
+            .local
+            .entry(scope.to_string())
+            .or_insert((BucketState::full(limit, now_ms), now_ms));
+        let (state, retry_after) = take_token(Some(entry.0), now_ms, limit);
+        *entry = (state, full_at_ms(&state, limit));
+        retry_after
+    }
+
+    /// Drop local buckets that have been idle long enough to be full again,
+    /// since a missing bucket starts full, and expired cached limits.
+    fn sweep(&self, now_ms: i64) {
+        let last = self.last_sweep_ms.load(Ordering::Relaxed);
+        if now_ms - last < SWEEP_INTERVAL_MS
+            || self
+                .last_sweep_ms
+                .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+
+        self.local.retain(|_, (_, full_at)| *full_at > now_ms);
+        LIMIT_CACHE.retain(|_, (_, cached_at)| cached_at.elapsed() < LIMIT_CACHE_TTL);
+    }
+}
+
+/// Look up a limit through `LIMIT_CACHE`, calling `load` on a miss.
+async fn cached_limit<F>(scope: &str, load: F) -> Option<V1RateLimit>
+where
+    F: std::future::Future<Output = Option<V1RateLimit>>,
+{
+    if let Some(entry) = LIMIT_CACHE.get(scope) {
+        let (limit, cached_at) = entry.value();
+        if cached_at.elapsed() < LIMIT_CACHE_TTL {
+            return limit.clone();
+        }
+    }
+
+    let limit = load.await;
+    LIMIT_CACHE.insert(scope.to_string(), (limit.clone(), Instant::now()));
+    limit
+}
+
+/// Get the global rate limiter, creating it from `message_queue` on first use.
+pub fn get_rate_limiter(message_queue: &MessageQueue) -> &'static RateLimiter {
+    RATE_LIMITER.get_or_init(|| RateLimiter::new(message_queue))
+}
+
+/// Limit for a namespace: its own `rate_limit`, or the server default.
+pub async fn namespace_rate_limit(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Option<RateLimitTarget> {
+    let scope = format!("namespace:{}", namespace);
+    let configured = cached_limit(&scope, async {
+        namespaces::Entity::find()
+            .filter(namespaces::Column::Name.eq(namespace))
+            .one(db)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|ns| ns.parse_rate_limit())
+    })
+    .await;
+
+    configured
+        .or_else(|| SERVER_CONFIG.rate_limits.namespace.clone())
+        .map(|limit| RateLimitTarget::new(scope, limit))
+}
+
+/// Limit for the API key in the request's `Authorization` header: the key's own
+/// `rate_limit`, or the server default. External tokens are identified by a
+/// hash so the raw token never ends up in Redis.
+pub async fn api_key_rate_limit(
+    db: &DatabaseConnection,
+    headers: &HeaderMap,
+) -> Option<RateLimitTarget> {
+    let token = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+
+    let (scope, configured) = match auth::api::api_key_id(token) {
+        Some(id) => {
+            let scope = format!("key:{}", id);
+            let configured = cached_limit(&scope, async {
+                auth::api::get_api_key_rate_limit(db, id)
+                    .await
+                    .ok()
+                    .flatten()
+            })
+            .await;
+            (scope, configured)
+        }
+        None => (format!("token:{}", token_fingerprint(token)), None),
+    };
+
+    configured
+        .or_else(|| SERVER_CONFIG.rate_limits.api_key.clone())
+        .map(|limit| RateLimitTarget::new(scope, limit))
+}
+
+fn token_fingerprint(token: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    digest.as_ref()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Check every target in order, returning a 429 response for the first one
+/// that is exhausted. Limit hits are logged rather than metered, so they are
+/// never billed.
+pub async fn enforce_rate_limits(
+    message_queue: &MessageQueue,
+    targets: &[RateLimitTarget],
+    subject: &str,
+    namespace: Option<&str>,
+    container_id: Option<&str>,
+) -> Result<(), Response> {
+    let limiter = get_rate_limiter(message_queue);
+
+    for target in targets {
+        let Some(retry_after) = limiter.check(&target.scope, &target.limit).await else {
+            continue;
+        };
+
+        let retry_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        info!(
+            "[RateLimit] {} exceeded {} req/s for {} (namespace {}, container {}), retry in {}s",
+            target.scope,
+            target.limit.requests_per_second,
+            subject,
+            namespace.unwrap_or("-"),
+            container_id.unwrap_or("-"),
+            retry_secs
+        );
+
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_secs.to_string())],
+            Json(json!({
+                "error": format!("Rate limit exceeded for {}", target.scope),
+                "retry_after": retry_secs,
+            })),
+        )
+            .into_response());
+    }
+
+    Ok(())
+}
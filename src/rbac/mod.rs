@@ -0,0 +1,241 @@
+use crate::config::SERVER_CONFIG;
+use crate::entities::{namespaces, role_bindings, roles};
+use crate::models::V1UserProfile;
+use crate::query::Query;
+use crate::resources::v1::roles::models::{builtin_role_rules, V1PolicyRule, V1RoleSubject};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Org roles that administer every namespace their organization owns.
+const ORG_ADMIN_ROLES: &[&str] = &["owner", "admin"];
+
+/// Whether the user is the root owner or in the root owner's org.
+pub fn is_root_owner(user_profile: &V1UserProfile) -> bool {
+    user_profile.email == SERVER_CONFIG.root_owner
+        || user_profile
+            .organizations
+            .as_ref()
+            .is_some_and(|orgs| orgs.contains_key(&SERVER_CONFIG.root_owner))
+}
+
+/// The principal making a request.
+#[derive(Debug, Clone, Copy)]
+pub struct Principal<'a> {
+    pub user_profile: &'a V1UserProfile,
+    pub api_key_id: Option<&'a str>,
+}
+
+impl<'a> Principal<'a> {
+    pub fn new(user_profile: &'a V1UserProfile, api_key_id: Option<&'a str>) -> Self {
+        Self {
+            user_profile,
+            api_key_id,
+        }
+    }
+
+    fn org_role(&self, org: &str) -> Option<&str> {
+        self.user_profile
+            .organizations
+            .as_ref()?
+            .get(org)?
+            .get("org_role")
+            .map(|role| role.as_str())
+    }
+
+    fn owner_ids(&self) -> Vec<&str> {
+        let mut owner_ids: Vec<&str> = self
+            .user_profile
+            .organizations
+            .as_ref()
+            .map(|orgs| orgs.keys().map(|k| k.as_str()).collect())
+            .unwrap_or_default();
+        owner_ids.push(self.user_profile.email.as_str());
+        owner_ids
+    }
+
+    /// Whether this principal administers `namespace`: its owner, an admin
+    /// of the org that owns it, or the root owner.
+    pub fn administers(&self, namespace: &namespaces::Model) -> bool {
+        namespace.owner == self.user_profile.email
+            || is_root_owner(self.user_profile)
+            || self
+                .org_role(&namespace.owner)
+                .map_or(false, |role| ORG_ADMIN_ROLES.contains(&role))
+    }
+
+    fn matches(&self, subject: &V1RoleSubject, namespace_owner: &str) -> bool {
+        match subject.kind.as_str() {
+            "User" => subject.name == self.user_profile.email,
+            "OrgRole" => {
+                let org = subject.org.as_deref().unwrap_or(namespace_owner);
+                self.org_role(org)
+                    .map_or(false, |role| subject.name == "*" || subject.name == role)
+            }
+            "ApiKey" => self.api_key_id == Some(subject.name.as_str()),
+            _ => false,
+        }
+    }
+}
+
+/// What `rbac_middleware` established about a request, for its handler.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    /// The API key the request was made with, if any.
+    pub api_key_id: Option<String>,
+    /// The existing namespace `authorize` allowed the request in, and its owner.
+    pub namespace: Option<(String, String)>,
+}
+
+impl Access {
+    pub fn principal<'a>(&'a self, user_profile: &'a V1UserProfile) -> Principal<'a> {
+        Principal::new(user_profile, self.api_key_id.as_deref())
+    }
+
+    /// The owners whose objects the handler may look up: the caller's orgs
+    /// and email, plus the owner of the namespace RBAC allowed the request
+    /// in, so role bindings reach beyond the caller's own orgs.
+    pub fn owner_ids(&self, user_profile: &V1UserProfile) -> Vec<String> {
+        let mut owner_ids: Vec<String> = user_profile
+            .organizations
+            .as_ref()
+            .map(|orgs| orgs.keys().cloned().collect())
+            .unwrap_or_default();
+        owner_ids.push(user_profile.email.clone());
+        if let Some((_, owner)) = &self.namespace {
+            if !owner_ids.contains(owner) {
+                owner_ids.push(owner.clone());
+            }
+        }
+        owner_ids
+    }
+}
+
+/// The rules of a built-in role, or of a custom role in `namespace`.
+async fn role_rules(
+    db: &DatabaseConnection,
+    namespace: &str,
+    role: &str,
+) -> Result<Vec<V1PolicyRule>, DbErr> {
+    if let Some(rules) = builtin_role_rules(role) {
+        return Ok(rules);
+    }
+    Ok(roles::Entity::find()
+        .filter(roles::Column::Namespace.eq(namespace))
+        .filter(roles::Column::Name.eq(role))
+        .one(db)
+        .await?
+        .and_then(|role| role.parse_rules().ok())
+        .unwrap_or_default())
+}
+
+/// Decide whether `principal` may perform `verb` on `kind` in `namespace`.
+///
+/// The namespace owner, org admins and the root owner can do anything. Users
+/// and orgs the namespace is shared with get the verbs their grant's role
+/// allows. Once a namespace has role bindings, everyone else needs a binding
+/// that grants the verb; namespaces without bindings keep plain owner-based
+/// access for any member of the owning org. Unknown namespaces are left to the
+/// handler.
+pub async fn authorize(
+    db: &DatabaseConnection,
+    principal: Principal<'_>,
+    verb: &str,
+    kind: &str,
+    namespace: &str,
+) -> Result<bool, DbErr> {
+    if is_root_owner(principal.user_profile) {
+        return Ok(true);
+    }
+    let owner_ids = principal.owner_ids();
+
+    let Some(ns) = namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(namespace))
+        .one(db)
+        .await?
+    else {
+        return Ok(true);
+    };
+
+    if principal.administers(&ns) {
+        return Ok(true);
+    }
+
+    // Grants share the namespace with users and orgs outside the owner
+    for grant in Query::find_namespace_grants(db, namespace).await? {
+        if !owner_ids.contains(&grant.subject.as_str()) {
+            continue;
+        }
+        if role_rules(db, namespace, &grant.role)
+            .await?
+            .iter()
+            .any(|rule| rule.allows(verb, kind))
+        {
+            debug!(
+                "[RBAC] {} allowed to {} {} in {} by grant to {}",
+                principal.user_profile.email, verb, kind, namespace, grant.subject
+            );
+            return Ok(true);
+        }
+    }
+
+    let bindings = role_bindings::Entity::find()
+        .filter(role_bindings::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?;
+
+    if bindings.is_empty() {
+        return Ok(owner_ids.contains(&ns.owner.as_str()));
+    }
+
+    for binding in bindings {
+        let subjects = binding.parse_subjects().unwrap_or_default();
+        if !subjects.iter().any(|s| principal.matches(s, &ns.owner)) {
+            continue;
+        }
+
+        let rules = role_rules(db, namespace, &binding.role).await?;
+        if rules.iter().any(|rule| rule.allows(verb, kind)) {
+            debug!(
+                "[RBAC] {} allowed to {} {} in {} by binding {}",
+                principal.user_profile.email, verb, kind, namespace, binding.name
+            );
+            return Ok(true);
+        }
+    }
+
+    debug!(
+        "[RBAC] {} denied {} {} in {}",
+        principal.user_profile.email, verb, kind, namespace
+    );
+    Ok(false)
+}
+
+/// Keep the items `principal` may `verb` in their own namespaces, for list
+/// routes that span every namespace the caller can reach.
+pub async fn retain_authorized<T>(
+    db: &DatabaseConnection,
+    principal: Principal<'_>,
+    verb: &str,
+    kind: &str,
+    items: Vec<T>,
+    namespace: impl Fn(&T) -> &str,
+) -> Result<Vec<T>, DbErr> {
+    let mut decisions: HashMap<String, bool> = HashMap::new();
+    let mut allowed = Vec::with_capacity(items.len());
+    for item in items {
+        let ns = namespace(&item);
+        let decision = match decisions.get(ns) {
+            Some(decision) => *decision,
+            None => {
+                let decision = authorize(db, principal, verb, kind, ns).await?;
+                decisions.insert(ns.to_string(), decision);
+                decision
+            }
+        };
+        if decision {
+            allowed.push(item);
+        }
+    }
+    Ok(allowed)
+}
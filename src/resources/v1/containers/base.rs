@@ -1,5 +1,6 @@
 use crate::agent::agent::create_agent_key;
 use crate::agent::aws::create_s3_scoped_user;
+use crate::auth::workload;
 use crate::config::{ClientConfig, SERVER_CONFIG};
 use crate::entities::containers;
 use crate::handlers::v1::volumes::ensure_volume;
@@ -175,6 +176,15 @@ pub trait ContainerPlatform {
         db: &DatabaseConnection,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
+    /// Release the container's compute but keep its record so it can be
+    /// started again later, e.g. when scaling an idle container to zero.
+    async fn stop(
+        &self,
+        container: &containers::Model,
+        reason: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
     fn accelerator_map(&self) -> HashMap<String, String>;
 
     // Default implementation for common environment variables
@@ -186,12 +196,24 @@ pub trait ContainerPlatform {
         let config = ClientConfig::read().unwrap();
         let mut env = HashMap::new();
 
-        debug!("Getting agent key");
-        let agent_key = match Query::get_agent_key(db, model.id.clone()).await {
-            Ok(key) => key,
-            Err(e) => {
-                error!("Error getting agent key: {:?}", e);
-                return Err(e.into());
+        // Containers get a short-lived token bound to them, falling back to
+        // the stored agent key when workload tokens are not configured
+        let agent_key = if workload::enabled() {
+            debug!("Issuing workload token");
+            let token = workload::issue_workload_token(model)?;
+            env.insert(
+                "NEBU_API_KEY_EXPIRES_AT".to_string(),
+                token.expires_at.to_rfc3339(),
+            );
+            Some(token.token)
+        } else {
+            debug!("Getting agent key");
+            match Query::get_agent_key(db, model.id.clone()).await {
+                Ok(key) => key,
+                Err(e) => {
+                    error!("Error getting agent key: {:?}", e);
+                    return Err(e.into());
+                }
             }
         };
 
@@ -217,16 +239,6 @@ pub trait ContainerPlatform {
             }
         };
 
-        debug!("Creating s3 token");
-        let s3_token =
-            match create_s3_scoped_user(&SERVER_CONFIG.bucket_name, &model.namespace, &model.id).await {
-                Ok(token) => token,
-                Err(e) => {
-                    error!("Error creating s3 token: {:?}", e);
-                    return Err(e.into());
-                }
-            };
-
         debug!("Adding RCLONE environment variables");
         // Add RCLONE environment variables
         env.insert("RCLONE_CONFIG_S3REMOTE_TYPE".to_string(), "s3".to_string());
@@ -238,14 +250,53 @@ pub trait ContainerPlatform {
             "RCLONE_CONFIG_S3REMOTE_ENV_AUTH".to_string(),
             "true".to_string(),
         );
-        debug!("Adding AWS credentials");
-        debug!("Access key: {}", s3_token.access_key_id);
-        debug!("Secret key: {}", s3_token.secret_access_key);
-        env.insert("AWS_ACCESS_KEY_ID".to_string(), s3_token.access_key_id);
-        env.insert(
-            "AWS_SECRET_ACCESS_KEY".to_string(),
-            s3_token.secret_access_key,
-        );
+
+        if workload::enabled() {
+            // S3 credentials are exchanged for the workload token when needed,
+            // rather than creating a long-lived IAM user for the container
+            debug!("Adding workload credential exchange");
+            let publish_url = SERVER_CONFIG.publish_url.clone().unwrap();
+            env.insert(
+                "AWS_CONTAINER_CREDENTIALS_FULL_URI".to_string(),
+                format!("{}/v1/auth/s3-credentials", publish_url),
+            );
+            env.insert(
+                "AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE".to_string(),
+                workload::AWS_AUTHORIZATION_FILE.to_string(),
+            );
+            // The startup script renews the token into NEBU_API_KEY_FILE
+            env.insert(
+                "NEBU_API_KEY_FILE".to_string(),
+                workload::TOKEN_FILE.to_string(),
+            );
+            env.insert(
+                "NEBU_API_KEY_REFRESH_URL".to_string(),
+                format!("{}/v1/auth/workload-token", publish_url),
+            );
+            env.insert(
+                "NEBU_API_KEY_REFRESH_SECS".to_string(),
+                (SERVER_CONFIG.auth.workload_token_ttl_secs / 2)
+                    .max(60)
+                    .to_string(),
+            );
+        } else {
+            debug!("Creating s3 token");
+            let s3_token =
+                match create_s3_scoped_user(&SERVER_CONFIG.bucket_name, &model.namespace, &model.id).await {
+                    Ok(token) => token,
+                    Err(e) => {
+                        error!("Error creating s3 token: {:?}", e);
+                        return Err(e.into());
+                    }
+                };
+
+            debug!("Adding AWS credentials");
+            env.insert("AWS_ACCESS_KEY_ID".to_string(), s3_token.access_key_id);
+            env.insert(
+                "AWS_SECRET_ACCESS_KEY".to_string(),
+                s3_token.secret_access_key,
+            );
+        }
         env.insert(
             "RCLONE_CONFIG_S3REMOTE_REGION".to_string(),
             SERVER_CONFIG.bucket_region.clone(),
@@ -266,7 +317,6 @@ pub trait ContainerPlatform {
             "NEBULOUS_SERVER".to_string(),
             SERVER_CONFIG.publish_url.clone().unwrap(),
         );
-
         env.insert("NEBU_NAMESPACE".to_string(), model.namespace.clone());
         env.insert("NEBU_NAME".to_string(), model.name.clone());
         env.insert("NEBU_CONTAINER_ID".to_string(), model.id.clone());
@@ -512,11 +562,15 @@ pub trait ContainerPlatform {
             owner_ref: Set(secret.owner_ref),
             encrypted_value: Set(secret.encrypted_value),
             nonce: Set(secret.nonce),
+            key_id: Set(secret.key_id),
+            encrypted_key: Set(secret.encrypted_key),
+            source: Set(None),
             labels: Set(None),
             created_by: Set(secret.created_by),
             updated_at: Set(secret.updated_at),
             created_at: Set(secret.created_at),
             expires_at: Set(None),
+            version: Set(Some(1)),
         };
 
         debug!("[DEBUG] store_agent_key_secret: Inserting secret into database");
@@ -1,5 +1,8 @@
 use crate::entities::containers;
 use crate::query::Query;
+use crate::resources::v1::containers::secret_rollout;
+use crate::resources::v1::events::models::EventType;
+use crate::resources::v1::events::recorder::{record_event, ResourceRef};
 use crate::state::AppState;
 use std::sync::Arc;
 use tokio::task::JoinHandle;
@@ -131,6 +134,15 @@ impl ContainerController {
                                 "[DEBUG:controller.rs:spawn] Calling platform.reconcile for container {}",
                                 container_clone.id
                             );
+                            // Roll the container instead if a secret it uses has changed
+                            if secret_rollout::restart_if_secrets_changed(
+                                &db_pool,
+                                &container_clone,
+                            )
+                            .await
+                            {
+                                return;
+                            }
                             // If your platform_factory is async, call it here.
                             let platform_name = container_clone
                                 .platform
@@ -140,7 +152,16 @@ impl ContainerController {
                                 crate::resources::v1::containers::factory::platform_factory(
                                     platform_name,
                                 );
-                            let _ = platform.reconcile(&container_clone, &db_pool).await;
+                            if let Err(e) = platform.reconcile(&container_clone, &db_pool).await {
+                                record_event(
+                                    &db_pool,
+                                    &ResourceRef::from(&container_clone),
+                                    EventType::Warning,
+                                    "PlatformError",
+                                    e.to_string(),
+                                )
+                                .await;
+                            }
                             debug!(
                                 "[DEBUG:controller.rs:spawn] Returned from platform.reconcile for container {}",
                                 container_clone.id
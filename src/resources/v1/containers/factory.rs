@@ -84,6 +84,18 @@ impl PlatformType {
         }
     }
 
+    pub async fn stop(
+        &self,
+        container: &containers::Model,
+        reason: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            PlatformType::Runpod(platform) => platform.stop(container, reason, db).await,
+            PlatformType::Kube(platform) => platform.stop(container, reason, db).await,
+        }
+    }
+
     // Add other methods as needed
 }
 
@@ -91,7 +103,8 @@ impl PlatformType {
 pub fn platform_factory(platform: String) -> PlatformType {
     match platform.as_str() {
         "runpod" => PlatformType::Runpod(RunpodPlatform::new()),
-        "kube" => PlatformType::Kube(KubePlatform::new()),
+        // Kubernetes containers are stored with platform "kubernetes"
+        "kube" | "kubernetes" => PlatformType::Kube(KubePlatform::new()),
         _ => panic!("Invalid platform"),
     }
 }
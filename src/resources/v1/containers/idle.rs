@@ -0,0 +1,198 @@
+use crate::entities::containers;
+use crate::mutation::Mutation;
+use crate::query::Query;
+use crate::resources::v1::containers::base::ContainerStatus;
+use crate::resources::v1::containers::factory::platform_factory;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use sea_orm::DatabaseConnection;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// How often the reaper looks for idle containers.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Minimum time between `last_active_at` writes for a single container, so a
+/// busy container doesn't cost a database write on every proxied request.
+const ACTIVITY_WRITE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often to re-check a waking container while a request is held.
+const WAKE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// When each container's activity was last written, keyed by container id.
+static LAST_ACTIVITY_WRITE: Lazy<DashMap<String, Instant>> = Lazy::new(DashMap::new);
+
+/// Spawn a background task that stops running containers whose idle policy
+/// `stop_after` has elapsed since their last proxied request.
+pub fn spawn_idle_reaper(db_pool: DatabaseConnection) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("[Idle] Idle reaper started");
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            stop_idle_containers(&db_pool).await;
+        }
+    })
+}
+
+async fn stop_idle_containers(db_pool: &DatabaseConnection) {
+    let containers = match Query::find_containers_by_status(db_pool, ContainerStatus::Running).await
+    {
+        Ok(containers) => containers,
+        Err(e) => {
+            error!("[Idle] Failed to list running containers: {}", e);
+            return;
+        }
+    };
+
+    let now = chrono::Utc::now();
+
+    for container in containers {
+        let Some(policy) = container.parse_idle().ok().flatten() else {
+            continue;
+        };
+
+        let stop_after = match humantime::parse_duration(&policy.stop_after) {
+            Ok(d) => d,
+            Err(e) => {
+                warn!(
+                    "[Idle] Container {} has invalid stop_after '{}': {}",
+                    container.id, policy.stop_after, e
+                );
+                continue;
+            }
+        };
+
+        let last_active = container.last_active_at.unwrap_or(container.created_at);
+        let idle_for = (now - last_active.with_timezone(&chrono::Utc))
+            .to_std()
+            .unwrap_or_default();
+        if idle_for < stop_after {
+            continue;
+        }
+
+        // Only runpod can currently release a container's compute and restart it
+        let platform = match container.platform.as_deref() {
+            Some("runpod") => "runpod".to_string(),
+            other => {
+                debug!(
+                    "[Idle] Skipping container {} on unsupported platform {:?}",
+                    container.id, other
+                );
+                continue;
+            }
+        };
+
+        info!(
+            "[Idle] Container {} idle for {}s, scaling to zero",
+            container.id,
+            idle_for.as_secs()
+        );
+        let reason = format!(
+            "Scaled to zero after {} without requests",
+            policy.stop_after
+        );
+        if let Err(e) = platform_factory(platform)
+            .stop(&container, &reason, db_pool)
+            .await
+        {
+            error!("[Idle] Failed to stop container {}: {}", container.id, e);
+        }
+    }
+}
+
+/// Record that a container just served a proxied request. Writes are
+/// throttled per container to `ACTIVITY_WRITE_INTERVAL`.
+pub async fn record_proxied_request(db: &DatabaseConnection, container_id: &str) {
+    let now = Instant::now();
+    if let Some(last) = LAST_ACTIVITY_WRITE.get(container_id) {
+        if now.duration_since(*last) < ACTIVITY_WRITE_INTERVAL {
+            return;
+        }
+    }
+    LAST_ACTIVITY_WRITE.insert(container_id.to_string(), now);
+
+    if let Err(e) = Mutation::update_container_last_active(db, container_id.to_string()).await {
+        error!(
+            "[Idle] Failed to record activity for container {}: {}",
+            container_id, e
+        );
+    }
+}
+
+/// Returns true if a container was scaled to zero by its idle policy.
+pub fn is_scaled_to_zero(container: &containers::Model) -> bool {
+    container.desired_status.as_deref() == Some(ContainerStatus::Stopped.to_string().as_str())
+}
+
+/// Returns true if a container is running and ready to serve requests.
+pub fn is_container_ready(container: &containers::Model) -> bool {
+    matches!(
+        container.parse_status(),
+        Ok(Some(status))
+            if status.status.as_deref() == Some(ContainerStatus::Running.to_string().as_str())
+                && status.ready == Some(true)
+    )
+}
+
+/// Ask the reconciler to start a container that was scaled to zero.
+pub async fn wake_container(
+    db: &DatabaseConnection,
+    container: &containers::Model,
+) -> Result<(), sea_orm::DbErr> {
+    info!("[Idle] Waking container {} on request", container.id);
+
+    // Count the wake-up as activity so the reaper doesn't stop it while starting
+    LAST_ACTIVITY_WRITE.remove(&container.id);
+    Mutation::update_container_last_active(db, container.id.clone()).await?;
+
+    Mutation::update_container_status(
+        db,
+        container.id.clone(),
+        Some(ContainerStatus::Pending.to_string()),
+        Some("Waking on request".to_string()),
+        None,
+        None,
+        None,
+        None,
+        Some(false),
+    )
+    .await?;
+
+    Mutation::update_container_desired_status(
+        db,
+        container.id.clone(),
+        Some(ContainerStatus::Running.to_string()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Poll a waking container until it is ready or `timeout` elapses. Returns
+/// the refreshed container if it became ready.
+pub async fn wait_until_ready(
+    db: &DatabaseConnection,
+    container_id: &str,
+    timeout: Duration,
+) -> Option<containers::Model> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match Query::find_container_by_id(db, container_id.to_string()).await {
+            Ok(Some(container)) if is_container_ready(&container) => return Some(container),
+            Ok(Some(_)) => {}
+            Ok(None) => return None,
+            Err(e) => {
+                error!("[Idle] Failed to poll container {}: {}", container_id, e);
+            }
+        }
+
+        if Instant::now() + WAKE_POLL_INTERVAL > deadline {
+            return None;
+        }
+        tokio::time::sleep(WAKE_POLL_INTERVAL).await;
+    }
+}
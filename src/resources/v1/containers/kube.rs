@@ -5,15 +5,19 @@ use crate::resources::v1::containers::base::{ContainerPlatform, ContainerStatus}
 use crate::resources::v1::containers::models::{
     V1Container, V1ContainerRequest, V1ContainerStatus,
 };
+use crate::resources::v1::secrets::providers::resolve_secret_version;
 use k8s_openapi::api::batch::v1::{Job, JobSpec};
 use k8s_openapi::api::core::v1::{
-    Container as K8sContainer, ContainerPort, EnvVar, PodSpec, PodTemplateSpec,
-    ResourceRequirements, Volume, VolumeMount,
+    Container as K8sContainer, ContainerPort, EnvVar, KeyToPath, PodSpec, PodTemplateSpec,
+    ResourceRequirements, Secret, SecretVolumeSource, Volume, VolumeMount,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use kube::{
+    api::{DeleteParams, Patch, PatchParams, PostParams},
+    Api, Client,
 };
-use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
-use kube::{api::PostParams, Api, Client};
 use petname;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use sea_orm::{DatabaseConnection, Set};
 use short_uuid::ShortUuid;
 use std::collections::{BTreeMap, HashMap};
 use tracing::{error, info};
@@ -371,7 +375,7 @@ impl ContainerPlatform for KubePlatform {
         }
 
         // Prepare volume mounts
-        let volume_mounts = vec![
+        let mut volume_mounts = vec![
             VolumeMount {
                 name: "huggingface-cache".to_string(),
                 mount_path: "/huggingface".to_string(),
@@ -385,7 +389,7 @@ impl ContainerPlatform for KubePlatform {
         ];
 
         // Prepare volumes
-        let volumes = vec![
+        let mut volumes = vec![
             Volume {
                 name: "huggingface-cache".to_string(),
                 persistent_volume_claim: Some(
@@ -408,6 +412,52 @@ impl ContainerPlatform for KubePlatform {
             },
         ];
 
+        // Secret files are mounted from a Kubernetes secret owned by the job
+        let secret_files_name = format!("{}-secret-files", name.clone().unwrap());
+        let mut secret_files_data = BTreeMap::new();
+        let mut secret_files_items = Vec::new();
+        for (index, file) in config.secret_files.iter().flatten().enumerate() {
+            let secret = crate::query::Query::find_secret_by_namespace_and_name(
+                db,
+                namespace,
+                &file.secret_name,
+            )
+            .await?
+            .ok_or_else(|| {
+                format!(
+                    "Secret {} not found for file {}",
+                    file.secret_name, file.path
+                )
+            })?;
+            let value = resolve_secret_version(db, &secret, file.secret_version).await?;
+
+            let key = format!("file-{}", index);
+            secret_files_data.insert(key.clone(), value);
+            secret_files_items.push(KeyToPath {
+                key: key.clone(),
+                path: key.clone(),
+                mode: Some(file.mode_bits()? as i32),
+            });
+            volume_mounts.push(VolumeMount {
+                name: "secret-files".to_string(),
+                mount_path: file.path.clone(),
+                sub_path: Some(key),
+                read_only: Some(true),
+                ..Default::default()
+            });
+        }
+        if !secret_files_items.is_empty() {
+            volumes.push(Volume {
+                name: "secret-files".to_string(),
+                secret: Some(SecretVolumeSource {
+                    secret_name: Some(secret_files_name.clone()),
+                    items: Some(secret_files_items),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        }
+
         // Prepare node selector for GPU scheduling
         let mut node_selector = BTreeMap::new();
         node_selector.insert("role".to_string(), "gpu".to_string());
@@ -491,11 +541,70 @@ impl ContainerPlatform for KubePlatform {
         rt.block_on(async {
             match self.get_client().await {
                 Ok(client) => {
-                    let jobs: Api<Job> = Api::namespaced(client, &self.namespace);
+                    let jobs: Api<Job> = Api::namespaced(client.clone(), &self.namespace);
+                    let secrets: Api<Secret> = Api::namespaced(client, &self.namespace);
+
+                    // The secret is created first so the job's pod never starts
+                    // without its files
+                    if !secret_files_data.is_empty() {
+                        let secret = Secret {
+                            metadata: ObjectMeta {
+                                name: Some(secret_files_name.clone()),
+                                ..Default::default()
+                            },
+                            string_data: Some(secret_files_data.clone()),
+                            ..Default::default()
+                        };
+                        secrets
+                            .create(&PostParams::default(), &secret)
+                            .await
+                            .map_err(|e| {
+                                format!(
+                                    "Failed to create secret files for Job '{}': {}",
+                                    name.clone().unwrap(),
+                                    e
+                                )
+                            })?;
+                    }
+
                     match jobs.create(&PostParams::default(), &job).await {
-                        Ok(_) => {
+                        Ok(created_job) => {
                             info!("[Kubernetes] Successfully created Job '{:?}'", name);
 
+                            // Hand the secret to the job so it is deleted along with it
+                            if !secret_files_data.is_empty() {
+                                let owner_reference = OwnerReference {
+                                    api_version: "batch/v1".to_string(),
+                                    kind: "Job".to_string(),
+                                    name: name.clone().unwrap(),
+                                    uid: created_job.metadata.uid.clone().unwrap_or_default(),
+                                    ..Default::default()
+                                };
+                                let patch = serde_json::json!({
+                                    "metadata": { "ownerReferences": [owner_reference] }
+                                });
+                                if let Err(e) = secrets
+                                    .patch(
+                                        &secret_files_name,
+                                        &PatchParams::default(),
+                                        &Patch::Merge(&patch),
+                                    )
+                                    .await
+                                {
+                                    let _ = jobs
+                                        .delete(&name.clone().unwrap(), &DeleteParams::background())
+                                        .await;
+                                    let _ = secrets
+                                        .delete(&secret_files_name, &DeleteParams::default())
+                                        .await;
+                                    return Err(format!(
+                                        "Failed to attach secret files to Job '{}': {}",
+                                        name.clone().unwrap(),
+                                        e
+                                    ));
+                                }
+                            }
+
                             let namespace = config
                                 .metadata
                                 .as_ref()
@@ -557,6 +666,14 @@ impl ContainerPlatform for KubePlatform {
                                     .clone()
                                     .map(|ports| serde_json::json!(ports))),
                                 proxy_port: Set(config.proxy_port.clone()),
+                                idle: Set(config.idle.clone().map(|idle| serde_json::json!(idle))),
+                                restart_on_secret_change: Set(config.restart_on_secret_change),
+                                secret_files: Set(config
+                                    .secret_files
+                                    .clone()
+                                    .map(|files| serde_json::json!(files))),
+                                secret_fingerprints: Set(None),
+                                last_active_at: Set(None),
                                 resources: Set(config
                                     .resources
                                     .clone()
@@ -579,7 +696,9 @@ impl ContainerPlatform for KubePlatform {
                                 created_at: Set(chrono::Utc::now().into()),
                             };
 
-                            if let Err(e) = container.insert(db).await {
+                            if let Err(e) =
+                                crate::mutation::Mutation::create_container(db, container).await
+                            {
                                 error!(
                                     "[Kubernetes] Failed to create container in database: {:?}",
                                     e
@@ -604,6 +723,11 @@ impl ContainerPlatform for KubePlatform {
                         }
                         Err(e) => {
                             error!("[Kubernetes] Error creating Job '{:?}': {:?}", name, e);
+                            if !secret_files_data.is_empty() {
+                                let _ = secrets
+                                    .delete(&secret_files_name, &DeleteParams::default())
+                                    .await;
+                            }
                         }
                     }
                 }
@@ -611,7 +735,8 @@ impl ContainerPlatform for KubePlatform {
                     error!("[Kubernetes] Failed to create K8s client: {:?}", e);
                 }
             }
-        });
+            Ok::<(), String>(())
+        })?;
 
         info!("[Kubernetes] Job {:?} created on Kubernetes", name);
         Ok(V1Container {
@@ -656,6 +781,9 @@ impl ContainerPlatform for KubePlatform {
             ports: config.ports.clone(),
             proxy_port: config.proxy_port.clone(),
             authz: config.authz.clone(),
+            idle: config.idle.clone(),
+            restart_on_secret_change: config.restart_on_secret_change,
+            secret_files: config.secret_files.clone(),
         })
     }
 
@@ -692,6 +820,61 @@ impl ContainerPlatform for KubePlatform {
         Ok(())
     }
 
+    async fn stop(
+        &self,
+        container: &containers::Model,
+        reason: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        info!(
+            "[Kubernetes] Stopping container {}: {}",
+            container.id, reason
+        );
+
+        crate::mutation::Mutation::update_container_desired_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Stopped.to_string()),
+        )
+        .await?;
+
+        // Deleting the job also removes its pod and secret files
+        let job_name = container
+            .resource_name
+            .clone()
+            .unwrap_or_else(|| container.name.clone());
+        let namespace = container
+            .resource_namespace
+            .clone()
+            .unwrap_or_else(|| self.namespace.clone());
+        let jobs: Api<Job> = Api::namespaced(self.get_client().await?, &namespace);
+        match jobs.delete(&job_name, &DeleteParams::background()).await {
+            Ok(_) => info!(
+                "[Kubernetes] Deleted Job {} for container {}",
+                job_name, container.id
+            ),
+            Err(kube::Error::Api(e)) if e.code == 404 => {
+                info!("[Kubernetes] No Job found with name: {}", job_name)
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        crate::mutation::Mutation::update_container_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Stopped.to_string()),
+            Some(reason.to_string()),
+            None,
+            None,
+            None,
+            None,
+            Some(false),
+        )
+        .await?;
+
+        Ok(())
+    }
+
     fn accelerator_map(&self) -> HashMap<String, String> {
         return HashMap::new();
     }
@@ -1,6 +1,8 @@
 pub mod base;
 pub mod controller;
 pub mod factory;
+pub mod idle;
 pub mod kube;
 pub mod models;
 pub mod runpod;
+pub mod secret_rollout;
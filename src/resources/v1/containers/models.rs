@@ -20,6 +20,35 @@ pub struct V1EnvVar {
     pub key: String,
     pub value: Option<String>,
     pub secret_name: Option<String>,
+    /// Pin `secret_name` to a version; the current version is used when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_version: Option<i32>,
+}
+
+/// A secret written to a file in the container at startup.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1SecretFile {
+    pub secret_name: String,
+    /// Absolute path of the file, e.g. `/etc/gcp/service-account.json`
+    pub path: String,
+    /// Octal file mode, defaults to `0600`
+    pub mode: Option<String>,
+    /// Pin the secret to a version; the current version is used when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_version: Option<i32>,
+}
+
+impl V1SecretFile {
+    /// The file mode as permission bits.
+    pub fn mode_bits(&self) -> Result<u32, String> {
+        match &self.mode {
+            Some(mode) => u32::from_str_radix(mode, 8)
+                .ok()
+                .filter(|bits| *bits <= 0o777)
+                .ok_or_else(|| format!("Invalid mode '{}' for {}", mode, self.path)),
+            None => Ok(0o600),
+        }
+    }
 }
 
 fn default_error_response_type() -> String {
@@ -69,6 +98,27 @@ pub struct V1ContainerRequest {
     pub ports: Option<Vec<V1PortRequest>>,
     pub proxy_port: Option<i16>,
     pub authz: Option<V1AuthzConfig>,
+    pub idle: Option<V1IdlePolicy>,
+    /// Restart the container when a secret referenced in `env` changes
+    pub restart_on_secret_change: Option<bool>,
+    pub secret_files: Option<Vec<V1SecretFile>>,
+}
+
+/// Scale-to-zero policy for containers served through the proxy.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1IdlePolicy {
+    /// Stop the container after this long without proxied requests, e.g. `15m`.
+    pub stop_after: String,
+    /// Start the container again when a proxied request arrives.
+    #[serde(default = "default_wake_on_request")]
+    pub wake_on_request: bool,
+    /// How long the proxy holds a request while the container wakes before
+    /// answering 503 with `Retry-After`, e.g. `30s`. Defaults to not holding.
+    pub wake_timeout: Option<String>,
+}
+
+fn default_wake_on_request() -> bool {
+    true
 }
 
 pub enum RestartPolicy {
@@ -152,6 +202,9 @@ pub struct V1Container {
     pub ports: Option<Vec<V1PortRequest>>,
     pub proxy_port: Option<i16>,
     pub authz: Option<V1AuthzConfig>,
+    pub idle: Option<V1IdlePolicy>,
+    pub restart_on_secret_change: Option<bool>,
+    pub secret_files: Option<Vec<V1SecretFile>>,
 }
 
 impl V1Container {
@@ -196,6 +249,9 @@ pub struct V1UpdateContainer {
     pub proxy_port: Option<i16>,
     pub no_delete: Option<bool>,
     pub authz: Option<V1AuthzConfig>,
+    pub idle: Option<V1IdlePolicy>,
+    pub restart_on_secret_change: Option<bool>,
+    pub secret_files: Option<Vec<V1SecretFile>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
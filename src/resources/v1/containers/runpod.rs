@@ -1,12 +1,18 @@
 use crate::accelerator::base::AcceleratorProvider;
 use crate::accelerator::runpod::RunPodProvider;
 use crate::agent::aws::delete_s3_scoped_user;
+use crate::auth::workload;
 use crate::entities::containers;
+use crate::meters::{record_meter_event, MeterEvent};
 use crate::models::{V1Meter, V1UserProfile};
 use crate::mutation::{self, Mutation};
 use crate::oci::client::pull_and_parse_config;
 use crate::query::Query;
 use crate::resources::v1::containers::base::{ContainerPlatform, ContainerStatus};
+use crate::resources::v1::containers::secret_rollout::secret_fingerprint;
+use crate::resources::v1::events::models::EventType;
+use crate::resources::v1::events::recorder::{record_event, ResourceRef};
+use crate::resources::v1::secrets::providers::resolve_secret_version;
 use crate::resources::v1::containers::models::{
     RestartPolicy, V1Container, V1ContainerHealthCheck, V1ContainerRequest, V1ContainerStatus,
     V1Port,
@@ -15,10 +21,11 @@ use crate::resources::v1::volumes::models::V1VolumePath;
 use crate::ssh::exec::run_ssh_command_ts;
 use crate::ssh::keys;
 use crate::volumes::rclone::{SymlinkConfig, VolumeConfig, VolumePath};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use petname;
 use regex::Regex;
 use runpod::*;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use sea_orm::{DatabaseConnection, Set};
 use short_uuid::ShortUuid;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -72,10 +79,11 @@ impl RunpodPlatform {
         }
     }
 
-    /// Report metrics to OpenMeter for a running container
+    /// Report metrics for a running container through the metering pipeline
     async fn report_meters(
         &self,
         container_id: String,
+        namespace: String,
         seconds: u64,
         meters: &serde_json::Value,
         owner_id: String,
@@ -95,27 +103,7 @@ impl RunpodPlatform {
             return;
         }
 
-        // Get OpenMeter configuration from environment
-        let openmeter_url = match std::env::var("OPENMETER_URL") {
-            Ok(url) => url,
-            Err(_) => {
-                error!("[Runpod Controller] OPENMETER_URL environment variable not set");
-                return;
-            }
-        };
-
-        let openmeter_token = match std::env::var("OPENMETER_TOKEN") {
-            Ok(token) => token,
-            Err(_) => {
-                error!("[Runpod Controller] OPENMETER_TOKEN environment variable not set");
-                return;
-            }
-        };
-
-        // Create OpenMeter client
-        let meter_client = openmeter::MeterClient::new(openmeter_url, openmeter_token);
-
-        // Create and send events for each meter
+        // Create and queue events for each meter
         for meter in meters_vec {
             // Determine final cost using cost plus cost percentage if present.
             let mut cost_value = if let Some(costp) = meter.costp {
@@ -187,6 +175,7 @@ impl RunpodPlatform {
                         "value": seconds as f64,
                         "metric": meter.metric,
                         "container_id": container_id,
+                        "namespace": namespace,
                         "currency": meter.currency,
                         "cost": cost_value,
                         "unit": meter.unit,
@@ -201,6 +190,7 @@ impl RunpodPlatform {
                         "value": seconds as f64,
                         "metric": meter.metric,
                         "container_id": container_id,
+                        "namespace": namespace,
                         "currency": meter.currency,
                         "cost": cost_value,
                         "unit": meter.unit,
@@ -211,34 +201,20 @@ impl RunpodPlatform {
                 }
             };
 
-            // Create CloudEvent
-            let cloud_event = openmeter::CloudEvent {
-                id: event_id,
-                source: "nebulous-runpod-controller".to_string(),
-                specversion: "1.0".to_string(),
-                r#type: meter.metric.clone(),
-                subject: owner_id.clone(),
-                time: Some(chrono::Utc::now().to_rfc3339()),
-                dataschema: None,
-                datacontenttype: Some("application/json".to_string()),
-                data: Some(data),
-            };
+            let event = MeterEvent::new(
+                "nebulous-runpod-controller",
+                &meter.metric,
+                &owner_id,
+                data,
+            )
+            .with_id(event_id);
 
-            // Send the event to OpenMeter
-            match meter_client.ingest_events(&[cloud_event]).await {
-                Ok(_) => {
-                    debug!(
-                        "[Runpod Controller] Successfully reported meter {:?} for container {}",
-                        meter, container_id
-                    );
-                }
-                Err(e) => {
-                    error!(
-                        "[Runpod Controller] Failed to report meter {:?} for container {}: {}",
-                        meter, container_id, e
-                    );
-                }
-            }
+            // Hand the event to the metering pipeline, which batches and retries delivery
+            record_meter_event(event).await;
+            debug!(
+                "[Runpod Controller] Queued meter {:?} for container {}",
+                meter, container_id
+            );
         }
     }
 
@@ -660,6 +636,7 @@ impl RunpodPlatform {
                                 }
                                 self.report_meters(
                                     container_id.clone(),
+                                    container.namespace.clone(),
                                     pause_seconds,
                                     meters,
                                     container.owner.clone(),
@@ -1358,6 +1335,8 @@ impl RunpodPlatform {
         };
         Mutation::update_container_user(db, model.id.clone(), Some(final_user)).await?;
 
+        // Versions of the secrets handed to the container, to spot later changes
+        let mut secret_fingerprints = HashMap::new();
         match model.parse_env() {
             Ok(Some(env)) => {
                 // We have a valid, non-empty list of environment variables.
@@ -1381,7 +1360,30 @@ impl RunpodPlatform {
                                         continue;
                                     }
                                 };
-                            secret_model.decrypt_value().ok()
+                            match resolve_secret_version(
+                                db,
+                                &secret_model,
+                                env_var.secret_version,
+                            )
+                            .await
+                            {
+                                Ok(value) => {
+                                    if env_var.secret_version.is_none() {
+                                        secret_fingerprints.insert(
+                                            secret_name.clone(),
+                                            secret_fingerprint(&secret_model, &value),
+                                        );
+                                    }
+                                    Some(value)
+                                }
+                                Err(e) => {
+                                    error!(
+                                        "[Runpod Controller] Failed to resolve secret {}: {}",
+                                        secret_name, e
+                                    );
+                                    None
+                                }
+                            }
                         }
                         None => env_var.value.clone(),
                     };
@@ -1413,6 +1415,45 @@ impl RunpodPlatform {
                 // Decide how you want to handle the error (return early, ignore, etc.)
             }
         }
+        // Secret files are passed as base64 env vars the startup script writes out.
+        // A file that can't be resolved fails the declare rather than starting
+        // the container without it.
+        if let Some(secret_files) = model.parse_secret_files()? {
+            for (index, file) in secret_files.iter().enumerate() {
+                let secret_model = Query::find_secret_by_namespace_and_name(
+                    db,
+                    &model.namespace,
+                    &file.secret_name,
+                )
+                .await?
+                .ok_or_else(|| {
+                    format!(
+                        "Secret {} not found for file {}",
+                        file.secret_name, file.path
+                    )
+                })?;
+                let value = resolve_secret_version(db, &secret_model, file.secret_version)
+                    .await
+                    .map_err(|e| {
+                        format!(
+                            "Failed to resolve secret {} for file {}: {}",
+                            file.secret_name, file.path, e
+                        )
+                    })?;
+                if file.secret_version.is_none() {
+                    secret_fingerprints.insert(
+                        file.secret_name.clone(),
+                        secret_fingerprint(&secret_model, &value),
+                    );
+                }
+                env_vec.push(runpod::EnvVar {
+                    key: secret_file_env(index),
+                    value: STANDARD.encode(value),
+                });
+            }
+        }
+        Mutation::update_container_secret_fingerprints(db, model.id.clone(), secret_fingerprints)
+            .await?;
         info!("[Runpod Controller] Environment variables: {:?}", env_vec);
 
         let env_map: HashMap<String, String> = env_vec
@@ -1598,6 +1639,22 @@ impl RunpodPlatform {
             "[Runpod Controller] Using datacenter '{}' for volume and pod creation.",
             datacenter_id
         );
+        let placement = if model.accelerators.as_ref().is_some_and(|a| !a.is_empty()) {
+            format!(
+                "Scheduled on RunPod datacenter {} with {}x {}",
+                datacenter_id, requested_gpu_count, nebu_gpu_type_id
+            )
+        } else {
+            format!("Scheduled on RunPod datacenter {}", datacenter_id)
+        };
+        record_event(
+            db,
+            &ResourceRef::from(&model),
+            EventType::Normal,
+            "Scheduled",
+            placement,
+        )
+        .await;
         let volume = match self
             .runpod_client
             .ensure_volume_in_datacenter(
@@ -1797,6 +1854,75 @@ impl RunpodPlatform {
 
         let log_file = "$HOME/.logs/nebu_container.log";
 
+        // Keep the workload token fresh for the nebu CLI and the AWS
+        // credentials provider, without tracing its value
+        let token_refresh_script = r#"
+    if [ -n "$NEBU_API_KEY_REFRESH_URL" ]; then
+        set +x
+        echo "[DEBUG] Starting workload token refresher..."
+        mkdir -p "$(dirname "$NEBU_API_KEY_FILE")" "$(dirname "$AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE")"
+        write_workload_token() {
+            (
+                umask 077
+                printf '%s' "$1" > "$NEBU_API_KEY_FILE.tmp" \
+                    && mv "$NEBU_API_KEY_FILE.tmp" "$NEBU_API_KEY_FILE"
+                printf 'Bearer %s' "$1" > "$AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE.tmp" \
+                    && mv "$AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE.tmp" "$AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE"
+            )
+        }
+        write_workload_token "$NEBU_API_KEY"
+        (
+            delay="${NEBU_API_KEY_REFRESH_SECS:-1800}"
+            while true; do
+                sleep "$delay"
+                token=$(curl -sf -X POST \
+                    -H "Authorization: Bearer $(cat "$NEBU_API_KEY_FILE")" \
+                    "$NEBU_API_KEY_REFRESH_URL" \
+                    | sed -n 's/.*"token" *: *"\([^"]*\)".*/\1/p')
+                if [ -n "$token" ]; then
+                    write_workload_token "$token"
+                    delay="${NEBU_API_KEY_REFRESH_SECS:-1800}"
+                else
+                    echo "[ERROR] Failed to refresh the workload token, retrying"
+                    delay=30
+                fi
+            done
+        ) > "$HOME/.logs/token_refresh.log" 2>&1 &
+        set -x
+    fi
+"#;
+
+        // Write secret files without tracing, so their values stay out of the log
+        let mut secret_files_script = String::new();
+        if let Ok(Some(secret_files)) = model.parse_secret_files() {
+            secret_files_script.push_str("set +x\n");
+            for (index, file) in secret_files.iter().enumerate() {
+                let var = secret_file_env(index);
+                let dir = std::path::Path::new(&file.path)
+                    .parent()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "/".to_string());
+                let mode = file.mode_bits().unwrap_or(0o600);
+                secret_files_script.push_str(&format!(
+                    r#"    if [ -n "${var}" ]; then
+        echo "[DEBUG] Writing secret file {path}"
+        mkdir -p '{dir}'
+        (umask 077; printf '%s' "${var}" | base64 -d > '{path}')
+        chmod {mode:o} '{path}'
+    else
+        echo "[ERROR] Secret for {path} is unavailable"
+    fi
+    unset {var}
+"#,
+                    var = var,
+                    dir = dir,
+                    path = file.path,
+                    mode = mode,
+                ));
+            }
+            secret_files_script.push_str("    set -x\n");
+        }
+
         // export ALL_PROXY={proxy_value}  # TODO: this is problematic for DNS resolution but we may need it
         // export HTTP_PROXY={proxy_value}
         // export HTTPS_PROXY={proxy_value}
@@ -1862,6 +1988,7 @@ impl RunpodPlatform {
     echo "[DEBUG] Starting tailscale up..."
     tailscale up --auth-key=$TS_AUTHKEY --hostname="{hostname}" --ssh --advertise-tags=tag:container
 
+    {token_refresh_script}
     echo "[DEBUG] Invoking nebu sync..."
     nebu sync volumes --config /nebu/sync.yaml --interval-seconds 5 \
         --create-if-missing --config-from-env
@@ -1870,12 +1997,15 @@ impl RunpodPlatform {
     nebu sync volumes --config /nebu/sync.yaml --interval-seconds 5 \
         --create-if-missing --watch --background --block-once --config-from-env
 
+    {secret_files_script}
     nvidia-smi
     echo "[DEBUG] All done with base_command; now your user command: {cmd}"
     ({cmd}) # Wrap in parentheses and add semicolon
     "#,
             curl_install = curl_install,
             nebu_install = nebu_install,
+            secret_files_script = secret_files_script,
+            token_refresh_script = token_refresh_script,
             cmd = cmd
         );
 
@@ -2066,6 +2196,14 @@ done
                         container.id,
                         response.status()
                     );
+                    record_event(
+                        db,
+                        &ResourceRef::from(container),
+                        EventType::Warning,
+                        "Unhealthy",
+                        format!("Health check {} returned {}", url, response.status()),
+                    )
+                    .await;
                     // Update DB to mark as not ready
                     Mutation::update_container_status(
                         db,
@@ -2086,6 +2224,14 @@ done
                     "[Runpod Controller] HTTP health check request failed for {}: {}",
                     container.id, e
                 );
+                record_event(
+                    db,
+                    &ResourceRef::from(container),
+                    EventType::Warning,
+                    "Unhealthy",
+                    format!("Health check {} failed: {}", url, e),
+                )
+                .await;
                 // If the HTTP request failed, mark as not ready
                 Mutation::update_container_status(
                     db,
@@ -2248,7 +2394,10 @@ impl ContainerPlatform for RunpodPlatform {
         debug!("[Runpod Controller] About to store agent key secret");
         // Restore user profile check (using the now-available full profile)
         debug!("[Runpod Controller] user_profile = {:?}", user_profile);
-        if user_profile.token.is_none() {
+        if workload::enabled() {
+            // The container is issued a workload token when it starts
+            debug!("[Runpod Controller] Workload tokens enabled, not storing an agent key");
+        } else if user_profile.token.is_none() {
             error!("[Runpod Controller] user_profile.token is None, cannot get agent key for container");
             return Err(Box::<dyn std::error::Error + Send + Sync>::from(
                 "Cannot create container: user profile is missing authentication token".to_string(),
@@ -2261,20 +2410,22 @@ impl ContainerPlatform for RunpodPlatform {
             "[Runpod Controller] Storing agent key secret: id={}, owner_id={}",
             id, owner_id
         );
-        match self
-            .store_agent_key_secret(db, user_profile, &id, owner_id, api_key)
-            .await
-        {
-            Ok(_) => debug!("[Runpod Controller] Successfully stored agent key secret"),
-            Err(e) => {
-                error!(
-                    "[Runpod Controller] Failed to store agent key secret: {}",
-                    e
-                );
-                return Err(Box::<dyn std::error::Error + Send + Sync>::from(format!(
-                    "Failed to store agent key secret: {}",
-                    e
-                )));
+        if !workload::enabled() {
+            match self
+                .store_agent_key_secret(db, user_profile, &id, owner_id, api_key)
+                .await
+            {
+                Ok(_) => debug!("[Runpod Controller] Successfully stored agent key secret"),
+                Err(e) => {
+                    error!(
+                        "[Runpod Controller] Failed to store agent key secret: {}",
+                        e
+                    );
+                    return Err(Box::<dyn std::error::Error + Send + Sync>::from(format!(
+                        "Failed to store agent key secret: {}",
+                        e
+                    )));
+                }
             }
         }
 
@@ -2348,6 +2499,14 @@ impl ContainerPlatform for RunpodPlatform {
                 .map(|health_check| serde_json::json!(health_check))),
             desired_status: Set(Some(ContainerStatus::Running.to_string())),
             ssh_keys: Set(config.ssh_keys.clone().map(|keys| serde_json::json!(keys))),
+            idle: Set(config.idle.clone().map(|idle| serde_json::json!(idle))),
+            restart_on_secret_change: Set(config.restart_on_secret_change),
+            secret_files: Set(config
+                .secret_files
+                .clone()
+                .map(|files| serde_json::json!(files))),
+            secret_fingerprints: Set(None),
+            last_active_at: Set(None),
             public_addr: Set(None),
             tailnet_ip: Set(None),
             authz: Set(config.authz.clone().map(|authz| serde_json::json!(authz))),
@@ -2360,7 +2519,7 @@ impl ContainerPlatform for RunpodPlatform {
             controller_data: Set(None),
         };
 
-        if let Err(e) = container.insert(db).await {
+        if let Err(e) = Mutation::create_container(db, container).await {
             error!(
                 "[Runpod Controller] Failed to create container in database: {:?}",
                 e
@@ -2412,6 +2571,9 @@ impl ContainerPlatform for RunpodPlatform {
             ports: config.ports.clone(),
             proxy_port: config.proxy_port.clone(),
             authz: config.authz.clone(),
+            idle: config.idle.clone(),
+            restart_on_secret_change: config.restart_on_secret_change,
+            secret_files: config.secret_files.clone(),
         })
     }
 
@@ -2818,12 +2980,68 @@ impl ContainerPlatform for RunpodPlatform {
         Ok(())
     }
 
+    async fn stop(
+        &self,
+        container: &containers::Model,
+        reason: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        info!(
+            "[Runpod Controller] Stopping container {}: {}",
+            container.id, reason
+        );
+
+        // Mark it stopped first so reconcile never recreates the pod mid-stop
+        Mutation::update_container_desired_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Stopped.to_string()),
+        )
+        .await?;
+
+        let pods_response = self.runpod_client.list_pods().await?;
+        if let Some(my_pods) = pods_response.data {
+            if let Some(pod) = my_pods.pods.iter().find(|p| p.name == container.id) {
+                self.runpod_client.delete_pod(&pod.id).await?;
+                info!(
+                    "[Runpod Controller] Deleted pod {} for container {}",
+                    pod.id, container.id
+                );
+            } else {
+                info!(
+                    "[Runpod Controller] No pod found with name: {}",
+                    container.id
+                );
+            }
+        }
+
+        Mutation::update_container_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Stopped.to_string()),
+            Some(reason.to_string()),
+            None,
+            None,
+            None,
+            None,
+            Some(false),
+        )
+        .await?;
+
+        Ok(())
+    }
+
     fn accelerator_map(&self) -> HashMap<String, String> {
         let provider = RunPodProvider::new();
         provider.accelerator_map().clone()
     }
 }
 
+/// Env var carrying the base64 contents of the container's `index`th secret file.
+fn secret_file_env(index: usize) -> String {
+    format!("NEBU_SECRET_FILE_{}", index)
+}
+
 /// Returns true if the given error indicates a 404 Not Found response.
 pub fn is_not_found(err: &reqwest::Error) -> bool {
     err.status() == Some(reqwest::StatusCode::NOT_FOUND)
@@ -0,0 +1,223 @@
+use crate::entities::{containers, secrets};
+use crate::mutation::Mutation;
+use crate::query::Query;
+use crate::resources::v1::containers::base::ContainerStatus;
+use crate::resources::v1::containers::factory::platform_factory;
+use crate::resources::v1::containers::idle::is_container_ready;
+use crate::resources::v1::events::models::EventType;
+use crate::resources::v1::events::recorder::{record_event, ResourceRef};
+use crate::resources::v1::secrets::providers::resolve_secret;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use sea_orm::{DatabaseConnection, DbErr};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Minimum time between secret checks for a single container, so external
+/// providers aren't queried on every controller pass.
+const SECRET_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When each container's secrets were last checked, keyed by container id.
+static LAST_SECRET_CHECK: Lazy<DashMap<String, Instant>> = Lazy::new(DashMap::new);
+
+/// Identify the value of a secret a container was given. Stored secrets are
+/// tracked by version; external ones by a hash of the resolved value, as
+/// their version doesn't change when the provider's value does.
+pub fn secret_fingerprint(secret: &secrets::Model, value: &str) -> String {
+    if secret.source.is_none() {
+        return format!("v{}", secret.current_version());
+    }
+    let digest = ring::digest::digest(&ring::digest::SHA256, value.as_bytes());
+    let hex: String = digest
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256:{}", hex)
+}
+
+/// Names of the secrets in a container's env and secret files that changed
+/// since it started. Secrets pinned to a version never change.
+pub async fn changed_secrets(
+    db: &DatabaseConnection,
+    container: &containers::Model,
+) -> Result<Vec<String>, DbErr> {
+    let Some(started_with) = container.parse_secret_fingerprints().ok().flatten() else {
+        return Ok(Vec::new());
+    };
+    let env = container.parse_env().ok().flatten().unwrap_or_default();
+    let files = container
+        .parse_secret_files()
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let referenced = env
+        .into_iter()
+        .filter(|env_var| env_var.secret_version.is_none())
+        .filter_map(|env_var| env_var.secret_name)
+        .chain(
+            files
+                .into_iter()
+                .filter(|file| file.secret_version.is_none())
+                .map(|file| file.secret_name),
+        );
+
+    let mut changed = Vec::new();
+    for name in referenced {
+        if changed.contains(&name) {
+            continue;
+        }
+        let Some(previous) = started_with.get(&name) else {
+            continue;
+        };
+        let Some(secret) =
+            Query::find_secret_by_namespace_and_name(db, &container.namespace, &name).await?
+        else {
+            continue;
+        };
+
+        let current = if secret.source.is_some() {
+            match resolve_secret(&secret).await {
+                Ok(value) => secret_fingerprint(&secret, &value),
+                Err(e) => {
+                    warn!(
+                        "[Secret Rollout] Failed to resolve secret {} for container {}: {}",
+                        name, container.id, e
+                    );
+                    continue;
+                }
+            }
+        } else {
+            secret_fingerprint(&secret, "")
+        };
+
+        if &current != previous {
+            changed.push(name);
+        }
+    }
+    Ok(changed)
+}
+
+/// Restart a running container that opted in with `restart_on_secret_change`
+/// if any secret it references changed. Replicas sharing an owner are rolled
+/// one at a time. Returns true if a restart was started.
+pub async fn restart_if_secrets_changed(
+    db: &DatabaseConnection,
+    container: &containers::Model,
+) -> bool {
+    if container.restart_on_secret_change != Some(true) || !is_container_ready(container) {
+        return false;
+    }
+
+    let now = Instant::now();
+    if let Some(last) = LAST_SECRET_CHECK.get(&container.id) {
+        if now.duration_since(*last) < SECRET_CHECK_INTERVAL {
+            return false;
+        }
+    }
+    LAST_SECRET_CHECK.insert(container.id.clone(), now);
+
+    let changed = match changed_secrets(db, container).await {
+        Ok(changed) if changed.is_empty() => return false,
+        Ok(changed) => changed,
+        Err(e) => {
+            error!(
+                "[Secret Rollout] Failed to check secrets for container {}: {}",
+                container.id, e
+            );
+            return false;
+        }
+    };
+
+    if let Some(owner_ref) = &container.owner_ref {
+        match Query::find_containers_by_owner_ref(db, owner_ref).await {
+            Ok(replicas) => {
+                let rolling = replicas.iter().any(|replica| {
+                    replica.id != container.id
+                        && replica
+                            .parse_status()
+                            .ok()
+                            .flatten()
+                            .and_then(|status| status.status)
+                            .and_then(|status| status.parse::<ContainerStatus>().ok())
+                            .is_some_and(|status| status.is_active())
+                        && !is_container_ready(replica)
+                });
+                if rolling {
+                    debug!(
+                        "[Secret Rollout] Waiting for other replicas of {} before restarting {}",
+                        owner_ref, container.id
+                    );
+                    // Check again on the next pass
+                    LAST_SECRET_CHECK.remove(&container.id);
+                    return false;
+                }
+            }
+            Err(e) => {
+                error!(
+                    "[Secret Rollout] Failed to list replicas of {}: {}",
+                    owner_ref, e
+                );
+                return false;
+            }
+        }
+    }
+
+    let reason = format!("Restarting after secret {} changed", changed.join(", "));
+    info!("[Secret Rollout] Container {}: {}", container.id, reason);
+
+    let platform = container
+        .platform
+        .clone()
+        .unwrap_or_else(|| "runpod".to_string());
+    if let Err(e) = platform_factory(platform)
+        .stop(container, &reason, db)
+        .await
+    {
+        error!(
+            "[Secret Rollout] Failed to stop container {}: {}",
+            container.id, e
+        );
+        return false;
+    }
+
+    let restarted = async {
+        Mutation::update_container_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Pending.to_string()),
+            Some(reason.clone()),
+            None,
+            None,
+            None,
+            None,
+            Some(false),
+        )
+        .await?;
+        Mutation::update_container_desired_status(
+            db,
+            container.id.clone(),
+            Some(ContainerStatus::Running.to_string()),
+        )
+        .await
+    };
+    match restarted.await {
+        Ok(_) => {
+            record_event(
+                db,
+                &ResourceRef::from(container),
+                EventType::Normal,
+                "Restarted",
+                reason,
+            )
+            .await;
+        }
+        Err(e) => {
+            error!(
+                "[Secret Rollout] Failed to restart container {}: {}",
+                container.id, e
+            );
+        }
+    }
+    true
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Whether an event is routine or points at a problem.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Normal,
+    Warning,
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventType::Normal => write!(f, "Normal"),
+            EventType::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+/// Something that happened to a container or processor, such as a status
+/// change, a scheduling decision or a failed health check.
+///
+/// The same event repeated back to back is kept once, with `count` and
+/// `last_seen` updated.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1ResourceEvent {
+    pub id: String,
+    /// `Container` or `Processor`
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    pub resource_id: String,
+    /// `Normal` or `Warning`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Short machine-readable cause, e.g. `StatusChanged` or `Unhealthy`
+    pub reason: String,
+    pub message: String,
+    pub count: i32,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1ResourceEvents {
+    pub events: Vec<V1ResourceEvent>,
+}
+
+/// Filters for the events of a resource. `since` is unix seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1ResourceEventQuery {
+    pub since: Option<i64>,
+    /// Newest events to return, 100 by default
+    pub limit: Option<u64>,
+}
@@ -0,0 +1,169 @@
+use crate::config::SERVER_CONFIG;
+use crate::entities::{containers, processors, resource_events};
+use crate::resources::v1::events::models::EventType;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder,
+};
+use short_uuid::ShortUuid;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// How often events past their retention are deleted.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// The resource an event is about.
+#[derive(Debug, Clone)]
+pub struct ResourceRef {
+    pub kind: &'static str,
+    pub id: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+impl From<&containers::Model> for ResourceRef {
+    fn from(container: &containers::Model) -> Self {
+        Self {
+            kind: "Container",
+            id: container.id.clone(),
+            namespace: container.namespace.clone(),
+            name: container.name.clone(),
+        }
+    }
+}
+
+impl From<&processors::Model> for ResourceRef {
+    fn from(processor: &processors::Model) -> Self {
+        Self {
+            kind: "Processor",
+            id: processor.id.clone(),
+            namespace: processor.namespace.clone(),
+            name: processor.name.clone(),
+        }
+    }
+}
+
+/// Add an event to a resource's history. If its latest event has the same
+/// type, reason and message, that one is counted again instead.
+///
+/// Failures are logged, since the change the event describes has already
+/// happened.
+pub async fn record_event(
+    db: &DatabaseConnection,
+    resource: &ResourceRef,
+    event_type: EventType,
+    reason: &str,
+    message: String,
+) {
+    if let Err(e) = try_record_event(db, resource, event_type, reason, message).await {
+        error!(
+            "[Events] Failed to record {} event for {} {}: {}",
+            reason, resource.kind, resource.id, e
+        );
+    }
+}
+
+async fn try_record_event(
+    db: &DatabaseConnection,
+    resource: &ResourceRef,
+    event_type: EventType,
+    reason: &str,
+    message: String,
+) -> Result<(), DbErr> {
+    let now = chrono::Utc::now();
+    let event_type = event_type.to_string();
+
+    let latest = resource_events::Entity::find()
+        .filter(resource_events::Column::ResourceId.eq(&resource.id))
+        .order_by_desc(resource_events::Column::LastSeenAt)
+        .one(db)
+        .await?;
+
+    match latest {
+        Some(latest)
+            if latest.event_type == event_type
+                && latest.reason == reason
+                && latest.message == message =>
+        {
+            let count = latest.count;
+            let mut active_model: resource_events::ActiveModel = latest.into();
+            active_model.count = Set(count + 1);
+            active_model.last_seen_at = Set(now.into());
+            active_model.update(db).await?;
+        }
+        _ => {
+            resource_events::ActiveModel {
+                id: Set(ShortUuid::generate().to_string()),
+                kind: Set(resource.kind.to_string()),
+                namespace: Set(resource.namespace.clone()),
+                name: Set(resource.name.clone()),
+                resource_id: Set(resource.id.clone()),
+                event_type: Set(event_type),
+                reason: Set(reason.to_string()),
+                message: Set(message),
+                count: Set(1),
+                last_seen_at: Set(now.into()),
+                created_at: Set(now.into()),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Record a resource moving from `previous` to `current` status, with the
+/// message that came with the change, if any. Moving to a failed or invalid
+/// status is a warning.
+pub async fn record_status_change(
+    db: &DatabaseConnection,
+    resource: &ResourceRef,
+    previous: Option<&str>,
+    current: &str,
+    message: Option<&str>,
+) {
+    if previous == Some(current) {
+        return;
+    }
+
+    let event_type = if matches!(current.to_lowercase().as_str(), "failed" | "invalid") {
+        EventType::Warning
+    } else {
+        EventType::Normal
+    };
+    let mut description = format!(
+        "Status changed from {} to {}",
+        previous.unwrap_or("none"),
+        current
+    );
+    if let Some(message) = message {
+        description = format!("{}: {}", description, message);
+    }
+    record_event(db, resource, event_type, "StatusChanged", description).await;
+}
+
+/// Delete events last seen longer ago than `NEBU_EVENT_RETENTION_SECS`.
+pub async fn prune_events(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let cutoff =
+        chrono::Utc::now() - chrono::Duration::seconds(SERVER_CONFIG.events.retention_secs as i64);
+    let result = resource_events::Entity::delete_many()
+        .filter(resource_events::Column::LastSeenAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+pub fn spawn_event_pruner(db: DatabaseConnection) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match prune_events(&db).await {
+                Ok(0) => {}
+                Ok(pruned) => info!("[Events] Pruned {} expired resource events", pruned),
+                Err(e) => error!("[Events] Failed to prune resource events: {}", e),
+            }
+        }
+    })
+}
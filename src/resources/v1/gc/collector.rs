@@ -0,0 +1,378 @@
+use crate::config::SERVER_CONFIG;
+use crate::entities::{containers, namespaces, processors, secrets, volumes};
+use crate::resources::v1::gc::models::{PropagationPolicy, V1DanglingReference};
+use crate::resources::v1::namespaces::finalizer::delete_resource;
+use crate::state::AppState;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Kinds that can own other resources through an `owner_ref`.
+const OWNER_KINDS: &[&str] = &["Processor", "Container", "Secret", "Volume"];
+
+/// An `owner_ref` of the form `name.namespace.Kind`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OwnerRef {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+impl OwnerRef {
+    pub fn new(kind: &str, namespace: &str, name: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Parse an `owner_ref`. Anything that isn't `name.namespace.Kind` with a
+    /// known kind, such as the owner emails some secrets carry, is not an
+    /// owner reference.
+    pub fn parse(owner_ref: &str) -> Option<Self> {
+        let mut parts = owner_ref.rsplitn(3, '.');
+        let kind = parts.next()?;
+        let namespace = parts.next()?;
+        let name = parts.next()?;
+        if !OWNER_KINDS.contains(&kind) || namespace.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(Self::new(kind, namespace, name))
+    }
+}
+
+impl fmt::Display for OwnerRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.name, self.namespace, self.kind)
+    }
+}
+
+/// A resource that has an `owner_ref`.
+#[derive(Debug, Clone)]
+pub struct Dependent {
+    pub kind: &'static str,
+    pub id: String,
+    pub name: String,
+    pub namespace: String,
+    pub owner: String,
+    pub owner_ref: String,
+    pub created_at: i64,
+}
+
+impl Dependent {
+    /// This resource as an owner of others, if its kind can own anything.
+    fn as_owner(&self) -> Option<OwnerRef> {
+        OWNER_KINDS
+            .contains(&self.kind)
+            .then(|| OwnerRef::new(self.kind, &self.namespace, &self.name))
+    }
+}
+
+/// Resources with an `owner_ref`, optionally only those naming `owner_ref`.
+async fn find_dependents_by_ref(
+    db: &DatabaseConnection,
+    owner_ref: Option<&str>,
+) -> Result<Vec<Dependent>, DbErr> {
+    let mut dependents = Vec::new();
+
+    let mut query = containers::Entity::find().filter(containers::Column::OwnerRef.is_not_null());
+    if let Some(owner_ref) = owner_ref {
+        query = query.filter(containers::Column::OwnerRef.eq(owner_ref));
+    }
+    for c in query.all(db).await? {
+        dependents.push(Dependent {
+            kind: "Container",
+            id: c.id,
+            name: c.name,
+            namespace: c.namespace,
+            owner: c.owner,
+            owner_ref: c.owner_ref.unwrap_or_default(),
+            created_at: c.created_at.timestamp(),
+        });
+    }
+
+    let mut query = secrets::Entity::find().filter(secrets::Column::OwnerRef.is_not_null());
+    if let Some(owner_ref) = owner_ref {
+        query = query.filter(secrets::Column::OwnerRef.eq(owner_ref));
+    }
+    for s in query.all(db).await? {
+        dependents.push(Dependent {
+            kind: "Secret",
+            id: s.id,
+            name: s.name,
+            namespace: s.namespace,
+            owner: s.owner,
+            owner_ref: s.owner_ref.unwrap_or_default(),
+            created_at: s.created_at.timestamp(),
+        });
+    }
+
+    let mut query = volumes::Entity::find().filter(volumes::Column::OwnerRef.is_not_null());
+    if let Some(owner_ref) = owner_ref {
+        query = query.filter(volumes::Column::OwnerRef.eq(owner_ref));
+    }
+    for v in query.all(db).await? {
+        dependents.push(Dependent {
+            kind: "Volume",
+            id: v.id,
+            name: v.name,
+            namespace: v.namespace,
+            owner: v.owner,
+            owner_ref: v.owner_ref.unwrap_or_default(),
+            created_at: v.created_at.timestamp(),
+        });
+    }
+
+    let mut query = namespaces::Entity::find().filter(namespaces::Column::OwnerRef.is_not_null());
+    if let Some(owner_ref) = owner_ref {
+        query = query.filter(namespaces::Column::OwnerRef.eq(owner_ref));
+    }
+    for n in query.all(db).await? {
+        dependents.push(Dependent {
+            kind: "Namespace",
+            id: n.id,
+            namespace: n.name.clone(),
+            name: n.name,
+            owner: n.owner,
+            owner_ref: n.owner_ref.unwrap_or_default(),
+            created_at: n.created_at.timestamp(),
+        });
+    }
+
+    Ok(dependents)
+}
+
+/// Resources owned by `owner`.
+pub async fn find_dependents(
+    db: &DatabaseConnection,
+    owner: &OwnerRef,
+) -> Result<Vec<Dependent>, DbErr> {
+    find_dependents_by_ref(db, Some(&owner.to_string())).await
+}
+
+/// Whether the resource an `owner_ref` names still exists.
+pub async fn owner_exists(db: &DatabaseConnection, owner: &OwnerRef) -> Result<bool, DbErr> {
+    let count = match owner.kind.as_str() {
+        "Processor" => {
+            processors::Entity::find()
+                .filter(processors::Column::Namespace.eq(&owner.namespace))
+                .filter(processors::Column::Name.eq(&owner.name))
+                .count(db)
+                .await?
+        }
+        "Container" => {
+            containers::Entity::find()
+                .filter(containers::Column::Namespace.eq(&owner.namespace))
+                .filter(containers::Column::Name.eq(&owner.name))
+                .count(db)
+                .await?
+        }
+        "Secret" => {
+            secrets::Entity::find()
+                .filter(secrets::Column::Namespace.eq(&owner.namespace))
+                .filter(secrets::Column::Name.eq(&owner.name))
+                .count(db)
+                .await?
+        }
+        "Volume" => {
+            volumes::Entity::find()
+                .filter(volumes::Column::Namespace.eq(&owner.namespace))
+                .filter(volumes::Column::Name.eq(&owner.name))
+                .count(db)
+                .await?
+        }
+        // Unknown kinds are never parsed, so treat them as present
+        _ => 1,
+    };
+    Ok(count > 0)
+}
+
+/// Every resource whose owner no longer exists.
+pub async fn find_dangling(db: &DatabaseConnection) -> Result<Vec<V1DanglingReference>, DbErr> {
+    let mut exists: HashMap<OwnerRef, bool> = HashMap::new();
+    let mut dangling = Vec::new();
+
+    for dependent in find_dependents_by_ref(db, None).await? {
+        let Some(owner) = OwnerRef::parse(&dependent.owner_ref) else {
+            continue;
+        };
+        let found = match exists.get(&owner) {
+            Some(found) => *found,
+            None => {
+                let found = owner_exists(db, &owner).await?;
+                exists.insert(owner, found);
+                found
+            }
+        };
+        if !found {
+            dangling.push(V1DanglingReference {
+                kind: dependent.kind.to_string(),
+                id: dependent.id,
+                name: dependent.name,
+                namespace: dependent.namespace,
+                owner: dependent.owner,
+                owner_ref: dependent.owner_ref,
+                created_at: dependent.created_at,
+            });
+        }
+    }
+
+    Ok(dangling)
+}
+
+/// Clear the `owner_ref` of everything `owner` owns, so deleting the owner
+/// leaves them in place. Returns the number of resources orphaned.
+pub async fn orphan_dependents(db: &DatabaseConnection, owner: &OwnerRef) -> Result<u64, DbErr> {
+    let owner_ref = owner.to_string();
+    let none: Option<String> = None;
+
+    let mut orphaned = containers::Entity::update_many()
+        .col_expr(containers::Column::OwnerRef, Expr::value(none.clone()))
+        .filter(containers::Column::OwnerRef.eq(&owner_ref))
+        .exec(db)
+        .await?
+        .rows_affected;
+    orphaned += secrets::Entity::update_many()
+        .col_expr(secrets::Column::OwnerRef, Expr::value(none.clone()))
+        .filter(secrets::Column::OwnerRef.eq(&owner_ref))
+        .exec(db)
+        .await?
+        .rows_affected;
+    orphaned += volumes::Entity::update_many()
+        .col_expr(volumes::Column::OwnerRef, Expr::value(none.clone()))
+        .filter(volumes::Column::OwnerRef.eq(&owner_ref))
+        .exec(db)
+        .await?
+        .rows_affected;
+    orphaned += namespaces::Entity::update_many()
+        .col_expr(namespaces::Column::OwnerRef, Expr::value(none))
+        .filter(namespaces::Column::OwnerRef.eq(&owner_ref))
+        .exec(db)
+        .await?
+        .rows_affected;
+
+    if orphaned > 0 {
+        info!("[GC] Orphaned {} dependents of {}", orphaned, owner);
+    }
+    Ok(orphaned)
+}
+
+/// Delete everything `owner` owns, dependents of dependents first. Returns
+/// the number of resources deleted; failures are logged and left for the
+/// collector to retry.
+pub async fn delete_dependents(state: &AppState, owner: &OwnerRef) -> Result<usize, DbErr> {
+    let mut visited = HashSet::new();
+    delete_dependents_inner(state, owner, &mut visited).await
+}
+
+async fn delete_dependents_inner(
+    state: &AppState,
+    owner: &OwnerRef,
+    visited: &mut HashSet<OwnerRef>,
+) -> Result<usize, DbErr> {
+    // Owner references can form a cycle
+    if !visited.insert(owner.clone()) {
+        return Ok(0);
+    }
+
+    let mut deleted = 0;
+    for dependent in find_dependents(&state.db_pool, owner).await? {
+        if let Some(child) = dependent.as_owner() {
+            deleted += Box::pin(delete_dependents_inner(state, &child, visited)).await?;
+        }
+        debug!(
+            "[GC] Deleting {} {} owned by {}",
+            dependent.kind, dependent.name, owner
+        );
+        match delete_resource(state, dependent.kind, &dependent.id).await {
+            Ok(()) => deleted += 1,
+            Err(e) => error!(
+                "[GC] Failed to delete {} {} owned by {}: {}",
+                dependent.kind, dependent.name, owner, e
+            ),
+        }
+    }
+    Ok(deleted)
+}
+
+/// Handle `owner`'s dependents before deleting it: foreground deletes them
+/// and orphan releases them. Background leaves them for
+/// [`spawn_dependent_deletion`] once the owner is gone.
+pub async fn prepare_owner_deletion(
+    state: &AppState,
+    owner: &OwnerRef,
+    policy: PropagationPolicy,
+) -> Result<(), DbErr> {
+    match policy {
+        PropagationPolicy::Foreground => {
+            delete_dependents(state, owner).await?;
+        }
+        PropagationPolicy::Orphan => {
+            orphan_dependents(&state.db_pool, owner).await?;
+        }
+        PropagationPolicy::Background => {}
+    }
+    Ok(())
+}
+
+/// Delete `owner`'s dependents in the background, after the owner itself has
+/// been deleted. Anything left over is picked up by the collector.
+pub fn spawn_dependent_deletion(state: AppState, owner: OwnerRef) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = delete_dependents(&state, &owner).await {
+            error!("[GC] Failed to delete dependents of {}: {}", owner, e);
+        }
+    })
+}
+
+/// Delete resources whose owner is gone, once they are older than the grace
+/// period. Returns the number of resources deleted.
+pub async fn collect_garbage(state: &AppState) -> Result<usize, DbErr> {
+    let cutoff = chrono::Utc::now().timestamp() - SERVER_CONFIG.gc.grace_secs as i64;
+
+    let mut deleted = 0;
+    for dangling in find_dangling(&state.db_pool).await? {
+        if dangling.created_at > cutoff {
+            continue;
+        }
+        warn!(
+            "[GC] {} {} in namespace {} has dangling owner {}, deleting",
+            dangling.kind, dangling.name, dangling.namespace, dangling.owner_ref
+        );
+        match delete_resource(state, &dangling.kind, &dangling.id).await {
+            Ok(()) => deleted += 1,
+            Err(e) => error!(
+                "[GC] Failed to delete {} {}: {}",
+                dangling.kind, dangling.name, e
+            ),
+        }
+    }
+    Ok(deleted)
+}
+
+/// Spawn a background task that periodically collects resources whose owner
+/// has been deleted.
+pub fn spawn_garbage_collector(state: AppState) -> JoinHandle<()> {
+    let interval_secs = SERVER_CONFIG.gc.interval_secs.max(1);
+
+    tokio::spawn(async move {
+        info!(
+            "[GC] Garbage collector started (interval={}s)",
+            interval_secs
+        );
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            match collect_garbage(&state).await {
+                Ok(0) => {}
+                Ok(deleted) => info!("[GC] Collected {} resources", deleted),
+                Err(e) => error!("[GC] Failed to collect garbage: {}", e),
+            }
+        }
+    })
+}
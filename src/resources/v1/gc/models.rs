@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// What happens to a resource's dependents when it is deleted.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PropagationPolicy {
+    /// Delete the dependents first, then the owner
+    Foreground,
+    /// Delete the owner now and collect the dependents afterwards
+    #[default]
+    Background,
+    /// Keep the dependents, clearing their `owner_ref`
+    Orphan,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1DeleteOptions {
+    pub propagation_policy: Option<PropagationPolicy>,
+}
+
+/// A resource whose `owner_ref` points at an owner that no longer exists.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct V1DanglingReference {
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub namespace: String,
+    pub owner: String,
+    pub owner_ref: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1DanglingReferences {
+    pub references: Vec<V1DanglingReference>,
+}
@@ -1,9 +1,14 @@
 pub mod clusters;
 pub mod containers;
+pub mod events;
+pub mod gc;
 pub mod namespaces;
+pub mod policies;
 pub mod processors;
+pub mod roles;
 pub mod secrets;
 pub mod services;
 pub mod volumes;
+pub mod watch;
 
 pub use processors::*;
@@ -0,0 +1,200 @@
+use crate::entities::namespaces;
+use crate::resources::v1::containers::models::{V1ContainerRequest, V1EnvVar};
+use crate::resources::v1::namespaces::models::{V1ContainerDefaults, V1ContainerEnforced};
+use axum::http::StatusCode;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DefaultsError {
+    #[error("{0}")]
+    NotAllowed(String),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl DefaultsError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            DefaultsError::NotAllowed(_) => StatusCode::FORBIDDEN,
+            DefaultsError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Add `vars` to `env` by key. For keys both have, `replace` decides whether
+/// the value from `vars` wins.
+fn merge_env(env: &mut Option<Vec<V1EnvVar>>, vars: &[V1EnvVar], replace: bool) {
+    let env = env.get_or_insert_with(Vec::new);
+    for var in vars {
+        match env.iter_mut().find(|existing| existing.key == var.key) {
+            Some(existing) if replace => *existing = var.clone(),
+            Some(_) => {}
+            None => env.push(var.clone()),
+        }
+    }
+}
+
+/// Fill the fields a container leaves unset from its namespace's defaults.
+/// `env` and `labels` are merged by key, with the container's values winning.
+pub fn merge_container_defaults(request: &mut V1ContainerRequest, defaults: &V1ContainerDefaults) {
+    if let Some(labels) = &defaults.labels {
+        let metadata = request.metadata.get_or_insert_with(Default::default);
+        let merged = metadata.labels.get_or_insert_with(Default::default);
+        for (key, value) in labels {
+            merged.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    if let Some(env) = &defaults.env {
+        merge_env(&mut request.env, env, false);
+    }
+
+    if request.platform.is_none() {
+        request.platform = defaults.platform.clone();
+    }
+    if request.volumes.is_none() {
+        request.volumes = defaults.volumes.clone();
+    }
+    if request.accelerators.is_none() {
+        request.accelerators = defaults.accelerators.clone();
+    }
+    if request.resources.is_none() {
+        request.resources = defaults.resources.clone();
+    }
+    if request.meters.is_none() {
+        request.meters = defaults.meters.clone();
+    }
+    if request.queue.is_none() {
+        request.queue = defaults.queue.clone();
+    }
+    if request.timeout.is_none() {
+        request.timeout = defaults.timeout.clone();
+    }
+    if request.health_check.is_none() {
+        request.health_check = defaults.health_check.clone();
+    }
+    if request.ssh_keys.is_none() {
+        request.ssh_keys = defaults.ssh_keys.clone();
+    }
+    if request.authz.is_none() {
+        request.authz = defaults.authz.clone();
+    }
+    if request.idle.is_none() {
+        request.idle = defaults.idle.clone();
+    }
+}
+
+/// Apply a namespace's enforced values to a container, refusing platforms and
+/// accelerators the namespace does not allow. A container without a platform
+/// is placed on the first allowed one.
+pub fn enforce_container_values(
+    request: &mut V1ContainerRequest,
+    enforced: &V1ContainerEnforced,
+) -> Result<(), DefaultsError> {
+    if let Some(labels) = &enforced.labels {
+        let metadata = request.metadata.get_or_insert_with(Default::default);
+        metadata
+            .labels
+            .get_or_insert_with(Default::default)
+            .extend(labels.clone());
+    }
+    if let Some(env) = &enforced.env {
+        merge_env(&mut request.env, env, true);
+    }
+
+    if let Some(allowed) = &enforced.allowed_platforms {
+        match &request.platform {
+            Some(platform) if !allowed.contains(platform) => {
+                return Err(DefaultsError::NotAllowed(format!(
+                    "Platform '{}' is not allowed in this namespace, allowed platforms are: {}",
+                    platform,
+                    allowed.join(", ")
+                )));
+            }
+            Some(_) => {}
+            None => request.platform = allowed.first().cloned(),
+        }
+    }
+
+    if let Some(allowed) = &enforced.allowed_accelerators {
+        for accelerator in request.accelerators.iter().flatten() {
+            let kind = accelerator
+                .split_once(':')
+                .map_or(accelerator.as_str(), |(_, kind)| kind);
+            if !allowed.iter().any(|a| a == kind) {
+                return Err(DefaultsError::NotAllowed(format!(
+                    "Accelerator '{}' is not allowed in this namespace, allowed accelerators are: {}",
+                    kind,
+                    allowed.join(", ")
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply `namespace`'s container defaults and then its enforced values to a
+/// container about to be created there.
+pub async fn apply_namespace_defaults(
+    db: &DatabaseConnection,
+    namespace: &str,
+    request: &mut V1ContainerRequest,
+) -> Result<(), DefaultsError> {
+    let Some(namespace) = find_namespace(db, namespace).await? else {
+        return Ok(());
+    };
+
+    if let Some(defaults) = namespace.parse_container_defaults() {
+        merge_container_defaults(request, &defaults);
+    }
+    if let Some(enforced) = namespace.parse_container_enforced() {
+        enforce_container_values(request, &enforced)?;
+    }
+    Ok(())
+}
+
+/// Apply only `namespace`'s enforced values, for a container being recreated
+/// from a spec that was already defaulted when it was first created.
+pub async fn apply_namespace_enforced(
+    db: &DatabaseConnection,
+    namespace: &str,
+    request: &mut V1ContainerRequest,
+) -> Result<(), DefaultsError> {
+    match find_namespace(db, namespace)
+        .await?
+        .and_then(|namespace| namespace.parse_container_enforced())
+    {
+        Some(enforced) => enforce_container_values(request, &enforced),
+        None => Ok(()),
+    }
+}
+
+async fn find_namespace(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Result<Option<namespaces::Model>, DbErr> {
+    namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(namespace))
+        .one(db)
+        .await
+}
+
+/// Check enforced values are well formed before saving them.
+pub fn validate_container_enforced(enforced: &V1ContainerEnforced) -> Result<(), String> {
+    if enforced
+        .allowed_platforms
+        .as_ref()
+        .is_some_and(|platforms| platforms.is_empty())
+    {
+        return Err("container_enforced.allowed_platforms must not be empty".to_string());
+    }
+    if enforced
+        .allowed_accelerators
+        .as_ref()
+        .is_some_and(|accelerators| accelerators.is_empty())
+    {
+        return Err("container_enforced.allowed_accelerators must not be empty".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,313 @@
+use crate::entities::{
+    admission_policies, budgets, containers, namespaces, processors, role_bindings, roles, secrets,
+    volumes,
+};
+use crate::mutation::Mutation;
+use crate::resources::v1::containers::base::get_vpn_device_name;
+use crate::resources::v1::containers::factory::platform_factory;
+use crate::resources::v1::namespaces::models::{V1NamespaceResourceRef, V1NamespaceStatus};
+use crate::resources::v1::processors::base::ProcessorPlatform;
+use crate::resources::v1::processors::standard::StandardProcessor;
+use crate::state::{AppState, MessageQueue};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Held by a terminating namespace until every resource in it is deleted.
+pub const RESOURCES_FINALIZER: &str = "nebu.io/resources";
+
+const FINALIZER_INTERVAL_SECS: u64 = 10;
+
+fn resource_ref(kind: &str, id: &str, name: &str) -> V1NamespaceResourceRef {
+    V1NamespaceResourceRef {
+        kind: kind.to_string(),
+        id: id.to_string(),
+        name: name.to_string(),
+    }
+}
+
+/// Everything in a namespace that must be deleted before the namespace can be.
+pub async fn namespace_resources(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Result<Vec<V1NamespaceResourceRef>, DbErr> {
+    let mut resources = Vec::new();
+
+    for p in processors::Entity::find()
+        .filter(processors::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Processor", &p.id, &p.name));
+    }
+    for c in containers::Entity::find()
+        .filter(containers::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Container", &c.id, &c.name));
+    }
+    for s in secrets::Entity::find()
+        .filter(secrets::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Secret", &s.id, &s.name));
+    }
+    for v in volumes::Entity::find()
+        .filter(volumes::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Volume", &v.id, &v.name));
+    }
+    for r in roles::Entity::find()
+        .filter(roles::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Role", &r.id, &r.name));
+    }
+    for b in role_bindings::Entity::find()
+        .filter(role_bindings::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("RoleBinding", &b.id, &b.name));
+    }
+    for b in budgets::Entity::find()
+        .filter(budgets::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("Budget", &b.id, &b.name));
+    }
+    for p in admission_policies::Entity::find()
+        .filter(admission_policies::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+    {
+        resources.push(resource_ref("AdmissionPolicy", &p.id, &p.name));
+    }
+
+    Ok(resources)
+}
+
+/// Mark a namespace as terminating. Safe to call again on a namespace that is
+/// already being deleted.
+pub async fn begin_namespace_deletion(
+    db: &DatabaseConnection,
+    namespace: namespaces::Model,
+) -> Result<namespaces::Model, DbErr> {
+    if namespace.is_terminating() {
+        return Ok(namespace);
+    }
+    info!("[Namespaces] Deleting namespace {}", namespace.name);
+
+    let mut finalizers = namespace.parse_finalizers();
+    if !finalizers.iter().any(|f| f == RESOURCES_FINALIZER) {
+        finalizers.push(RESOURCES_FINALIZER.to_string());
+    }
+
+    let mut active_model: namespaces::ActiveModel = namespace.into();
+    active_model.deletion_started_at = Set(Some(chrono::Utc::now().into()));
+    active_model.finalizers = Set(Some(serde_json::json!(finalizers)));
+    active_model.updated_at = Set(chrono::Utc::now().into());
+    active_model.update(db).await
+}
+
+/// Delete one resource through its controller, so cloud pods, VPN devices
+/// and streams are cleaned up along with the row.
+pub async fn delete_resource(
+    state: &AppState,
+    kind: &str,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let db = &state.db_pool;
+    match kind {
+        "Processor" => match &state.message_queue {
+            MessageQueue::Redis { client } => {
+                StandardProcessor::new(Arc::new(state.clone()))
+                    .delete(id, db, client)
+                    .await?;
+            }
+            _ => {
+                Mutation::delete_processor(db, id.to_string()).await?;
+            }
+        },
+        "Container" => {
+            let Some(container) = containers::Entity::find_by_id(id.to_string())
+                .one(db)
+                .await?
+            else {
+                return Ok(());
+            };
+            let platform = container
+                .platform
+                .clone()
+                .unwrap_or_else(|| "runpod".to_string());
+            platform_factory(platform).delete(&container.id, db).await?;
+
+            match crate::vpn::get_vpn_client() {
+                Ok(client) => {
+                    let device = get_vpn_device_name(&container).await;
+                    if let Err(e) = client.remove_device_by_name(&device).await {
+                        warn!("[Namespaces] Failed to remove VPN device {}: {}", device, e);
+                    }
+                }
+                Err(e) => debug!("[Namespaces] Skipping VPN cleanup: {}", e),
+            }
+
+            // The platform may already have removed the row
+            Mutation::delete_container_if_exists(db, container.id).await?;
+        }
+        "Secret" => {
+            Mutation::delete_secret(db, id.to_string()).await?;
+        }
+        "Volume" => {
+            volumes::Entity::delete_by_id(id.to_string())
+                .exec(db)
+                .await?;
+        }
+        "Role" => {
+            roles::Entity::delete_by_id(id.to_string()).exec(db).await?;
+        }
+        "RoleBinding" => {
+            role_bindings::Entity::delete_by_id(id.to_string())
+                .exec(db)
+                .await?;
+        }
+        "Budget" => {
+            budgets::Entity::delete_by_id(id.to_string())
+                .exec(db)
+                .await?;
+        }
+        "AdmissionPolicy" => {
+            admission_policies::Entity::delete_by_id(id.to_string())
+                .exec(db)
+                .await?;
+        }
+        "Namespace" => {
+            if let Some(namespace) = namespaces::Entity::find_by_id(id.to_string())
+                .one(db)
+                .await?
+            {
+                begin_namespace_deletion(db, namespace).await?;
+            }
+        }
+        kind => return Err(format!("Unknown resource kind '{}'", kind).into()),
+    }
+    Ok(())
+}
+
+/// Run one teardown pass over a terminating namespace and record its
+/// progress. The finalizer is released once nothing is left, and the
+/// namespace row is deleted once no finalizers remain. Returns the updated
+/// namespace, or `None` once it is gone.
+pub async fn finalize_namespace(
+    state: &AppState,
+    namespace: namespaces::Model,
+) -> Result<Option<namespaces::Model>, DbErr> {
+    let db = &state.db_pool;
+    let mut finalizers = namespace.parse_finalizers();
+
+    if finalizers.iter().any(|f| f == RESOURCES_FINALIZER) {
+        // Processors are listed first so they don't replace the containers being deleted
+        let mut failures = Vec::new();
+        for resource in namespace_resources(db, &namespace.name).await? {
+            debug!(
+                "[Namespaces] Deleting {} {} in namespace {}",
+                resource.kind, resource.name, namespace.name
+            );
+            if let Err(e) = delete_resource(state, &resource.kind, &resource.id).await {
+                error!(
+                    "[Namespaces] Failed to delete {} {} in namespace {}: {}",
+                    resource.kind, resource.name, namespace.name, e
+                );
+                failures.push(format!("{} {}: {}", resource.kind, resource.name, e));
+            }
+        }
+
+        let remaining = namespace_resources(db, &namespace.name).await?;
+        if remaining.is_empty() {
+            finalizers.retain(|f| f != RESOURCES_FINALIZER);
+        }
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for resource in &remaining {
+            *counts.entry(resource.kind.clone()).or_default() += 1;
+        }
+        let message = if remaining.is_empty() {
+            "All resources deleted".to_string()
+        } else if failures.is_empty() {
+            format!("Waiting for {} resources to be deleted", remaining.len())
+        } else {
+            format!(
+                "Waiting for {} resources to be deleted; errors: {}",
+                remaining.len(),
+                failures.join("; ")
+            )
+        };
+        let status = V1NamespaceStatus {
+            remaining: Some(counts),
+            message: Some(message),
+            ..Default::default()
+        };
+
+        let mut active_model: namespaces::ActiveModel = namespace.clone().into();
+        active_model.finalizers = Set(Some(serde_json::json!(finalizers)));
+        active_model.deletion_status = Set(serde_json::to_value(&status).ok());
+        active_model.updated_at = Set(chrono::Utc::now().into());
+        let namespace = active_model.update(db).await?;
+
+        if !finalizers.is_empty() {
+            return Ok(Some(namespace));
+        }
+    } else if !finalizers.is_empty() {
+        // Held by something else
+        return Ok(Some(namespace));
+    }
+
+    info!("[Namespaces] Namespace {} deleted", namespace.name);
+    namespaces::Entity::delete_by_id(namespace.id)
+        .exec(db)
+        .await?;
+    Ok(None)
+}
+
+/// Spawn a background task that drives terminating namespaces to deletion.
+pub fn spawn_namespace_finalizer(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(
+            "[Namespaces] Namespace finalizer started (interval={}s)",
+            FINALIZER_INTERVAL_SECS
+        );
+        let mut interval = tokio::time::interval(Duration::from_secs(FINALIZER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let terminating = match namespaces::Entity::find()
+                .filter(namespaces::Column::DeletionStartedAt.is_not_null())
+                .all(&state.db_pool)
+                .await
+            {
+                Ok(terminating) => terminating,
+                Err(e) => {
+                    error!("[Namespaces] Failed to list terminating namespaces: {}", e);
+                    continue;
+                }
+            };
+            for namespace in terminating {
+                let name = namespace.name.clone();
+                if let Err(e) = finalize_namespace(&state, namespace).await {
+                    error!("[Namespaces] Failed to finalize namespace {}: {}", name, e);
+                }
+            }
+        }
+    })
+}
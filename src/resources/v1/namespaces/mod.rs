@@ -1 +1,4 @@
+pub mod defaults;
+pub mod finalizer;
 pub mod models;
+pub mod quota;
@@ -1,4 +1,8 @@
-use crate::models::{V1ResourceMeta, V1ResourceMetaRequest};
+use crate::models::{V1AuthzConfig, V1Meter, V1RateLimit, V1ResourceMeta, V1ResourceMetaRequest};
+use crate::resources::v1::containers::models::{
+    V1ContainerHealthCheck, V1ContainerResources, V1EnvVar, V1IdlePolicy, V1SSHKey,
+};
+use crate::resources::v1::volumes::models::V1VolumePath;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
@@ -6,11 +10,76 @@ pub struct V1Namespace {
     #[serde(default = "default_namespace_kind")]
     pub kind: String,
     pub metadata: V1ResourceMeta,
+    pub rate_limit: Option<V1RateLimit>,
+    pub quota: Option<V1NamespaceQuota>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<V1NamespaceUsage>,
+    pub container_defaults: Option<V1ContainerDefaults>,
+    pub container_enforced: Option<V1ContainerEnforced>,
+    pub status: Option<V1NamespaceStatus>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct V1NamespaceRequest {
     pub metadata: V1NamespaceMetaRequest,
+    pub rate_limit: Option<V1RateLimit>,
+    pub quota: Option<V1NamespaceQuota>,
+    pub container_defaults: Option<V1ContainerDefaults>,
+}
+
+/// Fields filled into every container created in a namespace, including
+/// processor containers. Values set on the container win; `env` and `labels`
+/// are merged by key.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1ContainerDefaults {
+    pub platform: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+    pub env: Option<Vec<V1EnvVar>>,
+    pub volumes: Option<Vec<V1VolumePath>>,
+    pub accelerators: Option<Vec<String>>,
+    pub resources: Option<V1ContainerResources>,
+    pub meters: Option<Vec<V1Meter>>,
+    pub queue: Option<String>,
+    pub timeout: Option<String>,
+    pub health_check: Option<V1ContainerHealthCheck>,
+    pub ssh_keys: Option<Vec<V1SSHKey>>,
+    pub authz: Option<V1AuthzConfig>,
+    pub idle: Option<V1IdlePolicy>,
+}
+
+/// Values set by namespace admins that containers cannot override.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1ContainerEnforced {
+    /// Labels set on every container, replacing any value it gives
+    pub labels: Option<HashMap<String, String>>,
+    /// Env vars set on every container, replacing any value it gives
+    pub env: Option<Vec<V1EnvVar>>,
+    /// Platforms containers may run on; any platform when unset
+    pub allowed_platforms: Option<Vec<String>>,
+    /// Accelerator types containers may request; any type when unset
+    pub allowed_accelerators: Option<Vec<String>>,
+}
+
+/// Limits on what may run in a namespace at once. Unset fields are unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceQuota {
+    /// Max concurrent accelerators by type, e.g. `{"A100_SXM": 8}`
+    pub accelerators: Option<HashMap<String, i32>>,
+    pub max_containers: Option<i32>,
+    pub max_processors: Option<i32>,
+    /// Max processor replicas across the namespace
+    pub max_replicas: Option<i32>,
+    /// Max summed `resource_cost_per_hr` of active containers
+    pub max_cost_per_hr: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceUsage {
+    pub accelerators: HashMap<String, i32>,
+    pub containers: i32,
+    pub processors: i32,
+    pub replicas: i32,
+    pub cost_per_hr: f64,
 }
 
 fn default_namespace_kind() -> String {
@@ -28,3 +97,71 @@ pub struct V1NamespaceMetaRequest {
 pub struct V1Namespaces {
     pub namespaces: Vec<V1Namespace>,
 }
+
+/// `Active`, or `Terminating` while a deletion tears down its resources.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceStatus {
+    pub phase: String,
+    pub deletion_started_at: Option<i64>,
+    pub finalizers: Vec<String>,
+    /// Resources left to delete, by kind
+    pub remaining: Option<HashMap<String, usize>>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceResourceRef {
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceDeletion {
+    pub namespace: String,
+    pub dry_run: bool,
+    pub resources: Vec<V1NamespaceResourceRef>,
+    pub status: Option<V1NamespaceStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceDeleteQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_grant_subject_kind() -> String {
+    "User".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceGrant {
+    pub id: String,
+    pub namespace: String,
+    pub subject: String,
+    pub subject_kind: String,
+    pub role: String,
+    pub created_by: String,
+    pub updated_at: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceGrantRequest {
+    /// A user's email, or an organization id when `subject_kind` is `Org`
+    pub subject: String,
+    #[serde(default = "default_grant_subject_kind")]
+    pub subject_kind: String,
+    pub role: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceGrants {
+    pub grants: Vec<V1NamespaceGrant>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1NamespaceTransferRequest {
+    /// The new owner: a user's email or an organization id
+    pub owner: String,
+}
@@ -0,0 +1,524 @@
+use crate::billing::budget::exhausted_budget;
+use crate::entities::{containers, namespaces, processors};
+use crate::resources::v1::containers::base::ContainerStatus;
+use crate::resources::v1::namespaces::models::{V1NamespaceQuota, V1NamespaceUsage};
+use axum::http::StatusCode;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum QuotaError {
+    #[error("{0}")]
+    Exceeded(String),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl QuotaError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            QuotaError::Exceeded(_) => StatusCode::FORBIDDEN,
+            QuotaError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// What an operation is about to add to a namespace.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaRequest {
+    pub containers: i32,
+    pub processors: i32,
+    pub replicas: i32,
+    /// Accelerator alternatives of each new container, as `count:type`
+    pub accelerators: Vec<String>,
+}
+
+/// Per-namespace locks so this process checks one request per namespace at a time.
+static NAMESPACE_LOCKS: Lazy<DashMap<String, Arc<Mutex<()>>>> = Lazy::new(DashMap::new);
+
+/// Held from a passed quota check until the checked objects have been
+/// created, so concurrent requests can't each pass against the same usage.
+/// Other server instances are held off by a lock on the namespace row, which
+/// Postgres keeps until the lease is dropped.
+#[must_use = "the quota is only reserved while the lease is held"]
+#[derive(Default)]
+pub struct QuotaLease {
+    _local: Option<OwnedMutexGuard<()>>,
+    txn: Option<DatabaseTransaction>,
+}
+
+impl Drop for QuotaLease {
+    fn drop(&mut self) {
+        if let Some(txn) = self.txn.take() {
+            tokio::spawn(async move {
+                if let Err(e) = txn.commit().await {
+                    warn!("Failed to release namespace quota lock: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Take the namespace's quota lock.
+async fn lock_namespace(db: &DatabaseConnection, namespace: &str) -> Result<QuotaLease, DbErr> {
+    let lock = NAMESPACE_LOCKS
+        .entry(namespace.to_string())
+        .or_default()
+        .clone();
+    let local = lock.lock_owned().await;
+
+    let txn = if db.get_database_backend() == DbBackend::Postgres {
+        let txn = db.begin().await?;
+        namespaces::Entity::find()
+            .filter(namespaces::Column::Name.eq(namespace))
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        Some(txn)
+    } else {
+        None
+    };
+
+    Ok(QuotaLease {
+        _local: Some(local),
+        txn,
+    })
+}
+
+/// Parse a `count:type` accelerator.
+fn parse_accelerator(accelerator: &str) -> Option<(String, i32)> {
+    let (count, kind) = accelerator.split_once(':')?;
+    Some((kind.to_string(), count.parse().ok()?))
+}
+
+fn is_active(container: &containers::Model) -> bool {
+    container
+        .parse_status()
+        .ok()
+        .flatten()
+        .and_then(|status| status.status)
+        .and_then(|status| status.parse::<ContainerStatus>().ok())
+        .is_none_or(|status| status.is_active())
+}
+
+/// The accelerators a container holds: the one it was scheduled on, or its
+/// first choice while it waits to be scheduled.
+fn container_accelerators(container: &containers::Model) -> Option<(String, i32)> {
+    let requested = container.accelerators.as_ref()?;
+    let scheduled = container
+        .parse_status()
+        .ok()
+        .flatten()
+        .and_then(|status| status.accelerator);
+    let parsed: Vec<(String, i32)> = requested
+        .iter()
+        .filter_map(|a| parse_accelerator(a))
+        .collect();
+    scheduled
+        .and_then(|kind| parsed.iter().find(|(k, _)| *k == kind).cloned())
+        .or_else(|| parsed.into_iter().next())
+}
+
+/// What is currently running in a namespace.
+pub async fn namespace_usage(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Result<V1NamespaceUsage, DbErr> {
+    let mut usage = V1NamespaceUsage::default();
+
+    let active = containers::Entity::find()
+        .filter(containers::Column::Namespace.eq(namespace))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(is_active);
+    for container in active {
+        usage.containers += 1;
+        if container
+            .owner_ref
+            .as_deref()
+            .is_some_and(|owner_ref| owner_ref.ends_with(".Processor"))
+        {
+            usage.replicas += 1;
+        }
+        if let Some((kind, count)) = container_accelerators(&container) {
+            *usage.accelerators.entry(kind).or_default() += count;
+        }
+        usage.cost_per_hr += container.resource_cost_per_hr.unwrap_or(0.0);
+    }
+
+    usage.processors = processors::Entity::find()
+        .filter(processors::Column::Namespace.eq(namespace))
+        .count(db)
+        .await? as i32;
+
+    Ok(usage)
+}
+
+/// Find the quota set on a namespace, if any.
+pub async fn namespace_quota(
+    db: &DatabaseConnection,
+    namespace: &str,
+) -> Result<Option<V1NamespaceQuota>, DbErr> {
+    Ok(namespaces::Entity::find()
+        .filter(namespaces::Column::Name.eq(namespace))
+        .one(db)
+        .await?
+        .and_then(|namespace| namespace.parse_quota()))
+}
+
+/// Refuse `request` if it would take the namespace over its quota, or if an
+/// exhausted budget covers the namespace.
+///
+/// A new container must fit with every accelerator it lists, since the
+/// platform may pick any of them. Its cost is not known until it is
+/// scheduled, so new containers are refused once the namespace's current
+/// cost reaches the limit.
+///
+/// Keep the returned lease until the request's objects are stored.
+pub async fn check_quota(
+    db: &DatabaseConnection,
+    namespace: &str,
+    request: &QuotaRequest,
+) -> Result<QuotaLease, QuotaError> {
+    check_budget(db, namespace).await?;
+
+    let Some(quota) = namespace_quota(db, namespace).await? else {
+        return Ok(QuotaLease::default());
+    };
+    let lease = lock_namespace(db, namespace).await?;
+    enforce_quota(db, namespace, &quota, request).await?;
+    Ok(lease)
+}
+
+async fn check_budget(db: &DatabaseConnection, namespace: &str) -> Result<(), QuotaError> {
+    match exhausted_budget(db, namespace).await? {
+        Some(budget) => Err(QuotaError::Exceeded(format!(
+            "Budget '{}' covering namespace '{}' is exhausted",
+            budget.name, namespace
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check `request` against `quota` and what the namespace uses now. Call it
+/// holding the namespace lock.
+async fn enforce_quota(
+    db: &DatabaseConnection,
+    namespace: &str,
+    quota: &V1NamespaceQuota,
+    request: &QuotaRequest,
+) -> Result<(), QuotaError> {
+    let usage = namespace_usage(db, namespace).await?;
+
+    let exceeded = |what: &str, used: i32, adding: i32, limit: i32| {
+        QuotaError::Exceeded(format!(
+            "Namespace '{}' quota exceeded: {} {} in use, {} requested, limit is {}",
+            namespace, used, what, adding, limit
+        ))
+    };
+
+    if let Some(limit) = quota.max_containers {
+        if request.containers > 0 && usage.containers + request.containers > limit {
+            return Err(exceeded(
+                "containers",
+                usage.containers,
+                request.containers,
+                limit,
+            ));
+        }
+    }
+    if let Some(limit) = quota.max_processors {
+        if request.processors > 0 && usage.processors + request.processors > limit {
+            return Err(exceeded(
+                "processors",
+                usage.processors,
+                request.processors,
+                limit,
+            ));
+        }
+    }
+    if let Some(limit) = quota.max_replicas {
+        if request.replicas > 0 && usage.replicas + request.replicas > limit {
+            return Err(exceeded(
+                "replicas",
+                usage.replicas,
+                request.replicas,
+                limit,
+            ));
+        }
+    }
+    if let Some(limits) = &quota.accelerators {
+        for (kind, count) in request
+            .accelerators
+            .iter()
+            .filter_map(|a| parse_accelerator(a))
+        {
+            let Some(limit) = limits.get(&kind) else {
+                continue;
+            };
+            let used = usage.accelerators.get(&kind).copied().unwrap_or(0);
+            let adding = count * request.containers.max(1);
+            if used + adding > *limit {
+                return Err(exceeded(
+                    &format!("{} accelerators", kind),
+                    used,
+                    adding,
+                    *limit,
+                ));
+            }
+        }
+    }
+    if let Some(limit) = quota.max_cost_per_hr {
+        if request.containers > 0 && usage.cost_per_hr >= limit {
+            return Err(QuotaError::Exceeded(format!(
+                "Namespace '{}' quota exceeded: running cost is {:.2}/hr, limit is {:.2}/hr",
+                namespace, usage.cost_per_hr, limit
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse growing a processor to `target` replicas if the new replicas would
+/// take its namespace over quota.
+///
+/// Current replicas are counted under the namespace lock, so two scale-ups
+/// can't both be checked against the same count.
+pub async fn check_replicas_quota(
+    db: &DatabaseConnection,
+    processor: &processors::Model,
+    target: i32,
+) -> Result<QuotaLease, QuotaError> {
+    let namespace = &processor.namespace;
+    let lease = lock_namespace(db, namespace).await?;
+
+    let owner_ref = format!("{}.{}.Processor", processor.name, processor.namespace);
+    let current = containers::Entity::find()
+        .filter(containers::Column::OwnerRef.eq(owner_ref))
+        .all(db)
+        .await?
+        .iter()
+        .filter(|container| is_active(container))
+        .count() as i32;
+    if target <= current {
+        return Ok(QuotaLease::default());
+    }
+
+    check_budget(db, namespace).await?;
+    let Some(quota) = namespace_quota(db, namespace).await? else {
+        return Ok(lease);
+    };
+    let accelerators = processor
+        .parse_container()
+        .ok()
+        .flatten()
+        .and_then(|container| container.accelerators)
+        .unwrap_or_default();
+    enforce_quota(
+        db,
+        namespace,
+        &quota,
+        &QuotaRequest {
+            containers: target - current,
+            replicas: target - current,
+            accelerators,
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(lease)
+}
+
+/// Check a quota spec is well formed before saving it.
+pub fn validate_quota(quota: &V1NamespaceQuota) -> Result<(), String> {
+    let limits = [
+        ("max_containers", quota.max_containers),
+        ("max_processors", quota.max_processors),
+        ("max_replicas", quota.max_replicas),
+    ];
+    for (field, limit) in limits {
+        if limit.is_some_and(|limit| limit < 0) {
+            return Err(format!("quota.{} must not be negative", field));
+        }
+    }
+    if quota.max_cost_per_hr.is_some_and(|limit| limit < 0.0) {
+        return Err("quota.max_cost_per_hr must not be negative".to_string());
+    }
+    if let Some(accelerators) = &quota.accelerators {
+        if let Some((kind, _)) = accelerators.iter().find(|(_, limit)| **limit < 0) {
+            return Err(format!("quota.accelerators.{} must not be negative", kind));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::budgets;
+    use sea_orm::sea_query::{ColumnDef, Table};
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, Iterable, Schema, Set};
+
+    async fn quota_db(quota: V1NamespaceQuota) -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for statement in [
+            schema.create_table_from_entity(namespaces::Entity),
+            schema.create_table_from_entity(processors::Entity),
+            schema.create_table_from_entity(budgets::Entity),
+        ] {
+            db.execute(backend.build(&statement)).await.unwrap();
+        }
+        // SQLite has no array columns, so containers gets untyped ones; the
+        // tests never store a container.
+        let mut containers_table = Table::create();
+        containers_table.table(containers::Entity);
+        for column in containers::Column::iter() {
+            containers_table.col(ColumnDef::new(column).text());
+        }
+        db.execute(backend.build(&containers_table)).await.unwrap();
+
+        let mut namespace = namespaces::Model::new(
+            "ns-1".to_string(),
+            "team".to_string(),
+            "owner@example.com".to_string(),
+            "owner@example.com".to_string(),
+            None,
+        )
+        .unwrap();
+        namespace.quota = Some(serde_json::json!(quota));
+        namespaces::ActiveModel::from(namespace)
+            .insert(&db)
+            .await
+            .unwrap();
+        db
+    }
+
+    async fn create_processor(db: &DatabaseConnection, name: &str) -> Result<(), QuotaError> {
+        let _lease = check_quota(
+            db,
+            "team",
+            &QuotaRequest {
+                processors: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
+        // Give a concurrent request the chance to check before this one stores
+        tokio::task::yield_now().await;
+
+        let now = chrono::Utc::now().into();
+        processors::ActiveModel {
+            id: Set(name.to_string()),
+            namespace: Set("team".to_string()),
+            name: Set(name.to_string()),
+            full_name: Set(format!("team/{}", name)),
+            owner: Set("owner@example.com".to_string()),
+            scale: Set(serde_json::Value::Null),
+            stream: Set(format!("team:{}", name)),
+            updated_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_creates_cannot_both_pass_the_quota() {
+        let db = quota_db(V1NamespaceQuota {
+            max_processors: Some(1),
+            ..Default::default()
+        })
+        .await;
+
+        let (first, second) = tokio::join!(
+            create_processor(&db, "first"),
+            create_processor(&db, "second")
+        );
+
+        assert_eq!([&first, &second].iter().filter(|r| r.is_ok()).count(), 1);
+        let refused = if first.is_err() { first } else { second };
+        assert!(matches!(refused, Err(QuotaError::Exceeded(_))));
+        assert_eq!(namespace_usage(&db, "team").await.unwrap().processors, 1);
+    }
+
+    #[tokio::test]
+    async fn requests_within_quota_pass() {
+        let db = quota_db(V1NamespaceQuota {
+            max_processors: Some(2),
+            ..Default::default()
+        })
+        .await;
+
+        create_processor(&db, "first").await.unwrap();
+        create_processor(&db, "second").await.unwrap();
+        assert!(matches!(
+            create_processor(&db, "third").await,
+            Err(QuotaError::Exceeded(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_refuses_requests() {
+        let db = quota_db(V1NamespaceQuota::default()).await;
+        let now = chrono::Utc::now().into();
+        budgets::ActiveModel {
+            id: Set("budget-1".to_string()),
+            name: Set("monthly".to_string()),
+            owner: Set("owner@example.com".to_string()),
+            namespace: Set(Some("team".to_string())),
+            amount: Set(10.0),
+            currency: Set("USD".to_string()),
+            period: Set("monthly".to_string()),
+            thresholds: Set(serde_json::json!([])),
+            action: Set("stop".to_string()),
+            webhook_url: Set(None),
+            spent: Set(12.0),
+            period_start: Set(None),
+            last_threshold: Set(None),
+            exhausted: Set(true),
+            checked_at: Set(None),
+            created_by: Set("owner@example.com".to_string()),
+            updated_at: Set(now),
+            created_at: Set(now),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let err = create_processor(&db, "first").await.unwrap_err();
+        assert!(err.to_string().contains("Budget 'monthly'"));
+    }
+
+    #[test]
+    fn validate_quota_rejects_negative_limits() {
+        assert!(validate_quota(&V1NamespaceQuota {
+            max_containers: Some(-1),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_quota(&V1NamespaceQuota {
+            accelerators: Some([("A100".to_string(), -2)].into_iter().collect()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_quota(&V1NamespaceQuota {
+            max_processors: Some(3),
+            ..Default::default()
+        })
+        .is_ok());
+    }
+}
@@ -0,0 +1,596 @@
+use crate::entities::admission_policies;
+use crate::resources::v1::containers::models::V1ContainerRequest;
+use crate::resources::v1::policies::models::{
+    V1AdmissionPolicySpec, V1PolicyViolation, GLOBAL_POLICY_NAMESPACE, POLICY_KINDS,
+};
+use crate::resources::v1::processors::models::V1ProcessorRequest;
+use crate::resources::v1::secrets::models::V1SecretRequest;
+use crate::resources::v1::volumes::models::V1VolumeRequest;
+use axum::http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder,
+};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Violations kept on each policy for review.
+const MAX_RECORDED_VIOLATIONS: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum AdmissionError {
+    #[error("Admission denied: {}", describe_violations(.0))]
+    Denied(Vec<V1PolicyViolation>),
+    #[error("Policy '{0}' could not be evaluated")]
+    Unevaluable(String),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl AdmissionError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AdmissionError::Denied(_) => StatusCode::FORBIDDEN,
+            AdmissionError::Unevaluable(_) | AdmissionError::Database(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+fn describe_violations(violations: &[V1PolicyViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| format!("policy '{}': {}", v.policy, v.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A resource request being admitted.
+pub enum AdmissionObject<'a> {
+    Container(&'a mut V1ContainerRequest),
+    Processor(&'a mut V1ProcessorRequest),
+    Secret(&'a mut V1SecretRequest),
+    Volume(&'a mut V1VolumeRequest),
+}
+
+impl AdmissionObject<'_> {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AdmissionObject::Container(_) => "Container",
+            AdmissionObject::Processor(_) => "Processor",
+            AdmissionObject::Secret(_) => "Secret",
+            AdmissionObject::Volume(_) => "Volume",
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            AdmissionObject::Container(c) => c.metadata.as_ref()?.name.as_deref(),
+            AdmissionObject::Processor(p) => p.metadata.name.as_deref(),
+            AdmissionObject::Secret(s) => s.metadata.name.as_deref(),
+            AdmissionObject::Volume(v) => v.metadata.name.as_deref(),
+        }
+    }
+
+    fn labels(&mut self) -> &mut Option<HashMap<String, String>> {
+        match self {
+            AdmissionObject::Container(c) => {
+                &mut c.metadata.get_or_insert_with(Default::default).labels
+            }
+            AdmissionObject::Processor(p) => &mut p.metadata.labels,
+            AdmissionObject::Secret(s) => &mut s.metadata.labels,
+            AdmissionObject::Volume(v) => &mut v.metadata.labels,
+        }
+    }
+
+    /// The container spec, for containers and processors that have one.
+    fn container(&mut self) -> Option<&mut V1ContainerRequest> {
+        match self {
+            AdmissionObject::Container(c) => Some(&mut **c),
+            AdmissionObject::Processor(p) => p.container.as_mut(),
+            _ => None,
+        }
+    }
+}
+
+fn applies_to(spec: &V1AdmissionPolicySpec, kind: &str) -> bool {
+    spec.kinds
+        .as_ref()
+        .is_none_or(|kinds| kinds.iter().any(|k| k.eq_ignore_ascii_case(kind)))
+}
+
+/// The image with its registry spelled out, e.g. `ubuntu:22.04` becomes
+/// `docker.io/ubuntu:22.04`.
+fn qualified_image(image: &str) -> String {
+    match image.split_once('/') {
+        Some((registry, _))
+            if registry.contains('.') || registry.contains(':') || registry == "localhost" =>
+        {
+            image.to_string()
+        }
+        _ => format!("docker.io/{}", image),
+    }
+}
+
+/// Apply a policy's mutations to `object`.
+fn mutate(spec: &V1AdmissionPolicySpec, object: &mut AdmissionObject<'_>) {
+    if let Some(labels) = &spec.add_labels {
+        object
+            .labels()
+            .get_or_insert_with(Default::default)
+            .extend(labels.clone());
+    }
+    if let (Some(meters), Some(container)) = (&spec.add_meters, object.container()) {
+        let existing = container.meters.get_or_insert_with(Vec::new);
+        for meter in meters {
+            if !existing.iter().any(|m| m.metric == meter.metric) {
+                existing.push(meter.clone());
+            }
+        }
+    }
+}
+
+/// Check `object` against a policy, describing each check it fails.
+fn check(spec: &V1AdmissionPolicySpec, object: &mut AdmissionObject<'_>) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    if let Some(required) = &spec.required_labels {
+        let labels = object.labels().clone().unwrap_or_default();
+        let missing: Vec<&str> = required
+            .iter()
+            .filter(|key| !labels.contains_key(*key))
+            .map(|key| key.as_str())
+            .collect();
+        if !missing.is_empty() {
+            failures.push(format!("missing required labels: {}", missing.join(", ")));
+        }
+    }
+
+    let Some(container) = object.container() else {
+        return failures;
+    };
+
+    if let Some(allowed) = &spec.allowed_registries {
+        let image = qualified_image(&container.image);
+        if !allowed
+            .iter()
+            .any(|registry| image.starts_with(&format!("{}/", registry.trim_end_matches('/'))))
+        {
+            failures.push(format!(
+                "image '{}' is not from an allowed registry ({})",
+                container.image,
+                allowed.join(", ")
+            ));
+        }
+    }
+
+    if let Some(max) = &spec.max_timeout {
+        match (
+            humantime::parse_duration(max),
+            container.timeout.as_deref().map(humantime::parse_duration),
+        ) {
+            (Ok(_), None) => {
+                failures.push(format!("a timeout of at most {} is required", max));
+            }
+            (Ok(max_timeout), Some(Ok(timeout))) if timeout > max_timeout => {
+                failures.push(format!(
+                    "timeout {} is longer than the maximum of {}",
+                    container.timeout.as_deref().unwrap_or_default(),
+                    max
+                ));
+            }
+            (Ok(_), Some(Err(e))) => {
+                failures.push(format!("timeout could not be parsed: {}", e));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(forbidden) = &spec.forbidden_accelerators {
+        for accelerator in container.accelerators.iter().flatten() {
+            let kind = accelerator
+                .split_once(':')
+                .map_or(accelerator.as_str(), |(_, kind)| kind);
+            if forbidden.iter().any(|f| f == kind) {
+                failures.push(format!("accelerator '{}' is forbidden", kind));
+            }
+        }
+    }
+
+    failures
+}
+
+/// Run the admission policies of `namespace`, and the global ones, over a
+/// resource about to be created or updated there.
+///
+/// Mutations from enforcing policies are applied first, then every policy
+/// checks the result. Violations of enforcing policies reject the request;
+/// dry-run policies only record theirs. Violations are kept on the policy
+/// either way. A stored policy whose spec can't be read rejects every request
+/// in its namespace until it is fixed or deleted.
+pub async fn admit(
+    db: &DatabaseConnection,
+    namespace: &str,
+    object: &mut AdmissionObject<'_>,
+) -> Result<(), AdmissionError> {
+    let kind = object.kind();
+    let mut policies: Vec<(admission_policies::Model, V1AdmissionPolicySpec)> = Vec::new();
+    for policy in admission_policies::Entity::find()
+        .filter(admission_policies::Column::Namespace.is_in([namespace, GLOBAL_POLICY_NAMESPACE]))
+        .order_by_asc(admission_policies::Column::FullName)
+        .all(db)
+        .await?
+    {
+        match policy.parse_spec() {
+            Ok(spec) if applies_to(&spec, kind) => policies.push((policy, spec)),
+            Ok(_) => {}
+            Err(e) => {
+                warn!(
+                    "[Admission] Policy {} has an invalid spec: {}",
+                    policy.full_name, e
+                );
+                return Err(AdmissionError::Unevaluable(policy.full_name));
+            }
+        }
+    }
+    if policies.is_empty() {
+        return Ok(());
+    }
+
+    for (_, spec) in policies.iter().filter(|(_, spec)| !spec.dry_run) {
+        mutate(spec, object);
+    }
+
+    let resource = format!("{}/{}", namespace, object.name().unwrap_or_default());
+    let now = chrono::Utc::now();
+    let mut denied = Vec::new();
+    for (policy, spec) in policies {
+        let violations: Vec<V1PolicyViolation> = check(&spec, object)
+            .into_iter()
+            .map(|message| V1PolicyViolation {
+                policy: policy.full_name.clone(),
+                kind: kind.to_string(),
+                resource: resource.clone(),
+                message,
+                dry_run: spec.dry_run,
+                created_at: now.timestamp(),
+            })
+            .collect();
+        if violations.is_empty() {
+            continue;
+        }
+
+        info!(
+            "[Admission] {} {} violates policy {}{}: {}",
+            kind,
+            resource,
+            policy.full_name,
+            if spec.dry_run { " (dry run)" } else { "" },
+            describe_violations(&violations)
+        );
+        if !spec.dry_run {
+            denied.extend(violations.iter().cloned());
+        }
+        record_violations(db, policy, violations).await?;
+    }
+
+    if denied.is_empty() {
+        Ok(())
+    } else {
+        Err(AdmissionError::Denied(denied))
+    }
+}
+
+/// Keep the newest violations on the policy.
+async fn record_violations(
+    db: &DatabaseConnection,
+    policy: admission_policies::Model,
+    violations: Vec<V1PolicyViolation>,
+) -> Result<(), DbErr> {
+    let mut recorded = violations;
+    recorded.extend(policy.parse_violations());
+    recorded.truncate(MAX_RECORDED_VIOLATIONS);
+
+    let mut active_model: admission_policies::ActiveModel = policy.into();
+    active_model.violations = Set(serde_json::to_value(&recorded).ok());
+    active_model.update(db).await?;
+    Ok(())
+}
+
+/// Check a policy spec is well formed before saving it.
+pub fn validate_policy_spec(spec: &V1AdmissionPolicySpec) -> Result<(), String> {
+    if let Some(kinds) = &spec.kinds {
+        if let Some(kind) = kinds
+            .iter()
+            .find(|kind| !POLICY_KINDS.iter().any(|k| k.eq_ignore_ascii_case(kind)))
+        {
+            return Err(format!(
+                "spec.kinds: unknown kind '{}', expected one of {}",
+                kind,
+                POLICY_KINDS.join(", ")
+            ));
+        }
+    }
+    if let Some(max) = &spec.max_timeout {
+        humantime::parse_duration(max)
+            .map_err(|e| format!("spec.max_timeout is not a valid duration: {}", e))?;
+    }
+    if spec
+        .allowed_registries
+        .as_ref()
+        .is_some_and(|registries| registries.is_empty())
+    {
+        return Err("spec.allowed_registries must not be empty".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{V1Meter, V1ResourceMetaRequest};
+    use sea_orm::{ConnectionTrait, Database, Schema};
+
+    fn container(image: &str) -> V1ContainerRequest {
+        V1ContainerRequest {
+            image: image.to_string(),
+            metadata: Some(V1ResourceMetaRequest {
+                name: Some("web".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn meter(metric: &str) -> V1Meter {
+        V1Meter {
+            cost: Some(0.1),
+            currency: "USD".to_string(),
+            unit: "second".to_string(),
+            metric: metric.to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn policy_db(policies: &[(&str, &str, V1AdmissionPolicySpec)]) -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        db.execute(backend.build(&schema.create_table_from_entity(admission_policies::Entity)))
+            .await
+            .unwrap();
+
+        let now = chrono::Utc::now().into();
+        for (namespace, name, spec) in policies {
+            admission_policies::ActiveModel {
+                id: Set(format!("{}-{}", namespace, name)),
+                namespace: Set(namespace.to_string()),
+                name: Set(name.to_string()),
+                full_name: Set(format!("{}/{}", namespace, name)),
+                owner: Set("owner@example.com".to_string()),
+                spec: Set(serde_json::json!(spec)),
+                violations: Set(None),
+                labels: Set(None),
+                created_by: Set("owner@example.com".to_string()),
+                updated_at: Set(now),
+                created_at: Set(now),
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    async fn violations(db: &DatabaseConnection, id: &str) -> Vec<V1PolicyViolation> {
+        admission_policies::Entity::find_by_id(id.to_string())
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+            .parse_violations()
+    }
+
+    #[test]
+    fn images_without_a_registry_are_on_docker_hub() {
+        assert_eq!(qualified_image("ubuntu:22.04"), "docker.io/ubuntu:22.04");
+        assert_eq!(
+            qualified_image("library/ubuntu"),
+            "docker.io/library/ubuntu"
+        );
+        assert_eq!(qualified_image("ghcr.io/org/app:1"), "ghcr.io/org/app:1");
+        assert_eq!(qualified_image("localhost:5000/app"), "localhost:5000/app");
+    }
+
+    #[test]
+    fn container_checks() {
+        let spec = V1AdmissionPolicySpec {
+            allowed_registries: Some(vec!["ghcr.io/my-org/".to_string()]),
+            max_timeout: Some("1h".to_string()),
+            forbidden_accelerators: Some(vec!["H100_SXM".to_string()]),
+            ..Default::default()
+        };
+
+        let mut allowed = container("ghcr.io/my-org/app:1");
+        allowed.timeout = Some("30m".to_string());
+        allowed.accelerators = Some(vec!["1:A100_SXM".to_string()]);
+        assert!(check(&spec, &mut AdmissionObject::Container(&mut allowed)).is_empty());
+
+        let mut denied = container("ghcr.io/my-org-evil/app:1");
+        denied.timeout = Some("2h".to_string());
+        denied.accelerators = Some(vec!["2:H100_SXM".to_string()]);
+        let failures = check(&spec, &mut AdmissionObject::Container(&mut denied));
+        assert_eq!(failures.len(), 3, "{:?}", failures);
+
+        let mut no_timeout = container("ghcr.io/my-org/app:1");
+        assert_eq!(
+            check(&spec, &mut AdmissionObject::Container(&mut no_timeout)),
+            vec!["a timeout of at most 1h is required"]
+        );
+    }
+
+    #[test]
+    fn required_labels_apply_to_every_kind() {
+        let spec = V1AdmissionPolicySpec {
+            required_labels: Some(vec!["team".to_string()]),
+            ..Default::default()
+        };
+        let mut secret = V1SecretRequest::default();
+        assert_eq!(
+            check(&spec, &mut AdmissionObject::Secret(&mut secret)),
+            vec!["missing required labels: team"]
+        );
+
+        secret.metadata.labels = Some([("team".to_string(), "ml".to_string())].into());
+        assert!(check(&spec, &mut AdmissionObject::Secret(&mut secret)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn enforcing_policies_mutate_then_check() {
+        let db = policy_db(&[
+            (
+                "*",
+                "labels",
+                V1AdmissionPolicySpec {
+                    add_labels: Some([("team".to_string(), "ml".to_string())].into()),
+                    add_meters: Some(vec![meter("runtime")]),
+                    ..Default::default()
+                },
+            ),
+            (
+                "team",
+                "require-team",
+                V1AdmissionPolicySpec {
+                    required_labels: Some(vec!["team".to_string()]),
+                    ..Default::default()
+                },
+            ),
+        ])
+        .await;
+
+        let mut request = container("ubuntu:22.04");
+        request.meters = Some(vec![meter("runtime"), meter("tokens")]);
+        admit(&db, "team", &mut AdmissionObject::Container(&mut request))
+            .await
+            .unwrap();
+
+        let labels = request.metadata.unwrap().labels.unwrap();
+        assert_eq!(labels.get("team").map(String::as_str), Some("ml"));
+        // A meter for the same metric is not added twice
+        assert_eq!(request.meters.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn violations_reject_and_are_recorded() {
+        let db = policy_db(&[(
+            "team",
+            "registries",
+            V1AdmissionPolicySpec {
+                allowed_registries: Some(vec!["ghcr.io/my-org".to_string()]),
+                ..Default::default()
+            },
+        )])
+        .await;
+
+        let mut request = container("ubuntu:22.04");
+        let err = admit(&db, "team", &mut AdmissionObject::Container(&mut request))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+        assert!(err.to_string().contains("policy 'team/registries'"));
+
+        let recorded = violations(&db, "team-registries").await;
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].resource, "team/web");
+        assert!(!recorded[0].dry_run);
+
+        // Policies of other namespaces don't apply
+        admit(&db, "other", &mut AdmissionObject::Container(&mut request))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn dry_run_policies_only_record() {
+        let db = policy_db(&[(
+            "*",
+            "audit-only",
+            V1AdmissionPolicySpec {
+                dry_run: true,
+                kinds: Some(vec!["container".to_string()]),
+                required_labels: Some(vec!["team".to_string()]),
+                add_labels: Some([("audited".to_string(), "true".to_string())].into()),
+                ..Default::default()
+            },
+        )])
+        .await;
+
+        let mut request = container("ubuntu:22.04");
+        admit(&db, "team", &mut AdmissionObject::Container(&mut request))
+            .await
+            .unwrap();
+        assert!(request.metadata.unwrap().labels.is_none());
+
+        let recorded = violations(&db, "*-audit-only").await;
+        assert_eq!(recorded.len(), 1);
+        assert!(recorded[0].dry_run);
+
+        // The policy only covers containers
+        let mut secret = V1SecretRequest::default();
+        admit(&db, "team", &mut AdmissionObject::Secret(&mut secret))
+            .await
+            .unwrap();
+        assert_eq!(violations(&db, "*-audit-only").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unreadable_policies_reject() {
+        let db = policy_db(&[("team", "broken", V1AdmissionPolicySpec::default())]).await;
+        let mut policy: admission_policies::ActiveModel =
+            admission_policies::Entity::find_by_id("team-broken".to_string())
+                .one(&db)
+                .await
+                .unwrap()
+                .unwrap()
+                .into();
+        policy.spec = Set(serde_json::json!({ "kinds": "container" }));
+        policy.update(&db).await.unwrap();
+
+        let mut request = container("ubuntu:22.04");
+        let err = admit(&db, "team", &mut AdmissionObject::Container(&mut request))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            err.to_string(),
+            "Policy 'team/broken' could not be evaluated"
+        );
+    }
+
+    #[test]
+    fn policy_specs_are_validated() {
+        assert!(validate_policy_spec(&V1AdmissionPolicySpec {
+            kinds: Some(vec!["Pod".to_string()]),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_policy_spec(&V1AdmissionPolicySpec {
+            max_timeout: Some("soon".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_policy_spec(&V1AdmissionPolicySpec {
+            allowed_registries: Some(vec![]),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_policy_spec(&V1AdmissionPolicySpec {
+            kinds: Some(vec!["secret".to_string()]),
+            max_timeout: Some("2h".to_string()),
+            ..Default::default()
+        })
+        .is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::models::{V1Meter, V1ResourceMeta, V1ResourceMetaRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Namespace of policies that apply to every namespace.
+pub const GLOBAL_POLICY_NAMESPACE: &str = "*";
+
+/// Kinds admission policies can apply to.
+pub const POLICY_KINDS: &[&str] = &["Container", "Processor", "Secret", "Volume"];
+
+/// Checks and mutations applied when a resource is created or updated.
+///
+/// Mutations (`add_labels`, `add_meters`) are applied first, then every check
+/// runs and each failure is reported as a violation. Container checks apply to
+/// containers and to processor containers.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AdmissionPolicySpec {
+    /// Kinds the policy applies to; all of `POLICY_KINDS` when unset
+    pub kinds: Option<Vec<String>>,
+    /// Record violations without rejecting or mutating anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Registries, optionally with a path, images must come from, e.g.
+    /// `ghcr.io/my-org`. Images without a registry are on `docker.io`
+    pub allowed_registries: Option<Vec<String>>,
+    /// Label keys that must be set
+    pub required_labels: Option<Vec<String>>,
+    /// Longest container `timeout` allowed, e.g. `2h`. Containers must set one
+    pub max_timeout: Option<String>,
+    /// Accelerator types containers may not request
+    pub forbidden_accelerators: Option<Vec<String>>,
+    /// Labels set on every resource, replacing any value it gives
+    pub add_labels: Option<HashMap<String, String>>,
+    /// Meters added to every container that lacks a meter for the same metric
+    pub add_meters: Option<Vec<V1Meter>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1PolicyViolation {
+    /// `namespace/name` of the policy
+    pub policy: String,
+    pub kind: String,
+    /// `namespace/name` of the resource
+    pub resource: String,
+    pub message: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AdmissionPolicy {
+    #[serde(default = "default_admission_policy_kind")]
+    pub kind: String,
+    pub metadata: V1ResourceMeta,
+    pub spec: V1AdmissionPolicySpec,
+    /// The most recent violations, newest first
+    pub violations: Vec<V1PolicyViolation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AdmissionPolicyRequest {
+    /// Use `*` as the namespace for a policy over every namespace
+    pub metadata: V1ResourceMetaRequest,
+    pub spec: V1AdmissionPolicySpec,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1AdmissionPolicies {
+    pub policies: Vec<V1AdmissionPolicy>,
+}
+
+fn default_admission_policy_kind() -> String {
+    "AdmissionPolicy".to_string()
+}
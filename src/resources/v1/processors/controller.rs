@@ -1,5 +1,7 @@
 use crate::entities::processors;
 use crate::query::Query;
+use crate::resources::v1::events::models::EventType;
+use crate::resources::v1::events::recorder::{record_event, ResourceRef};
 use crate::resources::v1::processors::base::ProcessorPlatform;
 use crate::resources::v1::processors::standard::StandardProcessor;
 use crate::state::AppState;
@@ -150,6 +152,14 @@ impl ProcessorController {
                                         "Error reconciling processor {:?}: {:?}",
                                         processor_clone.id, e
                                     );
+                                    record_event(
+                                        &db_pool,
+                                        &ResourceRef::from(&processor_clone),
+                                        EventType::Warning,
+                                        "ReconcileError",
+                                        e.to_string(),
+                                    )
+                                    .await;
                                 }
                             }
 
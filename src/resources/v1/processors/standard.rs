@@ -1,4 +1,5 @@
 use crate::agent::agent::create_agent_key;
+use crate::auth::workload;
 use crate::config::{ClientConfig, SERVER_CONFIG};
 use crate::entities::containers;
 use crate::entities::processors;
@@ -11,6 +12,9 @@ use crate::resources::v1::containers::base::ContainerStatus;
 use crate::resources::v1::containers::factory::platform_factory;
 use crate::resources::v1::containers::models::V1ContainerRequest;
 use crate::resources::v1::containers::models::V1EnvVar;
+use crate::resources::v1::events::models::EventType;
+use crate::resources::v1::events::recorder::{record_event, ResourceRef};
+use crate::resources::v1::namespaces::quota::check_replicas_quota;
 use crate::resources::v1::processors::base::{ProcessorPlatform, ProcessorStatus};
 use crate::resources::v1::processors::models::{
     V1Processor, V1ProcessorRequest, V1ProcessorStatus,
@@ -131,18 +135,21 @@ impl StandardProcessor {
             key: "REDIS_USERNAME".to_string(),
             value: Some(username.clone()),
             secret_name: None,
+            secret_version: None,
         });
 
         env.push(V1EnvVar {
             key: "REDIS_PASSWORD".to_string(),
             value: Some(password.clone()),
             secret_name: None,
+            secret_version: None,
         });
 
         env.push(V1EnvVar {
             key: "REDISCLI_AUTH".to_string(),
             value: Some(password.clone()),
             secret_name: None,
+            secret_version: None,
         });
 
         // Fetch Redis IP from VPN
@@ -176,16 +183,19 @@ impl StandardProcessor {
             key: "REDIS_URL".to_string(),
             value: Some(redis_url),
             secret_name: None,
+            secret_version: None,
         });
         env.push(V1EnvVar {
             key: "REDIS_CONSUMER_GROUP".to_string(),
             value: Some(processor.id.clone()),
             secret_name: None,
+            secret_version: None,
         });
         env.push(V1EnvVar {
             key: "REDIS_STREAM".to_string(),
             value: Some(processor.stream.clone()),
             secret_name: None,
+            secret_version: None,
         });
 
         // Configure labels and metadata
@@ -250,7 +260,7 @@ impl StandardProcessor {
             info!("[Processor Controller] Setting processor {} desired_replicas to {} based on min_replicas", processor.id, target_replicas);
             let mut active_model = processors::ActiveModel::from(processor.clone());
             active_model.desired_replicas = sea_orm::ActiveValue::Set(Some(target_replicas));
-            active_model.update(db).await?;
+            Mutation::update_processor(db, active_model).await?;
             // Note: We use the original processor model below, but the update is now in DB for the watch loop
         } else {
             info!("[Processor Controller] Processor {} desired_replicas already matches min_replicas ({})", processor.id, target_replicas);
@@ -782,6 +792,25 @@ impl StandardProcessor {
                 processor.id, current_replicas, new_replica_target
             );
 
+            if new_replica_target > current_replicas {
+                if let Err(e) = check_replicas_quota(db, &processor, new_replica_target).await {
+                    warn!(
+                        "[Processor Controller] Not scaling up processor {}: {}",
+                        processor.id, e
+                    );
+                    record_event(
+                        db,
+                        &ResourceRef::from(&processor),
+                        EventType::Warning,
+                        "ScaleBlocked",
+                        format!("Not scaling up to {} replicas: {}", new_replica_target, e),
+                    )
+                    .await;
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    return Ok(());
+                }
+            }
+
             // Fetch latest processor model before updating to avoid race conditions
             let latest_processor_model = match processors::Entity::find_by_id(processor.id.clone())
                 .one(db)
@@ -800,7 +829,18 @@ impl StandardProcessor {
 
             let mut active_model = processors::ActiveModel::from(latest_processor_model); // Use latest model
             active_model.desired_replicas = sea_orm::ActiveValue::Set(Some(new_replica_target));
-            let updated_model = active_model.update(db).await?; // This updates the processor table
+            let updated_model = Mutation::update_processor(db, active_model).await?; // This updates the processor table
+            record_event(
+                db,
+                &ResourceRef::from(&updated_model),
+                EventType::Normal,
+                "Scaled",
+                format!(
+                    "Scaled from {} to {} replicas",
+                    current_replicas, new_replica_target
+                ),
+            )
+            .await;
 
             let parsed_container = match updated_model.parse_container() {
                 // Use updated_model
@@ -851,26 +891,12 @@ impl StandardProcessor {
         owner_profile: &V1UserProfile,
         redis_client: &redis::Client,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Get the processor's agent key
-        let secret_name = format!("processor-agent-key-{}", processor.id);
-        let secret_namespace = "root";
-
-        debug!("Fetching secret {}/{}", secret_namespace, secret_name);
-        let secret_model =
-            Query::find_secret_by_namespace_and_name(db, secret_namespace, &secret_name)
-                .await
-                .map_err(|e| format!("Database error fetching secret: {}", e))?
-                .ok_or_else(|| {
-                    format!(
-                        "Secret '{}/{}' not found for processor {}",
-                        secret_namespace, secret_name, processor.id
-                    )
-                })?;
-
-        debug!("Decrypting secret value for processor {}", processor.id);
-        let agent_key = secret_model
-            .decrypt_value()
-            .map_err(|e| format!("Failed to decrypt agent key: {}", e))?;
+        // Without workload tokens, replicas authenticate with the processor's agent key
+        let agent_key = if workload::enabled() {
+            None
+        } else {
+            Some(processor_agent_key(db, processor).await?)
+        };
 
         // Get the customized container with all our environment variables
         let container = self
@@ -887,6 +913,18 @@ impl StandardProcessor {
         let platform = platform_factory(platform_str);
 
         if new_replica_count > current_replicas {
+            // Held until the new replicas are stored
+            let _quota_lease = match check_replicas_quota(db, processor, new_replica_count).await {
+                Ok(lease) => lease,
+                Err(e) => {
+                    warn!(
+                        "[Processor Controller] Not creating replicas for processor {}: {}",
+                        processor.id, e
+                    );
+                    return Ok(());
+                }
+            };
+
             // Create containers for the difference between current and new count
             for replica_index in current_replicas..new_replica_count {
                 let mut request_for_replica = container.clone();
@@ -917,7 +955,7 @@ impl StandardProcessor {
                         owner_profile,
                         &processor.owner,
                         &processor.namespace,
-                        Some(agent_key.clone()),
+                        agent_key.clone(),
                     )
                     .await?;
 
@@ -959,10 +997,7 @@ impl StandardProcessor {
                     "Attempting to delete container DB record for ID: {}",
                     container.id
                 );
-                match crate::entities::containers::Entity::delete_by_id(container.id.clone()) // Clone ID for the call
-                    .exec(db)
-                    .await
-                {
+                match Mutation::delete_container_if_exists(db, container.id.clone()).await {
                     Ok(delete_result) => {
                         // delete_result is sea_orm::DeleteResult
                         if delete_result.rows_affected > 0 {
@@ -987,101 +1022,16 @@ impl StandardProcessor {
 
         Ok(())
     }
-}
 
-impl ProcessorPlatform for StandardProcessor {
-    async fn declare(
+    /// Mint a long-lived agent key for a processor's replicas and store it in
+    /// the root namespace. Only used when workload tokens are not configured.
+    async fn store_processor_agent_key(
         &self,
-        config: &V1ProcessorRequest,
         db: &DatabaseConnection,
         user_profile: &V1UserProfile,
-        owner_id: &str,
-        namespace: &str,
-    ) -> Result<V1Processor, Box<dyn std::error::Error + Send + Sync>> {
-        // 1. Generate a unique ID for the new processor.
-        let new_id = ShortUuid::generate().to_string();
-        let name = config
-            .metadata
-            .name
-            .clone()
-            .unwrap_or(petname::petname(3, "-").unwrap());
-
-        debug!(
-            "Declaring processor {:?} in namespace {:?}",
-            name, namespace
-        );
-
-        // 2. Create an ActiveModel to represent the new record in the database.
-        let processor_am = processors::ActiveModel {
-            // Primary fields
-            id: Set(new_id),
-            name: Set(name.clone()),
-            namespace: Set(namespace.to_string()),
-            full_name: Set(format!("{}/{}", namespace, name)),
-            owner: Set(owner_id.to_string()),
-            created_by: Set(Some(user_profile.email.clone())),
-
-            // Any JSON fields from config (e.g., container & scale).
-            // Adjust as needed depending on your actual request struct.
-            container: Set(config
-                .container
-                .clone()
-                .map(|c| serde_json::to_value(c))
-                .transpose()?),
-            scale: Set(
-                config
-                    .scale
-                    .clone()
-                    .map(serde_json::to_value)
-                    .transpose()? // produces Result<Option<JsonValue>, _>
-                    .unwrap_or(serde_json::Value::Null), // ensure a valid JSON Value
-            ),
-            labels: Set(config
-                .metadata
-                .labels
-                .clone()
-                .map(|l| serde_json::to_value(l))
-                .transpose()?),
-
-            stream: Set(format!("processor:{}:{}", namespace, name)),
-
-            // Typically set an initial status or desired_status to "Defined" or similar.
-            status: Set(Some(serde_json::to_value(V1ProcessorStatus {
-                status: Some(ProcessorStatus::Defined.to_string()),
-                message: None,
-                pressure: None,
-            })?)),
-            desired_status: Set(Some(ProcessorStatus::Running.to_string())),
-
-            // For scale, you might also set min_replicas/max_replicas if that's appropriate.
-            min_replicas: Set(config.min_replicas.clone()),
-            max_replicas: Set(config.max_replicas.clone()),
-
-            // Auto-set timestamps.
-            created_at: Set(Utc::now().into()),
-            updated_at: Set(Utc::now().into()),
-
-            ..Default::default()
-        };
-
-        debug!("Processor ActiveModel: {:?}", processor_am);
-
-        // 3. Insert into the DB.
-        let inserted_model = match processor_am.insert(db).await {
-            Ok(model) => model,
-            Err(e) => {
-                error!("Error inserting processor {:?}: {:?}", name, e);
-                return Err(e.into());
-            }
-        };
-
-        debug!("Inserted processor: {:?}", inserted_model);
-
-        // --- BEGIN: Add Processor Agent Key Creation ---
-        debug!(
-            "Creating agent key for processor {}",
-            inserted_model.id.clone()
-        );
+        processor: &processors::Model,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Creating agent key for processor {}", processor.id);
 
         // Assume a function exists to create the key using user profile
         // We need the auth server URL, user token, desired agent ID, name, and duration.
@@ -1098,8 +1048,8 @@ impl ProcessorPlatform for StandardProcessor {
             .ok_or_else(|| "User profile token is missing".to_string())?;
 
         let agent_key_request = V1CreateAgentKeyRequest {
-            agent_id: format!("processor-{}", inserted_model.id),
-            name: format!("Processor Key for {}", inserted_model.name),
+            agent_id: format!("processor-{}", processor.id),
+            name: format!("Processor Key for {}", processor.name),
             duration: 31536000, // e.g., 1 year
         };
 
@@ -1109,7 +1059,7 @@ impl ProcessorPlatform for StandardProcessor {
                 Err(e) => {
                     error!(
                         "Failed to create agent key for processor {}: {}",
-                        inserted_model.id, e
+                        processor.id, e
                     );
                     return Err(format!("Failed to create agent key for processor: {}", e).into());
                 }
@@ -1120,7 +1070,7 @@ impl ProcessorPlatform for StandardProcessor {
             .ok_or_else(|| "Auth server did not return an agent key".to_string())?;
 
         // Store the processor's agent key as a secret
-        let secret_name = format!("processor-agent-key-{}", inserted_model.id);
+        let secret_name = format!("processor-agent-key-{}", processor.id);
         let secret_namespace = "root";
         let secret_full_name = format!("{}/{}", secret_namespace, secret_name);
 
@@ -1171,7 +1121,7 @@ impl ProcessorPlatform for StandardProcessor {
             secret_namespace.to_string(),      // Namespace for the secret
             user_profile.email.clone(),        // User who created/owns this secret record
             &processor_agent_key,              // The value to encrypt and store
-            Some(inserted_model.id.clone()),   // owner_ref links to the processor
+            Some(processor.id.clone()),        // owner_ref links to the processor
             None,                              // Labels
             None,                              // Expires_at
         )
@@ -1190,56 +1140,17 @@ impl ProcessorPlatform for StandardProcessor {
                 format!("Failed to store processor agent key secret: {}", e)
             })?;
 
-        // Update the processor record with the secret ID
-
-        let v1_processor = match inserted_model.to_v1_processor() {
-            Ok(processor) => processor,
-            Err(e) => {
-                error!(
-                    "Error converting processor {:?} to V1Processor: {:?}",
-                    name, e
-                );
-                return Err(e.into());
-            }
-        };
-
-        debug!("V1 processor: {:?}", v1_processor);
-
-        Ok(v1_processor)
+        Ok(())
     }
 
-    async fn reconcile(
+    /// Fetch the processor owner's profile from the auth server, with the
+    /// processor's agent key.
+    async fn fetch_owner_profile(
         &self,
-        processor: &processors::Model,
         db: &DatabaseConnection,
-        redis_client: &redis::Client,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        debug!(
-            "[DEBUG:standard.rs:reconcile] Entering reconcile for processor {}",
-            processor.id
-        );
-
-        // --- BEGIN: Get Processor's Agent Key and User Profile ---
-        let secret_name = format!("processor-agent-key-{}", processor.id);
-        let secret_namespace = "root"; // As defined in `declare`
-
-        debug!("Fetching secret {}/{}", secret_namespace, secret_name);
-        let secret_model =
-            Query::find_secret_by_namespace_and_name(db, secret_namespace, &secret_name)
-                .await
-                .map_err(|e| format!("Database error fetching secret: {}", e))?
-                .ok_or_else(|| {
-                    format!(
-                        "Secret '{}/{}' not found for processor {}",
-                        secret_namespace, secret_name, processor.id
-                    )
-                })?;
-
-        debug!("Decrypting secret value for processor {}", processor.id);
-        let agent_key = secret_model
-            .decrypt_value()
-            .map_err(|e| format!("Failed to decrypt agent key: {}", e))?;
-
+        processor: &processors::Model,
+    ) -> Result<V1UserProfile, Box<dyn std::error::Error + Send + Sync>> {
+        let agent_key = processor_agent_key(db, processor).await?;
         let client_config = ClientConfig::read()
             .map_err(|e| format!("Failed to read global config: {}", e))?;
         let auth_server = client_config
@@ -1277,6 +1188,165 @@ impl ProcessorPlatform for StandardProcessor {
             .json::<V1UserProfile>()
             .await
             .map_err(|e| format!("Failed to parse user profile response: {}", e))?;
+        Ok(owner_profile)
+    }
+}
+
+/// The agent key stored for a processor by `store_processor_agent_key`.
+async fn processor_agent_key(
+    db: &DatabaseConnection,
+    processor: &processors::Model,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let secret_name = format!("processor-agent-key-{}", processor.id);
+    let secret_namespace = "root";
+
+    debug!("Fetching secret {}/{}", secret_namespace, secret_name);
+    let secret_model = Query::find_secret_by_namespace_and_name(db, secret_namespace, &secret_name)
+        .await
+        .map_err(|e| format!("Database error fetching secret: {}", e))?
+        .ok_or_else(|| {
+            format!(
+                "Secret '{}/{}' not found for processor {}",
+                secret_namespace, secret_name, processor.id
+            )
+        })?;
+
+    debug!("Decrypting secret value for processor {}", processor.id);
+    let agent_key = secret_model
+        .decrypt_value()
+        .map_err(|e| format!("Failed to decrypt agent key: {}", e))?;
+    Ok(agent_key)
+}
+
+impl ProcessorPlatform for StandardProcessor {
+    async fn declare(
+        &self,
+        config: &V1ProcessorRequest,
+        db: &DatabaseConnection,
+        user_profile: &V1UserProfile,
+        owner_id: &str,
+        namespace: &str,
+    ) -> Result<V1Processor, Box<dyn std::error::Error + Send + Sync>> {
+        // 1. Generate a unique ID for the new processor.
+        let new_id = ShortUuid::generate().to_string();
+        let name = config
+            .metadata
+            .name
+            .clone()
+            .unwrap_or(petname::petname(3, "-").unwrap());
+
+        debug!(
+            "Declaring processor {:?} in namespace {:?}",
+            name, namespace
+        );
+
+        // 2. Create an ActiveModel to represent the new record in the database.
+        let processor_am = processors::ActiveModel {
+            // Primary fields
+            id: Set(new_id),
+            name: Set(name.clone()),
+            namespace: Set(namespace.to_string()),
+            full_name: Set(format!("{}/{}", namespace, name)),
+            owner: Set(owner_id.to_string()),
+            created_by: Set(Some(user_profile.email.clone())),
+
+            // Any JSON fields from config (e.g., container & scale).
+            // Adjust as needed depending on your actual request struct.
+            container: Set(config
+                .container
+                .clone()
+                .map(|c| serde_json::to_value(c))
+                .transpose()?),
+            scale: Set(
+                config
+                    .scale
+                    .clone()
+                    .map(serde_json::to_value)
+                    .transpose()? // produces Result<Option<JsonValue>, _>
+                    .unwrap_or(serde_json::Value::Null), // ensure a valid JSON Value
+            ),
+            labels: Set(config
+                .metadata
+                .labels
+                .clone()
+                .map(|l| serde_json::to_value(l))
+                .transpose()?),
+
+            stream: Set(format!("processor:{}:{}", namespace, name)),
+
+            // Typically set an initial status or desired_status to "Defined" or similar.
+            status: Set(Some(serde_json::to_value(V1ProcessorStatus {
+                status: Some(ProcessorStatus::Defined.to_string()),
+                message: None,
+                pressure: None,
+            })?)),
+            desired_status: Set(Some(ProcessorStatus::Running.to_string())),
+
+            // For scale, you might also set min_replicas/max_replicas if that's appropriate.
+            min_replicas: Set(config.min_replicas.clone()),
+            max_replicas: Set(config.max_replicas.clone()),
+
+            // Auto-set timestamps.
+            created_at: Set(Utc::now().into()),
+            updated_at: Set(Utc::now().into()),
+
+            ..Default::default()
+        };
+
+        debug!("Processor ActiveModel: {:?}", processor_am);
+
+        // 3. Insert into the DB.
+        let inserted_model = match Mutation::create_processor(db, processor_am).await {
+            Ok(model) => model,
+            Err(e) => {
+                error!("Error inserting processor {:?}: {:?}", name, e);
+                return Err(e.into());
+            }
+        };
+
+        debug!("Inserted processor: {:?}", inserted_model);
+
+        // Replicas get short-lived workload tokens when those are configured;
+        // otherwise they authenticate with an agent key kept for the processor
+        if !workload::enabled() {
+            self.store_processor_agent_key(db, user_profile, &inserted_model)
+                .await?;
+        }
+
+        let v1_processor = match inserted_model.to_v1_processor() {
+            Ok(processor) => processor,
+            Err(e) => {
+                error!(
+                    "Error converting processor {:?} to V1Processor: {:?}",
+                    name, e
+                );
+                return Err(e.into());
+            }
+        };
+
+        debug!("V1 processor: {:?}", v1_processor);
+
+        Ok(v1_processor)
+    }
+
+    async fn reconcile(
+        &self,
+        processor: &processors::Model,
+        db: &DatabaseConnection,
+        redis_client: &redis::Client,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        debug!(
+            "[DEBUG:standard.rs:reconcile] Entering reconcile for processor {}",
+            processor.id
+        );
+
+        // --- BEGIN: Get Processor's Owner Profile ---
+        let owner_profile = if workload::enabled() {
+            // Replicas get workload tokens, so act for the processor's creator directly
+            workload::delegated_profile(&processor.owner, processor.created_by.as_deref())
+        } else {
+            self.fetch_owner_profile(db, processor).await?
+        };
 
         debug!("Retrieved owner profile: {:?}", owner_profile);
 
@@ -1366,7 +1436,6 @@ impl ProcessorPlatform for StandardProcessor {
         debug!("Deleting processor: {}", id);
         use crate::entities::processors;
         use crate::query::Query;
-        use crate::resources::v1::containers::factory::platform_factory;
         use sea_orm::EntityTrait;
 
         debug!("Finding processor: {}", id);
@@ -1423,82 +1492,8 @@ impl ProcessorPlatform for StandardProcessor {
         }
         // --- END: Delete Redis Stream ---
 
-        // 2) Query containers using the correct owner_ref format
-        let owner_ref_string = format!("{}.{}.Processor", processor.name, processor.namespace);
-        let associated_containers_result =
-            Query::find_containers_by_owner_ref(db, &owner_ref_string).await; // Use the formatted string
-        debug!(
-            "Container query result for processor {} using owner_ref '{}': {:?}",
-            processor.id, owner_ref_string, associated_containers_result
-        );
-        let associated_containers = associated_containers_result?;
-
-        debug!(
-            "Found {} containers referencing processor '{}'",
-            associated_containers.len(),
-            processor.id
-        );
-        if associated_containers.is_empty() {
-            tracing::info!(
-                "No containers found referencing processor '{}'",
-                processor.id
-            );
-        } else {
-            tracing::info!(
-                "Found {} container(s) referencing processor '{}'",
-                associated_containers.len(),
-                processor.id
-            );
-        }
-
-        // 3) We'll remove each container from its own platform:
-        for container in associated_containers {
-            debug!("Deleting container: {}", container.id);
-            // a) Parse the container's intended platform (e.g. "runpod" or "kube")
-            let platform_str = container.platform.clone().unwrap_or("runpod".to_string());
-            // fallback to "runpod" or whichever makes sense
-            debug!("Platform string: {}", platform_str);
-            let platform = platform_factory(platform_str);
-            match platform.delete(&container.id, db).await {
-                Ok(_) => info!(
-                    "Successfully deleted container {} from platform",
-                    container.id
-                ),
-                Err(e) => error!(
-                    "Failed to delete container {} from platform: {}",
-                    container.id, e
-                ),
-            }
-
-            // e) Remove the container record from DB
-            debug!(
-                "Attempting to delete container DB record for ID: {}",
-                container.id
-            );
-            match crate::entities::containers::Entity::delete_by_id(container.id.clone()) // Clone ID for the call
-                .exec(db)
-                .await
-            {
-                Ok(delete_result) => {
-                    // delete_result is sea_orm::DeleteResult
-                    if delete_result.rows_affected > 0 {
-                        info!(
-                            "Successfully deleted container DB record {} ({} row(s) affected).",
-                            container.id, // Original container.id is fine here
-                            delete_result.rows_affected
-                        );
-                    } else {
-                        warn!(
-                            "Container DB record {} not found or already deleted during attempt to delete.",
-                            container.id
-                        );
-                    }
-                }
-                Err(e) => {
-                    error!("Error deleting container DB record {}: {}", container.id, e);
-                }
-            }
-        }
+        // 2) Containers owned by the processor are deleted, or orphaned, by the
+        //    garbage collector according to the caller's propagation policy.
 
         // --- BEGIN: Delete Associated Secret ---
         let secret_name = format!("processor-agent-key-{}", processor.id);
@@ -1542,13 +1537,8 @@ impl ProcessorPlatform for StandardProcessor {
 
         debug!("Deleting processor record: {}", processor.id);
         // 4) Finally, delete the processor record
-        processors::Entity::delete_by_id(processor.id)
-            .exec(db)
-            .await?;
-        tracing::info!(
-            "Successfully deleted processor '{}' and its associated containers.",
-            id
-        );
+        Mutation::delete_processor(db, processor.id).await?;
+        tracing::info!("Successfully deleted processor '{}'.", id);
         Ok(())
     }
 }
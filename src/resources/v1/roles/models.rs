@@ -0,0 +1,117 @@
+use crate::auth::scope::kind_matches;
+use crate::models::{V1ResourceMeta, V1ResourceMetaRequest};
+use serde::{Deserialize, Serialize};
+
+/// Verbs granted by the built-in `viewer` role.
+pub const VIEWER_VERBS: &[&str] = &["get", "list", "logs"];
+
+/// Verbs granted by the built-in `editor` role.
+pub const EDITOR_VERBS: &[&str] = &[
+    "get", "list", "logs", "create", "update", "delete", "send", "scale",
+];
+
+/// Kinds the built-in `editor` role may change. Namespaces, roles and role
+/// bindings are left to admins.
+pub const EDITOR_KINDS: &[&str] = &[
+    "containers",
+    "processors",
+    "secrets",
+    "volumes",
+    "cache",
+    "s3-tokens",
+    "temp-s3-tokens",
+];
+
+/// Grants `verbs` on resource `kinds`; `*` matches any verb or kind.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1PolicyRule {
+    pub verbs: Vec<String>,
+    pub kinds: Vec<String>,
+}
+
+impl V1PolicyRule {
+    pub fn new(verbs: &[&str], kinds: &[&str]) -> Self {
+        Self {
+            verbs: verbs.iter().map(|v| v.to_string()).collect(),
+            kinds: kinds.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    pub fn allows(&self, verb: &str, kind: &str) -> bool {
+        self.verbs.iter().any(|v| v == "*" || v == verb)
+            && self.kinds.iter().any(|k| kind_matches(k, kind))
+    }
+}
+
+/// Rules for the built-in `viewer`, `editor` and `admin` roles.
+pub fn builtin_role_rules(name: &str) -> Option<Vec<V1PolicyRule>> {
+    match name {
+        "viewer" => Some(vec![V1PolicyRule::new(VIEWER_VERBS, &["*"])]),
+        "editor" => Some(vec![
+            V1PolicyRule::new(VIEWER_VERBS, &["*"]),
+            V1PolicyRule::new(EDITOR_VERBS, EDITOR_KINDS),
+        ]),
+        "admin" => Some(vec![V1PolicyRule::new(&["*"], &["*"])]),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1Role {
+    #[serde(default = "default_role_kind")]
+    pub kind: String,
+    pub metadata: V1ResourceMeta,
+    pub rules: Vec<V1PolicyRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RoleRequest {
+    pub metadata: V1ResourceMetaRequest,
+    pub rules: Vec<V1PolicyRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1Roles {
+    pub roles: Vec<V1Role>,
+}
+
+fn default_role_kind() -> String {
+    "Role".to_string()
+}
+
+/// Who a role binding applies to.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RoleSubject {
+    /// `User` (by email), `OrgRole` (members of `org` with role `name`) or
+    /// `ApiKey` (by key id).
+    pub kind: String,
+    pub name: String,
+    /// Organization for `OrgRole` subjects; defaults to the namespace owner.
+    pub org: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RoleBinding {
+    #[serde(default = "default_role_binding_kind")]
+    pub kind: String,
+    pub metadata: V1ResourceMeta,
+    /// Name of a built-in role or a role in the same namespace.
+    pub role: String,
+    pub subjects: Vec<V1RoleSubject>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RoleBindingRequest {
+    pub metadata: V1ResourceMetaRequest,
+    pub role: String,
+    pub subjects: Vec<V1RoleSubject>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1RoleBindings {
+    pub role_bindings: Vec<V1RoleBinding>,
+}
+
+fn default_role_binding_kind() -> String {
+    "RoleBinding".to_string()
+}
@@ -0,0 +1,53 @@
+use crate::config::SERVER_CONFIG;
+use crate::entities::{secret_versions, secrets};
+use crate::mutation::Mutation;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// Delete secrets, and previous secret versions, that expired more than the
+/// grace period ago. Returns the number of secrets deleted.
+pub async fn sweep_expired_secrets(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let cutoff = chrono::Utc::now().timestamp() - SERVER_CONFIG.secret_expiry.grace_secs as i64;
+    let cutoff = cutoff.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+
+    let expired = secrets::Entity::find()
+        .filter(secrets::Column::ExpiresAt.lt(cutoff))
+        .all(db)
+        .await?;
+
+    let mut deleted = 0;
+    for secret in expired {
+        info!(
+            "Deleting secret {} which expired at {:?}",
+            secret.full_name, secret.expires_at
+        );
+        deleted += Mutation::delete_secret(db, secret.id).await?.rows_affected;
+    }
+
+    let versions = secret_versions::Entity::delete_many()
+        .filter(secret_versions::Column::ExpiresAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    if versions.rows_affected > 0 {
+        info!("Deleted {} expired secret versions", versions.rows_affected);
+    }
+
+    Ok(deleted)
+}
+
+/// Spawn a background task that periodically deletes expired secrets.
+pub fn spawn_expiry_sweeper(db: DatabaseConnection) -> JoinHandle<()> {
+    let interval_secs = SERVER_CONFIG.secret_expiry.sweep_interval_secs.max(1);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_expired_secrets(&db).await {
+                error!("Failed to sweep expired secrets: {}", e);
+            }
+        }
+    })
+}
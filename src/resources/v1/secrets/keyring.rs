@@ -0,0 +1,499 @@
+use crate::config::{EncryptionConfig, SERVER_CONFIG};
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, RngCore};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Key id of the original `NEBU_ENCRYPTION_KEY`.
+pub const LEGACY_KEY_ID: &str = "legacy";
+
+/// Upper bound on cached data keys; the cache is cleared when it is reached.
+const MAX_CACHED_DATA_KEYS: usize = 10_000;
+
+static KEYRING: Lazy<Result<Keyring, String>> =
+    Lazy::new(|| Keyring::from_config(&SERVER_CONFIG.encryption));
+
+/// Wraps and unwraps per-secret data keys with key-encryption keys.
+pub trait KeyProvider: Send + Sync {
+    fn wrap(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>, String>;
+    fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Key-encryption keys held in memory.
+pub struct LocalKeyProvider {
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl LocalKeyProvider {
+    fn cipher(&self, key_id: &str) -> Result<Aes256Gcm, String> {
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| format!("Unknown encryption key '{}'", key_id))?;
+        Aes256Gcm::new_from_slice(key).map_err(|e| format!("Failed to create cipher: {}", e))
+    }
+}
+
+impl KeyProvider for LocalKeyProvider {
+    fn wrap(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>, String> {
+        let (ciphertext, nonce) = seal(&self.cipher(key_id)?, data_key)?;
+        Ok([nonce.as_slice(), ciphertext.as_slice()].concat())
+    }
+
+    fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, String> {
+        if wrapped.len() < 12 {
+            return Err("Wrapped data key is too short".to_string());
+        }
+        let (nonce, ciphertext) = wrapped.split_at(12);
+        open(&self.cipher(key_id)?, nonce, ciphertext)
+    }
+}
+
+/// Delegates wrapping to an external command, e.g. a KMS client. It is run as
+/// `<command> wrap|unwrap <key_id>` with the base64 input on stdin and must
+/// print the base64 output.
+pub struct PluginKeyProvider {
+    command: String,
+}
+
+impl PluginKeyProvider {
+    /// The provider API is synchronous, so on a multi-threaded runtime the
+    /// worker hands its other tasks off while the plugin runs.
+    fn run(&self, action: &str, key_id: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.run_blocking(action, key_id, input))
+            }
+            _ => self.run_blocking(action, key_id, input),
+        }
+    }
+
+    fn run_blocking(&self, action: &str, key_id: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        let mut child = Command::new(&self.command)
+            .args([action, key_id])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run KMS plugin: {}", e))?;
+
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| "Failed to open KMS plugin stdin".to_string())?
+            .write_all(BASE64.encode(input).as_bytes())
+            .map_err(|e| format!("Failed to write to KMS plugin: {}", e))?;
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("KMS plugin failed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "KMS plugin failed to {}: {}",
+                action,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        BASE64
+            .decode(String::from_utf8_lossy(&output.stdout).trim())
+            .map_err(|e| format!("Invalid KMS plugin output: {}", e))
+    }
+}
+
+impl KeyProvider for PluginKeyProvider {
+    fn wrap(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>, String> {
+        self.run("wrap", key_id, data_key)
+    }
+
+    fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, String> {
+        self.run("unwrap", key_id, wrapped)
+    }
+}
+
+/// A value encrypted under its own data key, ready to store on a secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedValue {
+    pub encrypted_value: String,
+    pub nonce: String,
+    /// Key-encryption key that wrapped `encrypted_key`.
+    pub key_id: String,
+    /// The wrapped data key, base64 encoded.
+    pub encrypted_key: String,
+}
+
+/// The configured key-encryption keys.
+pub struct Keyring {
+    active_key: String,
+    provider: Box<dyn KeyProvider>,
+    /// Used for secrets written before envelope encryption, which have no key id.
+    legacy_key: Option<[u8; 32]>,
+    /// Unwrapped data keys by (key id, wrapped key), so reads don't go back
+    /// to the provider every time.
+    data_keys: DashMap<(String, String), Vec<u8>>,
+}
+
+impl Keyring {
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self, String> {
+        let legacy_key = config
+            .legacy_key
+            .as_deref()
+            .map(|key| parse_key(LEGACY_KEY_ID, key))
+            .transpose()?;
+
+        if let Some(command) = &config.kms_plugin {
+            let active_key = config
+                .active_key
+                .clone()
+                .ok_or("NEBU_ENCRYPTION_ACTIVE_KEY must be set when using NEBU_KMS_PLUGIN")?;
+            return Ok(Self {
+                active_key,
+                provider: Box::new(PluginKeyProvider {
+                    command: command.clone(),
+                }),
+                legacy_key,
+                data_keys: DashMap::new(),
+            });
+        }
+
+        let mut keys = HashMap::new();
+        for (id, key) in &config.keys {
+            keys.insert(id.clone(), parse_key(id, key)?);
+        }
+        if let Some(legacy_key) = legacy_key {
+            keys.entry(LEGACY_KEY_ID.to_string()).or_insert(legacy_key);
+        }
+
+        let active_key = config
+            .active_key
+            .clone()
+            .or_else(|| config.keys.first().map(|(id, _)| id.clone()))
+            .or_else(|| legacy_key.map(|_| LEGACY_KEY_ID.to_string()))
+            .ok_or(
+                "No encryption keys configured; set NEBU_ENCRYPTION_KEYS or NEBU_ENCRYPTION_KEY",
+            )?;
+        if !keys.contains_key(&active_key) {
+            return Err(format!(
+                "Active encryption key '{}' is not configured",
+                active_key
+            ));
+        }
+
+        Ok(Self {
+            active_key,
+            provider: Box::new(LocalKeyProvider { keys }),
+            legacy_key,
+            data_keys: DashMap::new(),
+        })
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key
+    }
+
+    fn unwrap_data_key(&self, key_id: &str, encrypted_key: &str) -> Result<Vec<u8>, String> {
+        let cache_key = (key_id.to_string(), encrypted_key.to_string());
+        if let Some(data_key) = self.data_keys.get(&cache_key) {
+            return Ok(data_key.clone());
+        }
+        let wrapped = BASE64
+            .decode(encrypted_key)
+            .map_err(|e| format!("Failed to decode data key: {}", e))?;
+        let data_key = self.provider.unwrap(key_id, &wrapped)?;
+        if self.data_keys.len() >= MAX_CACHED_DATA_KEYS {
+            self.data_keys.clear();
+        }
+        self.data_keys.insert(cache_key, data_key.clone());
+        Ok(data_key)
+    }
+
+    /// Encrypt a value under a fresh data key wrapped by the active key.
+    pub fn encrypt(&self, value: &str) -> Result<EncryptedValue, String> {
+        let mut data_key = [0u8; 32];
+        OsRng.fill_bytes(&mut data_key);
+
+        let cipher = Aes256Gcm::new_from_slice(&data_key)
+            .map_err(|e| format!("Failed to create cipher: {}", e))?;
+        let (ciphertext, nonce) = seal(&cipher, value.as_bytes())?;
+        let wrapped = self.provider.wrap(&self.active_key, &data_key)?;
+
+        Ok(EncryptedValue {
+            encrypted_value: BASE64.encode(ciphertext),
+            nonce: BASE64.encode(nonce),
+            key_id: self.active_key.clone(),
+            encrypted_key: BASE64.encode(wrapped),
+        })
+    }
+
+    /// Decrypt a stored value. Secrets without a key id were encrypted
+    /// directly with the legacy key.
+    pub fn decrypt(
+        &self,
+        encrypted_value: &str,
+        nonce: &str,
+        key_id: Option<&str>,
+        encrypted_key: Option<&str>,
+    ) -> Result<String, String> {
+        let data_key = match (key_id, encrypted_key) {
+            (Some(key_id), Some(encrypted_key)) => self.unwrap_data_key(key_id, encrypted_key)?,
+            _ => self
+                .legacy_key
+                .ok_or("Secret was encrypted with NEBU_ENCRYPTION_KEY, which is not set")?
+                .to_vec(),
+        };
+
+        let cipher = Aes256Gcm::new_from_slice(&data_key)
+            .map_err(|e| format!("Failed to create cipher: {}", e))?;
+        let nonce = BASE64
+            .decode(nonce)
+            .map_err(|e| format!("Failed to decode nonce: {}", e))?;
+        let ciphertext = BASE64
+            .decode(encrypted_value)
+            .map_err(|e| format!("Failed to decode ciphertext: {}", e))?;
+        let plaintext = open(&cipher, &nonce, &ciphertext)?;
+
+        String::from_utf8(plaintext)
+            .map_err(|e| format!("Failed to convert decrypted bytes to string: {}", e))
+    }
+
+    /// Re-wrap a secret's data key with the active key, leaving the
+    /// ciphertext untouched. Legacy secrets are re-encrypted under a new data key.
+    pub fn rewrap(
+        &self,
+        encrypted_value: &str,
+        nonce: &str,
+        key_id: Option<&str>,
+        encrypted_key: Option<&str>,
+    ) -> Result<EncryptedValue, String> {
+        match (key_id, encrypted_key) {
+            (Some(key_id), Some(encrypted_key)) => {
+                let data_key = self.unwrap_data_key(key_id, encrypted_key)?;
+                let rewrapped = self.provider.wrap(&self.active_key, &data_key)?;
+                Ok(EncryptedValue {
+                    encrypted_value: encrypted_value.to_string(),
+                    nonce: nonce.to_string(),
+                    key_id: self.active_key.clone(),
+                    encrypted_key: BASE64.encode(rewrapped),
+                })
+            }
+            _ => {
+                let value = self.decrypt(encrypted_value, nonce, None, None)?;
+                self.encrypt(&value)
+            }
+        }
+    }
+}
+
+/// Get the global keyring built from the server's encryption config.
+pub fn keyring() -> Result<&'static Keyring, String> {
+    KEYRING.as_ref().map_err(|e| e.clone())
+}
+
+fn parse_key(id: &str, key: &str) -> Result<[u8; 32], String> {
+    let bytes = if key.len() == 32 {
+        key.as_bytes().to_vec()
+    } else {
+        BASE64
+            .decode(key)
+            .map_err(|_| format!("Encryption key '{}' must be 32 bytes or base64", id))?
+    };
+    bytes
+        .try_into()
+        .map_err(|_| format!("Encryption key '{}' must be exactly 32 bytes", id))
+}
+
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<(Vec<u8>, [u8; 12]), String> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+    Ok((ciphertext, nonce))
+}
+
+fn open(cipher: &Aes256Gcm, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if nonce.len() != 12 {
+        return Err("Invalid nonce length".to_string());
+    }
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| format!("Decryption failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW_KEY: &str = "0123456789abcdef0123456789abcdef";
+
+    fn config(keys: &[(&str, &str)], active_key: Option<&str>) -> EncryptionConfig {
+        EncryptionConfig {
+            keys: keys
+                .iter()
+                .map(|(id, key)| (id.to_string(), key.to_string()))
+                .collect(),
+            active_key: active_key.map(str::to_string),
+            legacy_key: None,
+            kms_plugin: None,
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let keyring = Keyring::from_config(&config(&[("k1", RAW_KEY)], None)).unwrap();
+        let encrypted = keyring.encrypt("hunter2").unwrap();
+
+        assert_eq!(encrypted.key_id, "k1");
+        assert_ne!(encrypted.encrypted_value, BASE64.encode("hunter2"));
+        let decrypted = keyring
+            .decrypt(
+                &encrypted.encrypted_value,
+                &encrypted.nonce,
+                Some(&encrypted.key_id),
+                Some(&encrypted.encrypted_key),
+            )
+            .unwrap();
+        assert_eq!(decrypted, "hunter2");
+    }
+
+    #[test]
+    fn decrypt_rejects_unknown_key_id() {
+        let keyring = Keyring::from_config(&config(&[("k1", RAW_KEY)], None)).unwrap();
+        let encrypted = keyring.encrypt("hunter2").unwrap();
+
+        assert!(keyring
+            .decrypt(
+                &encrypted.encrypted_value,
+                &encrypted.nonce,
+                Some("k2"),
+                Some(&encrypted.encrypted_key),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn rewrap_moves_data_key_to_active_key() {
+        let new_key = BASE64.encode([7u8; 32]);
+        let old = Keyring::from_config(&config(&[("k1", RAW_KEY)], None)).unwrap();
+        let encrypted = old.encrypt("hunter2").unwrap();
+
+        let keyring =
+            Keyring::from_config(&config(&[("k1", RAW_KEY), ("k2", &new_key)], Some("k2")))
+                .unwrap();
+        let rewrapped = keyring
+            .rewrap(
+                &encrypted.encrypted_value,
+                &encrypted.nonce,
+                Some(&encrypted.key_id),
+                Some(&encrypted.encrypted_key),
+            )
+            .unwrap();
+
+        assert_eq!(rewrapped.key_id, "k2");
+        assert_eq!(rewrapped.encrypted_value, encrypted.encrypted_value);
+        assert_eq!(rewrapped.nonce, encrypted.nonce);
+        assert_ne!(rewrapped.encrypted_key, encrypted.encrypted_key);
+
+        // Only the new key is needed to read the rewrapped value.
+        let k2_only = Keyring::from_config(&config(&[("k2", &new_key)], None)).unwrap();
+        let decrypted = k2_only
+            .decrypt(
+                &rewrapped.encrypted_value,
+                &rewrapped.nonce,
+                Some(&rewrapped.key_id),
+                Some(&rewrapped.encrypted_key),
+            )
+            .unwrap();
+        assert_eq!(decrypted, "hunter2");
+    }
+
+    fn legacy_ciphertext(key: &[u8; 32], value: &str) -> (String, String) {
+        let cipher = Aes256Gcm::new_from_slice(key).unwrap();
+        let (ciphertext, nonce) = seal(&cipher, value.as_bytes()).unwrap();
+        (BASE64.encode(ciphertext), BASE64.encode(nonce))
+    }
+
+    #[test]
+    fn legacy_raw_key_decrypts_and_rewraps() {
+        let mut config = config(&[], None);
+        config.legacy_key = Some(RAW_KEY.to_string());
+        let keyring = Keyring::from_config(&config).unwrap();
+        assert_eq!(keyring.active_key_id(), LEGACY_KEY_ID);
+
+        let raw: [u8; 32] = RAW_KEY.as_bytes().try_into().unwrap();
+        let (encrypted_value, nonce) = legacy_ciphertext(&raw, "hunter2");
+        assert_eq!(
+            keyring
+                .decrypt(&encrypted_value, &nonce, None, None)
+                .unwrap(),
+            "hunter2"
+        );
+
+        let rewrapped = keyring
+            .rewrap(&encrypted_value, &nonce, None, None)
+            .unwrap();
+        assert_eq!(rewrapped.key_id, LEGACY_KEY_ID);
+        assert_eq!(
+            keyring
+                .decrypt(
+                    &rewrapped.encrypted_value,
+                    &rewrapped.nonce,
+                    Some(&rewrapped.key_id),
+                    Some(&rewrapped.encrypted_key),
+                )
+                .unwrap(),
+            "hunter2"
+        );
+    }
+
+    #[test]
+    fn legacy_base64_key_decrypts() {
+        let key = [9u8; 32];
+        let mut config = config(&[("k1", RAW_KEY)], None);
+        config.legacy_key = Some(BASE64.encode(key));
+        let keyring = Keyring::from_config(&config).unwrap();
+        assert_eq!(keyring.active_key_id(), "k1");
+
+        let (encrypted_value, nonce) = legacy_ciphertext(&key, "hunter2");
+        assert_eq!(
+            keyring
+                .decrypt(&encrypted_value, &nonce, None, None)
+                .unwrap(),
+            "hunter2"
+        );
+    }
+
+    #[test]
+    fn parse_key_rejects_wrong_length() {
+        assert!(parse_key("k1", "short").is_err());
+        assert!(parse_key("k1", &BASE64.encode([1u8; 16])).is_err());
+        assert_eq!(
+            parse_key("k1", &BASE64.encode([1u8; 32])).unwrap(),
+            [1u8; 32]
+        );
+    }
+
+    #[test]
+    fn unwrapped_data_keys_are_cached() {
+        let keyring = Keyring::from_config(&config(&[("k1", RAW_KEY)], None)).unwrap();
+        let encrypted = keyring.encrypt("hunter2").unwrap();
+
+        for _ in 0..2 {
+            keyring
+                .decrypt(
+                    &encrypted.encrypted_value,
+                    &encrypted.nonce,
+                    Some(&encrypted.key_id),
+                    Some(&encrypted.encrypted_key),
+                )
+                .unwrap();
+        }
+        assert_eq!(keyring.data_keys.len(), 1);
+    }
+}
@@ -1 +1,5 @@
+pub mod expiry;
+pub mod keyring;
 pub mod models;
+pub mod providers;
+pub mod rotation;
@@ -8,6 +8,13 @@ pub struct V1Secret {
     pub metadata: V1ResourceMeta,
     pub value: Option<String>,
     pub expires_at: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<V1SecretSource>,
+    #[serde(default = "default_secret_version")]
+    pub version: i32,
+    /// Set once `expires_at` has passed; expired secrets are not injected
+    #[serde(default)]
+    pub expired: bool,
 }
 
 impl V1Secret {
@@ -25,15 +32,70 @@ fn default_secret_kind() -> String {
     "Secret".to_string()
 }
 
+fn default_secret_version() -> i32 {
+    1
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct V1Secrets {
     pub secrets: Vec<V1Secret>,
 }
 
 /// Request body used for creating or updating a secret
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct V1SecretRequest {
     pub metadata: V1ResourceMetaRequest,
+    /// The secret value; leave empty when `source` is set.
+    #[serde(default)]
     pub value: String,
     pub expires_at: Option<i32>,
+    /// Reference a value held outside nebu instead of storing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<V1SecretSource>,
+}
+
+/// Where an externally stored secret value lives. Paths are relative to the
+/// secret's namespace in the provider.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct V1SecretSource {
+    /// `vault`, `file` or `env`
+    pub provider: String,
+    /// Vault KV path, file or directory path, or env var suffix
+    pub path: String,
+    /// Field within a Vault secret, JSON file or directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// A version of a secret. The value is only returned when reading a single
+/// version.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct V1SecretVersion {
+    pub version: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<V1SecretSource>,
+    pub expires_at: Option<i32>,
+    #[serde(default)]
+    pub expired: bool,
+    /// Whether this is the secret's current version
+    #[serde(default)]
+    pub current: bool,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct V1SecretRollbackRequest {
+    pub version: i32,
+}
+
+/// Result of re-wrapping every secret with the active encryption key
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct V1KeyRotation {
+    pub active_key: String,
+    pub rewrapped: u64,
+    pub unchanged: u64,
+    /// Ids of secrets and secret versions that could not be re-wrapped
+    pub failed: Vec<String>,
 }
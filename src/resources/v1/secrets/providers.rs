@@ -0,0 +1,265 @@
+use crate::config::{SecretProviderConfig, SERVER_CONFIG};
+use crate::entities::secrets;
+use crate::query::Query;
+use crate::resources::v1::secrets::models::V1SecretSource;
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+pub const PROVIDERS: &[&str] = &["vault", "file", "env"];
+
+/// A backend holding secret values outside the nebu database.
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Fetch the value at `path`, relative to `namespace`, optionally picking
+    /// one field out of it.
+    async fn fetch(&self, namespace: &str, path: &str, key: Option<&str>)
+        -> Result<String, String>;
+}
+
+/// HashiCorp Vault KV version 2.
+pub struct VaultProvider {
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+    mount: String,
+}
+
+#[async_trait]
+impl SecretProvider for VaultProvider {
+    async fn fetch(
+        &self,
+        namespace: &str,
+        path: &str,
+        key: Option<&str>,
+    ) -> Result<String, String> {
+        let url = format!(
+            "{}/v1/{}/data/{}/{}",
+            self.addr,
+            self.mount,
+            namespace,
+            path.trim_start_matches('/')
+        );
+        debug!("Fetching secret from Vault: {}", url);
+
+        let mut request = reqwest::Client::new()
+            .get(&url)
+            .header("X-Vault-Token", &self.token);
+        if let Some(vault_namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", vault_namespace);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Vault request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Vault returned {} for {}/{}",
+                response.status(),
+                namespace,
+                path
+            ));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Vault response: {}", e))?;
+        let data = body
+            .pointer("/data/data")
+            .ok_or_else(|| "Vault response has no data".to_string())?;
+        pick_field(data, key)
+    }
+}
+
+/// Files under a root directory, e.g. a mounted Kubernetes secret. A
+/// directory path needs a key naming the file inside it; a file path with a
+/// key is read as a JSON object.
+pub struct FileProvider {
+    root: PathBuf,
+}
+
+#[async_trait]
+impl SecretProvider for FileProvider {
+    async fn fetch(
+        &self,
+        namespace: &str,
+        path: &str,
+        key: Option<&str>,
+    ) -> Result<String, String> {
+        let relative = Path::new(namespace).join(path);
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(format!("Invalid secret file path '{}'", path));
+        }
+        let full_path = self.root.join(relative);
+
+        let metadata = tokio::fs::metadata(&full_path)
+            .await
+            .map_err(|e| format!("Secret file {} not readable: {}", path, e))?;
+        if metadata.is_dir() {
+            let key = key.ok_or_else(|| format!("'{}' is a directory; a key is required", path))?;
+            if key.contains('/') || key.starts_with('.') {
+                return Err(format!("Invalid secret file key '{}'", key));
+            }
+            return read_file(&full_path.join(key)).await;
+        }
+
+        let contents = read_file(&full_path).await?;
+        match key {
+            Some(_) => {
+                let data: Value = serde_json::from_str(&contents)
+                    .map_err(|e| format!("Secret file {} is not JSON: {}", path, e))?;
+                pick_field(&data, key)
+            }
+            None => Ok(contents),
+        }
+    }
+}
+
+/// Environment variables of the server named `<prefix><NAMESPACE>_<PATH>`.
+pub struct EnvProvider {
+    prefix: String,
+}
+
+#[async_trait]
+impl SecretProvider for EnvProvider {
+    async fn fetch(
+        &self,
+        namespace: &str,
+        path: &str,
+        _key: Option<&str>,
+    ) -> Result<String, String> {
+        let name = format!("{}{}_{}", self.prefix, namespace, path)
+            .to_uppercase()
+            .replace(['-', '.', '/'], "_");
+        std::env::var(&name).map_err(|_| format!("Environment variable {} is not set", name))
+    }
+}
+
+/// Build the named provider from the server's configuration.
+pub fn provider_for(
+    name: &str,
+    config: &SecretProviderConfig,
+) -> Result<Box<dyn SecretProvider>, String> {
+    match name {
+        "vault" => {
+            let addr = config
+                .vault_addr
+                .clone()
+                .ok_or("Vault provider is not configured; set VAULT_ADDR")?;
+            let token = config
+                .vault_token
+                .clone()
+                .ok_or("Vault provider is not configured; set VAULT_TOKEN")?;
+            Ok(Box::new(VaultProvider {
+                addr,
+                token,
+                namespace: config.vault_namespace.clone(),
+                mount: config.vault_mount.clone(),
+            }))
+        }
+        "file" => {
+            let root = config
+                .file_root
+                .clone()
+                .ok_or("File provider is not configured; set NEBU_SECRET_FILE_ROOT")?;
+            Ok(Box::new(FileProvider { root: root.into() }))
+        }
+        "env" => Ok(Box::new(EnvProvider {
+            prefix: config.env_prefix.clone(),
+        })),
+        other => Err(format!(
+            "Unknown secret provider '{}', expected one of {}",
+            other,
+            PROVIDERS.join(", ")
+        )),
+    }
+}
+
+/// Check a source can be resolved by a configured provider before saving it.
+pub fn validate_source(source: &V1SecretSource) -> Result<(), String> {
+    provider_for(&source.provider, &SERVER_CONFIG.secret_providers)?;
+    if source.path.trim().is_empty() {
+        return Err("Secret source path is required".to_string());
+    }
+    if source.path.split('/').any(|part| part == "..") {
+        return Err(format!("Invalid secret source path '{}'", source.path));
+    }
+    Ok(())
+}
+
+/// Resolve a secret's value, fetching it from its provider when it is stored
+/// outside nebu. Expired secrets are refused.
+pub async fn resolve_secret(secret: &secrets::Model) -> Result<String, String> {
+    if secret.is_expired() {
+        return Err(format!("Secret {} has expired", secret.full_name));
+    }
+    match secret.parse_source() {
+        Some(source) => fetch_source(&secret.namespace, &source).await,
+        None => secret.decrypt_value(),
+    }
+}
+
+/// Resolve a pinned version of a secret, or its current version when unset.
+pub async fn resolve_secret_version(
+    db: &DatabaseConnection,
+    secret: &secrets::Model,
+    version: Option<i32>,
+) -> Result<String, String> {
+    let version = match version {
+        Some(version) if version != secret.current_version() => version,
+        _ => return resolve_secret(secret).await,
+    };
+
+    let snapshot = Query::find_secret_version(db, &secret.id, version)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Secret {} has no version {}", secret.full_name, version))?;
+    if snapshot.is_expired() {
+        return Err(format!(
+            "Version {} of secret {} has expired",
+            version, secret.full_name
+        ));
+    }
+    match snapshot.parse_source() {
+        Some(source) => fetch_source(&secret.namespace, &source).await,
+        None => snapshot.decrypt_value(),
+    }
+}
+
+async fn fetch_source(namespace: &str, source: &V1SecretSource) -> Result<String, String> {
+    let provider = provider_for(&source.provider, &SERVER_CONFIG.secret_providers)?;
+    provider
+        .fetch(namespace, &source.path, source.key.as_deref())
+        .await
+}
+
+async fn read_file(path: &Path) -> Result<String, String> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read secret file: {}", e))?;
+    Ok(contents.trim_end_matches('\n').to_string())
+}
+
+/// Pick `key` out of a JSON object, or its only field when no key is given.
+fn pick_field(data: &Value, key: Option<&str>) -> Result<String, String> {
+    let value = match key {
+        Some(key) => data
+            .get(key)
+            .ok_or_else(|| format!("Secret has no field '{}'", key))?,
+        None => match data.as_object() {
+            Some(fields) if fields.len() == 1 => fields.values().next().unwrap_or(&Value::Null),
+            Some(_) => return Err("Secret has several fields; a key is required".to_string()),
+            None => data,
+        },
+    };
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
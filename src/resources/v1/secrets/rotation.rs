@@ -0,0 +1,133 @@
+use crate::entities::{secret_versions, secrets};
+use crate::resources::v1::secrets::keyring::keyring;
+use crate::resources::v1::secrets::models::V1KeyRotation;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+};
+use tracing::{error, info};
+
+const BATCH_SIZE: u64 = 100;
+
+/// Re-wrap every secret's data key, and those of its previous versions, with
+/// the active key-encryption key.
+///
+/// Runs online: rows are updated one at a time and only if they have not
+/// changed since they were read, and every configured key stays readable, so
+/// servers keep serving secrets throughout. Once it reports no failures the
+/// old keys can be removed from the configuration.
+pub async fn rotate_secret_keys(db: &DatabaseConnection) -> Result<V1KeyRotation, DbErr> {
+    let keyring = keyring().map_err(DbErr::Custom)?;
+    let active_key = keyring.active_key_id().to_string();
+    let mut report = V1KeyRotation {
+        active_key: active_key.clone(),
+        ..Default::default()
+    };
+
+    let mut pages = secrets::Entity::find()
+        .order_by_asc(secrets::Column::Id)
+        .paginate(db, BATCH_SIZE);
+
+    while let Some(batch) = pages.fetch_and_next().await? {
+        for secret in batch {
+            if secret.key_id.as_deref() == Some(active_key.as_str()) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let rewrapped = match keyring.rewrap(
+                &secret.encrypted_value,
+                &secret.nonce,
+                secret.key_id.as_deref(),
+                secret.encrypted_key.as_deref(),
+            ) {
+                Ok(rewrapped) => rewrapped,
+                Err(e) => {
+                    error!("Failed to re-wrap secret {}: {}", secret.full_name, e);
+                    report.failed.push(secret.id);
+                    continue;
+                }
+            };
+
+            let result = secrets::Entity::update_many()
+                .col_expr(
+                    secrets::Column::EncryptedValue,
+                    Expr::value(rewrapped.encrypted_value),
+                )
+                .col_expr(secrets::Column::Nonce, Expr::value(rewrapped.nonce))
+                .col_expr(secrets::Column::KeyId, Expr::value(rewrapped.key_id))
+                .col_expr(
+                    secrets::Column::EncryptedKey,
+                    Expr::value(rewrapped.encrypted_key),
+                )
+                .filter(secrets::Column::Id.eq(&secret.id))
+                .filter(secrets::Column::EncryptedValue.eq(&secret.encrypted_value))
+                .filter(secrets::Column::Nonce.eq(&secret.nonce))
+                .exec(db)
+                .await?;
+
+            // A concurrent write re-encrypted the secret with the active key already
+            if result.rows_affected == 0 {
+                report.unchanged += 1;
+            } else {
+                report.rewrapped += 1;
+            }
+        }
+    }
+
+    // Previous versions never change once written
+    let mut pages = secret_versions::Entity::find()
+        .order_by_asc(secret_versions::Column::Id)
+        .paginate(db, BATCH_SIZE);
+
+    while let Some(batch) = pages.fetch_and_next().await? {
+        for version in batch {
+            if version.key_id.as_deref() == Some(active_key.as_str()) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let rewrapped = match keyring.rewrap(
+                &version.encrypted_value,
+                &version.nonce,
+                version.key_id.as_deref(),
+                version.encrypted_key.as_deref(),
+            ) {
+                Ok(rewrapped) => rewrapped,
+                Err(e) => {
+                    error!("Failed to re-wrap secret version {}: {}", version.id, e);
+                    report.failed.push(version.id);
+                    continue;
+                }
+            };
+
+            secret_versions::Entity::update_many()
+                .col_expr(
+                    secret_versions::Column::EncryptedValue,
+                    Expr::value(rewrapped.encrypted_value),
+                )
+                .col_expr(secret_versions::Column::Nonce, Expr::value(rewrapped.nonce))
+                .col_expr(
+                    secret_versions::Column::KeyId,
+                    Expr::value(rewrapped.key_id),
+                )
+                .col_expr(
+                    secret_versions::Column::EncryptedKey,
+                    Expr::value(rewrapped.encrypted_key),
+                )
+                .filter(secret_versions::Column::Id.eq(&version.id))
+                .exec(db)
+                .await?;
+            report.rewrapped += 1;
+        }
+    }
+
+    info!(
+        "Re-wrapped {} secrets with key {} ({} unchanged, {} failed)",
+        report.rewrapped,
+        active_key,
+        report.unchanged,
+        report.failed.len()
+    );
+    Ok(report)
+}
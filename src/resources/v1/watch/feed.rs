@@ -0,0 +1,410 @@
+use crate::auth::scope::canonical_kind;
+use crate::config::SERVER_CONFIG;
+use crate::entities::{containers, namespace_grants, processors};
+use crate::models::V1UserProfile;
+use crate::rbac::{self, Principal};
+use crate::resources::v1::watch::models::{ChangeType, V1WatchEvent, V1WatchQuery};
+use crate::state::MessageQueue;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::channel::mpsc;
+use futures::SinkExt;
+use once_cell::sync::{Lazy, OnceCell};
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamRangeReply, StreamReadReply};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, error, warn};
+
+/// Redis stream holding the change feed. Entry ids are the resume tokens.
+const CHANGE_STREAM: &str = "nebu:changes";
+
+/// How long one read waits for new changes before checking the client is
+/// still connected.
+const READ_BLOCK_MS: u64 = 15_000;
+
+/// Changes read from the feed at once.
+const READ_COUNT: usize = 100;
+
+/// Token of an empty feed.
+const EMPTY_FEED_TOKEN: &str = "0-0";
+
+static FEED_CLIENT: OnceCell<Arc<redis::Client>> = OnceCell::new();
+
+/// Shared connection changes are published on, reopened after an error.
+static PUBLISH_CONN: Lazy<Mutex<Option<MultiplexedConnection>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("Watching requires the Redis message queue")]
+    Unavailable,
+    #[error(
+        "Resume token '{0}' is no longer in the change feed; list again and start a new watch"
+    )]
+    Expired(String),
+    #[error("Invalid resume token '{0}'")]
+    InvalidToken(String),
+    #[error("Change feed error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl WatchError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            WatchError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            WatchError::Expired(_) => StatusCode::GONE,
+            WatchError::InvalidToken(_) => StatusCode::BAD_REQUEST,
+            WatchError::Redis(_) | WatchError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Publish changes to the message queue's Redis, so watches on every server
+/// replica see them. Without Redis, changes are dropped and watches refused.
+pub fn init_change_feed(message_queue: &MessageQueue) {
+    match message_queue {
+        MessageQueue::Redis { client } => {
+            let _ = FEED_CLIENT.set(client.clone());
+        }
+        MessageQueue::Kafka { .. } => {
+            warn!("[Watch] The change feed needs Redis; watches are disabled");
+        }
+    }
+}
+
+/// Append a container change to the feed.
+pub async fn publish_container_change(change_type: ChangeType, container: &containers::Model) {
+    match container.to_v1_container() {
+        Ok(object) => {
+            publish_change(
+                change_type,
+                "Container",
+                &container.namespace,
+                &container.owner,
+                serde_json::to_value(object).unwrap_or_default(),
+            )
+            .await
+        }
+        Err(e) => error!(
+            "[Watch] Failed to convert container {} for the change feed: {}",
+            container.id, e
+        ),
+    }
+}
+
+/// Append a processor change to the feed.
+pub async fn publish_processor_change(change_type: ChangeType, processor: &processors::Model) {
+    match processor.to_v1_processor() {
+        Ok(object) => {
+            publish_change(
+                change_type,
+                "Processor",
+                &processor.namespace,
+                &processor.owner,
+                serde_json::to_value(object).unwrap_or_default(),
+            )
+            .await
+        }
+        Err(e) => error!(
+            "[Watch] Failed to convert processor {} for the change feed: {}",
+            processor.id, e
+        ),
+    }
+}
+
+/// Failures are logged, since the change itself is already saved.
+async fn publish_change(
+    change_type: ChangeType,
+    kind: &str,
+    namespace: &str,
+    owner: &str,
+    object: Value,
+) {
+    let Some(client) = FEED_CLIENT.get() else {
+        return;
+    };
+
+    let mut conn_slot = PUBLISH_CONN.lock().await;
+    let mut conn = match conn_slot.as_ref() {
+        Some(conn) => conn.clone(),
+        None => match client.get_multiplexed_async_connection().await {
+            Ok(conn) => {
+                *conn_slot = Some(conn.clone());
+                conn
+            }
+            Err(e) => {
+                error!("[Watch] Failed to connect to the change feed: {}", e);
+                return;
+            }
+        },
+    };
+    drop(conn_slot);
+
+    let result = redis::cmd("XADD")
+        .arg(CHANGE_STREAM)
+        .arg("MAXLEN")
+        .arg("~")
+        .arg(SERVER_CONFIG.watch.history)
+        .arg("*")
+        .arg("type")
+        .arg(change_type.to_string())
+        .arg("kind")
+        .arg(kind)
+        .arg("namespace")
+        .arg(namespace)
+        .arg("owner")
+        .arg(owner)
+        .arg("object")
+        .arg(object.to_string())
+        .query_async::<String>(&mut conn)
+        .await;
+
+    if let Err(e) = result {
+        error!(
+            "[Watch] Failed to publish {} {} change: {}",
+            change_type, kind, e
+        );
+        *PUBLISH_CONN.lock().await = None;
+    }
+}
+
+/// Parse a stream id `<ms>-<seq>` so ids can be compared.
+fn parse_token(token: &str) -> Option<(u64, u64)> {
+    let (ms, seq) = token.split_once('-')?;
+    Some((ms.parse().ok()?, seq.parse().ok()?))
+}
+
+/// The token to resume a watch from: `resume_from`, or the `Last-Event-ID`
+/// header that reconnecting SSE clients send.
+pub fn resume_token(query: &V1WatchQuery, headers: &HeaderMap) -> Option<String> {
+    query.resume_from.clone().or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    })
+}
+
+/// A watch of one kind for one user, positioned in the feed.
+pub struct Watch {
+    client: Arc<redis::Client>,
+    db: DatabaseConnection,
+    user_profile: V1UserProfile,
+    api_key_id: Option<String>,
+    kind: &'static str,
+    owners: Vec<String>,
+    shared_namespaces: Vec<String>,
+    start: String,
+    resumed: bool,
+}
+
+impl Watch {
+    /// Start watching `kind` as `principal`. Without `resume_from` the watch
+    /// starts at the end of the feed and should be sent the current list; with
+    /// it, the watch continues after that token if it is still in the feed.
+    ///
+    /// Start the watch before listing, so no change made in between is lost.
+    pub async fn start(
+        db: &DatabaseConnection,
+        principal: Principal<'_>,
+        kind: &'static str,
+        resume_from: Option<String>,
+    ) -> Result<Self, WatchError> {
+        let client = FEED_CLIENT.get().cloned().ok_or(WatchError::Unavailable)?;
+        let mut conn = client.get_multiplexed_async_connection().await?;
+
+        let (start, resumed) = match resume_from {
+            Some(token) => {
+                let position =
+                    parse_token(&token).ok_or_else(|| WatchError::InvalidToken(token.clone()))?;
+                let oldest: StreamRangeReply = redis::cmd("XRANGE")
+                    .arg(CHANGE_STREAM)
+                    .arg("-")
+                    .arg("+")
+                    .arg("COUNT")
+                    .arg(1)
+                    .query_async(&mut conn)
+                    .await?;
+                // Changes after the token may have been trimmed from the feed
+                if let Some(oldest) = oldest.ids.first().and_then(|id| parse_token(&id.id)) {
+                    if position < oldest {
+                        return Err(WatchError::Expired(token));
+                    }
+                }
+                (token, true)
+            }
+            None => {
+                let latest: StreamRangeReply = redis::cmd("XREVRANGE")
+                    .arg(CHANGE_STREAM)
+                    .arg("+")
+                    .arg("-")
+                    .arg("COUNT")
+                    .arg(1)
+                    .query_async(&mut conn)
+                    .await?;
+                let start = latest
+                    .ids
+                    .first()
+                    .map(|id| id.id.clone())
+                    .unwrap_or_else(|| EMPTY_FEED_TOKEN.to_string());
+                (start, false)
+            }
+        };
+
+        let user_profile = principal.user_profile;
+        let mut owners: Vec<String> = user_profile
+            .organizations
+            .as_ref()
+            .map(|orgs| orgs.keys().cloned().collect())
+            .unwrap_or_default();
+        owners.push(user_profile.email.clone());
+
+        // Grants are read once; a watch started before a namespace was shared
+        // does not see it
+        let shared_namespaces = namespace_grants::Entity::find()
+            .filter(namespace_grants::Column::Subject.is_in(owners.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|grant| grant.namespace)
+            .collect();
+
+        Ok(Self {
+            client,
+            db: db.clone(),
+            user_profile: user_profile.clone(),
+            api_key_id: principal.api_key_id.map(|id| id.to_string()),
+            kind,
+            owners,
+            shared_namespaces,
+            start,
+            resumed,
+        })
+    }
+
+    /// Whether the watcher may see a change: it must be of the watched kind,
+    /// in a namespace the watcher owns or shares, and one RBAC lets it `list`
+    /// the kind in. RBAC decisions are kept in `decisions` by namespace.
+    async fn is_visible(
+        &self,
+        entry: &StreamId,
+        decisions: &mut HashMap<String, bool>,
+    ) -> Result<bool, DbErr> {
+        let field = |name: &str| entry.get::<String>(name).unwrap_or_default();
+        let namespace = field("namespace");
+        if field("kind") != self.kind
+            || !(self.owners.contains(&field("owner"))
+                || self.shared_namespaces.contains(&namespace))
+        {
+            return Ok(false);
+        }
+
+        if let Some(allowed) = decisions.get(&namespace) {
+            return Ok(*allowed);
+        }
+        let principal = Principal::new(&self.user_profile, self.api_key_id.as_deref());
+        let kind = canonical_kind(&self.kind.to_lowercase()).to_string();
+        let allowed = rbac::authorize(&self.db, principal, "list", &kind, &namespace).await?;
+        decisions.insert(namespace, allowed);
+        Ok(allowed)
+    }
+
+    /// Stream the watch as server-sent events: `current` as `ADDED`, unless
+    /// the watch was resumed, then every change from the feed. Each event's
+    /// SSE id is its resume token.
+    pub fn into_response(self, current: Vec<Value>) -> Response {
+        let (mut sender, receiver) = mpsc::channel::<Result<Event, Infallible>>(READ_COUNT);
+
+        tokio::spawn(async move {
+            if !self.resumed {
+                for object in current {
+                    let event = V1WatchEvent {
+                        change_type: ChangeType::Added,
+                        object,
+                        resume_token: self.start.clone(),
+                    };
+                    if sender.send(Ok(sse_event(&event))).await.is_err() {
+                        return;
+                    }
+                }
+            }
+
+            if let Err(e) = self.follow(&mut sender).await {
+                warn!("[Watch] {} watch ended: {}", self.kind, e);
+            }
+        });
+
+        Sse::new(receiver)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    }
+
+    /// Send changes from the feed until the client goes away.
+    async fn follow(
+        &self,
+        sender: &mut mpsc::Sender<Result<Event, Infallible>>,
+    ) -> Result<(), WatchError> {
+        // Blocking reads get their own connection
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut last_id = self.start.clone();
+
+        while !sender.is_closed() {
+            let reply: Option<StreamReadReply> = redis::cmd("XREAD")
+                .arg("COUNT")
+                .arg(READ_COUNT)
+                .arg("BLOCK")
+                .arg(READ_BLOCK_MS)
+                .arg("STREAMS")
+                .arg(CHANGE_STREAM)
+                .arg(&last_id)
+                .query_async(&mut conn)
+                .await?;
+
+            // Roles are checked again for every batch, so revoked bindings
+            // take effect on open watches
+            let mut decisions = HashMap::new();
+            for entry in reply.into_iter().flat_map(|r| r.keys).flat_map(|k| k.ids) {
+                last_id = entry.id.clone();
+                if !self.is_visible(&entry, &mut decisions).await? {
+                    continue;
+                }
+
+                let change_type = entry
+                    .get::<String>("type")
+                    .and_then(|t| ChangeType::from_str(&t).ok());
+                let object = entry
+                    .get::<String>("object")
+                    .and_then(|o| serde_json::from_str(&o).ok());
+                let (Some(change_type), Some(object)) = (change_type, object) else {
+                    debug!("[Watch] Skipping malformed change {}", entry.id);
+                    continue;
+                };
+
+                let event = V1WatchEvent {
+                    change_type,
+                    object,
+                    resume_token: entry.id.clone(),
+                };
+                if sender.send(Ok(sse_event(&event))).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sse_event(event: &V1WatchEvent) -> Event {
+    Event::default()
+        .id(event.resume_token.clone())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
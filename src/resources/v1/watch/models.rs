@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// What happened to a watched resource.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChangeType {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeType::Added => write!(f, "ADDED"),
+            ChangeType::Modified => write!(f, "MODIFIED"),
+            ChangeType::Deleted => write!(f, "DELETED"),
+        }
+    }
+}
+
+impl FromStr for ChangeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ADDED" => Ok(ChangeType::Added),
+            "MODIFIED" => Ok(ChangeType::Modified),
+            "DELETED" => Ok(ChangeType::Deleted),
+            _ => Err(format!("Unknown change type: {}", s)),
+        }
+    }
+}
+
+/// One change streamed to a watch. `object` is the resource as the list
+/// endpoint returns it; for `DELETED` it is the last state before deletion.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct V1WatchEvent {
+    #[serde(rename = "type")]
+    pub change_type: ChangeType,
+    pub object: serde_json::Value,
+    /// Pass back as `resume_from` (or `Last-Event-ID`) to continue after
+    /// this event
+    pub resume_token: String,
+}
+
+/// Query parameters of list endpoints that can be watched.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct V1WatchQuery {
+    /// Stream changes as server-sent events instead of returning a list
+    #[serde(default)]
+    pub watch: bool,
+    /// Continue a previous watch after this token, without listing again
+    pub resume_from: Option<String>,
+}
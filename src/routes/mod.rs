@@ -1,23 +1,35 @@
 use crate::auth::server::handlers::{get_api_key, list_api_keys};
+use crate::auth::server::oidc::oidc_routes;
 use crate::handlers::v1::{
-    check_processor_health, create_container, create_namespace, create_processor,
-    create_scoped_s3_token, create_secret, create_volume, delete_cache_key, delete_container,
-    delete_container_by_id, delete_namespace, delete_processor, delete_scoped_s3_token,
-    delete_secret, delete_secret_by_id, delete_volume, fetch_container_logs,
-    fetch_container_logs_by_id, generate_temp_s3_credentials, get_cache_key, get_container,
-    get_container_by_id, get_namespace, get_processor, get_processor_logs, get_secret,
-    get_secret_by_id, get_user_profile, get_volume, list_cache_keys, list_containers,
-    list_namespaces, list_processors, list_secrets, list_volumes, patch_container,
-    processor_websocket, read_processor_stream, read_return_message, scale_processor,
-    search_containers, send_processor, stream_logs_ws, stream_logs_ws_by_id,
-    stream_processor_return_ws, update_processor, update_secret, update_secret_by_id,
+    check_processor_health, create_budget, create_container, create_invoice, create_namespace,
+    create_namespace_grant, create_policy, create_processor, create_role, create_role_binding,
+    create_scoped_s3_token, create_secret, create_volume, delete_budget, delete_cache_key,
+    delete_container, delete_container_by_id, delete_namespace, delete_namespace_grant,
+    delete_policy, delete_price_book, delete_processor, delete_role, delete_role_binding,
+    delete_scoped_s3_token, delete_secret, delete_secret_by_id, delete_volume,
+    fetch_container_logs, fetch_container_logs_by_id, generate_temp_s3_credentials, get_budget,
+    get_cache_key, get_container, get_container_by_id, get_invoice, get_namespace, get_policy,
+    get_price_book, get_processor, get_processor_logs, get_role, get_role_binding, get_secret,
+    get_secret_by_id, get_secret_version, get_usage, get_user_profile, get_volume,
+    get_workload_s3_credentials, list_audit_events, list_budgets, list_cache_keys,
+    list_container_events, list_containers, list_dangling_references, list_invoices,
+    list_namespace_grants, list_namespaces, list_policies, list_price_books, list_processor_events,
+    list_processors, list_role_bindings, list_roles, list_secret_versions, list_secrets,
+    list_volumes, patch_container, processor_websocket, put_price_book, read_processor_stream,
+    read_return_message, refresh_workload_token, rollback_secret, rotate_secret_keys,
+    scale_processor, search_containers, send_processor, stream_logs_ws, stream_logs_ws_by_id,
+    stream_processor_return_ws, transfer_namespace, update_budget,
+    update_namespace_container_defaults, update_namespace_container_enforced,
+    update_namespace_quota, update_processor, update_secret, update_secret_by_id,
 };
 use crate::handlers::{health_handler, root_handler};
-use crate::middleware::auth_middleware;
+use crate::middleware::{
+    audit_middleware, auth_middleware, rate_limit_middleware, rbac_middleware, scope_middleware,
+};
 use crate::state::AppState;
 use axum::{
     middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::trace::{self, TraceLayer};
@@ -27,7 +39,8 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
     // Public routes that do not require authentication
     let public_routes = Router::new()
         .route("/", get(root_handler))
-        .route("/health", get(health_handler));
+        .route("/health", get(health_handler))
+        .nest("/auth", oidc_routes());
 
     // Private routes that require authentication
     let private_routes = Router::new()
@@ -62,6 +75,10 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
                 .delete(delete_container)
                 .patch(patch_container),
         )
+        .route(
+            "/v1/containers/:namespace/:name/events",
+            get(list_container_events),
+        )
         .route(
             "/v1/containers/:namespace/:name/logs",
             get(fetch_container_logs),
@@ -81,6 +98,18 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
             "/v1/secrets/:namespace/:name",
             get(get_secret).delete(delete_secret).put(update_secret),
         )
+        .route(
+            "/v1/secrets/:namespace/:name/versions",
+            get(list_secret_versions),
+        )
+        .route(
+            "/v1/secrets/:namespace/:name/versions/:version",
+            get(get_secret_version),
+        )
+        .route(
+            "/v1/secrets/:namespace/:name/rollback",
+            post(rollback_secret),
+        )
         .route("/v1/volumes", get(list_volumes).post(create_volume))
         .route(
             "/v1/volumes/:namespace/:name",
@@ -108,6 +137,10 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
             "/v1/processors/:namespace/:name/scale",
             post(scale_processor),
         )
+        .route(
+            "/v1/processors/:namespace/:name/events",
+            get(list_processor_events),
+        )
         .route(
             "/v1/processors/:namespace/:name/logs",
             get(get_processor_logs),
@@ -133,7 +166,29 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
             "/v1/cache/:namespace/:key",
             get(get_cache_key).delete(delete_cache_key),
         )
+        .route("/v1/admin/rotate-keys", post(rotate_secret_keys))
         .route("/v1/users/me", get(get_user_profile))
+        .route("/v1/auth/workload-token", post(refresh_workload_token))
+        .route("/v1/auth/s3-credentials", get(get_workload_s3_credentials))
+        .route("/v1/usage", get(get_usage))
+        .route("/v1/billing/price-books", get(list_price_books))
+        .route(
+            "/v1/billing/price-books/:owner",
+            get(get_price_book)
+                .put(put_price_book)
+                .delete(delete_price_book),
+        )
+        .route(
+            "/v1/billing/invoices",
+            get(list_invoices).post(create_invoice),
+        )
+        .route("/v1/billing/invoices/:id", get(get_invoice))
+        .route("/v1/billing/budgets", get(list_budgets).post(create_budget))
+        .route(
+            "/v1/billing/budgets/:id",
+            get(get_budget).put(update_budget).delete(delete_budget),
+        )
+        .route("/v1/gc/dangling", get(list_dangling_references))
         .route(
             "/v1/namespaces",
             get(list_namespaces).post(create_namespace),
@@ -142,6 +197,60 @@ pub fn create_routes(app_state: AppState) -> Router<AppState> {
             "/v1/namespaces/:name",
             get(get_namespace).delete(delete_namespace),
         )
+        .route("/v1/namespaces/:name/quota", put(update_namespace_quota))
+        .route(
+            "/v1/namespaces/:name/container-defaults",
+            put(update_namespace_container_defaults),
+        )
+        .route(
+            "/v1/namespaces/:name/container-enforced",
+            put(update_namespace_container_enforced),
+        )
+        .route(
+            "/v1/namespaces/:name/grants",
+            get(list_namespace_grants).post(create_namespace_grant),
+        )
+        .route(
+            "/v1/namespaces/:name/grants/:id",
+            delete(delete_namespace_grant),
+        )
+        .route("/v1/namespaces/:name/transfer", post(transfer_namespace))
+        .route("/v1/audit", get(list_audit_events))
+        .route("/v1/policies", get(list_policies).post(create_policy))
+        .route(
+            "/v1/policies/:namespace/:name",
+            get(get_policy).delete(delete_policy),
+        )
+        .route("/v1/roles", get(list_roles).post(create_role))
+        .route(
+            "/v1/roles/:namespace/:name",
+            get(get_role).delete(delete_role),
+        )
+        .route(
+            "/v1/rolebindings",
+            get(list_role_bindings).post(create_role_binding),
+        )
+        .route(
+            "/v1/rolebindings/:namespace/:name",
+            get(get_role_binding).delete(delete_role_binding),
+        )
+        // Rate limits run per route, after authentication
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit_middleware,
+        ))
+        // Namespace roles are checked before any rate limit is spent
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rbac_middleware,
+        ))
+        // API key and workload token scopes are checked before roles
+        .route_layer(middleware::from_fn(scope_middleware))
+        // Audit records every call that gets past authentication, including denied ones
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            audit_middleware,
+        ))
         // Apply the authentication middleware to private routes
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
@@ -1,4 +1,5 @@
 use crate::errors::ApiError;
+use crate::resources::v1::containers::models::V1SecretFile;
 use anyhow::{bail, Result};
 use axum::{
     async_trait,
@@ -8,6 +9,7 @@ use axum::{
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::de::DeserializeOwned;
+use std::collections::HashSet;
 
 pub struct ValidatedJson<T>(pub T);
 
@@ -39,6 +41,43 @@ pub fn validate_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+// Absolute paths of letters, digits and `._-@+`, without `..` segments.
+static SECRET_FILE_PATH_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(/[a-zA-Z0-9._@+-]+)+$").expect("Failed to compile SECRET_FILE_PATH_REGEX")
+});
+
+pub fn validate_secret_files(files: &[V1SecretFile]) -> Result<()> {
+    let mut paths = HashSet::new();
+    for file in files {
+        validate_name(&file.secret_name)?;
+        if !SECRET_FILE_PATH_REGEX.is_match(&file.path)
+            || file.path.split('/').any(|part| part == "..")
+        {
+            bail!(
+                "Invalid secret file path '{}': must be absolute and only contain letters, \
+                digits, underscores, hyphens, periods, '@' or '+'.",
+                file.path
+            );
+        }
+        if !paths.insert(file.path.as_str()) {
+            bail!("Secret file path '{}' is used more than once", file.path);
+        }
+        file.mode_bits().map_err(anyhow::Error::msg)?;
+    }
+    Ok(())
+}
+
+// Only RunPod can restart a container in place, which secret rollouts rely on.
+pub fn validate_restart_on_secret_change(platform: &str, restart: Option<bool>) -> Result<()> {
+    if restart == Some(true) && platform != "runpod" {
+        bail!(
+            "restart_on_secret_change is not supported on platform '{}'",
+            platform
+        );
+    }
+    Ok(())
+}
+
 pub fn validate_namespace(namespace: &str) -> Result<()> {
     if !NAME_REGEX.is_match(namespace) {
         bail!(